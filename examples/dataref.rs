@@ -56,11 +56,12 @@ impl Plugin for DataRefPlugin {
     }
 
     fn info(&self) -> PluginInfo {
-        PluginInfo {
-            name: String::from("Dataref Test"),
-            signature: String::from("org.samcrow.xplm.examples.dataref"),
-            description: String::from("Tests the DataRef features of xplm"),
-        }
+        PluginInfo::new(
+            "Dataref Test",
+            "org.samcrow.xplm.examples.dataref",
+            "Tests the DataRef features of xplm",
+        )
+        .version(xplm::plugin_version!())
     }
 }
 
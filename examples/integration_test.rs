@@ -0,0 +1,108 @@
+extern crate xplm;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use xplm::command::{Command, CommandCreateError, CommandHandler, OwnedCommand};
+use xplm::data::borrowed::DataRef;
+use xplm::data::owned::OwnedData;
+use xplm::data::{ArrayRead, DataRead, DataReadWrite, ReadWrite, StringRead};
+use xplm::plugin::{Plugin, PluginInfo};
+use xplm::testing::{TestCommand, TestResult, TestSuite};
+use xplm::xplane_plugin;
+
+/// Runs tests that can only be checked in X-Plane
+///
+/// Trigger the xplm/examples/integration_test/run command, for example from a keyboard
+/// shortcut, and check Log.txt for the results.
+struct IntegrationTestPlugin {
+    _command: TestCommand,
+}
+
+impl Plugin for IntegrationTestPlugin {
+    type Error = CommandCreateError;
+
+    fn start() -> Result<Self, Self::Error> {
+        let mut suite = TestSuite::new();
+        suite.add("borrowed datarefs", borrowed_datarefs);
+        suite.add("owned datarefs", owned_datarefs);
+        suite.add("commands", commands);
+        let command = TestCommand::new(
+            "xplm/examples/integration_test/run",
+            "Run the xplm integration tests",
+            suite,
+        )?;
+        Ok(IntegrationTestPlugin { _command: command })
+    }
+
+    fn info(&self) -> PluginInfo {
+        PluginInfo::new(
+            "xplm Integration Tests",
+            "org.samcrow.xplm.examples.integration_test",
+            "Tests xplm features inside X-Plane",
+        )
+        .version(xplm::plugin_version!())
+    }
+}
+
+fn borrowed_datarefs() -> TestResult {
+    let latitude: DataRef<f64> = DataRef::find("sim/flightmodel/position/latitude")?;
+    check(latitude.get().abs() <= 90.0, "latitude out of range")?;
+    let build: DataRef<[u8]> = DataRef::find("sim/version/sim_build_string")?;
+    check(!build.get_as_string()?.is_empty(), "empty build string")?;
+    let batteries: DataRef<[i32], ReadWrite> =
+        DataRef::find("sim/cockpit2/electrical/battery_on")?.writeable()?;
+    check(!batteries.as_vec().is_empty(), "no batteries")?;
+    check(
+        batteries.get_element(0) == Some(batteries.as_vec()[0]),
+        "first battery element differs",
+    )?;
+    check(
+        batteries.get_element(batteries.len()).is_none(),
+        "element beyond the end",
+    )?;
+    Ok(())
+}
+
+fn owned_datarefs() -> TestResult {
+    let mut owned: OwnedData<f32> =
+        OwnedData::create_with_value("xplm/examples/integration_test/value", &1.5)?;
+    let borrowed: DataRef<f32> = DataRef::find("xplm/examples/integration_test/value")?;
+    check(borrowed.get() == 1.5, "initial value not visible")?;
+    owned.set(-3.0);
+    check(borrowed.get() == -3.0, "changed value not visible")?;
+    Ok(())
+}
+
+fn commands() -> TestResult {
+    struct Counter(Rc<Cell<u32>>);
+    impl CommandHandler for Counter {
+        fn command_begin(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+        fn command_continue(&mut self) {}
+        fn command_end(&mut self) {}
+    }
+
+    let count = Rc::new(Cell::new(0));
+    let _owned = OwnedCommand::new(
+        "xplm/examples/integration_test/count",
+        "Count triggers",
+        Counter(Rc::clone(&count)),
+    )?;
+    let mut command = Command::find("xplm/examples/integration_test/count")?;
+    command.trigger();
+    check(count.get() == 1, "handler not called")?;
+    Ok(())
+}
+
+/// Returns an error with the provided message if the condition is false
+fn check(condition: bool, message: &str) -> TestResult {
+    if condition {
+        Ok(())
+    } else {
+        Err(message.into())
+    }
+}
+
+xplane_plugin!(IntegrationTestPlugin);
@@ -30,15 +30,16 @@ impl Plugin for MenuPlugin {
     }
 
     fn info(&self) -> PluginInfo {
-        PluginInfo {
-            name: String::from("Rust Menu Plugin"),
-            signature: String::from("org.samcrow.xplm.examples.menu"),
-            description: String::from("A plugin written in Rust that creates menus and menu items"),
-        }
+        PluginInfo::new(
+            "Rust Menu Plugin",
+            "org.samcrow.xplm.examples.menu",
+            "A plugin written in Rust that creates menus and menu items",
+        )
+        .version(xplm::plugin_version!())
     }
 }
 
-xplane_plugin!(MenuPlugin);
+xplane_plugin!(MenuPlugin, about = true);
 
 struct CheckHandler1;
 
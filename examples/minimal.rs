@@ -15,11 +15,12 @@ impl Plugin for MinimalPlugin {
     }
 
     fn info(&self) -> PluginInfo {
-        PluginInfo {
-            name: String::from("Minimal Rust Plugin"),
-            signature: String::from("org.samcrow.xplm.examples.minimal"),
-            description: String::from("A plugin written in Rust"),
-        }
+        PluginInfo::new(
+            "Minimal Rust Plugin",
+            "org.samcrow.xplm.examples.minimal",
+            "A plugin written in Rust",
+        )
+        .version(xplm::plugin_version!())
     }
 }
 
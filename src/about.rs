@@ -0,0 +1,258 @@
+//! # About window
+//!
+//! Many plugins have an "About" menu item that shows the plugin name, version, and license.
+//! An [`About`] provides this item and a small window that shows the information.
+//!
+//! The [`about_info!`](crate::about_info) macro reads the information from the `Cargo.toml`
+//! file of the plugin. The [`xplane_plugin!`](crate::xplane_plugin) macro can also add a menu
+//! with an about item, using the option `about = true`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::about::About;
+//! use xplm::menu::Menu;
+//!
+//! let menu = Menu::new("Example").unwrap();
+//! // Adds an "About example-plugin" item
+//! let about = About::new(xplm::about_info!()).unwrap();
+//! about.add_to_menu(&menu);
+//! menu.add_to_plugins_menu();
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::ffi::{CString, NulError};
+use std::rc::Rc;
+
+use xplm_sys;
+
+use super::draw::text::{self, Font, Rgb};
+use super::geometry::Rect;
+use super::menu::{ActionItem, Menu};
+use super::plugin::management;
+use super::window::{MouseAction, MouseEvent, Window, WindowDelegate, WindowRef};
+
+/// The width of the about window
+const WINDOW_WIDTH: i32 = 360;
+/// The space between the edge of the window and the text
+const PADDING: i32 = 10;
+
+/// Information shown in an about window
+#[derive(Debug, Clone)]
+pub struct AboutInfo {
+    name: String,
+    version: String,
+    description: Option<String>,
+    license: Option<String>,
+    website: Option<String>,
+}
+
+impl AboutInfo {
+    /// Creates information with a plugin name and version
+    pub fn new<N: Into<String>, V: Into<String>>(name: N, version: V) -> Self {
+        AboutInfo {
+            name: name.into(),
+            version: version.into(),
+            description: None,
+            license: None,
+            website: None,
+        }
+    }
+    /// Sets the description
+    ///
+    /// An empty description is not shown.
+    pub fn description<S: Into<String>>(self, description: S) -> Self {
+        AboutInfo {
+            description: non_empty(description.into()),
+            ..self
+        }
+    }
+    /// Sets the license
+    ///
+    /// An empty license is not shown.
+    pub fn license<S: Into<String>>(self, license: S) -> Self {
+        AboutInfo {
+            license: non_empty(license.into()),
+            ..self
+        }
+    }
+    /// Sets the website
+    ///
+    /// An empty website is not shown.
+    pub fn website<S: Into<String>>(self, website: S) -> Self {
+        AboutInfo {
+            website: non_empty(website.into()),
+            ..self
+        }
+    }
+
+    /// Returns the plugin name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the plugin version
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the lines of text shown in the window
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} {}", self.name, self.version)];
+        lines.extend(self.description.iter().cloned());
+        lines.push(format!(
+            "Signature: {}",
+            management::this_plugin().signature()
+        ));
+        lines.extend(
+            self.license
+                .iter()
+                .map(|license| format!("License: {}", license)),
+        );
+        lines.extend(self.website.iter().cloned());
+        lines
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Creates an AboutInfo from the Cargo.toml file of the crate that uses this macro
+///
+/// This uses the package name, version, description, license, and homepage.
+#[macro_export]
+macro_rules! about_info {
+    () => {
+        $crate::about::AboutInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .description(env!("CARGO_PKG_DESCRIPTION"))
+            .license(env!("CARGO_PKG_LICENSE"))
+            .website(env!("CARGO_PKG_HOMEPAGE"))
+    };
+}
+
+/// A menu item that opens a window with information about the plugin
+///
+/// The window is closed when this object is dropped. The menu item stays in any menu that it was
+/// added to, because the menu keeps its own reference to the item, but clicking it no longer
+/// opens the window. Use [`Menu::remove_child`] to remove the item.
+pub struct About {
+    /// The menu item
+    item: Rc<ActionItem>,
+    /// The information and the window, shared with the menu item
+    _shared: Rc<Shared>,
+}
+
+impl About {
+    /// Creates an "About <name>" menu item
+    ///
+    /// Returns an error if any of the information contains a null byte.
+    pub fn new(info: AboutInfo) -> Result<Self, NulError> {
+        let item_name = format!("About {}", info.name);
+        check_text(&info)?;
+        let shared = Rc::new(Shared {
+            info,
+            window: RefCell::new(None),
+        });
+        let item = ActionItem::with_context(item_name, &shared, |shared: &Shared, _| {
+            shared.show();
+        })?;
+        Ok(About {
+            item: Rc::new(item),
+            _shared: shared,
+        })
+    }
+
+    /// Adds the menu item to the end of a menu
+    pub fn add_to_menu(&self, menu: &Menu) {
+        menu.add_child::<_, ActionItem>(Rc::clone(&self.item));
+    }
+}
+
+/// Confirms that all text can be drawn
+fn check_text(info: &AboutInfo) -> Result<(), NulError> {
+    for text in [&info.name, &info.version]
+        .into_iter()
+        .chain(info.description.iter())
+        .chain(info.license.iter())
+        .chain(info.website.iter())
+    {
+        CString::new(text.as_str())?;
+    }
+    Ok(())
+}
+
+/// Information and the window, if it has been opened
+struct Shared {
+    info: AboutInfo,
+    window: RefCell<Option<WindowRef>>,
+}
+
+impl Shared {
+    /// Shows the window, creating it if needed
+    fn show(&self) {
+        let mut window = self.window.borrow_mut();
+        let window = window.get_or_insert_with(|| {
+            let lines = self.info.lines();
+            Window::new(centered_geometry(lines.len()), AboutWindow { lines })
+        });
+        window.set_visible(true);
+    }
+}
+
+/// Draws the text and closes the window when clicked
+struct AboutWindow {
+    lines: Vec<String>,
+}
+
+impl WindowDelegate for AboutWindow {
+    fn draw(&mut self, window: &Window) {
+        let geometry = window.geometry();
+        let line_height = line_height();
+        text::draw_translucent_dark_box(geometry);
+        for (i, line) in self.lines.iter().enumerate() {
+            let baseline = geometry.top() - PADDING - line_height * (i as i32 + 1);
+            text::draw_string(
+                Rgb::WHITE,
+                geometry.left() + PADDING,
+                baseline,
+                line,
+                Font::Proportional,
+            );
+        }
+    }
+
+    fn mouse_event(&mut self, window: &Window, event: MouseEvent) -> bool {
+        if let MouseAction::Down = event.action() {
+            window.set_visible(false);
+        }
+        false
+    }
+}
+
+/// Returns the height of a line of text
+fn line_height() -> i32 {
+    text::font_dimensions(Font::Proportional).char_height + 4
+}
+
+/// Returns the geometry of a window that fits the provided number of lines, in the center
+/// of the main screen
+fn centered_geometry(lines: usize) -> Rect<i32> {
+    let (mut left, mut top, mut right, mut bottom) = (0, 0, 0, 0);
+    unsafe {
+        xplm_sys::XPLMGetScreenBoundsGlobal(&mut left, &mut top, &mut right, &mut bottom);
+    }
+    let height = 2 * PADDING + line_height() * lines as i32;
+    let center_x = (left + right) / 2;
+    let center_y = (top + bottom) / 2;
+    Rect::from_left_top_right_bottom(
+        center_x - WINDOW_WIDTH / 2,
+        center_y + height / 2,
+        center_x + WINDOW_WIDTH / 2,
+        center_y - height / 2,
+    )
+}
@@ -0,0 +1,249 @@
+//! # Actions
+//!
+//! Most things that a plugin lets the user do from a menu should also be available as a
+//! command, so that the user can bind them to a key or joystick button.
+//!
+//! An [`Action`] creates a command and a menu item from one definition. Triggering the
+//! command and clicking the menu item both call the same handler, and the enabled and checked
+//! states of the command and menu item stay the same.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::action::Action;
+//! use xplm::menu::Menu;
+//!
+//! let menu = Menu::new("Example").unwrap();
+//! let autopilot = Action::new_checkable(
+//!     "example/toggle_autopilot",
+//!     "Toggle the example autopilot",
+//!     "Autopilot",
+//!     false,
+//!     |engaged| xplm::debugln!("Autopilot engaged: {}", engaged),
+//! )
+//! .unwrap();
+//! autopilot.add_to_menu(&menu);
+//! menu.add_to_plugins_menu();
+//! ```
+//!
+
+use std::cell::{Cell, RefCell};
+use std::ffi::NulError;
+use std::rc::{Rc, Weak};
+
+use super::command::{CommandCreateError, CommandHandler, OwnedCommand};
+use super::menu::{ActionItem, CheckItem, Menu};
+
+/// A command and a menu item that perform the same action
+///
+/// The command handler is removed when this object is dropped. The menu item stays in any menu
+/// that it was added to, because the menu keeps its own reference to the item, but clicking it
+/// no longer calls the handler. Use [`Menu::remove_child`] to remove the item.
+pub struct Action {
+    /// State shared with the command and menu item handlers
+    shared: Rc<Shared>,
+    /// The menu item
+    item: ActionMenuItem,
+    /// The command
+    _command: OwnedCommand,
+}
+
+impl Action {
+    /// Creates an action that calls a handler when the command is triggered or the menu item
+    /// is clicked
+    ///
+    /// If a command with the provided name already exists, the handler is attached to it.
+    pub fn new<F>(
+        name: &str,
+        description: &str,
+        menu_text: &str,
+        handler: F,
+    ) -> Result<Self, ActionError>
+    where
+        F: FnMut() + 'static,
+    {
+        let mut handler = handler;
+        let shared = Shared::new(None, move |_| handler());
+        let item = ActionItem::with_context(menu_text, &shared, |shared: &Shared, _| {
+            shared.run();
+        })?;
+        Self::finish(
+            name,
+            description,
+            shared,
+            ActionMenuItem::Action(Rc::new(item)),
+        )
+    }
+
+    /// Creates an action with a checked state
+    ///
+    /// Triggering the command or clicking the menu item toggles the checked state and calls
+    /// the handler with the new state.
+    pub fn new_checkable<F>(
+        name: &str,
+        description: &str,
+        menu_text: &str,
+        checked: bool,
+        handler: F,
+    ) -> Result<Self, ActionError>
+    where
+        F: FnMut(bool) + 'static,
+    {
+        let shared = Shared::new(Some(checked), handler);
+        // The check item changes its own check state before calling its handler
+        let item = Rc::new(CheckItem::with_context(
+            menu_text,
+            checked,
+            &shared,
+            |shared: &Shared, _, checked| {
+                if shared.enabled.get() {
+                    shared.checked.set(Some(checked));
+                    shared.call(checked);
+                }
+            },
+        )?);
+        *shared.check_item.borrow_mut() = Rc::downgrade(&item);
+        Self::finish(name, description, shared, ActionMenuItem::Check(item))
+    }
+
+    fn finish(
+        name: &str,
+        description: &str,
+        shared: Rc<Shared>,
+        item: ActionMenuItem,
+    ) -> Result<Self, ActionError> {
+        let command = OwnedCommand::new(
+            name,
+            description,
+            ActionCommand {
+                shared: Rc::clone(&shared),
+            },
+        )?;
+        Ok(Action {
+            shared,
+            item,
+            _command: command,
+        })
+    }
+
+    /// Adds the menu item of this action to the end of a menu
+    pub fn add_to_menu(&self, menu: &Menu) {
+        match self.item {
+            ActionMenuItem::Action(ref item) => menu.add_child::<_, ActionItem>(Rc::clone(item)),
+            ActionMenuItem::Check(ref item) => menu.add_child::<_, CheckItem>(Rc::clone(item)),
+        }
+    }
+
+    /// Returns true if this action is enabled
+    pub fn enabled(&self) -> bool {
+        self.shared.enabled.get()
+    }
+    /// Enables or disables this action
+    ///
+    /// When an action is disabled, its menu item is grayed out and triggering its command
+    /// has no effect.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.shared.enabled.set(enabled);
+        match self.item {
+            ActionMenuItem::Action(ref item) => item.set_enabled(enabled),
+            ActionMenuItem::Check(ref item) => item.set_enabled(enabled),
+        }
+    }
+
+    /// Returns true if this action is checkable and checked
+    pub fn checked(&self) -> bool {
+        self.shared.checked.get().unwrap_or(false)
+    }
+    /// Sets the checked state of a checkable action without calling the handler
+    ///
+    /// This has no effect if the action was not created with new_checkable.
+    pub fn set_checked(&self, checked: bool) {
+        if self.shared.checked.get().is_some() {
+            self.shared.set_checked(checked);
+        }
+    }
+}
+
+/// The menu item of an action
+enum ActionMenuItem {
+    Action(Rc<ActionItem>),
+    Check(Rc<CheckItem>),
+}
+
+/// State shared by an action and its handlers
+struct Shared {
+    /// If the action is enabled
+    enabled: Cell<bool>,
+    /// The checked state, or None if the action is not checkable
+    checked: Cell<Option<bool>>,
+    /// The check item, used to update its state when the command is triggered
+    check_item: RefCell<Weak<CheckItem>>,
+    /// The user's handler, which receives the new checked state
+    handler: RefCell<Box<dyn FnMut(bool)>>,
+}
+
+impl Shared {
+    fn new<F: FnMut(bool) + 'static>(checked: Option<bool>, handler: F) -> Rc<Self> {
+        Rc::new(Shared {
+            enabled: Cell::new(true),
+            checked: Cell::new(checked),
+            check_item: RefCell::new(Weak::new()),
+            handler: RefCell::new(Box::new(handler)),
+        })
+    }
+
+    /// Runs the action, toggling the checked state if it is checkable
+    fn run(&self) {
+        if !self.enabled.get() {
+            return;
+        }
+        let checked = match self.checked.get() {
+            Some(checked) => {
+                self.set_checked(!checked);
+                !checked
+            }
+            None => false,
+        };
+        self.call(checked);
+    }
+
+    /// Sets the checked state and the state of the check item
+    fn set_checked(&self, checked: bool) {
+        self.checked.set(Some(checked));
+        if let Some(item) = self.check_item.borrow().upgrade() {
+            item.set_checked(checked);
+        }
+    }
+
+    /// Calls the user's handler
+    fn call(&self, checked: bool) {
+        let mut handler = self.handler.borrow_mut();
+        handler(checked);
+    }
+}
+
+/// The command handler of an action
+struct ActionCommand {
+    shared: Rc<Shared>,
+}
+
+impl CommandHandler for ActionCommand {
+    fn command_begin(&mut self) {
+        self.shared.run();
+    }
+    fn command_continue(&mut self) {}
+    fn command_end(&mut self) {}
+}
+
+/// Errors that can occur when creating an action
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum ActionError {
+    /// The menu text contained a null byte
+    #[error("Null byte in menu text")]
+    MenuText(#[from] NulError),
+
+    /// The command could not be created
+    #[error("Command error: {0}")]
+    Command(#[from] CommandCreateError),
+}
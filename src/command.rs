@@ -1,10 +1,30 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
 use std::ffi::NulError;
+use std::fmt;
 use std::ops::DerefMut;
 use std::os::raw::{c_int, c_void};
 
 use xplm_sys::*;
 
+use super::feedback::{self, Feedback};
+
+/// Triggering commands from other threads
+pub mod proxy;
+/// Commands performed in order with delays
+pub mod sequence;
+/// Groups of commands with one handler
+pub mod table;
+
+thread_local! {
+    /// The command references of the live OwnedCommands, used to detect a second
+    /// OwnedCommand for the same command
+    static OWNED: RefCell<HashSet<usize>> = RefCell::new(HashSet::new());
+    /// The descriptions of commands that this plugin created, by command reference
+    static DESCRIPTIONS: RefCell<HashMap<usize, String>> = RefCell::new(HashMap::new());
+}
+
 /// A command created by X-Plane or another plugin, that can be triggered
 #[derive(Debug)]
 pub struct Command {
@@ -17,19 +37,34 @@ impl Command {
     ///
     /// The command should have already been created by X-Plane or another plugin.
     pub fn find(name: &str) -> Result<Self, CommandFindError> {
-        let name_c = CString::new(name)?;
-        let command_ref = unsafe { XPLMFindCommand(name_c.as_ptr()) };
-        if !command_ref.is_null() {
-            Ok(Command { id: command_ref })
-        } else {
-            Err(CommandFindError::NotFound)
-        }
+        CommandRef::find(name).map(Command::from)
+    }
+
+    /// Returns true if a command with the provided name exists
+    ///
+    /// This returns false if the name contains a null byte.
+    pub fn exists(name: &str) -> bool {
+        CommandRef::find(name).is_ok()
+    }
+
+    /// Returns a copyable reference to this command
+    pub fn command_ref(&self) -> CommandRef {
+        CommandRef(self.id)
+    }
+
+    /// Returns the description of this command, if this plugin created it
+    ///
+    /// The SDK does not provide the descriptions of commands that X-Plane or other plugins
+    /// created, so this returns None for those commands.
+    pub fn description(&self) -> Option<String> {
+        self.command_ref().description()
     }
 
     /// Triggers a command once
     ///
     /// This is equivalent to pressing a button down and immediately releasing it.
     pub fn trigger(&mut self) {
+        crate::debug_assert_main_thread!();
         unsafe {
             XPLMCommandOnce(self.id);
         }
@@ -38,19 +73,192 @@ impl Command {
     /// Starts holding down this command
     ///
     /// The command will be released when the returned hold object is dropped.
-    pub fn hold_down(&mut self) -> CommandHold {
+    pub fn hold_down(&mut self) -> CommandHold<'_> {
+        crate::debug_assert_main_thread!();
         unsafe {
             XPLMCommandBegin(self.id);
         }
         CommandHold { command: self }
     }
 
+    /// Returns the command reference
+    pub(crate) fn id(&self) -> XPLMCommandRef {
+        self.id
+    }
+
     /// Releases this command
     fn release(&mut self) {
         unsafe {
             XPLMCommandEnd(self.id);
         }
     }
+
+    /// Registers a handler for this command
+    ///
+    /// If before is true, the handler is called before X-Plane handles the command.
+    /// Otherwise, it is called afterwards.
+    ///
+    /// If consume is true, X-Plane and other handlers that would run after this handler do
+    /// not receive the command. This can be used to block a command.
+    ///
+    /// The handler is unregistered when the returned object is dropped.
+    pub fn intercept<H: CommandHandler>(
+        &self,
+        handler: H,
+        before: bool,
+        consume: bool,
+    ) -> CommandIntercept {
+        let mut data = Box::new(InterceptData {
+            consume,
+            handler: Box::new(handler),
+        });
+        let data_ptr: *mut InterceptData = data.deref_mut();
+        unsafe {
+            XPLMRegisterCommandHandler(
+                self.id,
+                Some(intercept_handler::<H>),
+                before as c_int,
+                data_ptr as *mut c_void,
+            );
+        }
+        CommandIntercept {
+            id: self.id,
+            data,
+            callback: Some(intercept_handler::<H>),
+            before,
+        }
+    }
+}
+
+impl From<CommandRef> for Command {
+    fn from(command_ref: CommandRef) -> Self {
+        Command { id: command_ref.0 }
+    }
+}
+
+/// A reference to a command that can be copied
+///
+/// This is useful for storing many commands in a table, for example to map hardware buttons
+/// to commands. Unlike a [`Command`], a held command is not released automatically, so
+/// every call to [`begin`](CommandRef::begin) should be followed by a call to
+/// [`end`](CommandRef::end).
+///
+/// # Examples
+///
+/// ```no_run
+/// use xplm::command::CommandRef;
+///
+/// let buttons: Vec<CommandRef> = ["sim/lights/landing_lights_toggle", "sim/flight_controls/flaps_down"]
+///     .iter()
+///     .filter_map(|name| CommandRef::find(name).ok())
+///     .collect();
+/// // When button 1 is pressed
+/// buttons[1].begin();
+/// // When button 1 is released
+/// buttons[1].end();
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CommandRef(XPLMCommandRef);
+
+impl CommandRef {
+    /// Finds a command
+    ///
+    /// The command should have already been created by X-Plane or another plugin.
+    pub fn find(name: &str) -> Result<Self, CommandFindError> {
+        crate::debug_assert_main_thread!();
+        let name_c = CString::new(name)?;
+        let command_ref = unsafe { XPLMFindCommand(name_c.as_ptr()) };
+        if !command_ref.is_null() {
+            Ok(CommandRef(command_ref))
+        } else {
+            Err(CommandFindError::NotFound)
+        }
+    }
+
+    /// Triggers the command once
+    ///
+    /// This is equivalent to pressing a button down and immediately releasing it.
+    pub fn trigger(self) {
+        crate::debug_assert_main_thread!();
+        unsafe { XPLMCommandOnce(self.0) }
+    }
+
+    /// Starts holding down the command
+    pub fn begin(self) {
+        crate::debug_assert_main_thread!();
+        unsafe { XPLMCommandBegin(self.0) }
+    }
+
+    /// Releases the command after a call to [`begin`](CommandRef::begin)
+    pub fn end(self) {
+        crate::debug_assert_main_thread!();
+        unsafe { XPLMCommandEnd(self.0) }
+    }
+
+    /// Returns the description of the command, if this plugin created it
+    ///
+    /// See [`Command::description`].
+    pub fn description(self) -> Option<String> {
+        DESCRIPTIONS.with(|descriptions| descriptions.borrow().get(&(self.0 as usize)).cloned())
+    }
+}
+
+/// A handler registered on a command that this plugin does not own
+///
+/// The handler is unregistered when this object is dropped.
+pub struct CommandIntercept {
+    /// The command reference
+    id: XPLMCommandRef,
+    /// The heap-allocated data
+    data: Box<InterceptData>,
+    /// The handler callback, used to unregister
+    callback: XPLMCommandCallback_f,
+    /// If the handler runs before X-Plane handles the command
+    before: bool,
+}
+
+impl CommandIntercept {
+    /// Returns true if the handler runs before X-Plane handles the command
+    pub fn before(&self) -> bool {
+        self.before
+    }
+    /// Returns true if the handler prevents later handlers from receiving the command
+    pub fn consume(&self) -> bool {
+        self.data.consume
+    }
+}
+
+impl Drop for CommandIntercept {
+    fn drop(&mut self) {
+        let data_ptr: *mut InterceptData = self.data.deref_mut();
+        unsafe {
+            XPLMUnregisterCommandHandler(
+                self.id,
+                self.callback,
+                self.before as c_int,
+                data_ptr as *mut c_void,
+            );
+        }
+        super::panics::forget(data_ptr);
+    }
+}
+
+impl fmt::Debug for CommandIntercept {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CommandIntercept")
+            .field("id", &self.id)
+            .field("before", &self.before)
+            .field("consume", &self.data.consume)
+            .finish()
+    }
+}
+
+/// Data for an intercepted command, used as a refcon
+struct InterceptData {
+    /// If other handlers should not receive the command
+    consume: bool,
+    /// The handler
+    handler: Box<dyn CommandHandler>,
 }
 
 /// An RAII lock that keeps a command held down
@@ -70,6 +278,7 @@ impl<'a> Drop for CommandHold<'a> {
 
 /// Errors that can occur when finding a command
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum CommandFindError {
     /// The provided command name contained a null byte
     #[error("Null byte in command name")]
@@ -103,12 +312,41 @@ impl OwnedCommand {
     /// or finds an existing command
     ///
     /// In either case, the command will trigger the provided handler.
+    ///
+    /// Each command can have only one OwnedCommand in this plugin at a time. If another
+    /// OwnedCommand for the same command has not been dropped, this function returns
+    /// [`CommandCreateError::AlreadyOwned`]. This usually means that a command was not dropped
+    /// when the plugin was disabled or stopped, or that two parts of the plugin use the same
+    /// command name.
+    ///
+    /// If the command already exists, its description is not changed. The SDK does not
+    /// provide a way to change the description of a command.
     pub fn new<H: CommandHandler>(
         name: &str,
         description: &str,
         handler: H,
+    ) -> Result<Self, CommandCreateError> {
+        Self::new_or_attach(name, description, handler)
+    }
+
+    /// Creates a new command, or attaches the handler to an existing command with the
+    /// provided name
+    ///
+    /// X-Plane does not delete commands when a plugin is unloaded, so a command created
+    /// before a plugin was reloaded still exists after the reload. This function registers
+    /// the handler on that command instead of returning an error.
+    /// [`is_attached`](OwnedCommand::is_attached) tells if the command already existed.
+    ///
+    /// This behaves the same way as [`new`](OwnedCommand::new).
+    pub fn new_or_attach<H: CommandHandler>(
+        name: &str,
+        description: &str,
+        handler: H,
     ) -> Result<Self, CommandCreateError> {
         let mut data = Box::new(OwnedCommandData::new(name, description, handler)?);
+        if !claim(data.id) {
+            return Err(CommandCreateError::AlreadyOwned(name.to_owned()));
+        }
         let data_ptr: *mut OwnedCommandData = data.deref_mut();
         unsafe {
             XPLMRegisterCommandHandler(
@@ -118,26 +356,70 @@ impl OwnedCommand {
                 data_ptr as *mut c_void,
             );
         }
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::created("OwnedCommand", data_ptr);
         Ok(OwnedCommand {
             data,
             callback: Some(command_handler::<H>),
         })
     }
+
+    /// Returns the name of this command
+    pub fn name(&self) -> &str {
+        &self.data.name
+    }
+
+    /// Returns the description that was provided when this command was created
+    ///
+    /// If the command already existed, X-Plane may show a different description.
+    pub fn description(&self) -> &str {
+        &self.data.description
+    }
+
+    /// Returns true if the command already existed when this object was created
+    ///
+    /// This is usually true after the plugin has been reloaded.
+    pub fn is_attached(&self) -> bool {
+        self.data.attached
+    }
 }
 
 impl Drop for OwnedCommand {
     fn drop(&mut self) {
         let data_ptr: *mut OwnedCommandData = self.data.deref_mut();
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::dropped("OwnedCommand", data_ptr);
+        super::panics::forget(data_ptr);
         unsafe {
             XPLMUnregisterCommandHandler(self.data.id, self.callback, 1, data_ptr as *mut c_void);
         }
+        release(self.data.id);
     }
 }
 
+/// Records that an OwnedCommand exists for a command
+///
+/// Returns false if another OwnedCommand already exists for the command.
+fn claim(id: XPLMCommandRef) -> bool {
+    OWNED.with(|owned| owned.borrow_mut().insert(id as usize))
+}
+
+/// Records that the OwnedCommand for a command has been dropped
+fn release(id: XPLMCommandRef) {
+    // The set may already be destroyed if this is called when the thread exits
+    let _ = OWNED.try_with(|owned| owned.borrow_mut().remove(&(id as usize)));
+}
+
 /// Data for an owned command, used as a refcon
 struct OwnedCommandData {
     /// The command reference
     id: XPLMCommandRef,
+    /// The command name
+    name: String,
+    /// The description provided when the command was created
+    description: String,
+    /// If the command existed before this plugin tried to create it
+    attached: bool,
     /// The handler
     handler: Box<dyn CommandHandler>,
 }
@@ -148,38 +430,104 @@ impl OwnedCommandData {
         description: &str,
         handler: H,
     ) -> Result<Self, CommandCreateError> {
+        crate::debug_assert_main_thread!();
         let name_c = CString::new(name)?;
         let description_c = CString::new(description)?;
+        #[cfg(feature = "fault-injection")]
+        if crate::fault::triggered(crate::fault::Fault::CommandCreate, Some(name)) {
+            return Err(CommandCreateError::Failed(name.to_owned()));
+        }
 
+        let existing = unsafe { XPLMFindCommand(name_c.as_ptr()) };
+        let id = if existing.is_null() {
+            unsafe { XPLMCreateCommand(name_c.as_ptr(), description_c.as_ptr()) }
+        } else {
+            existing
+        };
+        if id.is_null() {
+            return Err(CommandCreateError::Failed(name.to_owned()));
+        }
+        if existing.is_null() {
+            // Commands are never deleted, so the description is kept after this is dropped
+            DESCRIPTIONS.with(|descriptions| {
+                descriptions
+                    .borrow_mut()
+                    .insert(id as usize, description.to_owned())
+            });
+        }
         Ok(OwnedCommandData {
-            id: unsafe { XPLMCreateCommand(name_c.as_ptr(), description_c.as_ptr()) },
+            id,
+            name: name.to_owned(),
+            description: description.to_owned(),
+            attached: !existing.is_null(),
             handler: Box::new(handler),
         })
     }
 }
 
+/// Intercepted command handler callback
+unsafe extern "C" fn intercept_handler<H: CommandHandler>(
+    _: XPLMCommandRef,
+    phase: XPLMCommandPhase,
+    refcon: *mut c_void,
+) -> c_int {
+    if super::internal::suspended() {
+        return 1;
+    }
+    let data = refcon as *mut InterceptData;
+    super::panics::contain("command interception", data, 1, || {
+        let handler: *mut dyn CommandHandler = (*data).handler.deref_mut();
+        let handler = handler as *mut H;
+        if phase == xplm_CommandBegin as i32 {
+            (*handler).command_begin();
+        } else if phase == xplm_CommandContinue as i32 {
+            (*handler).command_continue();
+        } else if phase == xplm_CommandEnd as i32 {
+            (*handler).command_end();
+        }
+        if (*data).consume {
+            0
+        } else {
+            1
+        }
+    })
+}
+
 /// Command handler callback
 unsafe extern "C" fn command_handler<H: CommandHandler>(
     _: XPLMCommandRef,
     phase: XPLMCommandPhase,
     refcon: *mut c_void,
 ) -> c_int {
-    let data = refcon as *mut OwnedCommandData;
-    let handler: *mut dyn CommandHandler = (*data).handler.deref_mut();
-    let handler = handler as *mut H;
-    if phase == xplm_CommandBegin as i32 {
-        (*handler).command_begin();
-    } else if phase == xplm_CommandContinue as i32 {
-        (*handler).command_continue();
-    } else if phase == xplm_CommandEnd as i32 {
-        (*handler).command_end();
+    if super::internal::suspended() {
+        // Allow other components to handle the command
+        return 1;
+    }
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("OwnedCommand", refcon) {
+        return 1;
     }
-    // Prevent other components from handling this equivalent
-    0
+    let data = refcon as *mut OwnedCommandData;
+    // After a panic, allow other components to handle the command
+    super::panics::contain("command", data, 1, || {
+        let handler: *mut dyn CommandHandler = (*data).handler.deref_mut();
+        let handler = handler as *mut H;
+        if phase == xplm_CommandBegin as i32 {
+            feedback::notify(Feedback::Command(&(*data).name));
+            (*handler).command_begin();
+        } else if phase == xplm_CommandContinue as i32 {
+            (*handler).command_continue();
+        } else if phase == xplm_CommandEnd as i32 {
+            (*handler).command_end();
+        }
+        // Prevent other components from handling this equivalent
+        0
+    })
 }
 
 /// Errors that can occur when creating a Command
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum CommandCreateError {
     /// The provided Command name contained a null byte
     #[error("Null byte in Command name")]
@@ -189,4 +537,29 @@ pub enum CommandCreateError {
     #[deprecated(note = "commands persist between plugin reload - not an error if already exists")]
     #[error("Command exists already")]
     Exists,
+
+    /// This plugin already has an OwnedCommand for the command
+    #[error("This plugin already has an OwnedCommand for command {0}")]
+    AlreadyOwned(String),
+
+    /// X-Plane could not create the command
+    #[error("Could not create command {0}")]
+    Failed(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{claim, release};
+
+    #[test]
+    fn test_claim() {
+        let first = 0x1000 as xplm_sys::XPLMCommandRef;
+        let second = 0x2000 as xplm_sys::XPLMCommandRef;
+        assert!(claim(first));
+        assert!(claim(second));
+        // A second OwnedCommand for the same command is rejected until the first is dropped
+        assert!(!claim(first));
+        release(first);
+        assert!(claim(first));
+    }
 }
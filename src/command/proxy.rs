@@ -0,0 +1,184 @@
+//! # Triggering commands from other threads
+//!
+//! X-Plane functions must only be called from the main thread. Calling
+//! [`Command::trigger`](super::Command::trigger) from a network or hardware thread can crash
+//! X-Plane. A [`CommandProxy`] can be cloned and sent to other threads. Its actions are
+//! queued and performed in the next flight loop by the [`CommandQueue`] that created it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::thread;
+//! use xplm::command::proxy::CommandQueue;
+//! use xplm::command::Command;
+//!
+//! let mut queue = CommandQueue::new();
+//! let gear = queue.proxy(Command::find("sim/flight_controls/landing_gear_toggle").unwrap());
+//! thread::spawn(move || {
+//!     // Wait for a hardware switch, then
+//!     gear.trigger();
+//! });
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use xplm_sys::*;
+
+use super::super::flight_loop::{FlightLoop, LoopState};
+use super::Command;
+
+/// An action requested by a proxy
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Action {
+    Trigger,
+    Begin,
+    End,
+}
+
+/// Performs command actions requested from other threads
+///
+/// Actions are performed in the flight loop after they are requested. Actions requested
+/// after this object is dropped are ignored, and commands that proxies began are ended when
+/// this object is dropped.
+pub struct CommandQueue {
+    /// The commands and the requests, shared with the flight loop
+    shared: Rc<RefCell<Shared>>,
+    /// The sender that proxies clone
+    sender: Sender<(usize, Action)>,
+    /// The flight loop that performs the actions
+    _flight_loop: FlightLoop,
+}
+
+impl CommandQueue {
+    /// Creates a queue and starts its flight loop
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let shared = Rc::new(RefCell::new(Shared {
+            commands: Vec::new(),
+            receiver,
+        }));
+        let loop_shared = Rc::clone(&shared);
+        let mut flight_loop =
+            FlightLoop::new(move |_: &mut LoopState| loop_shared.borrow_mut().run());
+        flight_loop.schedule_immediate();
+        CommandQueue {
+            shared,
+            sender,
+            _flight_loop: flight_loop,
+        }
+    }
+
+    /// Adds a command to this queue and returns a proxy that can be sent to other threads
+    pub fn proxy(&mut self, command: Command) -> CommandProxy {
+        let mut shared = self.shared.borrow_mut();
+        shared.commands.push(QueuedCommand { command, held: 0 });
+        CommandProxy {
+            index: shared.commands.len() - 1,
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Performs all requested actions now, instead of waiting for the flight loop
+    pub fn run(&self) {
+        self.shared.borrow_mut().run();
+    }
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        CommandQueue::new()
+    }
+}
+
+impl fmt::Debug for CommandQueue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CommandQueue")
+            .field("commands", &self.shared.borrow().commands.len())
+            .finish()
+    }
+}
+
+/// A command in a queue
+struct QueuedCommand {
+    command: Command,
+    /// The number of times proxies have begun this command without ending it
+    held: u32,
+}
+
+struct Shared {
+    commands: Vec<QueuedCommand>,
+    receiver: Receiver<(usize, Action)>,
+}
+
+impl Shared {
+    /// Performs all requested actions
+    fn run(&mut self) {
+        while let Ok((index, action)) = self.receiver.try_recv() {
+            let Some(queued) = self.commands.get_mut(index) else {
+                continue;
+            };
+            let id = queued.command.id;
+            match action {
+                Action::Trigger => unsafe { XPLMCommandOnce(id) },
+                Action::Begin => {
+                    queued.held += 1;
+                    unsafe { XPLMCommandBegin(id) }
+                }
+                // Ignore extra ends so that X-Plane's begin and end calls stay balanced
+                Action::End if queued.held > 0 => {
+                    queued.held -= 1;
+                    unsafe { XPLMCommandEnd(id) }
+                }
+                Action::End => {}
+            }
+        }
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        for queued in &self.commands {
+            for _ in 0..queued.held {
+                unsafe { XPLMCommandEnd(queued.command.id) }
+            }
+        }
+    }
+}
+
+/// A handle to a command that can be used from any thread
+///
+/// Each method requests an action and returns immediately. It returns false if the queue
+/// that created this proxy has been dropped.
+#[derive(Debug, Clone)]
+pub struct CommandProxy {
+    /// The index of the command in the queue
+    index: usize,
+    sender: Sender<(usize, Action)>,
+}
+
+impl CommandProxy {
+    /// Triggers the command once
+    pub fn trigger(&self) -> bool {
+        self.send(Action::Trigger)
+    }
+
+    /// Starts holding down the command
+    ///
+    /// Each call to this function should be followed by a call to [`end`](CommandProxy::end).
+    pub fn begin(&self) -> bool {
+        self.send(Action::Begin)
+    }
+
+    /// Releases the command after a call to [`begin`](CommandProxy::begin)
+    pub fn end(&self) -> bool {
+        self.send(Action::End)
+    }
+
+    fn send(&self, action: Action) -> bool {
+        self.sender.send((self.index, action)).is_ok()
+    }
+}
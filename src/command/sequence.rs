@@ -0,0 +1,209 @@
+//! # Command sequences
+//!
+//! Some procedures, such as starting an engine, need several commands with delays between
+//! them. A [`CommandSequence`] performs a list of steps in order, using a flight loop to wait
+//! between steps.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use xplm::command::sequence::CommandSequence;
+//! use xplm::command::Command;
+//!
+//! let mut startup = CommandSequence::new()
+//!     .trigger(Command::find("sim/electrical/battery_1_on").unwrap())
+//!     .wait(Duration::from_millis(500))
+//!     .trigger(Command::find("sim/systems/avionics_on").unwrap())
+//!     .wait(Duration::from_secs(1))
+//!     .hold(
+//!         Command::find("sim/starters/engage_starter_1").unwrap(),
+//!         Duration::from_secs(3),
+//!     );
+//! startup.start();
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use xplm_sys::*;
+
+use super::super::flight_loop::{FlightLoop, LoopState};
+use super::Command;
+
+/// A list of command steps that are performed in order
+///
+/// The sequence does not run until [`start`](CommandSequence::start) is called. If this
+/// object is dropped while a command is held down, the command is released.
+pub struct CommandSequence {
+    /// The steps and progress, shared with the flight loop
+    state: Rc<RefCell<State>>,
+    /// The flight loop that performs the steps
+    flight_loop: FlightLoop,
+}
+
+impl CommandSequence {
+    /// Creates a sequence with no steps
+    pub fn new() -> Self {
+        let state = Rc::new(RefCell::new(State {
+            steps: Vec::new(),
+            next: 0,
+            held: None,
+            running: false,
+        }));
+        let loop_shared = Rc::clone(&state);
+        let flight_loop = FlightLoop::new(move |loop_state: &mut LoopState| {
+            loop_shared.borrow_mut().run(loop_state)
+        });
+        CommandSequence { state, flight_loop }
+    }
+
+    /// Adds a step that triggers a command once
+    ///
+    /// The next step starts immediately after this one.
+    pub fn trigger(self, command: Command) -> Self {
+        self.push(Step::Trigger(command))
+    }
+
+    /// Adds a step that holds down a command for a duration
+    ///
+    /// The next step starts after the command is released.
+    pub fn hold(self, command: Command, duration: Duration) -> Self {
+        self.push(Step::Hold(command, duration))
+    }
+
+    /// Adds a step that waits for a duration before the next step
+    pub fn wait(self, duration: Duration) -> Self {
+        self.push(Step::Wait(duration))
+    }
+
+    fn push(self, step: Step) -> Self {
+        self.state.borrow_mut().steps.push(step);
+        self
+    }
+
+    /// Starts performing the steps from the beginning, in the next flight loop
+    ///
+    /// If the sequence is already running, it is restarted and any held command is released.
+    pub fn start(&mut self) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.release();
+            state.next = 0;
+            state.running = true;
+        }
+        self.flight_loop.schedule_immediate();
+    }
+
+    /// Stops performing steps and releases any held command
+    pub fn stop(&mut self) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.release();
+            state.running = false;
+        }
+        self.flight_loop.deactivate();
+    }
+
+    /// Returns true if the sequence has been started and has not finished or been stopped
+    pub fn is_running(&self) -> bool {
+        self.state.borrow().running
+    }
+
+    /// Returns the number of steps in this sequence
+    pub fn len(&self) -> usize {
+        self.state.borrow().steps.len()
+    }
+
+    /// Returns true if this sequence has no steps
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for CommandSequence {
+    fn default() -> Self {
+        CommandSequence::new()
+    }
+}
+
+impl fmt::Debug for CommandSequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.state.borrow();
+        f.debug_struct("CommandSequence")
+            .field("steps", &state.steps.len())
+            .field("next", &state.next)
+            .field("running", &state.running)
+            .finish()
+    }
+}
+
+/// One step in a sequence
+enum Step {
+    Trigger(Command),
+    Hold(Command, Duration),
+    Wait(Duration),
+}
+
+struct State {
+    steps: Vec<Step>,
+    /// The index of the next step to perform
+    next: usize,
+    /// The index of the hold step whose command is held down
+    held: Option<usize>,
+    running: bool,
+}
+
+impl State {
+    /// Releases the held command, if any, then performs steps until one needs to wait
+    fn run(&mut self, loop_state: &mut LoopState) {
+        self.release();
+        while let Some(step) = self.steps.get_mut(self.next) {
+            let index = self.next;
+            self.next += 1;
+            match step {
+                Step::Trigger(command) => command.trigger(),
+                Step::Hold(command, duration) => {
+                    unsafe { XPLMCommandBegin(command.id) };
+                    self.held = Some(index);
+                    wait(loop_state, *duration);
+                    return;
+                }
+                Step::Wait(duration) => {
+                    wait(loop_state, *duration);
+                    return;
+                }
+            }
+        }
+        self.running = false;
+        loop_state.deactivate();
+    }
+
+    /// Releases the held command, if any
+    fn release(&mut self) {
+        if let Some(index) = self.held.take() {
+            if let Some(Step::Hold(command, _)) = self.steps.get_mut(index) {
+                command.release();
+            }
+        }
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Schedules the next callback after a duration, or in the next flight loop if the duration
+/// is zero
+fn wait(loop_state: &mut LoopState, duration: Duration) {
+    if duration.is_zero() {
+        loop_state.call_next_loop();
+    } else {
+        loop_state.call_after(duration);
+    }
+}
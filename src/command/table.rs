@@ -0,0 +1,171 @@
+//! # Command tables
+//!
+//! Plugins that provide many commands, such as aircraft systems, would otherwise need an
+//! [`OwnedCommand`] and a handler type for each command. A [`CommandTable`] creates a list of
+//! commands together and sends all of them to one handler, with an identifier that tells which
+//! command was triggered. The commands are removed together when the table is dropped.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::command::table::{CommandTable, TableHandler};
+//!
+//! #[derive(Debug, Copy, Clone, PartialEq)]
+//! enum Switch {
+//!     Battery,
+//!     Avionics,
+//!     Beacon,
+//! }
+//!
+//! struct Panel;
+//! impl TableHandler<Switch> for Panel {
+//!     fn command_begin(&mut self, switch: Switch) {
+//!         xplm::debugln!("[myplugin] {:?} toggled", switch);
+//!     }
+//! }
+//!
+//! let table = CommandTable::new(
+//!     &[
+//!         ("myplugin/battery", "Toggle the battery", Switch::Battery),
+//!         ("myplugin/avionics", "Toggle the avionics", Switch::Avionics),
+//!         ("myplugin/beacon", "Toggle the beacon", Switch::Beacon),
+//!     ],
+//!     Panel,
+//! )
+//! .unwrap();
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::{CommandCreateError, CommandHandler, OwnedCommand};
+
+/// Trait for things that can handle the commands in a table
+///
+/// K is the type that identifies each command.
+pub trait TableHandler<K>: 'static {
+    /// Called when a command begins (corresponds to a button being pressed down)
+    fn command_begin(&mut self, command: K);
+    /// Called frequently while a command button is held down
+    fn command_continue(&mut self, _command: K) {}
+    /// Called when a command ends (corresponds to a button being released)
+    fn command_end(&mut self, _command: K) {}
+}
+
+/// Closures can be used as TableHandlers
+///
+/// The closure is called when a command begins.
+impl<K, F> TableHandler<K> for F
+where
+    F: FnMut(K) + 'static,
+{
+    fn command_begin(&mut self, command: K) {
+        self(command)
+    }
+}
+
+/// A group of commands created by this plugin that share one handler
+///
+/// The commands are created when the table is created, and removed when it is dropped.
+pub struct CommandTable<K> {
+    /// The identifier and command for each entry, in the order provided
+    commands: Vec<(K, OwnedCommand)>,
+}
+
+impl<K: Copy + 'static> CommandTable<K> {
+    /// Creates a command for each entry and sends them all to a handler
+    ///
+    /// Each entry contains a command name, a description, and the identifier that is passed
+    /// to the handler. As with [`OwnedCommand::new`], commands that already exist are
+    /// reused.
+    ///
+    /// If any command cannot be created, the commands that were already created are removed
+    /// and this returns the error.
+    ///
+    /// If the handler triggers another command in the same table, the handler is not called
+    /// for that command.
+    pub fn new<H: TableHandler<K>>(
+        entries: &[(&str, &str, K)],
+        handler: H,
+    ) -> Result<Self, CommandCreateError> {
+        let handler: Rc<RefCell<dyn TableHandler<K>>> = Rc::new(RefCell::new(handler));
+        let commands = entries
+            .iter()
+            .map(|&(name, description, id)| {
+                let entry = Entry {
+                    id,
+                    handler: Rc::clone(&handler),
+                };
+                OwnedCommand::new(name, description, entry).map(|command| (id, command))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(CommandTable { commands })
+    }
+
+    /// Returns the command with an identifier, if the table contains it
+    pub fn get(&self, id: K) -> Option<&OwnedCommand>
+    where
+        K: PartialEq,
+    {
+        self.commands
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, command)| command)
+    }
+
+    /// Returns an iterator over the identifiers and commands in this table, in the order that
+    /// they were provided
+    pub fn iter(&self) -> impl Iterator<Item = (K, &OwnedCommand)> {
+        self.commands.iter().map(|(id, command)| (*id, command))
+    }
+
+    /// Returns the number of commands in this table
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns true if this table contains no commands
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for CommandTable<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.commands
+                    .iter()
+                    .map(|(id, command)| (id, command.name())),
+            )
+            .finish()
+    }
+}
+
+/// The handler for one command in a table
+struct Entry<K> {
+    /// The identifier passed to the table handler
+    id: K,
+    /// The table handler
+    handler: Rc<RefCell<dyn TableHandler<K>>>,
+}
+
+impl<K: Copy + 'static> CommandHandler for Entry<K> {
+    fn command_begin(&mut self) {
+        if let Ok(mut handler) = self.handler.try_borrow_mut() {
+            handler.command_begin(self.id);
+        }
+    }
+    fn command_continue(&mut self) {
+        if let Ok(mut handler) = self.handler.try_borrow_mut() {
+            handler.command_continue(self.id);
+        }
+    }
+    fn command_end(&mut self) {
+        if let Ok(mut handler) = self.handler.try_borrow_mut() {
+            handler.command_end(self.id);
+        }
+    }
+}
@@ -1,12 +1,40 @@
 use crate::ffi::StringBuffer;
-use std::ffi::{CString, NulError};
+use crate::optional::{self, GetDataRefInfoFn, RawDataRefInfo};
+use crate::plugin::messages::PluginId;
+use std::ffi::{CStr, CString, NulError};
+use std::fmt;
+use std::mem;
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+use std::os::raw::c_int;
 use std::string::FromUtf8Error;
+use std::vec;
 use xplm_sys::*;
 
+use self::borrowed::FindError;
+use self::dynamic::{DynDataRef, DynType};
+
+/// Batched dataref writes
+pub mod batch;
 /// Datarefs created by X-Plane or other plugins
 pub mod borrowed;
+/// Bulk reading of many datarefs
+pub mod cache;
+/// Datarefs with types chosen at runtime
+pub mod dynamic;
+/// Datarefs that are found when they are first used
+pub mod lazy;
+/// Matrix and quaternion views of float array datarefs
+pub mod matrix;
+/// Structs with fields mapped to datarefs
+pub mod mirror;
 /// Datarefs created by this plugin
 pub mod owned;
+/// Data shared between plugins
+pub mod shared;
+/// Rate-limited dataref subscriptions
+pub mod subscription;
+/// Dataref change notifications
+pub mod watch;
 
 /// Marks a dataref as readable
 pub enum ReadOnly {}
@@ -62,6 +90,11 @@ pub trait ArrayRead<T: ArrayType + ?Sized> {
     /// Returns the length of the data array
     fn len(&self) -> usize;
 
+    /// Returns true if the data array has no elements
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns all values in this accessor as a Vec
     fn as_vec(&self) -> Vec<T::Element>
     where
@@ -71,6 +104,32 @@ pub trait ArrayRead<T: ArrayType + ?Sized> {
         self.get(&mut values);
         values
     }
+
+    /// Reads values starting at an offset into the array
+    ///
+    /// Values are stored in the provided slice, like [`get`](ArrayRead::get). This function
+    /// returns the number of values that were read, which is zero if offset is not less than
+    /// the length of the array.
+    fn get_range(&self, offset: usize, dest: &mut [T::Element]) -> usize
+    where
+        T::Element: Default + Clone,
+    {
+        read_range(&self.as_vec(), offset, dest)
+    }
+
+    /// Returns the value at an index, or None if the index is out of bounds
+    fn get_element(&self, index: usize) -> Option<T::Element>
+    where
+        T::Element: Default + Clone,
+    {
+        let mut value = [T::Element::default()];
+        if self.get_range(index, &mut value) == 1 {
+            let [value] = value;
+            Some(value)
+        } else {
+            None
+        }
+    }
 }
 
 /// Trait for array accessors that can be read and written
@@ -83,6 +142,148 @@ pub trait ArrayReadWrite<T: ArrayType + ?Sized>: ArrayRead<T> {
     /// If the dataref is smaller than the provided slice, the values beyond the dataref bounds
     /// will be ignored.
     fn set(&mut self, values: &[T::Element]);
+
+    /// Writes values starting at an offset into the array
+    ///
+    /// Values beyond the end of the array are ignored. If offset is not less than the length
+    /// of the array, nothing is written.
+    fn set_range(&mut self, offset: usize, values: &[T::Element])
+    where
+        T::Element: Default + Clone,
+    {
+        let mut all = self.as_vec();
+        write_range(&mut all, offset, values);
+        self.set(&all);
+    }
+
+    /// Writes the value at an index
+    ///
+    /// If the index is out of bounds, nothing is written.
+    fn set_element(&mut self, index: usize, value: T::Element)
+    where
+        T::Element: Default + Clone,
+    {
+        self.set_range(index, &[value]);
+    }
+}
+
+/// Returns an iterator over all datarefs that X-Plane and plugins have registered
+///
+/// This uses SDK functions that are available in X-Plane 12 and later. It returns None in
+/// older versions.
+///
+/// The datarefs are the ones that existed when this function was called. Datarefs that
+/// plugins create later are not included.
+///
+/// # Examples
+///
+/// ```no_run
+/// if let Some(datarefs) = xplm::data::iter_all_datarefs() {
+///     for info in datarefs.filter(|info| info.writable) {
+///         xplm::debugln!("{} {:?}", info.name, info.types);
+///     }
+/// }
+/// ```
+pub fn iter_all_datarefs() -> Option<AllDataRefs> {
+    crate::debug_assert_main_thread!();
+    let count_data_refs = optional::count_data_refs()?;
+    let get_data_refs_by_index = optional::get_data_refs_by_index()?;
+    let get_data_ref_info = optional::get_data_ref_info()?;
+    let count = unsafe { count_data_refs() };
+    let mut ids = vec![std::ptr::null_mut(); usize::try_from(count).unwrap_or(0)];
+    unsafe { get_data_refs_by_index(0, ids.len() as c_int, ids.as_mut_ptr()) };
+    Some(AllDataRefs {
+        ids: ids.into_iter(),
+        get_data_ref_info,
+    })
+}
+
+/// An iterator over all registered datarefs, returned by [`iter_all_datarefs`]
+#[derive(Debug)]
+pub struct AllDataRefs {
+    /// The handles that have not been returned yet
+    ids: vec::IntoIter<XPLMDataRef>,
+    /// XPLMGetDataRefInfo
+    get_data_ref_info: GetDataRefInfoFn,
+}
+
+impl Iterator for AllDataRefs {
+    type Item = DataRefInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for id in self.ids.by_ref() {
+            if id.is_null() {
+                continue;
+            }
+            let mut raw = RawDataRefInfo {
+                struct_size: mem::size_of::<RawDataRefInfo>() as c_int,
+                name: std::ptr::null(),
+                data_type: 0,
+                writable: 0,
+                owner: 0,
+            };
+            unsafe { (self.get_data_ref_info)(id, &mut raw) };
+            if raw.name.is_null() {
+                continue;
+            }
+            let name = unsafe { CStr::from_ptr(raw.name) };
+            return Some(DataRefInfo {
+                name: name.to_string_lossy().into_owned(),
+                types: DynType::all(raw.data_type).collect(),
+                writable: raw.writable != 0,
+                owner: PluginId::from_raw(raw.owner),
+            });
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.ids.size_hint().1)
+    }
+}
+
+/// Information about a registered dataref
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DataRefInfo {
+    /// The name of the dataref
+    pub name: String,
+    /// The types that the dataref supports, best first
+    ///
+    /// Scalar types are listed before arrays, and more precise types are listed before less
+    /// precise types.
+    pub types: Vec<DynType>,
+    /// If the dataref can be written
+    pub writable: bool,
+    /// The plugin that created the dataref
+    pub owner: PluginId,
+}
+
+impl DataRefInfo {
+    /// Finds this dataref so that it can be read and written
+    pub fn find(&self) -> Result<DynDataRef, FindError> {
+        DynDataRef::find_auto(&self.name)
+    }
+}
+
+/// Copies values from source, starting at offset, into dest and returns the number of values
+/// copied
+fn read_range<T: Clone>(source: &[T], offset: usize, dest: &mut [T]) -> usize {
+    let Some(source) = source.get(offset..) else {
+        return 0;
+    };
+    let copy_length = source.len().min(dest.len());
+    dest[..copy_length].clone_from_slice(&source[..copy_length]);
+    copy_length
+}
+
+/// Copies values into dest, starting at offset, ignoring values that do not fit
+fn write_range<T: Clone>(dest: &mut [T], offset: usize, values: &[T]) {
+    let Some(dest) = dest.get_mut(offset..) else {
+        return;
+    };
+    let copy_length = dest.len().min(values.len());
+    dest[..copy_length].clone_from_slice(&values[..copy_length]);
 }
 
 /// Trait for data accessors that can be read as strings
@@ -197,3 +398,154 @@ impl_type!([u32]: array as xplmType_IntArray);
 impl_type!([f32]: array as xplmType_FloatArray);
 impl_type!([u8]: array as xplmType_Data);
 impl_type!([i8]: array as xplmType_Data);
+
+/// A set of X-Plane data types that a dataref supports
+///
+/// Sets can be combined with `|` and intersected with `&`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DataTypes(XPLMDataTypeID);
+
+impl DataTypes {
+    /// A 32-bit integer
+    pub const INT: DataTypes = DataTypes(xplmType_Int as XPLMDataTypeID);
+    /// A 32-bit float
+    pub const FLOAT: DataTypes = DataTypes(xplmType_Float as XPLMDataTypeID);
+    /// A 64-bit float
+    pub const DOUBLE: DataTypes = DataTypes(xplmType_Double as XPLMDataTypeID);
+    /// An array of 32-bit floats
+    pub const FLOAT_ARRAY: DataTypes = DataTypes(xplmType_FloatArray as XPLMDataTypeID);
+    /// An array of 32-bit integers
+    pub const INT_ARRAY: DataTypes = DataTypes(xplmType_IntArray as XPLMDataTypeID);
+    /// An array of bytes
+    pub const DATA: DataTypes = DataTypes(xplmType_Data as XPLMDataTypeID);
+
+    /// The names and values of the known types
+    const NAMES: [(&'static str, DataTypes); 6] = [
+        ("INT", DataTypes::INT),
+        ("FLOAT", DataTypes::FLOAT),
+        ("DOUBLE", DataTypes::DOUBLE),
+        ("FLOAT_ARRAY", DataTypes::FLOAT_ARRAY),
+        ("INT_ARRAY", DataTypes::INT_ARRAY),
+        ("DATA", DataTypes::DATA),
+    ];
+
+    /// Returns an empty set
+    pub const fn empty() -> Self {
+        DataTypes(0)
+    }
+
+    /// Creates a set from the value that X-Plane uses
+    ///
+    /// Unknown types are kept, so that [`bits`](DataTypes::bits) returns the same value.
+    pub const fn from_bits(bits: XPLMDataTypeID) -> Self {
+        DataTypes(bits)
+    }
+
+    /// Returns the value that X-Plane uses
+    pub const fn bits(self) -> XPLMDataTypeID {
+        self.0
+    }
+
+    /// Returns true if this set contains no types
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if this set contains all the types in other
+    pub const fn contains(self, other: DataTypes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if this set contains any of the types in other
+    pub const fn intersects(self, other: DataTypes) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for DataTypes {
+    type Output = DataTypes;
+    fn bitor(self, other: DataTypes) -> DataTypes {
+        DataTypes(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for DataTypes {
+    fn bitor_assign(&mut self, other: DataTypes) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitAnd for DataTypes {
+    type Output = DataTypes;
+    fn bitand(self, other: DataTypes) -> DataTypes {
+        DataTypes(self.0 & other.0)
+    }
+}
+
+/// Formats the set as type names separated by `|`, such as `INT | FLOAT`
+impl fmt::Debug for DataTypes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut remaining = self.0;
+        let mut first = true;
+        let mut separator = |f: &mut fmt::Formatter| {
+            if !mem::take(&mut first) {
+                f.write_str(" | ")?;
+            }
+            Ok(())
+        };
+        for (name, types) in DataTypes::NAMES {
+            if self.contains(types) {
+                separator(f)?;
+                f.write_str(name)?;
+                remaining &= !types.0;
+            }
+        }
+        if remaining != 0 {
+            separator(f)?;
+            write!(f, "{:#x}", remaining)?;
+        }
+        if self.is_empty() {
+            f.write_str("(empty)")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_range, write_range, DataTypes};
+
+    #[test]
+    fn test_data_types() {
+        let types = DataTypes::INT | DataTypes::FLOAT;
+        assert!(types.contains(DataTypes::INT));
+        assert!(!types.contains(DataTypes::INT | DataTypes::DOUBLE));
+        assert!(types.intersects(DataTypes::INT | DataTypes::DOUBLE));
+        assert_eq!(types & DataTypes::FLOAT, DataTypes::FLOAT);
+        assert!((types & DataTypes::DATA).is_empty());
+        assert_eq!(format!("{:?}", types), "INT | FLOAT");
+        assert_eq!(format!("{:?}", DataTypes::empty()), "(empty)");
+        assert_eq!(
+            format!("{:?}", DataTypes::from_bits(0x100) | DataTypes::DATA),
+            "DATA | 0x100"
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let source = [1, 2, 3, 4];
+        let mut dest = [0; 3];
+        assert_eq!(read_range(&source, 2, &mut dest), 2);
+        assert_eq!(dest, [3, 4, 0]);
+        assert_eq!(read_range(&source, 4, &mut dest), 0);
+        assert_eq!(read_range(&source, 9, &mut dest), 0);
+
+        let mut storage = [0; 4];
+        write_range(&mut storage, 1, &[7, 8]);
+        assert_eq!(storage, [0, 7, 8, 0]);
+        write_range(&mut storage, 3, &[5, 6, 7]);
+        assert_eq!(storage, [0, 7, 8, 5]);
+        write_range(&mut storage, 4, &[1]);
+        assert_eq!(storage, [0, 7, 8, 5]);
+    }
+}
@@ -0,0 +1,324 @@
+//! # Batched dataref writes
+//!
+//! Code that updates many datarefs from different places, such as network handlers and
+//! flight loop callbacks, can write the same dataref several times per frame. A
+//! [`WriteBatch`] collects pending values and writes each dataref at most once per frame, in
+//! the order the datarefs were added to the batch.
+//!
+//! A [`WritePolicy`] can clamp the values written to a dataref or limit how often it is
+//! written.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use xplm::data::batch::{WriteBatch, WritePolicy};
+//! use xplm::data::borrowed::DataRef;
+//!
+//! let batch = WriteBatch::new();
+//! let throttle = batch.add_array(
+//!     DataRef::find("sim/cockpit2/engine/actuators/throttle_ratio")
+//!         .unwrap()
+//!         .writeable()
+//!         .unwrap(),
+//!     WritePolicy::new().clamp(0.0, 1.0),
+//! );
+//! let heading = batch.add(
+//!     DataRef::find("sim/cockpit/autopilot/heading_mag")
+//!         .unwrap()
+//!         .writeable()
+//!         .unwrap(),
+//!     WritePolicy::new().min_interval(Duration::from_millis(100)),
+//! );
+//! // Only the last value is written when the batch is flushed
+//! throttle.set(vec![0.5, 0.5]);
+//! throttle.set(vec![0.8, 1.2]);
+//! heading.set(270.0f32);
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::super::flight_loop::{FlightLoop, LoopState};
+use super::{ArrayReadWrite, ArrayType, DataReadWrite};
+
+/// Collects dataref writes and performs them once per frame
+///
+/// The batch is flushed in a flight loop callback that runs every frame. Values that have not
+/// been written when this object is dropped are discarded.
+pub struct WriteBatch {
+    /// The datarefs, shared with the flight loop
+    targets: Rc<RefCell<Vec<Box<dyn Flush>>>>,
+    /// The flight loop that flushes the batch
+    _flight_loop: FlightLoop,
+}
+
+impl WriteBatch {
+    /// Creates an empty batch and starts its flight loop
+    pub fn new() -> Self {
+        let targets: Rc<RefCell<Vec<Box<dyn Flush>>>> = Rc::new(RefCell::new(Vec::new()));
+        let loop_targets = Rc::clone(&targets);
+        let mut flight_loop = FlightLoop::new(move |_: &mut LoopState| flush_all(&loop_targets));
+        flight_loop.schedule_immediate();
+        WriteBatch {
+            targets,
+            _flight_loop: flight_loop,
+        }
+    }
+
+    /// Adds a dataref to this batch and returns a writer that sets its pending value
+    pub fn add<T, D>(&self, dataref: D, policy: WritePolicy<T>) -> BatchWriter<T>
+    where
+        T: Copy + PartialOrd + 'static,
+        D: DataReadWrite<T> + 'static,
+    {
+        let writer = BatchWriter::new();
+        self.targets.borrow_mut().push(Box::new(ScalarTarget {
+            dataref,
+            pending: Rc::clone(&writer.pending),
+            policy,
+            last_write: None,
+        }));
+        writer
+    }
+
+    /// Adds an array dataref to this batch and returns a writer that sets its pending values
+    ///
+    /// The policy is applied to each element.
+    pub fn add_array<T, D>(&self, dataref: D, policy: WritePolicy<T>) -> BatchWriter<Vec<T>>
+    where
+        T: Copy + PartialOrd + 'static,
+        [T]: ArrayType<Element = T>,
+        D: ArrayReadWrite<[T]> + 'static,
+    {
+        let writer = BatchWriter::new();
+        self.targets.borrow_mut().push(Box::new(ArrayTarget {
+            dataref,
+            pending: Rc::clone(&writer.pending),
+            policy,
+            last_write: None,
+        }));
+        writer
+    }
+
+    /// Writes all pending values now, instead of waiting for the flight loop
+    ///
+    /// This can be used to write the values at a specific point in another flight loop
+    /// callback.
+    pub fn flush(&self) {
+        flush_all(&self.targets);
+    }
+}
+
+impl Default for WriteBatch {
+    fn default() -> Self {
+        WriteBatch::new()
+    }
+}
+
+impl fmt::Debug for WriteBatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WriteBatch")
+            .field("datarefs", &self.targets.borrow().len())
+            .finish()
+    }
+}
+
+/// Writes all pending values in order
+fn flush_all(targets: &RefCell<Vec<Box<dyn Flush>>>) {
+    let now = Instant::now();
+    for target in targets.borrow_mut().iter_mut() {
+        target.flush(now);
+    }
+}
+
+/// Sets the pending value of a dataref in a batch
+///
+/// Writers can be cloned and moved into callbacks. Setting a value after the batch has been
+/// dropped has no effect.
+pub struct BatchWriter<V> {
+    pending: Rc<RefCell<Option<V>>>,
+}
+
+impl<V> BatchWriter<V> {
+    fn new() -> Self {
+        BatchWriter {
+            pending: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Sets the value that will be written when the batch is flushed
+    ///
+    /// This replaces any value that has not been written yet.
+    pub fn set(&self, value: V) {
+        *self.pending.borrow_mut() = Some(value);
+    }
+
+    /// Discards the pending value, if any
+    pub fn clear(&self) {
+        self.pending.borrow_mut().take();
+    }
+
+    /// Returns true if a value is waiting to be written
+    pub fn is_pending(&self) -> bool {
+        self.pending.borrow().is_some()
+    }
+}
+
+impl<V> Clone for BatchWriter<V> {
+    fn clone(&self) -> Self {
+        BatchWriter {
+            pending: Rc::clone(&self.pending),
+        }
+    }
+}
+
+impl<V> fmt::Debug for BatchWriter<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BatchWriter")
+            .field("pending", &self.is_pending())
+            .finish()
+    }
+}
+
+/// Limits on the values written to a dataref and how often it is written
+#[derive(Debug, Clone)]
+pub struct WritePolicy<T> {
+    /// The minimum and maximum values
+    range: Option<(T, T)>,
+    /// The minimum time between writes
+    min_interval: Option<Duration>,
+}
+
+impl<T: Copy + PartialOrd> WritePolicy<T> {
+    /// Creates a policy that writes every pending value unchanged
+    pub fn new() -> Self {
+        WritePolicy {
+            range: None,
+            min_interval: None,
+        }
+    }
+
+    /// Limits written values to the range from min to max, inclusive
+    pub fn clamp(self, min: T, max: T) -> Self {
+        WritePolicy {
+            range: Some((min, max)),
+            ..self
+        }
+    }
+
+    /// Sets the minimum time between writes
+    ///
+    /// If a value is set before this time has passed, it stays pending and is written when
+    /// the time has passed. Only the latest value is written.
+    pub fn min_interval(self, interval: Duration) -> Self {
+        WritePolicy {
+            min_interval: Some(interval),
+            ..self
+        }
+    }
+
+    /// Applies the range to a value
+    fn limit(&self, value: T) -> T {
+        match self.range {
+            Some((min, _)) if value < min => min,
+            Some((_, max)) if value > max => max,
+            _ => value,
+        }
+    }
+
+    /// Returns true if a value can be written now, given the time of the last write
+    fn ready(&self, last_write: Option<Instant>, now: Instant) -> bool {
+        match (self.min_interval, last_write) {
+            (Some(interval), Some(last_write)) => now.duration_since(last_write) >= interval,
+            _ => true,
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd> Default for WritePolicy<T> {
+    fn default() -> Self {
+        WritePolicy::new()
+    }
+}
+
+/// A dataref in a batch, with its type hidden
+trait Flush {
+    /// Writes the pending value if there is one and the policy allows it
+    fn flush(&mut self, now: Instant);
+}
+
+struct ScalarTarget<T, D> {
+    dataref: D,
+    pending: Rc<RefCell<Option<T>>>,
+    policy: WritePolicy<T>,
+    last_write: Option<Instant>,
+}
+
+impl<T, D> Flush for ScalarTarget<T, D>
+where
+    T: Copy + PartialOrd,
+    D: DataReadWrite<T>,
+{
+    fn flush(&mut self, now: Instant) {
+        if !self.policy.ready(self.last_write, now) {
+            return;
+        }
+        if let Some(value) = self.pending.borrow_mut().take() {
+            self.dataref.set(self.policy.limit(value));
+            self.last_write = Some(now);
+        }
+    }
+}
+
+struct ArrayTarget<T, D> {
+    dataref: D,
+    pending: Rc<RefCell<Option<Vec<T>>>>,
+    policy: WritePolicy<T>,
+    last_write: Option<Instant>,
+}
+
+impl<T, D> Flush for ArrayTarget<T, D>
+where
+    T: Copy + PartialOrd,
+    [T]: ArrayType<Element = T>,
+    D: ArrayReadWrite<[T]>,
+{
+    fn flush(&mut self, now: Instant) {
+        if !self.policy.ready(self.last_write, now) {
+            return;
+        }
+        if let Some(mut values) = self.pending.borrow_mut().take() {
+            for value in values.iter_mut() {
+                *value = self.policy.limit(*value);
+            }
+            self.dataref.set(&values);
+            self.last_write = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WritePolicy;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_policy() {
+        let policy = WritePolicy::new()
+            .clamp(-1.0f32, 1.0)
+            .min_interval(Duration::from_millis(100));
+        assert_eq!(policy.limit(-3.0), -1.0);
+        assert_eq!(policy.limit(0.25), 0.25);
+        assert_eq!(policy.limit(2.0), 1.0);
+
+        let start = Instant::now();
+        assert!(policy.ready(None, start));
+        assert!(!policy.ready(Some(start), start + Duration::from_millis(50)));
+        assert!(policy.ready(Some(start), start + Duration::from_millis(100)));
+        assert!(WritePolicy::<i32>::new().ready(Some(start), start));
+    }
+}
@@ -1,4 +1,7 @@
-use super::{ArrayRead, ArrayReadWrite, DataRead, DataReadWrite, DataType, ReadOnly, ReadWrite};
+use super::lazy::LazyDataRef;
+use super::{
+    ArrayRead, ArrayReadWrite, DataRead, DataReadWrite, DataType, DataTypes, ReadOnly, ReadWrite,
+};
 use std::ffi::{CString, NulError};
 use std::marker::PhantomData;
 use std::os::raw::c_void;
@@ -24,8 +27,13 @@ impl<T: DataType + ?Sized> DataRef<T, ReadOnly> {
     ///
     /// Returns an error if the dataref does not exist or has the wrong type
     pub fn find(name: &str) -> Result<Self, FindError> {
+        crate::debug_assert_main_thread!();
         let name_c = CString::new(name)?;
         let expected_type = T::sim_type();
+        #[cfg(feature = "fault-injection")]
+        if crate::fault::triggered(crate::fault::Fault::DataRefNotFound, Some(name)) {
+            return Err(FindError::NotFound);
+        }
 
         let dataref = unsafe { XPLMFindDataRef(name_c.as_ptr()) };
         if dataref.is_null() {
@@ -44,6 +52,14 @@ impl<T: DataType + ?Sized> DataRef<T, ReadOnly> {
         }
     }
 
+    /// Creates a handle that finds a readable dataref by its name when it is first used
+    ///
+    /// This can be used for datarefs that other plugins create after this plugin starts.
+    /// See the [`lazy`](super::lazy) module for details.
+    pub fn find_lazy(name: &str) -> Result<LazyDataRef<T>, NulError> {
+        LazyDataRef::new(name)
+    }
+
     /// Makes this dataref writable
     ///
     /// Returns an error if the dataref cannot be written.
@@ -61,6 +77,48 @@ impl<T: DataType + ?Sized> DataRef<T, ReadOnly> {
     }
 }
 
+impl<T: ?Sized, A> DataRef<T, A> {
+    /// Creates a dataref from a handle that has already been checked
+    pub(crate) fn from_id(id: XPLMDataRef) -> Self {
+        DataRef {
+            id,
+            type_phantom: PhantomData,
+            access_phantom: PhantomData,
+        }
+    }
+
+    /// Returns the dataref handle
+    pub(crate) fn id(&self) -> XPLMDataRef {
+        self.id
+    }
+
+    /// Returns true if this dataref can still be used
+    ///
+    /// A dataref that another plugin created becomes invalid when that plugin removes it, for
+    /// example when the plugin is disabled. Reading an invalid dataref returns zero and
+    /// writing it has no effect.
+    pub fn is_good(&self) -> bool {
+        crate::debug_assert_main_thread!();
+        unsafe { XPLMIsDataRefGood(self.id) == 1 }
+    }
+
+    /// Returns all the types that this dataref supports
+    ///
+    /// This can include types other than T.
+    pub fn types(&self) -> DataTypes {
+        crate::debug_assert_main_thread!();
+        DataTypes::from_bits(unsafe { XPLMGetDataRefTypes(self.id) })
+    }
+
+    /// Returns true if this dataref can be written
+    ///
+    /// Unlike [`writeable`](DataRef::writeable), this does not consume the dataref.
+    pub fn is_writeable(&self) -> bool {
+        crate::debug_assert_main_thread!();
+        unsafe { XPLMCanWriteDataRef(self.id) == 1 }
+    }
+}
+
 /// Creates a DataType implementation, DataRef::get() and DataRef::set() for a type
 macro_rules! dataref_type {
     // Basic case
@@ -75,11 +133,13 @@ macro_rules! dataref_type {
     ) => {
         impl<A> DataRead<$native_type> for DataRef<$native_type, A> {
             fn get(&self) -> $native_type {
+                crate::debug_assert_main_thread!();
                 unsafe { $read_fn(self.id) as $native_type }
             }
         }
         impl DataReadWrite<$native_type> for DataRef<$native_type, ReadWrite> {
             fn set(&mut self, value: $native_type) {
+                crate::debug_assert_main_thread!();
                 unsafe { $write_fn(self.id, value as $sim_native_type) }
             }
         }
@@ -99,26 +159,59 @@ macro_rules! dataref_type {
         impl<A> ArrayRead<[$native_type]> for DataRef<[$native_type], A> {
             #[allow(trivial_casts)]
             fn get(&self, dest: &mut [$native_type]) -> usize {
+                crate::debug_assert_main_thread!();
                 let size = array_size(dest.len());
                 let copy_count = unsafe {
                     $read_fn(self.id, dest.as_mut_ptr() as *mut $sim_native_type, 0, size)
                 };
-                copy_count as usize
+                copy_count.max(0) as usize
             }
             fn len(&self) -> usize {
                 let size = unsafe { $read_fn(self.id, ptr::null_mut(), 0, 0) };
-                size as usize
+                size.max(0) as usize
+            }
+            #[allow(trivial_casts)]
+            fn get_range(&self, offset: usize, dest: &mut [$native_type]) -> usize {
+                let Ok(offset) = i32::try_from(offset) else {
+                    return 0;
+                };
+                let size = array_size(dest.len());
+                let copy_count = unsafe {
+                    $read_fn(
+                        self.id,
+                        dest.as_mut_ptr() as *mut $sim_native_type,
+                        offset,
+                        size,
+                    )
+                };
+                copy_count.max(0) as usize
             }
         }
 
         impl ArrayReadWrite<[$native_type]> for DataRef<[$native_type], ReadWrite> {
             fn set(&mut self, values: &[$native_type]) {
+                crate::debug_assert_main_thread!();
                 let size = array_size(values.len());
                 unsafe {
                     // Cast to *mut because the API requires it
                     $write_fn(self.id, values.as_ptr() as *mut $sim_native_type, 0, size);
                 }
             }
+            fn set_range(&mut self, offset: usize, values: &[$native_type]) {
+                let Ok(offset) = i32::try_from(offset) else {
+                    return;
+                };
+                let size = array_size(values.len());
+                unsafe {
+                    // Cast to *mut because the API requires it
+                    $write_fn(
+                        self.id,
+                        values.as_ptr() as *mut $sim_native_type,
+                        offset,
+                        size,
+                    );
+                }
+            }
         }
     };
 }
@@ -252,6 +345,7 @@ fn array_size(size: usize) -> i32 {
 
 /// Errors that can occur when finding DataRefs
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum FindError {
     /// The provided DataRef name contained a null byte
     #[error("Null byte in DataRef name")]
@@ -0,0 +1,405 @@
+//! # Dataref cache
+//!
+//! Instrument panels and similar plugins may read more than a hundred datarefs every frame.
+//! A [`DataCache`] holds the handles of many datarefs, grouped by type, and reads all of them
+//! in one [`refresh`](DataCache::refresh) call. Other code then reads the cached values
+//! without calling X-Plane.
+//!
+//! Datarefs that change slowly, such as the aircraft tail number or the number of engines,
+//! can be added as [`Temperature::Cold`]. Cold datarefs are only read every few refreshes.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::cache::{DataCache, Temperature};
+//!
+//! let mut cache = DataCache::new();
+//! let airspeed = cache
+//!     .find::<f32>("sim/flightmodel/position/indicated_airspeed", Temperature::Hot)
+//!     .unwrap();
+//! let engines = cache
+//!     .find::<i32>("sim/aircraft/engine/acf_num_engines", Temperature::Cold)
+//!     .unwrap();
+//! let n1 = cache
+//!     .find::<[f32]>("sim/flightmodel/engine/ENGN_N1_", Temperature::Hot)
+//!     .unwrap();
+//!
+//! // In a flight loop callback
+//! cache.refresh();
+//! let airspeed: f32 = *cache.get(airspeed);
+//! let n1: &[f32] = cache.get(n1);
+//! ```
+//!
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::os::raw::c_int;
+use std::ptr;
+
+use xplm_sys::*;
+
+use super::borrowed::{DataRef, FindError};
+use super::DataType;
+
+/// The default number of refreshes between reads of cold datarefs
+pub const DEFAULT_COLD_INTERVAL: u32 = 30;
+
+/// How often a cached dataref is read
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Temperature {
+    /// The dataref is read on every refresh
+    Hot,
+    /// The dataref is read every few refreshes, as set by
+    /// [`set_cold_interval`](DataCache::set_cold_interval)
+    Cold,
+}
+
+/// Reads many datarefs at once and stores their values
+#[derive(Debug)]
+pub struct DataCache {
+    floats: [Scalars<f32>; 2],
+    doubles: [Scalars<f64>; 2],
+    ints: [Scalars<i32>; 2],
+    float_arrays: [Arrays<f32>; 2],
+    int_arrays: [Arrays<i32>; 2],
+    /// The number of refreshes between reads of cold datarefs
+    cold_interval: u32,
+    /// The number of refreshes since cold datarefs were last read
+    since_cold: u32,
+}
+
+impl DataCache {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        DataCache {
+            floats: Default::default(),
+            doubles: Default::default(),
+            ints: Default::default(),
+            float_arrays: Default::default(),
+            int_arrays: Default::default(),
+            cold_interval: DEFAULT_COLD_INTERVAL,
+            since_cold: 0,
+        }
+    }
+
+    /// Sets the number of refreshes between reads of cold datarefs
+    ///
+    /// An interval of 0 or 1 reads cold datarefs on every refresh.
+    pub fn set_cold_interval(&mut self, interval: u32) {
+        self.cold_interval = interval;
+    }
+
+    /// Adds a dataref to this cache and reads its current value
+    pub fn add<T, A>(&mut self, dataref: DataRef<T, A>, temperature: Temperature) -> CacheKey<T>
+    where
+        T: CacheValue + ?Sized,
+    {
+        let group = temperature as usize;
+        let index = T::add(self, group, dataref.id());
+        CacheKey {
+            group,
+            index,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Finds a dataref, adds it to this cache, and reads its current value
+    pub fn find<T>(
+        &mut self,
+        name: &str,
+        temperature: Temperature,
+    ) -> Result<CacheKey<T>, FindError>
+    where
+        T: CacheValue + DataType + ?Sized,
+    {
+        Ok(self.add(DataRef::<T>::find(name)?, temperature))
+    }
+
+    /// Reads all hot datarefs, and cold datarefs if the cold interval has passed
+    pub fn refresh(&mut self) {
+        let read_cold = self.cold_due();
+        self.refresh_group(Temperature::Hot as usize);
+        if read_cold {
+            self.refresh_group(Temperature::Cold as usize);
+        }
+    }
+
+    /// Counts a refresh and returns true if cold datarefs should be read in it
+    fn cold_due(&mut self) -> bool {
+        self.since_cold += 1;
+        let read_cold = self.since_cold >= self.cold_interval;
+        if read_cold {
+            self.since_cold = 0;
+        }
+        read_cold
+    }
+
+    /// Reads all datarefs, including cold datarefs
+    pub fn refresh_all(&mut self) {
+        self.since_cold = 0;
+        self.refresh_group(Temperature::Hot as usize);
+        self.refresh_group(Temperature::Cold as usize);
+    }
+
+    fn refresh_group(&mut self, group: usize) {
+        self.floats[group].refresh(|id| unsafe { XPLMGetDataf(id) });
+        self.doubles[group].refresh(|id| unsafe { XPLMGetDatad(id) });
+        self.ints[group].refresh(|id| unsafe { XPLMGetDatai(id) });
+        self.float_arrays[group]
+            .refresh(|id, values, max| unsafe { XPLMGetDatavf(id, values, 0, max) });
+        self.int_arrays[group]
+            .refresh(|id, values, max| unsafe { XPLMGetDatavi(id, values, 0, max) });
+    }
+
+    /// Returns the cached value of a dataref
+    ///
+    /// The key must have been returned by this cache. Otherwise, this function may panic or
+    /// return a different value.
+    pub fn get<T: CacheValue + ?Sized>(&self, key: CacheKey<T>) -> &T {
+        T::get(self, key.group, key.index)
+    }
+
+    /// Returns the number of datarefs in this cache
+    pub fn len(&self) -> usize {
+        (0..2)
+            .map(|group| {
+                self.floats[group].ids.len()
+                    + self.doubles[group].ids.len()
+                    + self.ints[group].ids.len()
+                    + self.float_arrays[group].ids.len()
+                    + self.int_arrays[group].ids.len()
+            })
+            .sum()
+    }
+
+    /// Returns true if this cache has no datarefs
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for DataCache {
+    fn default() -> Self {
+        DataCache::new()
+    }
+}
+
+/// Identifies a dataref in a cache
+pub struct CacheKey<T: ?Sized> {
+    /// The temperature of the dataref, as an index
+    group: usize,
+    /// The index of the dataref in the list for its type and temperature
+    index: usize,
+    phantom: PhantomData<fn() -> *const T>,
+}
+
+impl<T: ?Sized> Clone for CacheKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized> Copy for CacheKey<T> {}
+
+impl<T: ?Sized> fmt::Debug for CacheKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CacheKey")
+            .field("group", &self.group)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+/// Single-value datarefs of one type and temperature
+#[derive(Debug)]
+struct Scalars<T> {
+    ids: Vec<XPLMDataRef>,
+    values: Vec<T>,
+}
+
+impl<T> Default for Scalars<T> {
+    fn default() -> Self {
+        Scalars {
+            ids: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T> Scalars<T> {
+    fn add<F: Fn(XPLMDataRef) -> T>(&mut self, id: XPLMDataRef, read: F) -> usize {
+        self.ids.push(id);
+        self.values.push(read(id));
+        self.ids.len() - 1
+    }
+
+    fn refresh<F: Fn(XPLMDataRef) -> T>(&mut self, read: F) {
+        for (id, value) in self.ids.iter().zip(self.values.iter_mut()) {
+            *value = read(*id);
+        }
+    }
+}
+
+/// Array datarefs of one type and temperature
+#[derive(Debug)]
+struct Arrays<T> {
+    ids: Vec<XPLMDataRef>,
+    values: Vec<Vec<T>>,
+}
+
+impl<T> Default for Arrays<T> {
+    fn default() -> Self {
+        Arrays {
+            ids: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T: Default + Clone> Arrays<T> {
+    fn add<F>(&mut self, id: XPLMDataRef, read: F) -> usize
+    where
+        F: Fn(XPLMDataRef, *mut T, c_int) -> c_int,
+    {
+        self.ids.push(id);
+        let mut values = Vec::new();
+        read_array(id, &mut values, &read);
+        self.values.push(values);
+        self.ids.len() - 1
+    }
+
+    fn refresh<F>(&mut self, read: F)
+    where
+        F: Fn(XPLMDataRef, *mut T, c_int) -> c_int,
+    {
+        for (id, values) in self.ids.iter().zip(self.values.iter_mut()) {
+            read_array(*id, values, &read);
+        }
+    }
+}
+
+/// Reads an array dataref into a Vec, resizing it if the length has changed
+fn read_array<T, F>(id: XPLMDataRef, values: &mut Vec<T>, read: &F)
+where
+    T: Default + Clone,
+    F: Fn(XPLMDataRef, *mut T, c_int) -> c_int,
+{
+    let length = read(id, ptr::null_mut(), 0).max(0);
+    values.resize(length as usize, T::default());
+    read(id, values.as_mut_ptr(), length);
+}
+
+/// Types that can be stored in a cache
+///
+/// This trait is implemented for f32, f64, i32, `[f32]`, and `[i32]`. It cannot be
+/// implemented outside this crate.
+pub trait CacheValue: private::Sealed {
+    /// Adds a dataref and returns its index
+    #[doc(hidden)]
+    fn add(cache: &mut DataCache, group: usize, id: XPLMDataRef) -> usize;
+    /// Returns a cached value
+    #[doc(hidden)]
+    fn get(cache: &DataCache, group: usize, index: usize) -> &Self;
+}
+
+macro_rules! cache_value {
+    ([$native_type:ty], $field:ident, $read_fn:ident) => {
+        impl private::Sealed for [$native_type] {}
+        impl CacheValue for [$native_type] {
+            fn add(cache: &mut DataCache, group: usize, id: XPLMDataRef) -> usize {
+                cache.$field[group].add(id, |id, values, max| unsafe {
+                    $read_fn(id, values, 0, max)
+                })
+            }
+            fn get(cache: &DataCache, group: usize, index: usize) -> &Self {
+                &cache.$field[group].values[index]
+            }
+        }
+    };
+    ($native_type:ty, $field:ident, $read_fn:ident) => {
+        impl private::Sealed for $native_type {}
+        impl CacheValue for $native_type {
+            fn add(cache: &mut DataCache, group: usize, id: XPLMDataRef) -> usize {
+                cache.$field[group].add(id, |id| unsafe { $read_fn(id) })
+            }
+            fn get(cache: &DataCache, group: usize, index: usize) -> &Self {
+                &cache.$field[group].values[index]
+            }
+        }
+    };
+}
+
+cache_value!(f32, floats, XPLMGetDataf);
+cache_value!(f64, doubles, XPLMGetDatad);
+cache_value!(i32, ints, XPLMGetDatai);
+cache_value!([f32], float_arrays, XPLMGetDatavf);
+cache_value!([i32], int_arrays, XPLMGetDatavi);
+
+mod private {
+    pub trait Sealed {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::os::raw::c_int;
+    use std::ptr;
+
+    use super::{read_array, Arrays, DataCache, Scalars};
+
+    #[test]
+    fn test_scalars() {
+        let reads = Cell::new(0);
+        let read = |_| {
+            reads.set(reads.get() + 1);
+            reads.get()
+        };
+        let mut scalars = Scalars::default();
+        let first = scalars.add(ptr::null_mut(), read);
+        let second = scalars.add(ptr::null_mut(), read);
+        assert_eq!(scalars.values, [1, 2]);
+        // Reading a cached value does not read the dataref
+        assert_eq!(scalars.values[first], 1);
+        assert_eq!(reads.get(), 2);
+        scalars.refresh(read);
+        assert_eq!(scalars.values[first], 3);
+        assert_eq!(scalars.values[second], 4);
+    }
+
+    #[test]
+    fn test_arrays() {
+        let source = Cell::new(vec![1, 2, 3]);
+        let read = |_, values: *mut i32, max: c_int| {
+            let current = source.take();
+            let length = current.len() as c_int;
+            if !values.is_null() {
+                let count = max.min(length) as usize;
+                unsafe { ptr::copy_nonoverlapping(current.as_ptr(), values, count) };
+            }
+            source.set(current);
+            length
+        };
+        let mut arrays = Arrays::default();
+        let key = arrays.add(ptr::null_mut(), read);
+        assert_eq!(arrays.values[key], [1, 2, 3]);
+        // The cached array follows the dataref when its length changes
+        source.set(vec![4, 5]);
+        arrays.refresh(read);
+        assert_eq!(arrays.values[key], [4, 5]);
+        source.set(vec![6, 7, 8, 9]);
+        read_array(ptr::null_mut(), &mut arrays.values[key], &read);
+        assert_eq!(arrays.values[key], [6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_cold_interval() {
+        let mut cache = DataCache::new();
+        cache.set_cold_interval(3);
+        let due: Vec<bool> = (0..6).map(|_| cache.cold_due()).collect();
+        assert_eq!(due, [false, false, true, false, false, true]);
+        // An interval of 0 or 1 reads cold datarefs on every refresh
+        cache.set_cold_interval(0);
+        assert!(cache.cold_due());
+        cache.set_cold_interval(1);
+        assert!(cache.cold_due());
+    }
+}
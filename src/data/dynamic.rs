@@ -0,0 +1,486 @@
+//! # Datarefs with types chosen at runtime
+//!
+//! Generic tools such as dataref browsers, recorders, and network bridges work with datarefs
+//! whose types are not known when the plugin is compiled. [`DynDataRef::find_auto`] finds a
+//! dataref and chooses the most precise type that it supports. Values are read and written as
+//! [`DynValue`]s.
+//!
+//! Some datarefs support more than one type, for example int and float. An [`AnyDataRef`]
+//! finds a dataref once and can read and write it as any of the types that it supports.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::dynamic::{AnyDataRef, DynDataRef};
+//!
+//! let dataref = DynDataRef::find_auto("sim/flightmodel/position/latitude").unwrap();
+//! xplm::debugln!("{:?} = {}", dataref.data_type(), dataref.get());
+//!
+//! let gear = AnyDataRef::find("sim/cockpit2/controls/gear_handle_down").unwrap();
+//! let down = gear.get_i32().unwrap() != 0;
+//! let position = gear.get_f32().unwrap_or(if down { 1.0 } else { 0.0 });
+//! ```
+//!
+
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+use xplm_sys::*;
+
+use super::borrowed::FindError;
+use super::DataTypes;
+
+/// The types that a [`DynDataRef`] can have
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DynType {
+    /// A 32-bit integer
+    Int,
+    /// A 32-bit float
+    Float,
+    /// A 64-bit float
+    Double,
+    /// An array of 32-bit integers
+    IntArray,
+    /// An array of 32-bit floats
+    FloatArray,
+    /// An array of bytes
+    Bytes,
+}
+
+/// The known X-Plane data types, best first
+const PREFERENCE: [(u32, DynType); 6] = [
+    (xplmType_Double, DynType::Double),
+    (xplmType_Float, DynType::Float),
+    (xplmType_Int, DynType::Int),
+    (xplmType_FloatArray, DynType::FloatArray),
+    (xplmType_IntArray, DynType::IntArray),
+    (xplmType_Data, DynType::Bytes),
+];
+
+impl DynType {
+    /// Chooses the best type from a set of X-Plane data types
+    ///
+    /// Scalar types are preferred over arrays, and more precise types are preferred over less
+    /// precise types. This returns None if the set contains no known types.
+    fn best(types: XPLMDataTypeID) -> Option<Self> {
+        Self::all(types).next()
+    }
+
+    /// Returns the known types in a set of X-Plane data types, best first
+    pub(crate) fn all(types: XPLMDataTypeID) -> impl Iterator<Item = Self> {
+        PREFERENCE
+            .iter()
+            .filter(move |(sim_type, _)| types & *sim_type as XPLMDataTypeID != 0)
+            .map(|&(_, dyn_type)| dyn_type)
+    }
+}
+
+/// A value read from or written to a [`DynDataRef`]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DynValue {
+    /// A 32-bit integer
+    Int(i32),
+    /// A 32-bit float
+    Float(f32),
+    /// A 64-bit float
+    Double(f64),
+    /// An array of 32-bit integers
+    IntArray(Vec<i32>),
+    /// An array of 32-bit floats
+    FloatArray(Vec<f32>),
+    /// An array of bytes
+    Bytes(Vec<u8>),
+}
+
+impl DynValue {
+    /// Returns the type of this value
+    pub fn data_type(&self) -> DynType {
+        match self {
+            DynValue::Int(_) => DynType::Int,
+            DynValue::Float(_) => DynType::Float,
+            DynValue::Double(_) => DynType::Double,
+            DynValue::IntArray(_) => DynType::IntArray,
+            DynValue::FloatArray(_) => DynType::FloatArray,
+            DynValue::Bytes(_) => DynType::Bytes,
+        }
+    }
+}
+
+/// Formats scalars as numbers and arrays as comma-separated lists in brackets
+///
+/// Byte arrays are formatted as text, up to the first null byte.
+impl fmt::Display for DynValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn list<T: fmt::Display>(f: &mut fmt::Formatter, values: &[T]) -> fmt::Result {
+            f.write_str("[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i != 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", value)?;
+            }
+            f.write_str("]")
+        }
+        match self {
+            DynValue::Int(value) => write!(f, "{}", value),
+            DynValue::Float(value) => write!(f, "{}", value),
+            DynValue::Double(value) => write!(f, "{}", value),
+            DynValue::IntArray(values) => list(f, values),
+            DynValue::FloatArray(values) => list(f, values),
+            DynValue::Bytes(bytes) => {
+                let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+                f.write_str(&String::from_utf8_lossy(&bytes[..end]))
+            }
+        }
+    }
+}
+
+/// A dataref with a type that was chosen at runtime
+#[derive(Debug)]
+pub struct DynDataRef {
+    /// The dataref handle
+    id: XPLMDataRef,
+    /// The type used to read and write the dataref
+    data_type: DynType,
+    /// If the dataref can be written
+    writeable: bool,
+}
+
+impl DynDataRef {
+    /// Finds a dataref and chooses the best type that it supports
+    ///
+    /// If the dataref supports more than one type, scalar types are preferred over arrays and
+    /// more precise types are preferred over less precise types. For example, a dataref that
+    /// supports int, float, and double values is read as a double.
+    pub fn find_auto(name: &str) -> Result<Self, FindError> {
+        let name_c = CString::new(name)?;
+        let id = unsafe { XPLMFindDataRef(name_c.as_ptr()) };
+        if id.is_null() {
+            return Err(FindError::NotFound);
+        }
+        let types = unsafe { XPLMGetDataRefTypes(id) };
+        let data_type = DynType::best(types).ok_or(FindError::WrongType)?;
+        Ok(DynDataRef {
+            id,
+            data_type,
+            writeable: unsafe { XPLMCanWriteDataRef(id) == 1 },
+        })
+    }
+
+    /// Returns the type used to read and write this dataref
+    pub fn data_type(&self) -> DynType {
+        self.data_type
+    }
+
+    /// Returns true if this dataref can be written
+    pub fn is_writeable(&self) -> bool {
+        self.writeable
+    }
+
+    /// Reads the value of this dataref
+    pub fn get(&self) -> DynValue {
+        unsafe {
+            match self.data_type {
+                DynType::Int => DynValue::Int(XPLMGetDatai(self.id)),
+                DynType::Float => DynValue::Float(XPLMGetDataf(self.id)),
+                DynType::Double => DynValue::Double(XPLMGetDatad(self.id)),
+                DynType::IntArray => DynValue::IntArray(read_array(|values, max| {
+                    XPLMGetDatavi(self.id, values, 0, max)
+                })),
+                DynType::FloatArray => DynValue::FloatArray(read_array(|values, max| {
+                    XPLMGetDatavf(self.id, values, 0, max)
+                })),
+                DynType::Bytes => DynValue::Bytes(read_array(|values: *mut u8, max| {
+                    XPLMGetDatab(self.id, values as *mut c_void, 0, max)
+                })),
+            }
+        }
+    }
+
+    /// Writes a value to this dataref
+    ///
+    /// The value must have the type returned by [`data_type`](DynDataRef::data_type).
+    pub fn set(&self, value: &DynValue) -> Result<(), DynSetError> {
+        if !self.writeable {
+            return Err(DynSetError::NotWritable);
+        }
+        if value.data_type() != self.data_type {
+            return Err(DynSetError::WrongType {
+                expected: self.data_type,
+                actual: value.data_type(),
+            });
+        }
+        unsafe {
+            match value {
+                DynValue::Int(value) => XPLMSetDatai(self.id, *value),
+                DynValue::Float(value) => XPLMSetDataf(self.id, *value),
+                DynValue::Double(value) => XPLMSetDatad(self.id, *value),
+                DynValue::IntArray(values) => {
+                    XPLMSetDatavi(self.id, values.as_ptr() as *mut c_int, 0, len(values))
+                }
+                DynValue::FloatArray(values) => {
+                    XPLMSetDatavf(self.id, values.as_ptr() as *mut f32, 0, len(values))
+                }
+                DynValue::Bytes(values) => {
+                    XPLMSetDatab(self.id, values.as_ptr() as *mut c_void, 0, len(values))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A dataref that can be read and written as any of the types that it supports
+#[derive(Debug)]
+pub struct AnyDataRef {
+    /// The dataref handle
+    id: XPLMDataRef,
+    /// The types that the dataref supports
+    types: DataTypes,
+    /// If the dataref can be written
+    writeable: bool,
+}
+
+impl AnyDataRef {
+    /// Finds a dataref
+    ///
+    /// Returns an error if the dataref does not exist.
+    pub fn find(name: &str) -> Result<Self, FindError> {
+        crate::debug_assert_main_thread!();
+        let name_c = CString::new(name)?;
+        let id = unsafe { XPLMFindDataRef(name_c.as_ptr()) };
+        if id.is_null() {
+            return Err(FindError::NotFound);
+        }
+        Ok(AnyDataRef {
+            id,
+            types: DataTypes::from_bits(unsafe { XPLMGetDataRefTypes(id) }),
+            writeable: unsafe { XPLMCanWriteDataRef(id) == 1 },
+        })
+    }
+
+    /// Returns the types that this dataref supports
+    pub fn types(&self) -> DataTypes {
+        self.types
+    }
+
+    /// Returns true if this dataref can be written
+    pub fn is_writeable(&self) -> bool {
+        self.writeable
+    }
+
+    /// Reads this dataref as an integer
+    pub fn get_i32(&self) -> Result<i32, AnyAccessError> {
+        self.check(DataTypes::INT, false)?;
+        Ok(unsafe { XPLMGetDatai(self.id) })
+    }
+
+    /// Reads this dataref as a 32-bit float
+    pub fn get_f32(&self) -> Result<f32, AnyAccessError> {
+        self.check(DataTypes::FLOAT, false)?;
+        Ok(unsafe { XPLMGetDataf(self.id) })
+    }
+
+    /// Reads this dataref as a 64-bit float
+    pub fn get_f64(&self) -> Result<f64, AnyAccessError> {
+        self.check(DataTypes::DOUBLE, false)?;
+        Ok(unsafe { XPLMGetDatad(self.id) })
+    }
+
+    /// Reads this dataref as an array of integers
+    pub fn get_i32_array(&self) -> Result<Vec<i32>, AnyAccessError> {
+        self.check(DataTypes::INT_ARRAY, false)?;
+        Ok(read_array(|values, max| unsafe {
+            XPLMGetDatavi(self.id, values, 0, max)
+        }))
+    }
+
+    /// Reads this dataref as an array of 32-bit floats
+    pub fn get_f32_array(&self) -> Result<Vec<f32>, AnyAccessError> {
+        self.check(DataTypes::FLOAT_ARRAY, false)?;
+        Ok(read_array(|values, max| unsafe {
+            XPLMGetDatavf(self.id, values, 0, max)
+        }))
+    }
+
+    /// Reads this dataref as an array of bytes
+    pub fn get_bytes(&self) -> Result<Vec<u8>, AnyAccessError> {
+        self.check(DataTypes::DATA, false)?;
+        Ok(read_array(|values: *mut u8, max| unsafe {
+            XPLMGetDatab(self.id, values as *mut c_void, 0, max)
+        }))
+    }
+
+    /// Writes an integer to this dataref
+    pub fn set_i32(&self, value: i32) -> Result<(), AnyAccessError> {
+        self.check(DataTypes::INT, true)?;
+        unsafe { XPLMSetDatai(self.id, value) };
+        Ok(())
+    }
+
+    /// Writes a 32-bit float to this dataref
+    pub fn set_f32(&self, value: f32) -> Result<(), AnyAccessError> {
+        self.check(DataTypes::FLOAT, true)?;
+        unsafe { XPLMSetDataf(self.id, value) };
+        Ok(())
+    }
+
+    /// Writes a 64-bit float to this dataref
+    pub fn set_f64(&self, value: f64) -> Result<(), AnyAccessError> {
+        self.check(DataTypes::DOUBLE, true)?;
+        unsafe { XPLMSetDatad(self.id, value) };
+        Ok(())
+    }
+
+    /// Writes integers to this dataref, starting at the beginning of the array
+    pub fn set_i32_array(&self, values: &[i32]) -> Result<(), AnyAccessError> {
+        self.check(DataTypes::INT_ARRAY, true)?;
+        unsafe { XPLMSetDatavi(self.id, values.as_ptr() as *mut c_int, 0, len(values)) };
+        Ok(())
+    }
+
+    /// Writes 32-bit floats to this dataref, starting at the beginning of the array
+    pub fn set_f32_array(&self, values: &[f32]) -> Result<(), AnyAccessError> {
+        self.check(DataTypes::FLOAT_ARRAY, true)?;
+        unsafe { XPLMSetDatavf(self.id, values.as_ptr() as *mut f32, 0, len(values)) };
+        Ok(())
+    }
+
+    /// Writes bytes to this dataref, starting at the beginning of the array
+    pub fn set_bytes(&self, values: &[u8]) -> Result<(), AnyAccessError> {
+        self.check(DataTypes::DATA, true)?;
+        unsafe { XPLMSetDatab(self.id, values.as_ptr() as *mut c_void, 0, len(values)) };
+        Ok(())
+    }
+
+    /// Checks that this dataref supports a type and, if writing, can be written
+    fn check(&self, requested: DataTypes, write: bool) -> Result<(), AnyAccessError> {
+        crate::debug_assert_main_thread!();
+        check_access(self.types, self.writeable, requested, write)
+    }
+}
+
+/// Checks that a dataref with the provided types and writability supports an access
+fn check_access(
+    supported: DataTypes,
+    writeable: bool,
+    requested: DataTypes,
+    write: bool,
+) -> Result<(), AnyAccessError> {
+    if !supported.contains(requested) {
+        Err(AnyAccessError::UnsupportedType {
+            requested,
+            supported,
+        })
+    } else if write && !writeable {
+        Err(AnyAccessError::NotWritable)
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads an array by calling read once to get the length and once to get the values
+fn read_array<T: Copy + Default, F>(read: F) -> Vec<T>
+where
+    F: Fn(*mut T, c_int) -> c_int,
+{
+    let length = read(ptr::null_mut(), 0).max(0);
+    let mut values = vec![T::default(); length as usize];
+    let read_length = read(values.as_mut_ptr(), length).clamp(0, length);
+    values.truncate(read_length as usize);
+    values
+}
+
+/// Returns the length of a slice as a c_int, limited to the largest c_int
+fn len<T>(values: &[T]) -> c_int {
+    values.len().min(c_int::MAX as usize) as c_int
+}
+
+/// Errors that can occur when writing a [`DynDataRef`]
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum DynSetError {
+    /// The dataref cannot be written
+    #[error("DataRef not writable")]
+    NotWritable,
+    /// The value has a different type from the dataref
+    #[error("Expected a {expected:?} value, got {actual:?}")]
+    WrongType {
+        /// The type of the dataref
+        expected: DynType,
+        /// The type of the value
+        actual: DynType,
+    },
+}
+
+/// Errors that can occur when reading or writing an [`AnyDataRef`]
+#[derive(thiserror::Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AnyAccessError {
+    /// The dataref does not support the requested type
+    #[error("DataRef does not support {requested:?} (supports {supported:?})")]
+    UnsupportedType {
+        /// The type that was requested
+        requested: DataTypes,
+        /// The types that the dataref supports
+        supported: DataTypes,
+    },
+    /// The dataref cannot be written
+    #[error("DataRef not writable")]
+    NotWritable,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_access, AnyAccessError, DynType, DynValue};
+    use crate::data::DataTypes;
+    use xplm_sys::*;
+
+    #[test]
+    fn test_best_type() {
+        let types = (xplmType_Int | xplmType_Float | xplmType_Double) as XPLMDataTypeID;
+        assert_eq!(DynType::best(types), Some(DynType::Double));
+        let types = (xplmType_IntArray | xplmType_FloatArray) as XPLMDataTypeID;
+        assert_eq!(DynType::best(types), Some(DynType::FloatArray));
+        assert_eq!(DynType::best(xplmType_Data as _), Some(DynType::Bytes));
+        assert_eq!(DynType::best(0), None);
+        let types = (xplmType_Float | xplmType_FloatArray) as XPLMDataTypeID;
+        assert_eq!(
+            DynType::all(types).collect::<Vec<_>>(),
+            [DynType::Float, DynType::FloatArray]
+        );
+    }
+
+    #[test]
+    fn test_check_access() {
+        let supported = DataTypes::INT | DataTypes::FLOAT;
+        assert_eq!(
+            check_access(supported, false, DataTypes::FLOAT, false),
+            Ok(())
+        );
+        assert_eq!(
+            check_access(supported, false, DataTypes::FLOAT, true),
+            Err(AnyAccessError::NotWritable)
+        );
+        assert_eq!(
+            check_access(supported, true, DataTypes::DOUBLE, true),
+            Err(AnyAccessError::UnsupportedType {
+                requested: DataTypes::DOUBLE,
+                supported,
+            })
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            DynValue::FloatArray(vec![1.5, -2.0]).to_string(),
+            "[1.5, -2]"
+        );
+        assert_eq!(DynValue::Bytes(b"12.1\0\0".to_vec()).to_string(), "12.1");
+    }
+}
@@ -0,0 +1,191 @@
+//! # Datarefs found after the plugin starts
+//!
+//! Datarefs that other plugins create, such as aircraft systems, may not exist when this
+//! plugin starts. A [`LazyDataRef`] remembers the name of a dataref and looks it up when it is
+//! used, until the dataref is found.
+//!
+//! Looking up a dataref by name is slower than using a dataref that has already been found.
+//! After a lookup fails, another lookup is only done when X-Plane reports that datarefs have
+//! been added (in X-Plane 12) or when a second has passed since the last lookup. This library
+//! enables the `XPLM_WANTS_DATAREF_NOTIFICATIONS` feature when the plugin starts, so that
+//! X-Plane sends messages when datarefs are added.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::data::{DataRead, DataReadWrite};
+//!
+//! let mut battery = DataRef::<i32>::find_lazy("myaircraft/electrical/battery_on")
+//!     .unwrap()
+//!     .writeable();
+//! // Later, in a flight loop callback
+//! if let Some(battery) = battery.dataref_mut() {
+//!     if battery.get() == 0 {
+//!         battery.set(1);
+//!     }
+//! }
+//! ```
+//!
+
+use std::cell::{Cell, OnceCell};
+use std::ffi::{CString, NulError};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super::borrowed::{DataRef, FindError};
+use super::{Access, DataType, ReadOnly, ReadWrite};
+
+/// The minimum time between lookups, unless datarefs have been added
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+thread_local! {
+    /// The number of times X-Plane has reported that datarefs were added
+    static GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Records that X-Plane has reported that datarefs were added
+///
+/// This is called when the plugin receives the datarefs added message.
+pub(crate) fn datarefs_added() {
+    GENERATION.with(|generation| generation.set(generation.get().wrapping_add(1)));
+}
+
+/// A dataref that is found when it is first used
+///
+/// T is the data type stored in the dataref.
+///
+/// A is the access level (`ReadOnly` or `ReadWrite`)
+pub struct LazyDataRef<T: ?Sized, A = ReadOnly> {
+    /// The name of the dataref
+    name: String,
+    /// The dataref, once it has been found
+    dataref: OnceCell<DataRef<T, A>>,
+    /// The time and generation of the last lookup that failed
+    last_attempt: Cell<Option<Attempt>>,
+}
+
+/// A lookup that failed
+#[derive(Debug, Copy, Clone)]
+struct Attempt {
+    time: Instant,
+    generation: u64,
+}
+
+impl Attempt {
+    /// Returns true if another lookup should be done
+    fn should_retry(&self, now: Instant, generation: u64) -> bool {
+        generation != self.generation || now.duration_since(self.time) >= RETRY_INTERVAL
+    }
+}
+
+impl<T: DataType + ?Sized> LazyDataRef<T, ReadOnly> {
+    /// Creates a lazy dataref with the provided name
+    ///
+    /// The dataref is not looked up until it is used.
+    pub fn new(name: &str) -> Result<Self, NulError> {
+        // Check the name now so that it cannot fail later
+        CString::new(name)?;
+        Ok(LazyDataRef {
+            name: name.to_owned(),
+            dataref: OnceCell::new(),
+            last_attempt: Cell::new(None),
+        })
+    }
+
+    /// Makes this dataref writable
+    ///
+    /// The dataref will only be found if it can be written.
+    pub fn writeable(self) -> LazyDataRef<T, ReadWrite> {
+        LazyDataRef {
+            name: self.name,
+            dataref: OnceCell::new(),
+            last_attempt: Cell::new(None),
+        }
+    }
+}
+
+impl<T: DataType + ?Sized, A: Access> LazyDataRef<T, A> {
+    /// Returns the name of the dataref
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true if the dataref has been found
+    ///
+    /// This does not look up the dataref.
+    pub fn is_found(&self) -> bool {
+        self.dataref.get().is_some()
+    }
+
+    /// Returns the dataref, looking it up if it has not been found yet
+    ///
+    /// Returns None if the dataref does not exist, has the wrong type, or cannot be written
+    /// if this is a writable dataref.
+    pub fn dataref(&self) -> Option<&DataRef<T, A>> {
+        if let Some(dataref) = self.dataref.get() {
+            return Some(dataref);
+        }
+        let now = Instant::now();
+        let generation = GENERATION.with(Cell::get);
+        if let Some(attempt) = self.last_attempt.get() {
+            if !attempt.should_retry(now, generation) {
+                return None;
+            }
+        }
+        match self.find() {
+            Ok(dataref) => Some(self.dataref.get_or_init(|| dataref)),
+            Err(_) => {
+                self.last_attempt.set(Some(Attempt {
+                    time: now,
+                    generation,
+                }));
+                None
+            }
+        }
+    }
+
+    /// Returns the dataref for writing, looking it up if it has not been found yet
+    pub fn dataref_mut(&mut self) -> Option<&mut DataRef<T, A>> {
+        self.dataref()?;
+        self.dataref.get_mut()
+    }
+
+    /// Looks up the dataref now
+    fn find(&self) -> Result<DataRef<T, A>, FindError> {
+        let dataref = DataRef::<T>::find(&self.name)?;
+        let id = dataref.id();
+        if A::writeable() {
+            dataref.writeable()?;
+        }
+        Ok(DataRef::from_id(id))
+    }
+}
+
+impl<T: ?Sized, A> fmt::Debug for LazyDataRef<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LazyDataRef")
+            .field("name", &self.name)
+            .field("found", &self.dataref.get().is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Attempt, RETRY_INTERVAL};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_retry() {
+        let start = Instant::now();
+        let attempt = Attempt {
+            time: start,
+            generation: 2,
+        };
+        assert!(!attempt.should_retry(start, 2));
+        assert!(!attempt.should_retry(start + Duration::from_millis(500), 2));
+        assert!(attempt.should_retry(start + Duration::from_millis(500), 3));
+        assert!(attempt.should_retry(start + RETRY_INTERVAL, 2));
+    }
+}
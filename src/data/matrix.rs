@@ -0,0 +1,246 @@
+//! # Matrix and quaternion datarefs
+//!
+//! Some float array datarefs contain matrices or quaternions. For example,
+//! `sim/graphics/view/world_matrix` contains a 4x4 transformation matrix and
+//! `sim/flightmodel/position/q` contains the orientation of the user's aircraft.
+//!
+//! The types in this module wrap any float array accessor and convert its elements to and from
+//! plain Rust arrays. Matrices are always returned in row-major form, `matrix[row][column]`,
+//! regardless of how the dataref stores them.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::data::matrix::{Layout, Matrix4, QuaternionData};
+//!
+//! // X-Plane stores OpenGL matrices in column-major order
+//! let world: Matrix4<DataRef<[f32]>> = Matrix4::new(
+//!     DataRef::find("sim/graphics/view/world_matrix").unwrap(),
+//!     Layout::ColumnMajor,
+//! )
+//! .unwrap();
+//! let translation_x = world.get()[0][3];
+//!
+//! let orientation = QuaternionData::new(DataRef::find("sim/flightmodel/position/q").unwrap())
+//!     .unwrap();
+//! let q = orientation.get();
+//! ```
+//!
+
+use super::{ArrayRead, ArrayReadWrite};
+
+/// The number of elements in a 4x4 matrix
+const MATRIX4_LENGTH: usize = 16;
+/// The number of elements in a quaternion
+const QUATERNION_LENGTH: usize = 4;
+
+/// The order in which a dataref stores the elements of a matrix
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Layout {
+    /// Each group of elements is a row
+    RowMajor,
+    /// Each group of elements is a column, as used by OpenGL
+    ColumnMajor,
+}
+
+/// A 4x4 matrix stored in a float array dataref
+///
+/// D is the underlying accessor, for example `DataRef<[f32]>` or `OwnedData<[f32]>`.
+#[derive(Debug)]
+pub struct Matrix4<D> {
+    /// The float array
+    data: D,
+    /// The element order of the array
+    layout: Layout,
+}
+
+impl<D: ArrayRead<[f32]>> Matrix4<D> {
+    /// Wraps a float array accessor
+    ///
+    /// Returns an error if the array has fewer than 16 elements.
+    pub fn new(data: D, layout: Layout) -> Result<Self, LengthError> {
+        check_length(&data, MATRIX4_LENGTH)?;
+        Ok(Matrix4 { data, layout })
+    }
+
+    /// Reads the matrix, in the form `matrix[row][column]`
+    pub fn get(&self) -> [[f32; 4]; 4] {
+        let mut elements = [0.0; MATRIX4_LENGTH];
+        self.data.get(&mut elements);
+        to_rows(&elements, self.layout)
+    }
+
+    /// Returns the layout of the array
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns the underlying accessor
+    pub fn into_inner(self) -> D {
+        self.data
+    }
+}
+
+impl<D: ArrayReadWrite<[f32]>> Matrix4<D> {
+    /// Writes a matrix provided in the form `matrix[row][column]`
+    pub fn set(&mut self, matrix: &[[f32; 4]; 4]) {
+        let elements = from_rows(matrix, self.layout);
+        self.data.set(&elements);
+    }
+}
+
+/// A rotation quaternion
+///
+/// X-Plane quaternions are stored as `[w, x, y, z]`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    /// The real part
+    pub w: f32,
+    /// The i component
+    pub x: f32,
+    /// The j component
+    pub y: f32,
+    /// The k component
+    pub z: f32,
+}
+
+impl Quaternion {
+    /// Creates a quaternion from an array in X-Plane order, `[w, x, y, z]`
+    pub fn from_array(values: [f32; 4]) -> Self {
+        let [w, x, y, z] = values;
+        Quaternion { w, x, y, z }
+    }
+
+    /// Returns this quaternion as an array in X-Plane order, `[w, x, y, z]`
+    pub fn to_array(self) -> [f32; 4] {
+        [self.w, self.x, self.y, self.z]
+    }
+
+    /// Converts this quaternion into a 3x3 rotation matrix, in the form `matrix[row][column]`
+    ///
+    /// The quaternion should be normalized.
+    pub fn to_rotation_matrix(self) -> [[f32; 3]; 3] {
+        let Quaternion { w, x, y, z } = self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+}
+
+/// A quaternion stored in a float array dataref
+///
+/// D is the underlying accessor, for example `DataRef<[f32]>` or `OwnedData<[f32]>`.
+#[derive(Debug)]
+pub struct QuaternionData<D> {
+    /// The float array
+    data: D,
+}
+
+impl<D: ArrayRead<[f32]>> QuaternionData<D> {
+    /// Wraps a float array accessor
+    ///
+    /// Returns an error if the array has fewer than 4 elements.
+    pub fn new(data: D) -> Result<Self, LengthError> {
+        check_length(&data, QUATERNION_LENGTH)?;
+        Ok(QuaternionData { data })
+    }
+
+    /// Reads the quaternion
+    pub fn get(&self) -> Quaternion {
+        let mut elements = [0.0; QUATERNION_LENGTH];
+        self.data.get(&mut elements);
+        Quaternion::from_array(elements)
+    }
+
+    /// Returns the underlying accessor
+    pub fn into_inner(self) -> D {
+        self.data
+    }
+}
+
+impl<D: ArrayReadWrite<[f32]>> QuaternionData<D> {
+    /// Writes a quaternion
+    pub fn set(&mut self, quaternion: Quaternion) {
+        self.data.set(&quaternion.to_array());
+    }
+}
+
+/// Returns an error if an array is shorter than the required length
+fn check_length<D: ArrayRead<[f32]>>(data: &D, required: usize) -> Result<(), LengthError> {
+    let actual = data.len();
+    if actual < required {
+        Err(LengthError { required, actual })
+    } else {
+        Ok(())
+    }
+}
+
+/// Converts array elements into rows
+fn to_rows(elements: &[f32; MATRIX4_LENGTH], layout: Layout) -> [[f32; 4]; 4] {
+    let mut rows = [[0.0; 4]; 4];
+    for (index, &element) in elements.iter().enumerate() {
+        let (major, minor) = (index / 4, index % 4);
+        match layout {
+            Layout::RowMajor => rows[major][minor] = element,
+            Layout::ColumnMajor => rows[minor][major] = element,
+        }
+    }
+    rows
+}
+
+/// Converts rows into array elements
+fn from_rows(rows: &[[f32; 4]; 4], layout: Layout) -> [f32; MATRIX4_LENGTH] {
+    let mut elements = [0.0; MATRIX4_LENGTH];
+    for (index, element) in elements.iter_mut().enumerate() {
+        let (major, minor) = (index / 4, index % 4);
+        *element = match layout {
+            Layout::RowMajor => rows[major][minor],
+            Layout::ColumnMajor => rows[minor][major],
+        };
+    }
+    elements
+}
+
+/// An error returned when an array is too short to contain a matrix or quaternion
+#[derive(thiserror::Error, Debug)]
+#[error("Array has {actual} elements, expected at least {required}")]
+pub struct LengthError {
+    /// The number of elements required
+    required: usize,
+    /// The number of elements in the array
+    actual: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_rows, to_rows, Layout};
+
+    /// Checks that column-major elements are transposed into rows and back
+    #[test]
+    fn test_column_major() {
+        let elements: [f32; 16] = std::array::from_fn(|i| i as f32);
+        let rows = to_rows(&elements, Layout::ColumnMajor);
+        // The translation of an OpenGL matrix is in elements 12, 13, and 14
+        assert_eq!(rows[0][3], 12.0);
+        assert_eq!(rows[1][3], 13.0);
+        assert_eq!(rows[2][3], 14.0);
+        assert_eq!(rows[3][0], 3.0);
+        assert_eq!(from_rows(&rows, Layout::ColumnMajor), elements);
+        assert_eq!(to_rows(&elements, Layout::RowMajor)[0][3], 3.0);
+    }
+}
@@ -0,0 +1,213 @@
+//! # Dataref structs
+//!
+//! Plugins often copy many sim variables into a Rust struct. The
+//! [`dataref_struct!`](crate::dataref_struct) macro defines a struct whose fields are mapped
+//! to dataref names. A [`Mirror`] finds all the datarefs when it is created and reads or
+//! writes all fields at once.
+//!
+//! Fields must have a type that can be read from a single dataref, such as `f32`, `f64`,
+//! `i32`, or `bool`. Fields marked `writeable` are written by
+//! [`write_all`](Mirror::write_all). Other fields are only read.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::mirror::Mirror;
+//!
+//! xplm::dataref_struct! {
+//!     /// The position of the user aircraft
+//!     #[derive(Debug)]
+//!     pub struct Position {
+//!         #[dataref("sim/flightmodel/position/latitude")]
+//!         pub latitude: f64,
+//!         #[dataref("sim/flightmodel/position/longitude")]
+//!         pub longitude: f64,
+//!         #[dataref("sim/flightmodel/position/local_y", writeable)]
+//!         pub local_y: f64,
+//!     }
+//! }
+//!
+//! let mut position: Mirror<Position> = Mirror::find().unwrap();
+//! // In a flight loop callback
+//! position.read_all();
+//! xplm::debugln!("{}, {}", position.latitude, position.longitude);
+//! position.local_y += 1.0;
+//! position.write_all();
+//! ```
+//!
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use super::borrowed::{DataRef, FindError};
+use super::{DataType, ReadWrite};
+
+/// A struct whose fields are mapped to datarefs
+///
+/// This trait is implemented by the [`dataref_struct!`](crate::dataref_struct) macro.
+pub trait DataStruct: Sized {
+    /// The datarefs of the fields
+    #[doc(hidden)]
+    type Refs;
+    /// Finds the datarefs of all fields
+    #[doc(hidden)]
+    fn find_refs() -> Result<Self::Refs, FieldFindError>;
+    /// Reads all fields
+    #[doc(hidden)]
+    fn read(refs: &Self::Refs) -> Self;
+    /// Writes all writeable fields
+    #[doc(hidden)]
+    fn write(&self, refs: &mut Self::Refs);
+}
+
+/// A struct with the datarefs of its fields
+///
+/// A mirror dereferences to the struct, so fields can be accessed directly. Field values only
+/// change when [`read_all`](Mirror::read_all) is called.
+pub struct Mirror<S: DataStruct> {
+    values: S,
+    refs: S::Refs,
+}
+
+impl<S: DataStruct> Mirror<S> {
+    /// Finds the datarefs of all fields and reads their values
+    ///
+    /// Returns an error if any dataref does not exist, has the wrong type, or is marked
+    /// writeable but cannot be written.
+    pub fn find() -> Result<Self, FieldFindError> {
+        let refs = S::find_refs()?;
+        Ok(Mirror {
+            values: S::read(&refs),
+            refs,
+        })
+    }
+
+    /// Reads the values of all fields from their datarefs
+    pub fn read_all(&mut self) {
+        self.values = S::read(&self.refs);
+    }
+
+    /// Writes the values of all writeable fields to their datarefs
+    pub fn write_all(&mut self) {
+        self.values.write(&mut self.refs);
+    }
+
+    /// Returns the struct, discarding the datarefs
+    pub fn into_inner(self) -> S {
+        self.values
+    }
+}
+
+impl<S: DataStruct> Deref for Mirror<S> {
+    type Target = S;
+    fn deref(&self) -> &S {
+        &self.values
+    }
+}
+
+impl<S: DataStruct> DerefMut for Mirror<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.values
+    }
+}
+
+impl<S: DataStruct + fmt::Debug> fmt::Debug for Mirror<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Mirror").field(&self.values).finish()
+    }
+}
+
+/// An error returned when the dataref of a field cannot be found
+#[derive(thiserror::Error, Debug)]
+#[error("DataRef {name}: {error}")]
+pub struct FieldFindError {
+    /// The name of the dataref
+    pub name: &'static str,
+    /// The reason the dataref could not be found
+    #[source]
+    pub error: FindError,
+}
+
+/// Finds a readable dataref for a field
+#[doc(hidden)]
+pub fn find_field<T: DataType>(name: &'static str) -> Result<DataRef<T>, FieldFindError> {
+    DataRef::find(name).map_err(|error| FieldFindError { name, error })
+}
+
+/// Finds a writeable dataref for a field
+#[doc(hidden)]
+pub fn find_writeable_field<T: DataType>(
+    name: &'static str,
+) -> Result<DataRef<T, ReadWrite>, FieldFindError> {
+    DataRef::find(name)
+        .and_then(DataRef::writeable)
+        .map_err(|error| FieldFindError { name, error })
+}
+
+/// Defines a struct whose fields are mapped to datarefs
+///
+/// Each field has a `#[dataref("name")]` attribute with the name of its dataref. Fields with
+/// `#[dataref("name", writeable)]` are also written by
+/// [`Mirror::write_all`](crate::data::mirror::Mirror::write_all).
+///
+/// See the [`mirror`](crate::data::mirror) module for an example.
+#[macro_export]
+macro_rules! dataref_struct {
+    (@access) => { $crate::data::ReadOnly };
+    (@access writeable) => { $crate::data::ReadWrite };
+    (@find $dataref:literal) => { $crate::data::mirror::find_field($dataref) };
+    (@find $dataref:literal writeable) => {
+        $crate::data::mirror::find_writeable_field($dataref)
+    };
+    (@write ; $dataref:ident, $value:expr) => {
+        let _ = $dataref;
+    };
+    (@write writeable ; $dataref:ident, $value:expr) => {
+        $crate::data::DataReadWrite::set($dataref, $value);
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                #[dataref($dataref:literal $(, $writeable:ident)?)]
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::data::mirror::DataStruct for $name {
+            type Refs = (
+                $(
+                    $crate::data::borrowed::DataRef<
+                        $ty,
+                        $crate::dataref_struct!(@access $($writeable)?)
+                    >,
+                )*
+            );
+            fn find_refs() -> ::std::result::Result<
+                Self::Refs,
+                $crate::data::mirror::FieldFindError,
+            > {
+                Ok(($($crate::dataref_struct!(@find $dataref $($writeable)?)?,)*))
+            }
+            fn read(refs: &Self::Refs) -> Self {
+                let ($($field,)*) = refs;
+                $name {
+                    $($field: $crate::data::DataRead::get($field),)*
+                }
+            }
+            fn write(&self, refs: &mut Self::Refs) {
+                let ($($field,)*) = refs;
+                $($crate::dataref_struct!(@write $($writeable)? ; $field, self.$field);)*
+            }
+        }
+    };
+}
@@ -1,10 +1,16 @@
-use super::{Access, ArrayRead, ArrayReadWrite, DataRead, DataReadWrite, DataType, ReadOnly};
+use super::{
+    read_range, write_range, Access, ArrayRead, ArrayReadWrite, DataRead, DataReadWrite, DataType,
+    DataTypes, ReadOnly,
+};
+use std::borrow::Borrow;
+use std::cell::Cell;
 use std::cmp;
 use std::ffi::{CString, NulError};
-use std::i32;
 use std::marker::PhantomData;
 use std::os::raw::{c_int, c_void};
 use std::ptr;
+use std::slice;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use xplm_sys::*;
 
 /// A dataref owned by this plugin
@@ -14,11 +20,11 @@ use xplm_sys::*;
 pub struct OwnedData<T: DataType + ?Sized, A = ReadOnly> {
     /// The dataref handle
     id: XPLMDataRef,
-    /// The current value
+    /// The current value and access counters
     ///
     /// This is boxed so that it will have a constant memory location that is
     /// provided as a refcon to the callbacks.
-    value: Box<T::Storage>,
+    slot: Box<Slot<T::Storage>>,
     /// Data access phantom data
     access_phantom: PhantomData<A>,
 }
@@ -34,55 +40,96 @@ impl<T: DataType + ?Sized, A: Access> OwnedData<T, A> {
 
     /// Creates a new dataref with the provided name and value
     pub fn create_with_value(name: &str, value: &T) -> Result<Self, CreateError> {
-        let name_c = CString::new(name)?;
-
-        let existing = unsafe { XPLMFindDataRef(name_c.as_ptr()) };
-        if !existing.is_null() {
-            return Err(CreateError::Exists);
-        }
-
-        let value = value.to_storage();
-        let mut value_box = Box::new(value);
-        let value_ptr: *mut T::Storage = value_box.as_mut();
-
-        let id = unsafe {
-            XPLMRegisterDataAccessor(
-                name_c.as_ptr(),
-                T::sim_type(),
-                Self::writeable(),
-                Self::int_read(),
-                Self::int_write(),
-                Self::float_read(),
-                Self::float_write(),
-                Self::double_read(),
-                Self::double_write(),
-                Self::int_array_read(),
-                Self::int_array_write(),
-                Self::float_array_read(),
-                Self::float_array_write(),
-                Self::byte_array_read(),
-                Self::byte_array_write(),
-                value_ptr as *mut c_void,
-                value_ptr as *mut c_void,
-            )
-        };
+        crate::debug_assert_main_thread!();
+        let mut slot = Box::new(Slot::new(value.to_storage()));
+        let slot_ptr: *mut Slot<T::Storage> = slot.as_mut();
 
-        assert!(!id.is_null());
+        let callbacks = Callbacks {
+            int_read: Self::int_read(),
+            int_write: Self::int_write(),
+            float_read: Self::float_read(),
+            float_write: Self::float_write(),
+            double_read: Self::double_read(),
+            double_write: Self::double_write(),
+            int_array_read: Self::int_array_read(),
+            int_array_write: Self::int_array_write(),
+            float_array_read: Self::float_array_read(),
+            float_array_write: Self::float_array_write(),
+            byte_array_read: Self::byte_array_read(),
+            byte_array_write: Self::byte_array_write(),
+        };
+        let id = register(
+            name,
+            T::sim_type(),
+            A::writeable(),
+            callbacks,
+            slot_ptr as *mut c_void,
+        )?;
+        #[cfg(feature = "drop-diagnostics")]
+        crate::diagnostics::created("OwnedData", slot_ptr);
         Ok(OwnedData {
             id,
-            value: value_box,
+            slot,
             access_phantom: PhantomData,
         })
     }
 
-    /// Returns 1 if this dataref should be writeable by other plugins and X-Plane
-    fn writeable() -> i32 {
-        if A::writeable() {
-            1
-        } else {
-            0
-        }
+    /// Starts or stops counting reads and writes by X-Plane and other plugins
+    ///
+    /// Counting is disabled by default. Reads and writes by this plugin through this object
+    /// are not counted.
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.slot.counters.enabled.set(enabled);
+    }
+
+    /// Returns the number of reads and writes counted since counting was enabled or the
+    /// counts were reset
+    pub fn stats(&self) -> AccessStats {
+        self.slot.counters.stats()
     }
+
+    /// Sets the read and write counts to zero
+    pub fn reset_stats(&mut self) {
+        self.slot.counters.reset();
+    }
+
+    /// Sets a function that is called with the new value after X-Plane or another plugin
+    /// writes this dataref
+    ///
+    /// This replaces any previous function. For array datarefs, the function is called after
+    /// each write with the whole array, even if only some elements changed. Writes by this
+    /// plugin through this object do not call the function.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::data::owned::OwnedData;
+    /// use xplm::data::ReadWrite;
+    ///
+    /// let mut volume: OwnedData<f32, ReadWrite> =
+    ///     OwnedData::create("com/me/radio/volume").unwrap();
+    /// volume.on_write(|volume: &f32| {
+    ///     xplm::debugln!("Volume changed to {}", volume);
+    /// });
+    /// ```
+    pub fn on_write<F>(&mut self, mut hook: F)
+    where
+        T: 'static,
+        T::Storage: Borrow<T>,
+        F: FnMut(&T) + 'static,
+    {
+        let hook: WriteHook = Box::new(move |value: *const c_void| {
+            let value = unsafe { &*value.cast::<T::Storage>() };
+            hook(value.borrow())
+        });
+        self.slot.on_write.set(Some(hook));
+    }
+
+    /// Removes the function set with [`on_write`](OwnedData::on_write)
+    pub fn clear_on_write(&mut self) {
+        self.slot.on_write.set(None);
+    }
+
     fn int_read() -> XPLMGetDatai_f {
         if T::sim_type() & xplmType_Int as i32 != 0 {
             Some(int_read)
@@ -169,46 +216,752 @@ impl<T: DataType + ?Sized, A: Access> OwnedData<T, A> {
     }
 }
 
+/// Array length changes
+///
+/// X-Plane and other plugins see the new length the next time they read the dataref.
+impl<T, A> OwnedData<[T], A>
+where
+    T: Clone,
+    [T]: DataType<Storage = Vec<T>>,
+{
+    /// Changes the length of this dataref
+    ///
+    /// If the new length is greater than the current length, new elements are set to value.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::data::owned::OwnedData;
+    /// use xplm::data::ArrayRead;
+    ///
+    /// let mut temperatures: OwnedData<[f32]> =
+    ///     OwnedData::create_with_value("com/me/cylinder_temperatures", &[15.0; 4][..]).unwrap();
+    /// // Six cylinders
+    /// temperatures.resize(6, 15.0);
+    /// assert_eq!(temperatures.len(), 6);
+    /// ```
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        self.slot.value.resize(new_len, value);
+    }
+
+    /// Adds an element to the end of this dataref
+    pub fn push(&mut self, value: T) {
+        self.slot.value.push(value);
+    }
+
+    /// Removes the last element of this dataref and returns it, or returns None if this
+    /// dataref is empty
+    pub fn pop(&mut self) -> Option<T> {
+        self.slot.value.pop()
+    }
+
+    /// Shortens this dataref to the provided length
+    ///
+    /// This has no effect if the dataref is already shorter.
+    pub fn truncate(&mut self, len: usize) {
+        self.slot.value.truncate(len);
+    }
+
+    /// Replaces all values in this dataref, changing its length to match
+    pub fn replace(&mut self, values: &[T]) {
+        self.slot.value.clear();
+        self.slot.value.extend_from_slice(values);
+    }
+}
+
 impl<T: DataType + ?Sized, A> Drop for OwnedData<T, A> {
     fn drop(&mut self) {
+        #[cfg(feature = "drop-diagnostics")]
+        crate::diagnostics::dropped("OwnedData", &*self.slot);
+        crate::panics::forget(&*self.slot);
+        unsafe { XPLMUnregisterDataAccessor(self.id) }
+    }
+}
+
+/// A byte array dataref owned by this plugin that contains a null-terminated string
+///
+/// The dataref has a capacity that can be larger than the current string, so that X-Plane
+/// and other plugins can write longer strings. Its length, as seen by readers, is the
+/// capacity. The string ends at the first null byte.
+///
+/// The dataref is removed when this object is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xplm::data::owned::OwnedString;
+/// use xplm::data::{ReadWrite, StringRead};
+///
+/// let mut callsign: OwnedString<ReadWrite> =
+///     OwnedString::create_with_value("com/me/callsign", "N123AB", 32).unwrap();
+/// callsign.set_string("N456CD");
+/// // Other plugins may also change the callsign
+/// let current = callsign.get_as_string().unwrap();
+/// ```
+pub struct OwnedString<A = ReadOnly> {
+    data: OwnedData<[u8], A>,
+}
+
+impl<A: Access> OwnedString<A> {
+    /// Creates a new dataref with the provided name and capacity in bytes, containing an
+    /// empty string
+    pub fn create(name: &str, capacity: usize) -> Result<Self, CreateError> {
+        Self::create_with_value(name, "", capacity)
+    }
+
+    /// Creates a new dataref with the provided name, value, and capacity in bytes
+    ///
+    /// If the value and its null terminator do not fit in the capacity, the capacity is
+    /// increased.
+    pub fn create_with_value(
+        name: &str,
+        value: &str,
+        capacity: usize,
+    ) -> Result<Self, CreateError> {
+        let mut bytes = vec![0u8; capacity];
+        store_string(&mut bytes, value);
+        Ok(OwnedString {
+            data: OwnedData::create_with_value(name, &bytes[..])?,
+        })
+    }
+
+    /// Returns the capacity of this dataref in bytes, including the null terminator
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Changes the capacity of this dataref
+    ///
+    /// If the capacity is reduced, the string may be truncated. The last byte is always a
+    /// null terminator.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.data.resize(capacity, 0);
+        if let Some(last) = self.data.slot.value.last_mut() {
+            *last = 0;
+        }
+    }
+
+    /// Sets the string in this dataref
+    ///
+    /// If the value and its null terminator do not fit in the capacity, the capacity is
+    /// increased. If the value contains a null byte, readers see only the part before it.
+    pub fn set_string(&mut self, value: &str) {
+        store_string(&mut self.data.slot.value, value);
+    }
+
+    /// Sets a function that is called with the new string after X-Plane or another plugin
+    /// writes this dataref
+    ///
+    /// If the bytes before the first null are not valid UTF-8, invalid sequences are replaced
+    /// with the replacement character. See [`OwnedData::on_write`] for details.
+    pub fn on_write<F>(&mut self, mut hook: F)
+    where
+        F: FnMut(&str) + 'static,
+    {
+        self.data.on_write(move |bytes: &[u8]| {
+            let length = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            hook(&String::from_utf8_lossy(&bytes[..length]))
+        });
+    }
+
+    /// Removes the function set with [`on_write`](OwnedString::on_write)
+    pub fn clear_on_write(&mut self) {
+        self.data.clear_on_write();
+    }
+}
+
+impl<A> ArrayRead<[u8]> for OwnedString<A> {
+    fn get(&self, dest: &mut [u8]) -> usize {
+        self.data.get(dest)
+    }
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl<A> ArrayReadWrite<[u8]> for OwnedString<A> {
+    fn set(&mut self, values: &[u8]) {
+        self.data.set(values)
+    }
+}
+
+/// Copies a string and a null terminator into a buffer and sets the remaining bytes to null,
+/// growing the buffer if it is too small
+fn store_string(buffer: &mut Vec<u8>, value: &str) {
+    let length = value.len() + 1;
+    if buffer.len() < length {
+        buffer.resize(length, 0);
+    }
+    let (string_bytes, rest) = buffer.split_at_mut(value.len());
+    string_bytes.copy_from_slice(value.as_bytes());
+    rest.fill(0);
+}
+
+/// The number of times that X-Plane and other plugins have read and written a dataref
+///
+/// Array length queries are not counted as reads.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AccessStats {
+    /// The number of reads
+    pub reads: u64,
+    /// The number of writes
+    pub writes: u64,
+}
+
+/// Access counters that the accessor callbacks update
+#[derive(Default)]
+struct Counters {
+    /// If reads and writes are being counted
+    enabled: Cell<bool>,
+    reads: Cell<u64>,
+    writes: Cell<u64>,
+}
+
+impl Counters {
+    fn read(&self) {
+        if self.enabled.get() {
+            self.reads.set(self.reads.get().wrapping_add(1));
+        }
+    }
+    fn write(&self) {
+        if self.enabled.get() {
+            self.writes.set(self.writes.get().wrapping_add(1));
+        }
+    }
+    fn stats(&self) -> AccessStats {
+        AccessStats {
+            reads: self.reads.get(),
+            writes: self.writes.get(),
+        }
+    }
+    fn reset(&self) {
+        self.reads.set(0);
+        self.writes.set(0);
+    }
+}
+
+/// A function called with a pointer to the value in a slot after the value is written
+type WriteHook = Box<dyn FnMut(*const c_void)>;
+
+/// A value, its access counters, and its write hook, provided as the refcon to the accessor
+/// callbacks
+///
+/// The counters and hook come first. Both have sizes that are multiples of 8, so the value
+/// has the same offset for every storage type and the callbacks do not need to know the exact
+/// storage type.
+#[repr(C)]
+struct Slot<S> {
+    counters: Counters,
+    on_write: Cell<Option<WriteHook>>,
+    value: S,
+}
+
+impl<S> Slot<S> {
+    fn new(value: S) -> Self {
+        Slot {
+            counters: Counters::default(),
+            on_write: Cell::new(None),
+            value,
+        }
+    }
+
+    /// Calls the write hook, if any
+    ///
+    /// This must be called after the value has been written.
+    unsafe fn written(slot: *const Slot<S>) {
+        if let Some(mut hook) = (*slot).on_write.take() {
+            hook(ptr::addr_of!((*slot).value).cast());
+            // Keep any hook that was set while this one was running
+            let replacement = (*slot).on_write.take();
+            (*slot).on_write.set(replacement.or(Some(hook)));
+        }
+    }
+}
+
+/// The accessor callbacks for a dataref, with None for unsupported types
+struct Callbacks {
+    int_read: XPLMGetDatai_f,
+    int_write: XPLMSetDatai_f,
+    float_read: XPLMGetDataf_f,
+    float_write: XPLMSetDataf_f,
+    double_read: XPLMGetDatad_f,
+    double_write: XPLMSetDatad_f,
+    int_array_read: XPLMGetDatavi_f,
+    int_array_write: XPLMSetDatavi_f,
+    float_array_read: XPLMGetDatavf_f,
+    float_array_write: XPLMSetDatavf_f,
+    byte_array_read: XPLMGetDatab_f,
+    byte_array_write: XPLMSetDatab_f,
+}
+
+impl Callbacks {
+    /// Returns callbacks that support no types
+    fn none() -> Self {
+        Callbacks {
+            int_read: None,
+            int_write: None,
+            float_read: None,
+            float_write: None,
+            double_read: None,
+            double_write: None,
+            int_array_read: None,
+            int_array_write: None,
+            float_array_read: None,
+            float_array_write: None,
+            byte_array_read: None,
+            byte_array_write: None,
+        }
+    }
+}
+
+/// Registers a dataref with the provided callbacks
+///
+/// The same refcon is provided to the read and write callbacks. This returns an error if
+/// the name contains a null byte or a dataref with the name already exists.
+fn register(
+    name: &str,
+    sim_type: XPLMDataTypeID,
+    writeable: bool,
+    callbacks: Callbacks,
+    refcon: *mut c_void,
+) -> Result<XPLMDataRef, CreateError> {
+    let name_c = CString::new(name)?;
+
+    let existing = unsafe { XPLMFindDataRef(name_c.as_ptr()) };
+    if !existing.is_null() {
+        return Err(CreateError::Exists);
+    }
+
+    let id = unsafe {
+        XPLMRegisterDataAccessor(
+            name_c.as_ptr(),
+            sim_type,
+            writeable as c_int,
+            callbacks.int_read,
+            callbacks.int_write,
+            callbacks.float_read,
+            callbacks.float_write,
+            callbacks.double_read,
+            callbacks.double_write,
+            callbacks.int_array_read,
+            callbacks.int_array_write,
+            callbacks.float_array_read,
+            callbacks.float_array_write,
+            callbacks.byte_array_read,
+            callbacks.byte_array_write,
+            refcon,
+            refcon,
+        )
+    };
+    assert!(!id.is_null());
+    Ok(id)
+}
+
+/// Provides the values of a [`CustomData`] dataref
+///
+/// Each read method returns None by default. The dataref supports the types returned by
+/// [`types`](DataAccessor::types), which are found once, when the dataref is created. The
+/// write methods are only called if [`writeable`](DataAccessor::writeable) returns true.
+pub trait DataAccessor: 'static {
+    /// Returns the types that this dataref supports
+    ///
+    /// The default implementation calls each read method once and returns the types for
+    /// which the accessor returned Some. An accessor whose values may not be available yet
+    /// when the dataref is created should override this, so that a type is not lost because
+    /// a read method returned None at that time.
+    fn types(&mut self) -> DataTypes {
+        accessor_types(self)
+    }
+
+    /// Returns true if X-Plane and other plugins can write this dataref
+    ///
+    /// The default implementation returns false.
+    fn writeable(&self) -> bool {
+        false
+    }
+
+    /// Returns the value as an integer
+    fn int(&mut self) -> Option<i32> {
+        None
+    }
+    /// Sets the value from an integer
+    fn set_int(&mut self, _value: i32) {}
+
+    /// Returns the value as a float
+    fn float(&mut self) -> Option<f32> {
+        None
+    }
+    /// Sets the value from a float
+    fn set_float(&mut self, _value: f32) {}
+
+    /// Returns the value as a double
+    fn double(&mut self) -> Option<f64> {
+        None
+    }
+    /// Sets the value from a double
+    fn set_double(&mut self, _value: f64) {}
+
+    /// Returns the value as an array of integers
+    fn int_array(&mut self) -> Option<&[i32]> {
+        None
+    }
+    /// Sets some integer array elements, starting at offset
+    fn set_int_array(&mut self, _offset: usize, _values: &[i32]) {}
+
+    /// Returns the value as an array of floats
+    fn float_array(&mut self) -> Option<&[f32]> {
+        None
+    }
+    /// Sets some float array elements, starting at offset
+    fn set_float_array(&mut self, _offset: usize, _values: &[f32]) {}
+
+    /// Returns the value as an array of bytes
+    fn bytes(&mut self) -> Option<&[u8]> {
+        None
+    }
+    /// Sets some bytes, starting at offset
+    fn set_bytes(&mut self, _offset: usize, _values: &[u8]) {}
+}
+
+/// A dataref owned by this plugin, with values provided by a [`DataAccessor`]
+///
+/// Unlike [`OwnedData`], this does not store a value. The accessor is called every time
+/// X-Plane or another plugin reads or writes the dataref, so it can compute values from
+/// other datarefs or filter values that are written.
+///
+/// The dataref is removed when this object is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xplm::data::borrowed::DataRef;
+/// use xplm::data::owned::{CustomData, DataAccessor};
+/// use xplm::data::ArrayRead;
+///
+/// struct BusVolts {
+///     battery_volts: DataRef<[f32]>,
+/// }
+///
+/// impl DataAccessor for BusVolts {
+///     fn float(&mut self) -> Option<f32> {
+///         // The highest battery voltage
+///         Some(self.battery_volts.as_vec().into_iter().fold(0.0, f32::max))
+///     }
+/// }
+///
+/// let accessor = BusVolts {
+///     battery_volts: DataRef::find("sim/cockpit2/electrical/battery_voltage_actual_volts")
+///         .unwrap(),
+/// };
+/// let bus_volts = CustomData::create("com/me/bus_volts", accessor).unwrap();
+/// ```
+pub struct CustomData<D> {
+    /// The dataref handle
+    id: XPLMDataRef,
+    /// The accessor and access counters
+    ///
+    /// This is boxed so that it will have a constant memory location that is
+    /// provided as a refcon to the callbacks.
+    slot: Box<Slot<D>>,
+}
+
+impl<D: DataAccessor> CustomData<D> {
+    /// Creates a new dataref with the provided name and accessor
+    pub fn create(name: &str, accessor: D) -> Result<Self, CreateError> {
+        let mut slot = Box::new(Slot::new(accessor));
+        let types = slot.value.types().bits();
+        let write = slot.value.writeable();
+        let supports = |sim_type: XPLMDataTypeID| types & sim_type != 0;
+        let writes = |sim_type: XPLMDataTypeID| write && supports(sim_type);
+        let callbacks = Callbacks {
+            int_read: supports(xplmType_Int as _).then_some(custom_int_read::<D>),
+            int_write: writes(xplmType_Int as _).then_some(custom_int_write::<D>),
+            float_read: supports(xplmType_Float as _).then_some(custom_float_read::<D>),
+            float_write: writes(xplmType_Float as _).then_some(custom_float_write::<D>),
+            double_read: supports(xplmType_Double as _).then_some(custom_double_read::<D>),
+            double_write: writes(xplmType_Double as _).then_some(custom_double_write::<D>),
+            int_array_read: supports(xplmType_IntArray as _).then_some(custom_int_array_read::<D>),
+            int_array_write: writes(xplmType_IntArray as _).then_some(custom_int_array_write::<D>),
+            float_array_read: supports(xplmType_FloatArray as _)
+                .then_some(custom_float_array_read::<D>),
+            float_array_write: writes(xplmType_FloatArray as _)
+                .then_some(custom_float_array_write::<D>),
+            byte_array_read: supports(xplmType_Data as _).then_some(custom_byte_array_read::<D>),
+            byte_array_write: writes(xplmType_Data as _).then_some(custom_byte_array_write::<D>),
+        };
+        let slot_ptr: *mut Slot<D> = slot.as_mut();
+        let id = register(name, types, write, callbacks, slot_ptr as *mut c_void)?;
+        Ok(CustomData { id, slot })
+    }
+
+    /// Returns a reference to the accessor
+    pub fn accessor(&self) -> &D {
+        &self.slot.value
+    }
+
+    /// Returns a mutable reference to the accessor
+    pub fn accessor_mut(&mut self) -> &mut D {
+        &mut self.slot.value
+    }
+
+    /// Starts or stops counting reads and writes by X-Plane and other plugins
+    ///
+    /// Counting is disabled by default.
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.slot.counters.enabled.set(enabled);
+    }
+
+    /// Returns the number of reads and writes counted since counting was enabled or the
+    /// counts were reset
+    pub fn stats(&self) -> AccessStats {
+        self.slot.counters.stats()
+    }
+
+    /// Sets the read and write counts to zero
+    pub fn reset_stats(&mut self) {
+        self.slot.counters.reset();
+    }
+}
+
+impl<D> Drop for CustomData<D> {
+    fn drop(&mut self) {
+        #[cfg(feature = "drop-diagnostics")]
+        crate::diagnostics::dropped("CustomData", &*self.slot);
+        crate::panics::forget(&*self.slot);
+        unsafe { XPLMUnregisterDataAccessor(self.id) }
+    }
+}
+
+/// An array dataref owned by this plugin, with values stored in a shared vector
+///
+/// X-Plane and other plugins read and write the shared vector directly, so a subsystem that
+/// already keeps its state in an `Arc<RwLock<Vec<T>>>` can publish it without copying it
+/// every frame. The vector may be updated, and its length changed, from any thread.
+///
+/// Element types i32, f32, and u8 are supported.
+///
+/// The dataref is removed when this object is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::{Arc, RwLock};
+/// use std::thread;
+/// use xplm::data::owned::SharedArrayData;
+///
+/// let temperatures = Arc::new(RwLock::new(vec![15.0f32; 8]));
+/// let dataref = SharedArrayData::<f32>::create(
+///     "com/me/cylinder_temperatures",
+///     Arc::clone(&temperatures),
+/// )
+/// .unwrap();
+/// thread::spawn(move || {
+///     // Simulate the engine
+///     temperatures.write().unwrap()[0] = 180.0;
+/// });
+/// ```
+pub struct SharedArrayData<T, A = ReadOnly> {
+    /// The dataref handle
+    id: XPLMDataRef,
+    /// The storage and access counters
+    ///
+    /// This is boxed so that it will have a constant memory location that is
+    /// provided as a refcon to the callbacks.
+    slot: Box<Slot<Arc<RwLock<Vec<T>>>>>,
+    /// Data access phantom data
+    access_phantom: PhantomData<A>,
+}
+
+impl<T, A> SharedArrayData<T, A> {
+    /// Creates a dataref with the provided callbacks
+    fn create_with_callbacks(
+        name: &str,
+        sim_type: XPLMDataTypeID,
+        writeable: bool,
+        storage: Arc<RwLock<Vec<T>>>,
+        callbacks: Callbacks,
+    ) -> Result<Self, CreateError> {
+        let mut slot = Box::new(Slot::new(storage));
+        let slot_ptr: *mut Slot<Arc<RwLock<Vec<T>>>> = slot.as_mut();
+        let id = register(
+            name,
+            sim_type,
+            writeable,
+            callbacks,
+            slot_ptr as *mut c_void,
+        )?;
+        Ok(SharedArrayData {
+            id,
+            slot,
+            access_phantom: PhantomData,
+        })
+    }
+
+    /// Returns the shared storage
+    pub fn storage(&self) -> &Arc<RwLock<Vec<T>>> {
+        &self.slot.value
+    }
+
+    /// Starts or stops counting reads and writes by X-Plane and other plugins
+    ///
+    /// Counting is disabled by default.
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.slot.counters.enabled.set(enabled);
+    }
+
+    /// Returns the number of reads and writes counted since counting was enabled or the
+    /// counts were reset
+    pub fn stats(&self) -> AccessStats {
+        self.slot.counters.stats()
+    }
+
+    /// Sets the read and write counts to zero
+    pub fn reset_stats(&mut self) {
+        self.slot.counters.reset();
+    }
+}
+
+impl<T, A> Drop for SharedArrayData<T, A> {
+    fn drop(&mut self) {
+        #[cfg(feature = "drop-diagnostics")]
+        crate::diagnostics::dropped("SharedArrayData", &*self.slot);
+        crate::panics::forget(&*self.slot);
         unsafe { XPLMUnregisterDataAccessor(self.id) }
     }
 }
 
+macro_rules! impl_shared_array {
+    ($native_type:ty, $sim_type:expr, $read_field:ident: $read:ident, $write_field:ident: $write:ident) => {
+        impl<A: Access> SharedArrayData<$native_type, A> {
+            /// Creates a new dataref with the provided name and storage
+            pub fn create(
+                name: &str,
+                storage: Arc<RwLock<Vec<$native_type>>>,
+            ) -> Result<Self, CreateError> {
+                let callbacks = Callbacks {
+                    $read_field: Some($read),
+                    $write_field: A::writeable().then_some($write),
+                    ..Callbacks::none()
+                };
+                Self::create_with_callbacks(
+                    name,
+                    $sim_type as XPLMDataTypeID,
+                    A::writeable(),
+                    storage,
+                    callbacks,
+                )
+            }
+        }
+
+        impl<A> ArrayRead<[$native_type]> for SharedArrayData<$native_type, A> {
+            fn get(&self, dest: &mut [$native_type]) -> usize {
+                let values = read_lock(&self.slot.value);
+                let copy_length = cmp::min(dest.len(), values.len());
+                dest[..copy_length].copy_from_slice(&values[..copy_length]);
+                copy_length
+            }
+            fn len(&self) -> usize {
+                read_lock(&self.slot.value).len()
+            }
+            fn get_range(&self, offset: usize, dest: &mut [$native_type]) -> usize {
+                read_range(&read_lock(&self.slot.value), offset, dest)
+            }
+        }
+
+        impl<A> ArrayReadWrite<[$native_type]> for SharedArrayData<$native_type, A> {
+            fn set(&mut self, values: &[$native_type]) {
+                let mut storage = write_lock(&self.slot.value);
+                let copy_length = cmp::min(values.len(), storage.len());
+                storage[..copy_length].copy_from_slice(&values[..copy_length]);
+            }
+            fn set_range(&mut self, offset: usize, values: &[$native_type]) {
+                write_range(&mut write_lock(&self.slot.value), offset, values);
+            }
+        }
+    };
+}
+
+impl_shared_array!(i32, xplmType_IntArray, int_array_read: shared_int_array_read, int_array_write: shared_int_array_write);
+impl_shared_array!(f32, xplmType_FloatArray, float_array_read: shared_float_array_read, float_array_write: shared_float_array_write);
+impl_shared_array!(u8, xplmType_Data, byte_array_read: shared_byte_array_read, byte_array_write: shared_byte_array_write);
+
+/// Locks shared storage for reading, ignoring any panic that happened while another thread
+/// held the lock
+fn read_lock<T>(storage: &RwLock<Vec<T>>) -> RwLockReadGuard<'_, Vec<T>> {
+    storage.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Locks shared storage for writing, ignoring any panic that happened while another thread
+/// held the lock
+fn write_lock<T>(storage: &RwLock<Vec<T>>) -> RwLockWriteGuard<'_, Vec<T>> {
+    storage.write().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Returns the data types for which an accessor's read methods return Some
+fn accessor_types<D: DataAccessor + ?Sized>(accessor: &mut D) -> DataTypes {
+    let mut types = DataTypes::empty();
+    if accessor.int().is_some() {
+        types |= DataTypes::INT;
+    }
+    if accessor.float().is_some() {
+        types |= DataTypes::FLOAT;
+    }
+    if accessor.double().is_some() {
+        types |= DataTypes::DOUBLE;
+    }
+    if accessor.int_array().is_some() {
+        types |= DataTypes::INT_ARRAY;
+    }
+    if accessor.float_array().is_some() {
+        types |= DataTypes::FLOAT_ARRAY;
+    }
+    if accessor.bytes().is_some() {
+        types |= DataTypes::DATA;
+    }
+    types
+}
+
 // DataRead and DataReadWrite
 macro_rules! impl_read_write {
     (for $native_type:ty) => {
         impl<A> DataRead<$native_type> for OwnedData<$native_type, A> {
             fn get(&self) -> $native_type {
-                *self.value
+                self.slot.value
             }
         }
         impl<A> DataReadWrite<$native_type> for OwnedData<$native_type, A> {
             fn set(&mut self, value: $native_type) {
-                *self.value = value;
+                self.slot.value = value;
             }
         }
     };
     (for array [$native_type:ty]) => {
         impl<A> ArrayRead<[$native_type]> for OwnedData<[$native_type], A> {
             fn get(&self, dest: &mut [$native_type]) -> usize {
-                let copy_length = cmp::min(dest.len(), self.value.len());
+                let copy_length = cmp::min(dest.len(), self.slot.value.len());
                 let dest_sub = &mut dest[..copy_length];
-                let value_sub = &self.value[..copy_length];
+                let value_sub = &self.slot.value[..copy_length];
                 dest_sub.copy_from_slice(value_sub);
                 copy_length
             }
             fn len(&self) -> usize {
-                self.value.len()
+                self.slot.value.len()
+            }
+            fn get_range(&self, offset: usize, dest: &mut [$native_type]) -> usize {
+                read_range(&self.slot.value, offset, dest)
             }
         }
         impl<A> ArrayReadWrite<[$native_type]> for OwnedData<[$native_type], A> {
             fn set(&mut self, values: &[$native_type]) {
-                let copy_length = cmp::min(values.len(), self.value.len());
+                let copy_length = cmp::min(values.len(), self.slot.value.len());
                 let src_sub = &values[..copy_length];
-                let values_sub = &mut self.value[..copy_length];
+                let values_sub = &mut self.slot.value[..copy_length];
                 values_sub.copy_from_slice(src_sub);
             }
+            fn set_range(&mut self, offset: usize, values: &[$native_type]) {
+                write_range(&mut self.slot.value, offset, values);
+            }
         }
     };
 }
@@ -230,6 +983,7 @@ impl_read_write!(for array [i8]);
 
 /// Errors that can occur when creating a DataRef
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum CreateError {
     /// The provided DataRef name contained a null byte
     #[error("Null byte in dataref name")]
@@ -241,42 +995,75 @@ pub enum CreateError {
 }
 
 // Read/write callbacks
-// The refcon is a pointer to the data
+// The refcon is a pointer to a Slot that contains the data
 
 /// Integer read callback
 unsafe extern "C" fn int_read(refcon: *mut c_void) -> c_int {
-    let data_ptr = refcon as *mut c_int;
-    *data_ptr
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return 0;
+    }
+    let slot = refcon as *mut Slot<c_int>;
+    (*slot).counters.read();
+    (*slot).value
 }
 
 /// Integer write callback
 unsafe extern "C" fn int_write(refcon: *mut c_void, value: c_int) {
-    let data_ptr = refcon as *mut c_int;
-    *data_ptr = value;
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return;
+    }
+    let slot = refcon as *mut Slot<c_int>;
+    (*slot).counters.write();
+    (*slot).value = value;
+    crate::panics::contain("dataref", slot, (), || Slot::written(slot));
 }
 
 /// Float read callback
 unsafe extern "C" fn float_read(refcon: *mut c_void) -> f32 {
-    let data_ptr = refcon as *mut f32;
-    *data_ptr
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return 0.0;
+    }
+    let slot = refcon as *mut Slot<f32>;
+    (*slot).counters.read();
+    (*slot).value
 }
 
 /// Float write callback
 unsafe extern "C" fn float_write(refcon: *mut c_void, value: f32) {
-    let data_ptr = refcon as *mut f32;
-    *data_ptr = value;
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return;
+    }
+    let slot = refcon as *mut Slot<f32>;
+    (*slot).counters.write();
+    (*slot).value = value;
+    crate::panics::contain("dataref", slot, (), || Slot::written(slot));
 }
 
 /// Double read callback
 unsafe extern "C" fn double_read(refcon: *mut c_void) -> f64 {
-    let data_ptr = refcon as *mut f64;
-    *data_ptr
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return 0.0;
+    }
+    let slot = refcon as *mut Slot<f64>;
+    (*slot).counters.read();
+    (*slot).value
 }
 
 /// Double write callback
 unsafe extern "C" fn double_write(refcon: *mut c_void, value: f64) {
-    let data_ptr = refcon as *mut f64;
-    *data_ptr = value;
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return;
+    }
+    let slot = refcon as *mut Slot<f64>;
+    (*slot).counters.write();
+    (*slot).value = value;
+    crate::panics::contain("dataref", slot, (), || Slot::written(slot));
 }
 
 /// Integer array read callback
@@ -350,20 +1137,32 @@ unsafe fn array_read<T: Copy>(
     offset: c_int,
     max: c_int,
 ) -> c_int {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return 0;
+    }
+    let slot = refcon as *const Slot<Vec<T>>;
+    if !values.is_null() {
+        (*slot).counters.read();
+    }
+    slice_read(&(*slot).value, values, offset, max)
+}
+
+/// If values is null, returns the length of source.
+/// Otherwise, copies up to max elements from source starting at offset offset into values.
+#[inline]
+unsafe fn slice_read<T: Copy>(source: &[T], values: *mut T, offset: c_int, max: c_int) -> c_int {
     let offset = offset as usize;
     let max = max as usize;
-    let dataref_content = refcon as *const Vec<T>;
-    let dataref_length = (*dataref_content).len();
     if values.is_null() {
-        dataref_length as c_int
+        source.len() as c_int
     } else {
         // Check that offset is within dataref content
-        if offset >= dataref_length {
+        if offset >= source.len() {
             return 0;
         }
-        let dataref_offset = (*dataref_content).as_ptr().add(offset);
-        let copy_length = cmp::min(max, dataref_length - offset);
-        ptr::copy_nonoverlapping(dataref_offset, values, copy_length);
+        let copy_length = cmp::min(max, source.len() - offset);
+        ptr::copy_nonoverlapping(source.as_ptr().add(offset), values, copy_length);
         copy_length as c_int
     }
 }
@@ -373,7 +1172,13 @@ unsafe fn array_read<T: Copy>(
 unsafe fn array_write<T: Copy>(refcon: *mut c_void, values: *const T, offset: c_int, max: c_int) {
     let offset = offset as usize;
     let max = max as usize;
-    let dataref_content = refcon as *mut Vec<T>;
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("OwnedData", refcon) {
+        return;
+    }
+    let slot = refcon as *mut Slot<Vec<T>>;
+    (*slot).counters.write();
+    let dataref_content: *mut Vec<T> = &mut (*slot).value;
     let dataref_length = (*dataref_content).len();
 
     if offset >= dataref_length {
@@ -382,4 +1187,413 @@ unsafe fn array_write<T: Copy>(refcon: *mut c_void, values: *const T, offset: c_
     let dataref_offset = (*dataref_content).as_mut_ptr().add(offset);
     let copy_length = cmp::min(max, dataref_length - offset);
     ptr::copy_nonoverlapping(values, dataref_offset, copy_length);
+    crate::panics::contain("dataref", slot, (), || Slot::written(slot));
+}
+
+// Shared storage callbacks
+// The refcon is a pointer to a Slot that contains the Arc<RwLock<Vec<T>>>
+
+/// Shared integer array read callback
+unsafe extern "C" fn shared_int_array_read(
+    refcon: *mut c_void,
+    values: *mut c_int,
+    offset: c_int,
+    max: c_int,
+) -> c_int {
+    shared_array_read::<i32>(refcon, values, offset, max)
+}
+
+/// Shared integer array write callback
+unsafe extern "C" fn shared_int_array_write(
+    refcon: *mut c_void,
+    values: *mut c_int,
+    offset: c_int,
+    max: c_int,
+) {
+    shared_array_write::<i32>(refcon, values, offset, max);
+}
+
+/// Shared float array read callback
+unsafe extern "C" fn shared_float_array_read(
+    refcon: *mut c_void,
+    values: *mut f32,
+    offset: c_int,
+    max: c_int,
+) -> c_int {
+    shared_array_read::<f32>(refcon, values, offset, max)
+}
+
+/// Shared float array write callback
+unsafe extern "C" fn shared_float_array_write(
+    refcon: *mut c_void,
+    values: *mut f32,
+    offset: c_int,
+    max: c_int,
+) {
+    shared_array_write::<f32>(refcon, values, offset, max);
+}
+
+/// Shared byte array read callback
+unsafe extern "C" fn shared_byte_array_read(
+    refcon: *mut c_void,
+    values: *mut c_void,
+    offset: c_int,
+    max: c_int,
+) -> c_int {
+    shared_array_read::<u8>(refcon, values as *mut u8, offset, max)
+}
+
+/// Shared byte array write callback
+unsafe extern "C" fn shared_byte_array_write(
+    refcon: *mut c_void,
+    values: *mut c_void,
+    offset: c_int,
+    max: c_int,
+) {
+    shared_array_write::<u8>(refcon, values as *const u8, offset, max);
+}
+
+/// Like array_read, but reads from shared storage
+#[inline]
+unsafe fn shared_array_read<T: Copy>(
+    refcon: *mut c_void,
+    values: *mut T,
+    offset: c_int,
+    max: c_int,
+) -> c_int {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("SharedArrayData", refcon) {
+        return 0;
+    }
+    let slot = refcon as *const Slot<Arc<RwLock<Vec<T>>>>;
+    if !values.is_null() {
+        (*slot).counters.read();
+    }
+    slice_read(&read_lock(&(*slot).value), values, offset, max)
+}
+
+/// Like array_write, but writes to shared storage
+#[inline]
+unsafe fn shared_array_write<T: Copy>(
+    refcon: *mut c_void,
+    values: *const T,
+    offset: c_int,
+    max: c_int,
+) {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("SharedArrayData", refcon) {
+        return;
+    }
+    let slot = refcon as *const Slot<Arc<RwLock<Vec<T>>>>;
+    let Some(values) = input_slice(values, max) else {
+        return;
+    };
+    (*slot).counters.write();
+    let mut storage = write_lock(&(*slot).value);
+    let offset = offset as usize;
+    if offset >= storage.len() {
+        return;
+    }
+    let copy_length = cmp::min(values.len(), storage.len() - offset);
+    storage[offset..offset + copy_length].copy_from_slice(&values[..copy_length]);
+}
+
+// Custom accessor callbacks
+// The refcon is a pointer to a Slot that contains the accessor
+
+/// Custom integer read callback
+unsafe extern "C" fn custom_int_read<D: DataAccessor>(refcon: *mut c_void) -> c_int {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return 0;
+    }
+    crate::panics::contain("dataref", refcon, 0, || {
+        let slot = refcon as *mut Slot<D>;
+        (*slot).counters.read();
+        (*slot).value.int().unwrap_or_default()
+    })
+}
+
+/// Custom integer write callback
+unsafe extern "C" fn custom_int_write<D: DataAccessor>(refcon: *mut c_void, value: c_int) {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return;
+    }
+    crate::panics::contain("dataref", refcon, (), || {
+        let slot = refcon as *mut Slot<D>;
+        (*slot).counters.write();
+        (*slot).value.set_int(value);
+    })
+}
+
+/// Custom float read callback
+unsafe extern "C" fn custom_float_read<D: DataAccessor>(refcon: *mut c_void) -> f32 {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return 0.0;
+    }
+    crate::panics::contain("dataref", refcon, 0.0, || {
+        let slot = refcon as *mut Slot<D>;
+        (*slot).counters.read();
+        (*slot).value.float().unwrap_or_default()
+    })
+}
+
+/// Custom float write callback
+unsafe extern "C" fn custom_float_write<D: DataAccessor>(refcon: *mut c_void, value: f32) {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return;
+    }
+    crate::panics::contain("dataref", refcon, (), || {
+        let slot = refcon as *mut Slot<D>;
+        (*slot).counters.write();
+        (*slot).value.set_float(value);
+    })
+}
+
+/// Custom double read callback
+unsafe extern "C" fn custom_double_read<D: DataAccessor>(refcon: *mut c_void) -> f64 {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return 0.0;
+    }
+    crate::panics::contain("dataref", refcon, 0.0, || {
+        let slot = refcon as *mut Slot<D>;
+        (*slot).counters.read();
+        (*slot).value.double().unwrap_or_default()
+    })
+}
+
+/// Custom double write callback
+unsafe extern "C" fn custom_double_write<D: DataAccessor>(refcon: *mut c_void, value: f64) {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return;
+    }
+    crate::panics::contain("dataref", refcon, (), || {
+        let slot = refcon as *mut Slot<D>;
+        (*slot).counters.write();
+        (*slot).value.set_double(value);
+    })
+}
+
+/// Custom integer array read callback
+unsafe extern "C" fn custom_int_array_read<D: DataAccessor>(
+    refcon: *mut c_void,
+    values: *mut c_int,
+    offset: c_int,
+    max: c_int,
+) -> c_int {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return 0;
+    }
+    crate::panics::contain("dataref", refcon, 0, || {
+        let slot = refcon as *mut Slot<D>;
+        if !values.is_null() {
+            (*slot).counters.read();
+        }
+        match (*slot).value.int_array() {
+            Some(array) => slice_read(array, values, offset, max),
+            None => 0,
+        }
+    })
+}
+
+/// Custom integer array write callback
+unsafe extern "C" fn custom_int_array_write<D: DataAccessor>(
+    refcon: *mut c_void,
+    values: *mut c_int,
+    offset: c_int,
+    max: c_int,
+) {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return;
+    }
+    crate::panics::contain("dataref", refcon, (), || {
+        if let Some(values) = input_slice(values, max) {
+            let slot = refcon as *mut Slot<D>;
+            (*slot).counters.write();
+            (*slot).value.set_int_array(offset as usize, values);
+        }
+    })
+}
+
+/// Custom float array read callback
+unsafe extern "C" fn custom_float_array_read<D: DataAccessor>(
+    refcon: *mut c_void,
+    values: *mut f32,
+    offset: c_int,
+    max: c_int,
+) -> c_int {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return 0;
+    }
+    crate::panics::contain("dataref", refcon, 0, || {
+        let slot = refcon as *mut Slot<D>;
+        if !values.is_null() {
+            (*slot).counters.read();
+        }
+        match (*slot).value.float_array() {
+            Some(array) => slice_read(array, values, offset, max),
+            None => 0,
+        }
+    })
+}
+
+/// Custom float array write callback
+unsafe extern "C" fn custom_float_array_write<D: DataAccessor>(
+    refcon: *mut c_void,
+    values: *mut f32,
+    offset: c_int,
+    max: c_int,
+) {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return;
+    }
+    crate::panics::contain("dataref", refcon, (), || {
+        if let Some(values) = input_slice(values, max) {
+            let slot = refcon as *mut Slot<D>;
+            (*slot).counters.write();
+            (*slot).value.set_float_array(offset as usize, values);
+        }
+    })
+}
+
+/// Custom byte array read callback
+unsafe extern "C" fn custom_byte_array_read<D: DataAccessor>(
+    refcon: *mut c_void,
+    values: *mut c_void,
+    offset: c_int,
+    max: c_int,
+) -> c_int {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return 0;
+    }
+    crate::panics::contain("dataref", refcon, 0, || {
+        let slot = refcon as *mut Slot<D>;
+        if !values.is_null() {
+            (*slot).counters.read();
+        }
+        match (*slot).value.bytes() {
+            Some(array) => slice_read(array, values as *mut u8, offset, max),
+            None => 0,
+        }
+    })
+}
+
+/// Custom byte array write callback
+unsafe extern "C" fn custom_byte_array_write<D: DataAccessor>(
+    refcon: *mut c_void,
+    values: *mut c_void,
+    offset: c_int,
+    max: c_int,
+) {
+    #[cfg(feature = "drop-diagnostics")]
+    if !crate::diagnostics::callback("CustomData", refcon) {
+        return;
+    }
+    crate::panics::contain("dataref", refcon, (), || {
+        if let Some(values) = input_slice(values as *const u8, max) {
+            let slot = refcon as *mut Slot<D>;
+            (*slot).counters.write();
+            (*slot).value.set_bytes(offset as usize, values);
+        }
+    })
+}
+
+/// Converts values provided to an array write callback into a slice
+///
+/// Returns None if values is null or max is negative.
+#[inline]
+unsafe fn input_slice<'a, T>(values: *const T, max: c_int) -> Option<&'a [T]> {
+    if values.is_null() || max < 0 {
+        None
+    } else {
+        Some(slice::from_raw_parts(values, max as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{store_string, DataAccessor, DataTypes, Slot, WriteHook};
+    use std::cell::Cell;
+    use std::mem;
+    use std::os::raw::{c_int, c_void};
+    use std::rc::Rc;
+
+    /// An accessor whose value is only available after the first update
+    struct Pending(Option<f32>);
+
+    impl DataAccessor for Pending {
+        fn float(&mut self) -> Option<f32> {
+            self.0
+        }
+        fn double(&mut self) -> Option<f64> {
+            Some(1.0)
+        }
+    }
+
+    /// The same accessor, with its types declared
+    struct Declared(Option<f32>);
+
+    impl DataAccessor for Declared {
+        fn types(&mut self) -> DataTypes {
+            DataTypes::FLOAT
+        }
+        fn float(&mut self) -> Option<f32> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_accessor_types() {
+        // Probing loses the float type while the value is not available
+        assert_eq!(Pending(None).types(), DataTypes::DOUBLE);
+        assert_eq!(
+            Pending(Some(0.0)).types(),
+            DataTypes::FLOAT | DataTypes::DOUBLE
+        );
+        assert_eq!(Declared(None).types(), DataTypes::FLOAT);
+    }
+
+    #[test]
+    fn test_slot_value_offset() {
+        let offset = mem::offset_of!(Slot<c_int>, value);
+        assert_eq!(mem::offset_of!(Slot<f32>, value), offset);
+        assert_eq!(mem::offset_of!(Slot<f64>, value), offset);
+        assert_eq!(mem::offset_of!(Slot<bool>, value), offset);
+        assert_eq!(mem::offset_of!(Slot<Vec<u8>>, value), offset);
+    }
+
+    #[test]
+    fn test_write_hook() {
+        let written = Rc::new(Cell::new(0.0f32));
+        let hook_written = Rc::clone(&written);
+        let mut slot = Box::new(Slot::new(1.0f32));
+        let hook: WriteHook =
+            Box::new(move |value: *const c_void| hook_written.set(unsafe { *value.cast::<f32>() }));
+        slot.on_write.set(Some(hook));
+        slot.value = 2.5;
+        unsafe { Slot::written(&*slot) };
+        assert_eq!(written.get(), 2.5);
+        assert!(slot.on_write.take().is_some());
+    }
+
+    #[test]
+    fn test_store_string() {
+        let mut buffer = vec![b'x'; 8];
+        store_string(&mut buffer, "abc");
+        assert_eq!(buffer, b"abc\0\0\0\0\0");
+        store_string(&mut buffer, "too long!");
+        assert_eq!(buffer, b"too long!\0");
+        store_string(&mut buffer, "");
+        assert_eq!(buffer, [0; 10]);
+    }
 }
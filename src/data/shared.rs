@@ -0,0 +1,204 @@
+//! # Shared data
+//!
+//! Shared data is a dataref that is not owned by any plugin. Any number of plugins can share
+//! data with the same name and type, and X-Plane stores its value. Each plugin can be
+//! notified when another plugin changes the value.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::shared::SharedData;
+//! use xplm::data::{DataRead, DataReadWrite};
+//!
+//! let mut mode: SharedData<i32> =
+//!     SharedData::share_with_callback("example/shared/display_mode", || {
+//!         xplm::debugln!("Display mode changed");
+//!     })
+//!     .unwrap();
+//! mode.set(2);
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::ffi::{CString, NulError};
+use std::fmt;
+use std::os::raw::c_void;
+use std::ptr;
+
+use xplm_sys::*;
+
+use super::borrowed::{DataRef, FindError};
+use super::{ArrayRead, ArrayReadWrite, ArrayType, DataRead, DataReadWrite, DataType, ReadWrite};
+
+/// A change callback, boxed so that it has a constant address to use as the refcon
+type ChangeCallback = RefCell<Box<dyn FnMut()>>;
+
+/// Data shared with other plugins
+///
+/// The data is unshared when this object is dropped. X-Plane keeps the value while any
+/// plugin still shares it.
+pub struct SharedData<T: DataType + ?Sized> {
+    /// The data name
+    name: CString,
+    /// The dataref used to read and write the value
+    dataref: DataRef<T, ReadWrite>,
+    /// The change callback, if any
+    callback: Option<Box<ChangeCallback>>,
+}
+
+impl<T: DataType + ?Sized> SharedData<T> {
+    /// Shares data with the provided name and no change callback
+    ///
+    /// Returns an error if the data is already shared with a different type.
+    pub fn share(name: &str) -> Result<Self, ShareError> {
+        Self::share_inner(name, None)
+    }
+
+    /// Shares data with the provided name and calls a callback when the value changes
+    ///
+    /// The callback is called when this plugin or any other plugin changes the value.
+    ///
+    /// Returns an error if the data is already shared with a different type.
+    pub fn share_with_callback<F>(name: &str, callback: F) -> Result<Self, ShareError>
+    where
+        F: FnMut() + 'static,
+    {
+        Self::share_inner(name, Some(Box::new(RefCell::new(Box::new(callback)))))
+    }
+
+    fn share_inner(name: &str, callback: Option<Box<ChangeCallback>>) -> Result<Self, ShareError> {
+        let name_c = CString::new(name)?;
+        let (notify, refcon) = notification(&callback);
+        let status = unsafe { XPLMShareData(name_c.as_ptr(), T::sim_type(), notify, refcon) };
+        if status != 1 {
+            return Err(ShareError::WrongType);
+        }
+        match DataRef::find(name).and_then(DataRef::writeable) {
+            Ok(dataref) => Ok(SharedData {
+                name: name_c,
+                dataref,
+                callback,
+            }),
+            Err(e) => {
+                unsafe { XPLMUnshareData(name_c.as_ptr(), T::sim_type(), notify, refcon) };
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Returns the name of this data
+    pub fn name(&self) -> &str {
+        self.name.to_str().unwrap_or_default()
+    }
+}
+
+impl<T: DataType + ?Sized> Drop for SharedData<T> {
+    fn drop(&mut self) {
+        let (notify, refcon) = notification(&self.callback);
+        unsafe { XPLMUnshareData(self.name.as_ptr(), T::sim_type(), notify, refcon) };
+    }
+}
+
+impl<T: DataType + ?Sized> fmt::Debug for SharedData<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedData")
+            .field("name", &self.name)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+impl<T> DataRead<T> for SharedData<T>
+where
+    T: DataType,
+    DataRef<T, ReadWrite>: DataRead<T>,
+{
+    fn get(&self) -> T {
+        self.dataref.get()
+    }
+}
+
+impl<T> DataReadWrite<T> for SharedData<T>
+where
+    T: DataType,
+    DataRef<T, ReadWrite>: DataReadWrite<T>,
+{
+    fn set(&mut self, value: T) {
+        self.dataref.set(value)
+    }
+}
+
+impl<T> ArrayRead<T> for SharedData<T>
+where
+    T: ArrayType + ?Sized,
+    DataRef<T, ReadWrite>: ArrayRead<T>,
+{
+    fn get(&self, dest: &mut [T::Element]) -> usize {
+        self.dataref.get(dest)
+    }
+    fn len(&self) -> usize {
+        self.dataref.len()
+    }
+    fn get_range(&self, offset: usize, dest: &mut [T::Element]) -> usize
+    where
+        T::Element: Default + Clone,
+    {
+        self.dataref.get_range(offset, dest)
+    }
+}
+
+impl<T> ArrayReadWrite<T> for SharedData<T>
+where
+    T: ArrayType + ?Sized,
+    DataRef<T, ReadWrite>: ArrayReadWrite<T>,
+{
+    fn set(&mut self, values: &[T::Element]) {
+        self.dataref.set(values)
+    }
+    fn set_range(&mut self, offset: usize, values: &[T::Element])
+    where
+        T::Element: Default + Clone,
+    {
+        self.dataref.set_range(offset, values)
+    }
+}
+
+/// Returns the notification function and refcon for a callback
+fn notification(callback: &Option<Box<ChangeCallback>>) -> (XPLMDataChanged_f, *mut c_void) {
+    match callback {
+        Some(callback) => {
+            let refcon: *const ChangeCallback = &**callback;
+            (Some(data_changed), refcon as *mut c_void)
+        }
+        None => (None, ptr::null_mut()),
+    }
+}
+
+/// The change callback provided to X-Plane
+unsafe extern "C" fn data_changed(refcon: *mut c_void) {
+    if super::super::internal::suspended() {
+        return;
+    }
+    let callback = &*(refcon as *const ChangeCallback);
+    // A callback that changes the value is not called again for its own change
+    if let Ok(mut callback) = callback.try_borrow_mut() {
+        super::super::panics::contain("shared data", refcon, (), &mut *callback);
+    }
+}
+
+/// Errors that can occur when sharing data
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum ShareError {
+    /// The provided name contained a null byte
+    #[error("Null byte in shared data name")]
+    Null(#[from] NulError),
+
+    /// The data is already shared with a different type
+    #[error("Shared data already exists with a different type")]
+    WrongType,
+
+    /// The shared data could not be found after it was shared
+    #[error("Shared data not found: {0}")]
+    Find(#[from] FindError),
+}
@@ -0,0 +1,324 @@
+//! # Dataref subscriptions
+//!
+//! Logging and networking code often needs dataref values at a fixed rate that is lower than
+//! the frame rate. A [`Subscriptions`] object reads subscribed datarefs every flight loop
+//! and calls each subscriber at its requested rate with a value that summarizes the samples
+//! taken since the previous call.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::data::subscription::{Reduce, Subscriptions};
+//!
+//! let subscriptions = Subscriptions::new();
+//! let airspeed: DataRef<f32> =
+//!     DataRef::find("sim/flightmodel/position/indicated_airspeed").unwrap();
+//! // Called 10 times per second with the average airspeed
+//! let id = subscriptions.subscribe(airspeed, 10.0, Reduce::Average, |airspeed: f32| {
+//!     xplm::debugln!("Airspeed: {}", airspeed);
+//! });
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::super::flight_loop::{FlightLoop, LoopState};
+use super::DataRead;
+
+/// How the samples taken between two callbacks are combined into one value
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reduce {
+    /// The most recent sample
+    Latest,
+    /// The mean of the samples
+    Average,
+    /// The smallest sample
+    Min,
+    /// The largest sample
+    Max,
+}
+
+/// Values that can be subscribed to
+///
+/// This trait is implemented for f32, f64, and i32.
+pub trait Sample: Copy + 'static {
+    /// Converts this value into an f64 for accumulation
+    fn to_f64(self) -> f64;
+    /// Converts an accumulated value back into this type
+    fn from_f64(value: f64) -> Self;
+}
+
+impl Sample for f32 {
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+impl Sample for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+impl Sample for i32 {
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+    fn from_f64(value: f64) -> Self {
+        value.round() as i32
+    }
+}
+
+/// Identifies a subscription so that it can be removed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// Reads subscribed datarefs every flight loop and calls subscribers at their requested rates
+///
+/// All subscriptions end when this object is dropped.
+pub struct Subscriptions {
+    /// The subscriptions, shared with the flight loop
+    inner: Rc<RefCell<Inner>>,
+    /// The flight loop that samples the datarefs
+    _flight_loop: FlightLoop,
+}
+
+impl Subscriptions {
+    /// Creates an object with no subscriptions and starts its flight loop
+    pub fn new() -> Self {
+        let inner = Rc::new(RefCell::new(Inner::default()));
+        let loop_inner = Rc::clone(&inner);
+        let mut flight_loop =
+            FlightLoop::new(move |state: &mut LoopState| Inner::run(&loop_inner, state));
+        flight_loop.schedule_immediate();
+        Subscriptions {
+            inner,
+            _flight_loop: flight_loop,
+        }
+    }
+
+    /// Subscribes to a dataref
+    ///
+    /// The callback is called rate times per second, but no more than once per flight loop,
+    /// with the samples taken since the previous call combined as specified by reduce.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if rate is not greater than zero.
+    pub fn subscribe<T, D, F>(
+        &self,
+        dataref: D,
+        rate: f32,
+        reduce: Reduce,
+        callback: F,
+    ) -> SubscriptionId
+    where
+        T: Sample,
+        D: DataRead<T> + 'static,
+        F: FnMut(T) + 'static,
+    {
+        let period = period(rate);
+        let mut inner = self.inner.borrow_mut();
+        let id = SubscriptionId(inner.next_id);
+        inner.next_id += 1;
+        inner.subscriptions.push(Subscription {
+            id,
+            period,
+            since_callback: Duration::ZERO,
+            reduce,
+            accumulator: Accumulator::default(),
+            sampler: Box::new(TypedSampler {
+                dataref,
+                callback,
+                phantom: PhantomData,
+            }),
+        });
+        id
+    }
+
+    /// Ends a subscription
+    ///
+    /// This has no effect if the subscription has already ended.
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        let mut inner = self.inner.borrow_mut();
+        let before = inner.subscriptions.len();
+        inner
+            .subscriptions
+            .retain(|subscription| subscription.id != id);
+        if inner.subscriptions.len() == before {
+            // The subscription may be running right now
+            inner.removed.push(id);
+        }
+    }
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Subscriptions::new()
+    }
+}
+
+/// Converts a rate in callbacks per second into the time between callbacks
+///
+/// Rates too small to represent as a duration are treated as the longest possible period.
+fn period(rate: f32) -> Duration {
+    // This also rejects NaN
+    assert!(rate > 0.0, "Subscription rate must be greater than zero");
+    Duration::try_from_secs_f32(1.0 / rate).unwrap_or(Duration::MAX)
+}
+
+/// The subscriptions
+#[derive(Default)]
+struct Inner {
+    subscriptions: Vec<Subscription>,
+    /// Subscriptions removed while the flight loop was running callbacks
+    removed: Vec<SubscriptionId>,
+    next_id: u64,
+}
+
+impl Inner {
+    /// Samples all datarefs and calls subscribers that are due
+    fn run(inner: &RefCell<Inner>, state: &LoopState) {
+        // Callbacks may subscribe or unsubscribe, so the list is not borrowed while they run
+        let mut subscriptions = mem::take(&mut inner.borrow_mut().subscriptions);
+        let elapsed = state.since_last_call();
+        for subscription in subscriptions.iter_mut() {
+            subscription.update(elapsed);
+        }
+        let mut inner = inner.borrow_mut();
+        let removed = mem::take(&mut inner.removed);
+        subscriptions.retain(|subscription| !removed.contains(&subscription.id));
+        // Keep subscriptions added by callbacks
+        subscriptions.append(&mut inner.subscriptions);
+        inner.subscriptions = subscriptions;
+    }
+}
+
+/// One subscription
+struct Subscription {
+    id: SubscriptionId,
+    /// The time between callbacks
+    period: Duration,
+    /// The time since the callback was last called
+    since_callback: Duration,
+    reduce: Reduce,
+    accumulator: Accumulator,
+    sampler: Box<dyn Sampler>,
+}
+
+impl Subscription {
+    fn update(&mut self, elapsed: Duration) {
+        self.accumulator.add(self.sampler.sample());
+        self.since_callback = self.since_callback.saturating_add(elapsed);
+        if self.since_callback >= self.period {
+            // Keep the time past the deadline so that callbacks do not drift, but skip
+            // callbacks that were missed entirely
+            self.since_callback -= self.period;
+            if self.since_callback >= self.period {
+                self.since_callback = Duration::ZERO;
+            }
+            if let Some(value) = self.accumulator.reduce(self.reduce) {
+                self.sampler.deliver(value);
+            }
+            self.accumulator = Accumulator::default();
+        }
+    }
+}
+
+/// Combines samples
+#[derive(Debug, Default)]
+struct Accumulator {
+    count: u32,
+    sum: f64,
+    min: f64,
+    max: f64,
+    latest: f64,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += value;
+        self.latest = value;
+    }
+
+    fn reduce(&self, reduce: Reduce) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(match reduce {
+            Reduce::Latest => self.latest,
+            Reduce::Average => self.sum / f64::from(self.count),
+            Reduce::Min => self.min,
+            Reduce::Max => self.max,
+        })
+    }
+}
+
+/// Reads a dataref and calls a callback, hiding the value type
+trait Sampler {
+    fn sample(&mut self) -> f64;
+    fn deliver(&mut self, value: f64);
+}
+
+struct TypedSampler<T, D, F> {
+    dataref: D,
+    callback: F,
+    phantom: PhantomData<fn(T)>,
+}
+
+impl<T, D, F> Sampler for TypedSampler<T, D, F>
+where
+    T: Sample,
+    D: DataRead<T>,
+    F: FnMut(T),
+{
+    fn sample(&mut self) -> f64 {
+        self.dataref.get().to_f64()
+    }
+    fn deliver(&mut self, value: f64) {
+        (self.callback)(T::from_f64(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{period, Accumulator, Reduce};
+
+    #[test]
+    fn test_period() {
+        assert_eq!(period(4.0), Duration::from_millis(250));
+        assert_eq!(period(f32::MIN_POSITIVE), Duration::MAX);
+    }
+
+    #[test]
+    fn test_accumulator() {
+        let mut accumulator = Accumulator::default();
+        assert_eq!(accumulator.reduce(Reduce::Average), None);
+        for value in [3.0, -1.0, 4.0] {
+            accumulator.add(value);
+        }
+        assert_eq!(accumulator.reduce(Reduce::Latest), Some(4.0));
+        assert_eq!(accumulator.reduce(Reduce::Average), Some(2.0));
+        assert_eq!(accumulator.reduce(Reduce::Min), Some(-1.0));
+        assert_eq!(accumulator.reduce(Reduce::Max), Some(4.0));
+    }
+}
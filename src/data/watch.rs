@@ -0,0 +1,261 @@
+//! # Dataref change notifications
+//!
+//! Plugins often need to run code when a dataref changes, for example when the user moves a
+//! switch. A [`Watchers`] object reads all of its watched datarefs in one flight loop callback
+//! and calls each watcher's callback when the value changes. For floating-point datarefs, an
+//! epsilon can be used to ignore small changes.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::data::watch::Watchers;
+//!
+//! let watchers = Watchers::new();
+//! let beacon: DataRef<i32> = DataRef::find("sim/cockpit/electrical/beacon_lights_on").unwrap();
+//! let _beacon = watchers.watch(beacon, |on: i32| {
+//!     xplm::debugln!("Beacon {}", if on != 0 { "on" } else { "off" });
+//! });
+//! let altitude: DataRef<f32> =
+//!     DataRef::find("sim/cockpit2/autopilot/altitude_dial_ft").unwrap();
+//! let _altitude = watchers.watch_with_epsilon(altitude, 1.0, |feet: f32| {
+//!     xplm::debugln!("Altitude selected: {}", feet);
+//! });
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Sub;
+use std::rc::{Rc, Weak};
+
+use super::super::flight_loop::{FlightLoop, LoopState};
+use super::DataRead;
+
+/// Reads watched datarefs every flight loop and calls watchers when values change
+///
+/// All watchers stop when this object is dropped.
+pub struct Watchers {
+    /// The watchers, shared with the flight loop
+    inner: Rc<RefCell<Inner>>,
+    /// The flight loop that reads the datarefs
+    _flight_loop: FlightLoop,
+}
+
+impl Watchers {
+    /// Creates an object with no watchers and starts its flight loop
+    pub fn new() -> Self {
+        let inner = Rc::new(RefCell::new(Inner::default()));
+        let loop_inner = Rc::clone(&inner);
+        let mut flight_loop = FlightLoop::new(move |_: &mut LoopState| Inner::run(&loop_inner));
+        flight_loop.schedule_immediate();
+        Watchers {
+            inner,
+            _flight_loop: flight_loop,
+        }
+    }
+
+    /// Watches a dataref and calls the callback with the new value whenever it changes
+    ///
+    /// The callback is not called with the initial value. Watching stops when the returned
+    /// object is dropped.
+    pub fn watch<T, D, F>(&self, dataref: D, callback: F) -> DataRefWatcher<T>
+    where
+        T: Copy + PartialEq + 'static,
+        D: DataRead<T> + 'static,
+        F: FnMut(T) + 'static,
+    {
+        self.add(dataref, |old: T, new: T| old != new, callback)
+    }
+
+    /// Watches a dataref and calls the callback with the new value whenever it differs from
+    /// the value last reported by more than epsilon
+    ///
+    /// Slow changes are reported once they add up to more than epsilon. The callback is not
+    /// called with the initial value. Watching stops when the returned object is dropped.
+    pub fn watch_with_epsilon<T, D, F>(
+        &self,
+        dataref: D,
+        epsilon: T,
+        callback: F,
+    ) -> DataRefWatcher<T>
+    where
+        T: Copy + PartialOrd + Sub<Output = T> + 'static,
+        D: DataRead<T> + 'static,
+        F: FnMut(T) + 'static,
+    {
+        self.add(
+            dataref,
+            move |old: T, new: T| exceeds(old, new, epsilon),
+            callback,
+        )
+    }
+
+    fn add<T, D, C, F>(&self, dataref: D, changed: C, callback: F) -> DataRefWatcher<T>
+    where
+        T: Copy + 'static,
+        D: DataRead<T> + 'static,
+        C: Fn(T, T) -> bool + 'static,
+        F: FnMut(T) + 'static,
+    {
+        let mut inner = self.inner.borrow_mut();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        let last = dataref.get();
+        inner.watches.push(Watch {
+            id,
+            poll: Box::new(TypedWatch {
+                dataref,
+                last,
+                changed,
+                callback,
+            }),
+        });
+        DataRefWatcher {
+            id,
+            inner: Rc::downgrade(&self.inner),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of datarefs being watched
+    pub fn len(&self) -> usize {
+        self.inner.borrow().watches.len()
+    }
+
+    /// Returns true if no datarefs are being watched
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Watchers {
+    fn default() -> Self {
+        Watchers::new()
+    }
+}
+
+impl fmt::Debug for Watchers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watchers")
+            .field("datarefs", &self.inner.borrow().watches.len())
+            .finish()
+    }
+}
+
+/// Watches one dataref
+///
+/// Watching stops when this object is dropped.
+pub struct DataRefWatcher<T> {
+    id: u64,
+    inner: Weak<RefCell<Inner>>,
+    phantom: PhantomData<fn(T)>,
+}
+
+impl<T> Drop for DataRefWatcher<T> {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.upgrade() {
+            let mut inner = inner.borrow_mut();
+            let before = inner.watches.len();
+            inner.watches.retain(|watch| watch.id != self.id);
+            if inner.watches.len() == before {
+                // The watch may be running right now
+                inner.removed.push(self.id);
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for DataRefWatcher<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DataRefWatcher")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// The watched datarefs
+#[derive(Default)]
+struct Inner {
+    watches: Vec<Watch>,
+    /// Watches removed while the flight loop was running callbacks
+    removed: Vec<u64>,
+    next_id: u64,
+}
+
+impl Inner {
+    /// Reads all datarefs and calls callbacks for those that changed
+    fn run(inner: &RefCell<Inner>) {
+        // Callbacks may add or remove watches, so the list is not borrowed while they run
+        let mut watches = mem::take(&mut inner.borrow_mut().watches);
+        for watch in watches.iter_mut() {
+            watch.poll.poll();
+        }
+        let mut inner = inner.borrow_mut();
+        let removed = mem::take(&mut inner.removed);
+        watches.retain(|watch| !removed.contains(&watch.id));
+        // Keep watches added by callbacks
+        watches.append(&mut inner.watches);
+        inner.watches = watches;
+    }
+}
+
+struct Watch {
+    id: u64,
+    poll: Box<dyn Poll>,
+}
+
+/// Reads a dataref and calls a callback if it changed, hiding the value type
+trait Poll {
+    fn poll(&mut self);
+}
+
+struct TypedWatch<T, D, C, F> {
+    dataref: D,
+    /// The value last reported, or the initial value
+    last: T,
+    changed: C,
+    callback: F,
+}
+
+impl<T, D, C, F> Poll for TypedWatch<T, D, C, F>
+where
+    T: Copy,
+    D: DataRead<T>,
+    C: Fn(T, T) -> bool,
+    F: FnMut(T),
+{
+    fn poll(&mut self) {
+        let value = self.dataref.get();
+        if (self.changed)(self.last, value) {
+            self.last = value;
+            (self.callback)(value);
+        }
+    }
+}
+
+/// Returns true if new differs from old by more than epsilon
+fn exceeds<T: Copy + PartialOrd + Sub<Output = T>>(old: T, new: T, epsilon: T) -> bool {
+    if new > old {
+        new - old > epsilon
+    } else {
+        old - new > epsilon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::exceeds;
+
+    #[test]
+    fn test_exceeds() {
+        assert!(!exceeds(10.0f32, 10.5, 1.0));
+        assert!(!exceeds(10.0f32, 9.0, 1.0));
+        assert!(exceeds(10.0f32, 11.5, 1.0));
+        assert!(exceeds(10.0f32, 8.5, 1.0));
+        assert!(exceeds(3, 5, 1));
+        assert!(!exceeds(3, 3, 0));
+    }
+}
@@ -0,0 +1,188 @@
+//! # Debug configuration
+//!
+//! Reads debugging options when a plugin starts, so that users can turn on extra logging or
+//! turn off parts of a plugin while collecting diagnostics, without a special build.
+//!
+//! Options are read from a `debug.ini` file in the plugin folder, then from environment
+//! variables, which take precedence. The file contains `key = value` lines. Lines starting
+//! with `#` or `;` are comments.
+//!
+//! ```ini
+//! # Write extra information to Log.txt
+//! verbose = true
+//! # Turn off the weather and sound subsystems
+//! disable = weather, sound
+//! ```
+//!
+//! An environment variable named `<PREFIX>_<KEY>`, with the key in upper case, sets the same
+//! option. For example, with the prefix `MYPLUGIN`, `MYPLUGIN_VERBOSE=1` sets `verbose`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::debug_config::DebugConfig;
+//!
+//! let config = DebugConfig::load("MYPLUGIN");
+//! if config.verbose() {
+//!     xplm::debugln!("[myplugin] Verbose logging enabled");
+//! }
+//! if config.subsystem_enabled("weather") {
+//!     // Start weather subsystem
+//! }
+//! ```
+//!
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::debugln;
+use super::plugin::management;
+
+/// The name of the file that is read from the plugin folder
+pub const FILE_NAME: &str = "debug.ini";
+
+/// Debugging options read from a file and environment variables
+#[derive(Debug, Clone, Default)]
+pub struct DebugConfig {
+    /// Option values by lower-case key
+    values: HashMap<String, String>,
+}
+
+impl DebugConfig {
+    /// Reads options from `debug.ini` in this plugin's folder and from environment variables
+    /// that start with `<env_prefix>_`
+    ///
+    /// A missing file is not an error. Other problems reading the file are written to the log.
+    pub fn load(env_prefix: &str) -> Self {
+        Self::load_from(&plugin_folder().join(FILE_NAME), env_prefix)
+    }
+
+    /// Reads options from a file and from environment variables that start with
+    /// `<env_prefix>_`
+    ///
+    /// A missing file is not an error. Other problems reading the file are written to the log.
+    pub fn load_from(path: &Path, env_prefix: &str) -> Self {
+        let mut config = match fs::read_to_string(path) {
+            Ok(text) => DebugConfig::parse(&text),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => DebugConfig::default(),
+            Err(e) => {
+                debugln!("[xplm] Failed to read {}: {}", path.display(), e);
+                DebugConfig::default()
+            }
+        };
+        let env_prefix = format!("{}_", env_prefix.to_uppercase());
+        // Variables that are not Unicode are skipped
+        let vars = env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        });
+        for (name, value) in vars {
+            if let Some(key) = name.strip_prefix(&env_prefix) {
+                config.values.insert(key.to_lowercase(), value);
+            }
+        }
+        config
+    }
+
+    /// Parses options from the contents of a file
+    ///
+    /// Lines that are not comments and do not contain `=` are written to the log.
+    pub fn parse(text: &str) -> Self {
+        let (config, invalid) = Self::parse_lines(text);
+        for line in invalid {
+            debugln!("[xplm] Ignoring invalid debug option line: {}", line);
+        }
+        config
+    }
+
+    /// Parses options and returns them with the lines that could not be parsed
+    fn parse_lines(text: &str) -> (Self, Vec<&str>) {
+        let mut values = HashMap::new();
+        let mut invalid = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((key, value)) => {
+                    values.insert(key.trim().to_lowercase(), value.trim().to_owned());
+                }
+                None => invalid.push(line),
+            }
+        }
+        (DebugConfig { values }, invalid)
+    }
+
+    /// Returns the value of an option, if it is set
+    ///
+    /// Keys are not case-sensitive.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(&key.to_lowercase()).map(String::as_str)
+    }
+
+    /// Returns true if an option is set to `1`, `true`, `yes`, or `on`
+    ///
+    /// Values are not case-sensitive.
+    pub fn flag(&self, key: &str) -> bool {
+        match self.get(key) {
+            Some(value) => matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on"),
+            None => false,
+        }
+    }
+
+    /// Returns true if the `verbose` option is set
+    pub fn verbose(&self) -> bool {
+        self.flag("verbose")
+    }
+
+    /// Returns false if a subsystem is listed in the comma-separated `disable` option
+    ///
+    /// Names are not case-sensitive.
+    pub fn subsystem_enabled(&self, name: &str) -> bool {
+        match self.get("disable") {
+            Some(disabled) => !disabled
+                .split(',')
+                .any(|item| item.trim().eq_ignore_ascii_case(name)),
+            None => true,
+        }
+    }
+}
+
+/// Returns the folder that contains this plugin
+///
+/// For a plugin in the `64` or `<platform>_x64` folder of a fat plugin, this is the parent of
+/// that folder.
+pub(crate) fn plugin_folder() -> PathBuf {
+    let path = management::this_plugin().path();
+    let folder = path.parent().unwrap_or_else(|| Path::new("."));
+    let is_platform_folder = folder
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name == "64" || name.ends_with("_x64"))
+        .unwrap_or(false);
+    if is_platform_folder {
+        folder.parent().unwrap_or(folder).to_owned()
+    } else {
+        folder.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DebugConfig;
+
+    #[test]
+    fn test_parse() {
+        let (config, invalid) =
+            DebugConfig::parse_lines("# comment\n Verbose = Yes\n; comment\ndisable = a, B\nbad\n");
+        assert_eq!(invalid, vec!["bad"]);
+        assert!(config.verbose());
+        assert!(!config.subsystem_enabled("b"));
+        assert!(config.subsystem_enabled("c"));
+        assert_eq!(config.get("DISABLE"), Some("a, B"));
+        assert!(!config.flag("missing"));
+    }
+}
@@ -0,0 +1,179 @@
+//! # Drop diagnostics
+//!
+//! Crashes when a plugin stops are often caused by objects that are dropped in an unexpected
+//! order, or by X-Plane calling a callback for an object that has already been dropped. With
+//! the `drop-diagnostics` feature, windows, draw callbacks, flight loops, owned datarefs,
+//! owned commands, and menus write a message to Log.txt when they are dropped, and the order
+//! is recorded.
+//!
+//! If X-Plane calls a callback for a window, draw callback, flight loop, owned dataref, or
+//! owned command that has been dropped, the callback is not run and a message is written to
+//! Log.txt. In strict mode, the plugin aborts instead with a message that describes the
+//! problem, so that the problem is found before it corrupts memory.
+//!
+//! This feature is intended for debugging. It should not be enabled in released plugins.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::diagnostics;
+//!
+//! diagnostics::set_strict(true);
+//! // When the plugin stops
+//! for record in diagnostics::drop_log() {
+//!     xplm::debugln!("{}", record);
+//! }
+//! ```
+//!
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::process;
+
+use super::debugln;
+
+/// A record of an object that was dropped
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DropRecord {
+    /// The position of this record in the drop order, starting at 0
+    pub sequence: u64,
+    /// The type of object that was dropped
+    pub kind: &'static str,
+    /// The address of the object's data, which X-Plane provides to its callbacks
+    pub address: usize,
+}
+
+impl fmt::Display for DropRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{} {} {:#x}", self.sequence, self.kind, self.address)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// The number of live objects at each address
+    ///
+    /// More than one object can have the same address if they contain zero-sized callbacks.
+    live: HashMap<usize, u32>,
+    /// Addresses of objects that have been dropped
+    dropped: HashSet<usize>,
+    /// Drop records, in order
+    log: Vec<DropRecord>,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+    static STRICT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables or disables strict mode
+///
+/// In strict mode, the plugin aborts when X-Plane calls a callback for an object that has
+/// been dropped. Strict mode is disabled by default.
+pub fn set_strict(strict: bool) {
+    STRICT.with(|value| value.set(strict));
+}
+
+/// Returns true if strict mode is enabled
+pub fn is_strict() -> bool {
+    STRICT.with(Cell::get)
+}
+
+/// Returns the objects that have been dropped, in the order they were dropped
+pub fn drop_log() -> Vec<DropRecord> {
+    STATE.with(|state| state.borrow().log.clone())
+}
+
+/// Removes all records from the drop log
+pub fn clear_drop_log() {
+    STATE.with(|state| state.borrow_mut().log.clear());
+}
+
+/// Records that an object was created with data at an address
+pub(crate) fn created<T: ?Sized>(_kind: &'static str, address: *const T) {
+    STATE.with(|state| state.borrow_mut().created(address.cast::<()>() as usize));
+}
+
+/// Records that an object with data at an address was dropped
+pub(crate) fn dropped<T: ?Sized>(kind: &'static str, address: *const T) {
+    let address = address.cast::<()>() as usize;
+    let record = STATE.with(|state| state.borrow_mut().dropped(kind, address));
+    debugln!("[xplm] Dropped {}", record);
+}
+
+/// Checks that the object that a callback is for has not been dropped
+///
+/// This returns true if the callback can run. In strict mode, this aborts if the object has
+/// been dropped.
+pub(crate) fn callback<T: ?Sized>(kind: &'static str, address: *const T) -> bool {
+    let address = address.cast::<()>() as usize;
+    let valid = STATE.with(|state| state.borrow().is_valid(address));
+    if !valid {
+        debugln!(
+            "[xplm] X-Plane called a callback for a {} at {:#x} that has been dropped",
+            kind,
+            address
+        );
+        if is_strict() {
+            debugln!("[xplm] Aborting because drop diagnostics strict mode is enabled");
+            process::abort();
+        }
+    }
+    valid
+}
+
+impl State {
+    fn created(&mut self, address: usize) {
+        *self.live.entry(address).or_default() += 1;
+        self.dropped.remove(&address);
+    }
+
+    fn dropped(&mut self, kind: &'static str, address: usize) -> DropRecord {
+        if let Some(count) = self.live.get_mut(&address) {
+            *count -= 1;
+            if *count == 0 {
+                self.live.remove(&address);
+            }
+        }
+        self.dropped.insert(address);
+        let record = DropRecord {
+            sequence: self.log.len() as u64,
+            kind,
+            address,
+        };
+        self.log.push(record.clone());
+        record
+    }
+
+    fn is_valid(&self, address: usize) -> bool {
+        self.live.contains_key(&address) || !self.dropped.contains(&address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::State;
+
+    #[test]
+    fn test_state() {
+        let mut state = State::default();
+        state.created(16);
+        state.created(16);
+        state.created(32);
+        assert!(state.is_valid(16));
+        state.dropped("Draw", 16);
+        // Another object still has the same address
+        assert!(state.is_valid(16));
+        state.dropped("Draw", 16);
+        assert!(!state.is_valid(16));
+        state.dropped("Window", 32);
+        assert!(!state.is_valid(32));
+        // Memory is reused
+        state.created(32);
+        assert!(state.is_valid(32));
+        assert_eq!(state.log.len(), 3);
+        assert_eq!(state.log[2].to_string(), "#2 Window 0x20");
+    }
+}
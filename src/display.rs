@@ -0,0 +1,48 @@
+//! # Mouse location and screen size
+//!
+//! These functions help with drawing things that follow the mouse, such as tooltips, and
+//! with sizing overlays to the X-Plane window.
+//!
+//! The mouse location is in global desktop coordinates, in boxels, like the geometry of
+//! windows. See the [`monitor`](crate::window::monitor) module for details about the
+//! coordinate systems and the bounds of each monitor.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::display;
+//! use xplm::geometry::Rect;
+//!
+//! // A tooltip just above and to the right of the mouse
+//! let (x, y) = display::mouse_location().into_xy();
+//! let tooltip = Rect::from_left_top_right_bottom(x + 10, y + 40, x + 210, y + 10);
+//! let size = display::screen_size();
+//! xplm::debugln!("Screen is {} by {}", size.width(), size.height());
+//! ```
+//!
+
+use std::os::raw::c_int;
+
+use super::geometry::{Point, Size};
+
+/// Returns the location of the mouse in global desktop coordinates
+///
+/// If the mouse is outside the X-Plane window, the location may be outside the bounds of the
+/// screen.
+pub fn mouse_location() -> Point<i32> {
+    crate::debug_assert_main_thread!();
+    let (mut x, mut y): (c_int, c_int) = (0, 0);
+    unsafe { xplm_sys::XPLMGetMouseLocationGlobal(&mut x, &mut y) };
+    Point::from_xy(x, y)
+}
+
+/// Returns the size of the main X-Plane window
+///
+/// In full-screen mode, this is the size of the main monitor. For the bounds of all monitors,
+/// use [`monitor::screen_bounds`](crate::window::monitor::screen_bounds).
+pub fn screen_size() -> Size<i32> {
+    crate::debug_assert_main_thread!();
+    let (mut width, mut height): (c_int, c_int) = (0, 0);
+    unsafe { xplm_sys::XPLMGetScreenSize(&mut width, &mut height) };
+    Size::new(width, height)
+}
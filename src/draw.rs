@@ -1,6 +1,14 @@
+use std::marker::PhantomData;
 use std::os::raw::*;
 use xplm_sys;
 
+use super::gl;
+
+/// Text drawing and font metrics
+pub mod text;
+/// OpenGL textures from pixel data
+pub mod texture;
+
 /// A callback that can be called while X-Plane draws graphics
 pub trait DrawCallback: 'static {
     /// Draws
@@ -31,6 +39,7 @@ pub struct Draw {
 impl Draw {
     /// Creates a new drawing callback
     pub fn new<C: DrawCallback>(phase: Phase, callback: C) -> Result<Self, Error> {
+        crate::debug_assert_main_thread!();
         let xplm_phase = phase.to_xplm();
         let callback_box = Box::new(callback);
         let callback_ptr: *const _ = &*callback_box;
@@ -43,6 +52,8 @@ impl Draw {
             )
         };
         if status == 1 {
+            #[cfg(feature = "drop-diagnostics")]
+            super::diagnostics::created("Draw", callback_ptr);
             Ok(Draw {
                 _callback: callback_box,
                 phase,
@@ -58,6 +69,9 @@ impl Draw {
 impl Drop for Draw {
     /// Unregisters this draw callback
     fn drop(&mut self) {
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::dropped("Draw", self.callback_ptr);
+        super::panics::forget(self.callback_ptr);
         let phase = self.phase.to_xplm();
         unsafe {
             xplm_sys::XPLMUnregisterDrawCallback(self.c_callback, phase, 0, self.callback_ptr);
@@ -73,14 +87,22 @@ unsafe extern "C" fn draw_callback<C: DrawCallback>(
     _before: c_int,
     refcon: *mut c_void,
 ) -> c_int {
+    if super::internal::suspended() {
+        return 1;
+    }
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("Draw", refcon) {
+        return 1;
+    }
     let callback_ptr = refcon as *mut C;
-    (*callback_ptr).draw();
+    super::panics::contain("draw", callback_ptr, (), || (*callback_ptr).draw());
     // Always allow X-Plane to draw
     1
 }
 
 /// Phases in which drawing can occur
 #[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub enum Phase {
     // TODO: Some phases have been removed because they were removed from the upstream X-Plane SDK.
     // The replacements should be added back in.
@@ -99,9 +121,9 @@ pub enum Phase {
 
 impl Phase {
     /// Converts this phase into an XPLMDrawingPhase and a 0 for after or 1 for before
-    fn to_xplm(&self) -> xplm_sys::XPLMDrawingPhase {
+    fn to_xplm(self) -> xplm_sys::XPLMDrawingPhase {
         use self::Phase::*;
-        let phase = match *self {
+        let phase = match self {
             AfterPanel => xplm_sys::xplm_Phase_Panel,
             AfterGauges => xplm_sys::xplm_Phase_Gauges,
             AfterWindows => xplm_sys::xplm_Phase_Window,
@@ -115,6 +137,7 @@ impl Phase {
 
 /// Errors that can occur when creating a draw callback
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// X-Plane does not support the provided phase
     #[error("Unsupported draw phase: {0:?}")]
@@ -122,7 +145,16 @@ pub enum Error {
 }
 
 /// Stores various flags that can be enabled or disabled
-#[derive(Debug, Clone)]
+///
+/// # Examples
+///
+/// ```no_run
+/// use xplm::draw::{self, GraphicsState};
+///
+/// draw::set_state(&GraphicsState::new().alpha_blending(true).textures(1));
+/// ```
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct GraphicsState {
     /// Enable status of fog
     ///
@@ -144,6 +176,53 @@ pub struct GraphicsState {
     pub textures: i32,
 }
 
+impl GraphicsState {
+    /// Creates a state with everything disabled and no textures
+    pub fn new() -> Self {
+        GraphicsState::default()
+    }
+    /// Sets the enable status of fog
+    pub fn fog(self, fog: bool) -> Self {
+        GraphicsState { fog, ..self }
+    }
+    /// Sets the enable status of 3D lighting
+    pub fn lighting(self, lighting: bool) -> Self {
+        GraphicsState { lighting, ..self }
+    }
+    /// Sets the enable status of alpha testing
+    pub fn alpha_testing(self, alpha_testing: bool) -> Self {
+        GraphicsState {
+            alpha_testing,
+            ..self
+        }
+    }
+    /// Sets the enable status of alpha blending
+    pub fn alpha_blending(self, alpha_blending: bool) -> Self {
+        GraphicsState {
+            alpha_blending,
+            ..self
+        }
+    }
+    /// Sets the enable status of depth testing
+    pub fn depth_testing(self, depth_testing: bool) -> Self {
+        GraphicsState {
+            depth_testing,
+            ..self
+        }
+    }
+    /// Sets the enable status of depth writing
+    pub fn depth_writing(self, depth_writing: bool) -> Self {
+        GraphicsState {
+            depth_writing,
+            ..self
+        }
+    }
+    /// Sets the number of textures that are enabled for use
+    pub fn textures(self, textures: i32) -> Self {
+        GraphicsState { textures, ..self }
+    }
+}
+
 /// Sets the graphics state
 pub fn set_state(state: &GraphicsState) {
     unsafe {
@@ -175,10 +254,10 @@ pub fn bind_texture(texture_number: i32, texture_id: i32) {
 /// Texture IDs are placed in the provided slice. If the slice contains more than i32::max_value()
 /// elements, no more than i32::max_value() texture IDs will be generated.
 pub fn generate_texture_numbers(numbers: &mut [i32]) {
-    let count = if numbers.len() < (i32::max_value() as usize) {
+    let count = if numbers.len() < (i32::MAX as usize) {
         numbers.len() as i32
     } else {
-        i32::max_value()
+        i32::MAX
     };
     unsafe {
         xplm_sys::XPLMGenerateTextureNumbers(numbers.as_mut_ptr(), count);
@@ -191,7 +270,165 @@ pub fn generate_texture_numbers(numbers: &mut [i32]) {
 /// See generate_texture_numbers for more detail.
 ///
 pub fn generate_texture_number() -> i32 {
-    let number = 0;
-    generate_texture_numbers(&mut [number]);
-    number
+    let mut numbers = [0];
+    generate_texture_numbers(&mut numbers);
+    numbers[0]
+}
+
+/// Returns the address of an OpenGL function, or null if it is not available
+///
+/// Plugins that use OpenGL bindings from another crate can use this to load them. It works
+/// on all platforms and for functions from all OpenGL versions. For example, with the glow
+/// crate, `glow::Context::from_loader_function_cstr(xplm::draw::gl_function)` creates a
+/// context.
+///
+/// X-Plane provides the OpenGL context. Plugins must not create their own.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::mem;
+///
+/// type BlendFunc = unsafe extern "system" fn(source: u32, destination: u32);
+///
+/// let address = xplm::draw::gl_function(c"glBlendFunc");
+/// if !address.is_null() {
+///     let blend_func: BlendFunc = unsafe { mem::transmute(address) };
+/// }
+/// ```
+pub fn gl_function(name: &std::ffi::CStr) -> *const c_void {
+    gl::function(name)
+}
+
+/// Saves OpenGL state when created and restores it when dropped
+///
+/// X-Plane 12 may draw into multisampled framebuffers, and on Vulkan and Metal it runs OpenGL
+/// plugins through a translation layer that expects plugins to leave the OpenGL state as they
+/// found it. Changing and not restoring state is a common cause of missing or corrupted
+/// graphics.
+///
+/// A guard saves and restores this state:
+///
+/// * The draw and read framebuffer bindings. X-Plane may have bound a multisampled
+///   framebuffer, so binding framebuffer 0 is not a correct way to restore it.
+/// * The viewport, scissor box, and scissor test
+/// * The depth write mask
+/// * The current shader program, vertex array, and array buffer
+/// * The active texture unit and the 2D texture bound to it
+/// * The pixel pack and unpack alignment
+///
+/// Blending, depth testing, fog, lighting, and alpha testing should only be changed with
+/// [`set_state`], so that X-Plane knows about the changes. Textures should be bound with
+/// [`bind_texture`].
+///
+/// State that requires a function newer than OpenGL 1.1 is only restored if the function is
+/// available.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xplm::draw::{self, Draw, Phase};
+///
+/// let draw = Draw::new(Phase::AfterWindows, || {
+///     let _guard = draw::gl_guard();
+///     // Drawing code that changes the viewport, shader, or framebuffer
+/// })
+/// .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct GlGuard {
+    draw_framebuffer: c_int,
+    read_framebuffer: c_int,
+    viewport: [c_int; 4],
+    scissor_box: [c_int; 4],
+    scissor_test: bool,
+    depth_write: bool,
+    program: c_int,
+    vertex_array: c_int,
+    array_buffer: c_int,
+    active_texture: c_int,
+    texture_2d: c_int,
+    pack_alignment: c_int,
+    unpack_alignment: c_int,
+    _not_send: PhantomData<*const ()>,
+}
+
+/// Saves the OpenGL state that X-Plane expects to be unchanged after a draw callback
+///
+/// The state is restored when the returned guard is dropped. This must only be called from
+/// a draw callback or another callback in which X-Plane allows drawing.
+pub fn gl_guard() -> GlGuard {
+    unsafe {
+        let mut depth_write = gl::GL_FALSE;
+        gl::glGetBooleanv(gl::GL_DEPTH_WRITEMASK, &mut depth_write);
+        GlGuard {
+            draw_framebuffer: get_integer(gl::GL_DRAW_FRAMEBUFFER_BINDING),
+            read_framebuffer: get_integer(gl::GL_READ_FRAMEBUFFER_BINDING),
+            viewport: get_integers(gl::GL_VIEWPORT),
+            scissor_box: get_integers(gl::GL_SCISSOR_BOX),
+            scissor_test: gl::glIsEnabled(gl::GL_SCISSOR_TEST) != gl::GL_FALSE,
+            depth_write: depth_write != gl::GL_FALSE,
+            program: get_integer(gl::GL_CURRENT_PROGRAM),
+            vertex_array: get_integer(gl::GL_VERTEX_ARRAY_BINDING),
+            array_buffer: get_integer(gl::GL_ARRAY_BUFFER_BINDING),
+            active_texture: get_integer(gl::GL_ACTIVE_TEXTURE),
+            texture_2d: get_integer(gl::GL_TEXTURE_BINDING_2D),
+            pack_alignment: get_integer(gl::GL_PACK_ALIGNMENT),
+            unpack_alignment: get_integer(gl::GL_UNPACK_ALIGNMENT),
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for GlGuard {
+    fn drop(&mut self) {
+        let extensions = gl::extensions();
+        unsafe {
+            if let Some(bind_framebuffer) = extensions.bind_framebuffer {
+                bind_framebuffer(gl::GL_DRAW_FRAMEBUFFER, self.draw_framebuffer as c_uint);
+                bind_framebuffer(gl::GL_READ_FRAMEBUFFER, self.read_framebuffer as c_uint);
+            }
+            let [x, y, width, height] = self.viewport;
+            gl::glViewport(x, y, width, height);
+            let [x, y, width, height] = self.scissor_box;
+            gl::glScissor(x, y, width, height);
+            if self.scissor_test {
+                gl::glEnable(gl::GL_SCISSOR_TEST);
+            } else {
+                gl::glDisable(gl::GL_SCISSOR_TEST);
+            }
+            gl::glDepthMask(self.depth_write as _);
+            if let Some(use_program) = extensions.use_program {
+                use_program(self.program as c_uint);
+            }
+            if let Some(bind_vertex_array) = extensions.bind_vertex_array {
+                bind_vertex_array(self.vertex_array as c_uint);
+            }
+            if let Some(bind_buffer) = extensions.bind_buffer {
+                bind_buffer(gl::GL_ARRAY_BUFFER, self.array_buffer as c_uint);
+            }
+            // Restore the texture through X-Plane so that its texture cache stays correct
+            let unit = (self.active_texture as c_uint).saturating_sub(gl::GL_TEXTURE0);
+            xplm_sys::XPLMBindTexture2d(self.texture_2d, unit as c_int);
+            if let Some(active_texture) = extensions.active_texture {
+                active_texture(self.active_texture as c_uint);
+            }
+            gl::glPixelStorei(gl::GL_PACK_ALIGNMENT, self.pack_alignment);
+            gl::glPixelStorei(gl::GL_UNPACK_ALIGNMENT, self.unpack_alignment);
+        }
+    }
+}
+
+/// Reads one integer of OpenGL state
+unsafe fn get_integer(name: c_uint) -> c_int {
+    let mut value = 0;
+    gl::glGetIntegerv(name, &mut value);
+    value
+}
+
+/// Reads four integers of OpenGL state
+unsafe fn get_integers(name: c_uint) -> [c_int; 4] {
+    let mut values = [0; 4];
+    gl::glGetIntegerv(name, values.as_mut_ptr());
+    values
 }
@@ -0,0 +1,190 @@
+//! # Text drawing
+//!
+//! Functions that draw text with X-Plane's fonts and measure it. These must only be called
+//! from a draw callback or a window's draw function.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::draw::text::{self, Font, Rgb};
+//! use xplm::geometry::Rect;
+//! use xplm::window::{Window, WindowDelegate};
+//!
+//! struct Status;
+//!
+//! impl WindowDelegate for Status {
+//!     fn draw(&mut self, window: &Window) {
+//!         let geometry = window.geometry();
+//!         text::draw_translucent_dark_box(geometry);
+//!         let height = text::font_dimensions(Font::Proportional).char_height;
+//!         text::draw_string(
+//!             Rgb::WHITE,
+//!             geometry.left() + 5,
+//!             geometry.top() - 5 - height,
+//!             "Connected",
+//!             Font::Proportional,
+//!         );
+//!     }
+//! }
+//! ```
+//!
+
+use std::ffi::CString;
+use std::os::raw::c_int;
+use std::ptr;
+
+use xplm_sys::*;
+
+use super::super::geometry::Rect;
+
+/// X-Plane's fonts
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Font {
+    /// The monospaced font used in X-Plane's menus and dialogs
+    Basic,
+    /// The proportional font used in X-Plane's user interface
+    #[default]
+    Proportional,
+}
+
+impl Font {
+    fn to_xplm(self) -> XPLMFontID {
+        let font = match self {
+            Font::Basic => xplmFont_Basic,
+            Font::Proportional => xplmFont_Proportional,
+        };
+        font as XPLMFontID
+    }
+}
+
+/// A color with red, green, and blue components from 0 to 1
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rgb {
+    /// The red component
+    pub r: f32,
+    /// The green component
+    pub g: f32,
+    /// The blue component
+    pub b: f32,
+}
+
+impl Rgb {
+    /// White
+    pub const WHITE: Rgb = Rgb::new(1.0, 1.0, 1.0);
+    /// Black
+    pub const BLACK: Rgb = Rgb::new(0.0, 0.0, 0.0);
+
+    /// Creates a color from its components
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Rgb { r, g, b }
+    }
+}
+
+impl From<[f32; 3]> for Rgb {
+    fn from([r, g, b]: [f32; 3]) -> Self {
+        Rgb { r, g, b }
+    }
+}
+
+/// The size of the characters in a font, in pixels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FontDimensions {
+    /// The width of a character, or the widest character in a proportional font
+    pub char_width: i32,
+    /// The height of a line of text
+    pub char_height: i32,
+    /// If the font contains only digits
+    pub digits_only: bool,
+}
+
+/// Draws text with its baseline starting at (x, y)
+///
+/// Text after a null character is not drawn.
+pub fn draw_string(color: Rgb, x: i32, y: i32, text: &str, font: Font) {
+    draw_string_inner(color, x, y, text, None, font);
+}
+
+/// Draws text with its first baseline starting at (x, y), wrapping lines that are wider than
+/// width pixels
+///
+/// Text after a null character is not drawn.
+pub fn draw_string_wrapped(color: Rgb, x: i32, y: i32, text: &str, width: i32, font: Font) {
+    draw_string_inner(color, x, y, text, Some(width), font);
+}
+
+fn draw_string_inner(color: Rgb, x: i32, y: i32, text: &str, width: Option<i32>, font: Font) {
+    let text_c = to_c_string(text);
+    let mut color = [color.r, color.g, color.b];
+    let mut width = width.unwrap_or_default();
+    let width_ptr: *mut c_int = if width > 0 {
+        &mut width
+    } else {
+        ptr::null_mut()
+    };
+    unsafe {
+        XPLMDrawString(
+            color.as_mut_ptr(),
+            x,
+            y,
+            text_c.as_ptr() as *mut _,
+            width_ptr,
+            font.to_xplm(),
+        );
+    }
+}
+
+/// Draws the translucent dark background that X-Plane uses behind text
+pub fn draw_translucent_dark_box<R: Into<Rect<i32>>>(rect: R) {
+    crate::debug_assert_main_thread!();
+    let rect = rect.into();
+    unsafe {
+        XPLMDrawTranslucentDarkBox(rect.left(), rect.top(), rect.right(), rect.bottom());
+    }
+}
+
+/// Returns the size of the characters in a font
+pub fn font_dimensions(font: Font) -> FontDimensions {
+    crate::debug_assert_main_thread!();
+    let mut char_width = 0;
+    let mut char_height = 0;
+    let mut digits_only = 0;
+    unsafe {
+        XPLMGetFontDimensions(
+            font.to_xplm(),
+            &mut char_width,
+            &mut char_height,
+            &mut digits_only,
+        );
+    }
+    FontDimensions {
+        char_width,
+        char_height,
+        digits_only: digits_only != 0,
+    }
+}
+
+/// Returns the width of text in a font, in pixels
+pub fn measure_string(font: Font, text: &str) -> f32 {
+    crate::debug_assert_main_thread!();
+    let length = text.len().min(c_int::MAX as usize) as c_int;
+    unsafe { XPLMMeasureString(font.to_xplm(), text.as_ptr() as *const _, length) }
+}
+
+/// Converts text into a C string, removing anything after the first null character
+fn to_c_string(text: &str) -> CString {
+    let end = text.find('\0').unwrap_or(text.len());
+    CString::new(&text[..end]).expect("Text contains no null characters")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_c_string;
+
+    #[test]
+    fn test_to_c_string() {
+        assert_eq!(to_c_string("abc").as_bytes(), b"abc");
+        assert_eq!(to_c_string("ab\0c").as_bytes(), b"ab");
+    }
+}
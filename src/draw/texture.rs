@@ -0,0 +1,328 @@
+//! # Textures
+//!
+//! A [`Texture`] uploads RGBA pixels into an OpenGL texture with a texture number from
+//! X-Plane, and deletes the texture when dropped. Textures can be drawn in windows with
+//! [`Texture::draw`] or bound with [`Texture::bind`] for custom drawing code.
+//!
+//! With the `png` feature, [`Texture::load_png`] loads a texture from a PNG file. Plugins can
+//! decode other image formats with a crate such as `image`, and pass the RGBA pixels to
+//! [`Texture::from_rgba`].
+//!
+//! Textures must only be created, updated, and dropped while X-Plane's OpenGL context is
+//! current, for example in a draw callback or a window's draw function.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::draw::texture::Texture;
+//! use xplm::window::{Window, WindowDelegate};
+//!
+//! struct Logo {
+//!     texture: Option<Texture>,
+//! }
+//!
+//! impl WindowDelegate for Logo {
+//!     fn draw(&mut self, window: &Window) {
+//!         // A 2 by 2 checkerboard, created in the first draw call
+//!         let texture = self.texture.get_or_insert_with(|| {
+//!             #[rustfmt::skip]
+//!             let pixels = [
+//!                 255, 255, 255, 255,  0, 0, 0, 255,
+//!                 0, 0, 0, 255,  255, 255, 255, 255,
+//!             ];
+//!             Texture::from_rgba(2, 2, &pixels).unwrap()
+//!         });
+//!         texture.draw(window.geometry());
+//!     }
+//! }
+//! ```
+//!
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::os::raw::{c_int, c_uint, c_void};
+
+use super::super::geometry::Rect;
+use super::super::gl;
+use super::{bind_texture, generate_texture_number, set_state, GraphicsState};
+
+/// The number of bytes in each pixel
+const BYTES_PER_PIXEL: usize = 4;
+
+/// An OpenGL texture with a texture number from X-Plane
+///
+/// The texture is deleted when this object is dropped.
+pub struct Texture {
+    id: i32,
+    width: u32,
+    height: u32,
+    /// Textures belong to X-Plane's OpenGL context, which is only current on the main thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Texture {
+    /// Creates a texture from pixels in RGBA order, 8 bits per channel
+    ///
+    /// The first row of pixels is the top of the image. The texture uses linear filtering.
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8]) -> Result<Self, TextureError> {
+        check_size(width, height, pixels.len())?;
+        let texture = Texture {
+            id: generate_texture_number(),
+            width,
+            height,
+            _not_send: PhantomData,
+        };
+        texture.bind(0);
+        unsafe {
+            let _guard = super::gl_guard();
+            gl::glPixelStorei(gl::GL_UNPACK_ALIGNMENT, 1);
+            gl::glTexParameteri(gl::GL_TEXTURE_2D, gl::GL_TEXTURE_MIN_FILTER, gl::GL_LINEAR);
+            gl::glTexParameteri(gl::GL_TEXTURE_2D, gl::GL_TEXTURE_MAG_FILTER, gl::GL_LINEAR);
+            gl::glTexParameteri(
+                gl::GL_TEXTURE_2D,
+                gl::GL_TEXTURE_WRAP_S,
+                gl::GL_CLAMP_TO_EDGE,
+            );
+            gl::glTexParameteri(
+                gl::GL_TEXTURE_2D,
+                gl::GL_TEXTURE_WRAP_T,
+                gl::GL_CLAMP_TO_EDGE,
+            );
+            gl::glTexImage2D(
+                gl::GL_TEXTURE_2D,
+                0,
+                gl::GL_RGBA as c_int,
+                width as c_int,
+                height as c_int,
+                0,
+                gl::GL_RGBA,
+                gl::GL_UNSIGNED_BYTE,
+                pixels.as_ptr().cast::<c_void>(),
+            );
+        }
+        Ok(texture)
+    }
+
+    /// Loads a texture from a PNG file
+    ///
+    /// Images in all PNG color types are converted to RGBA with 8 bits per channel.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::draw::texture::Texture;
+    /// use xplm::paths::plugin_folder;
+    ///
+    /// // In a draw callback
+    /// let logo = Texture::load_png(plugin_folder().join("logo.png")).unwrap();
+    /// ```
+    #[cfg(feature = "png")]
+    pub fn load_png<P: AsRef<std::path::Path>>(path: P) -> Result<Self, TextureError> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let (width, height, pixels) = decode_png(file)?;
+        Texture::from_rgba(width, height, &pixels)
+    }
+
+    /// Replaces all pixels of this texture
+    ///
+    /// The pixels must have the same size as the texture.
+    pub fn update(&mut self, pixels: &[u8]) -> Result<(), TextureError> {
+        check_size(self.width, self.height, pixels.len())?;
+        self.bind(0);
+        unsafe {
+            let _guard = super::gl_guard();
+            gl::glPixelStorei(gl::GL_UNPACK_ALIGNMENT, 1);
+            gl::glTexSubImage2D(
+                gl::GL_TEXTURE_2D,
+                0,
+                0,
+                0,
+                self.width as c_int,
+                self.height as c_int,
+                gl::GL_RGBA,
+                gl::GL_UNSIGNED_BYTE,
+                pixels.as_ptr().cast::<c_void>(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the X-Plane texture number of this texture
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// Returns the width of this texture in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of this texture in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Binds this texture to a texture unit, starting at 0
+    pub fn bind(&self, texture_unit: i32) {
+        bind_texture(self.id, texture_unit);
+    }
+
+    /// Draws this texture stretched to fill a rectangle in window coordinates
+    pub fn draw(&self, rect: Rect<i32>) {
+        set_state(&GraphicsState::new().textures(1).alpha_blending(true));
+        self.bind(0);
+        let (left, top, right, bottom) = (
+            rect.left() as f32,
+            rect.top() as f32,
+            rect.right() as f32,
+            rect.bottom() as f32,
+        );
+        unsafe {
+            gl::glColor4f(1.0, 1.0, 1.0, 1.0);
+            gl::glBegin(gl::GL_QUADS);
+            // The first row of pixels is at texture coordinate 0, at the top
+            gl::glTexCoord2f(0.0, 0.0);
+            gl::glVertex2f(left, top);
+            gl::glTexCoord2f(1.0, 0.0);
+            gl::glVertex2f(right, top);
+            gl::glTexCoord2f(1.0, 1.0);
+            gl::glVertex2f(right, bottom);
+            gl::glTexCoord2f(0.0, 1.0);
+            gl::glVertex2f(left, bottom);
+            gl::glEnd();
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        let id = self.id as c_uint;
+        unsafe { gl::glDeleteTextures(1, &id) }
+    }
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Texture")
+            .field("id", &self.id)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+/// Checks that a number of bytes matches the size of an image
+fn check_size(width: u32, height: u32, length: usize) -> Result<(), TextureError> {
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(BYTES_PER_PIXEL));
+    match expected {
+        _ if width == 0 || height == 0 => Err(TextureError::Empty),
+        Some(expected) if expected == length => Ok(()),
+        _ => Err(TextureError::WrongLength {
+            width,
+            height,
+            length,
+        }),
+    }
+}
+
+/// Decodes a PNG image and returns its width, height, and RGBA pixels
+#[cfg(feature = "png")]
+fn decode_png<R: std::io::Read>(source: R) -> Result<(u32, u32, Vec<u8>), TextureError> {
+    let mut decoder = png::Decoder::new(source);
+    // Expand palettes and low bit depths, and reduce 16-bit channels to 8 bits
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer)?;
+    buffer.truncate(frame.buffer_size());
+    let pixels = match frame.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|gray| [gray[0], gray[0], gray[0], gray[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer
+            .iter()
+            .flat_map(|&gray| [gray, gray, gray, 255])
+            .collect(),
+        png::ColorType::Indexed => unreachable!("Palettes are expanded by the transformations"),
+    };
+    Ok((frame.width, frame.height, pixels))
+}
+
+/// Errors that can occur when creating or updating a texture
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum TextureError {
+    /// The width or height is zero
+    #[error("Texture has no pixels")]
+    Empty,
+    /// The number of bytes does not match the size of the texture
+    #[error("{length} bytes do not match a {width} by {height} RGBA image")]
+    WrongLength {
+        /// The width of the texture
+        width: u32,
+        /// The height of the texture
+        height: u32,
+        /// The number of bytes provided
+        length: usize,
+    },
+    /// The image file could not be read
+    #[cfg(feature = "png")]
+    #[error("Could not read image file")]
+    Io(#[from] std::io::Error),
+    /// The image file is not a valid PNG image
+    #[cfg(feature = "png")]
+    #[error("Could not decode PNG image")]
+    Png(#[from] png::DecodingError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_size, TextureError};
+
+    #[test]
+    fn test_check_size() {
+        assert!(check_size(2, 3, 24).is_ok());
+        assert!(matches!(
+            check_size(2, 3, 23),
+            Err(TextureError::WrongLength { length: 23, .. })
+        ));
+        assert!(matches!(check_size(0, 3, 0), Err(TextureError::Empty)));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_decode_png() {
+        // A 2 by 2 RGB image: red, green, blue, white
+        #[rustfmt::skip]
+        const IMAGE: [u8; 75] = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00,
+            0x00, 0xfd, 0xd4, 0x9a, 0x73, 0x00, 0x00, 0x00, 0x12, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0xda, 0x63, 0xf8, 0xcf, 0xc0, 0xc0, 0x00, 0xc2, 0x0c, 0xff, 0x81, 0x00, 0x00, 0x1f,
+            0xee, 0x05, 0xfb, 0xf1, 0xab, 0xba, 0x77, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e,
+            0x44, 0xae, 0x42, 0x60, 0x82,
+        ];
+        let (width, height, pixels) = super::decode_png(&IMAGE[..]).unwrap();
+        assert_eq!((width, height), (2, 2));
+        #[rustfmt::skip]
+        assert_eq!(
+            pixels,
+            [
+                255, 0, 0, 255,  0, 255, 0, 255,
+                0, 0, 255, 255,  255, 255, 255, 255,
+            ]
+        );
+        assert!(matches!(
+            super::decode_png(&IMAGE[..40]),
+            Err(TextureError::Png(_))
+        ));
+    }
+}
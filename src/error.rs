@@ -1,17 +1,239 @@
+//! # Error detection
+//!
+//! X-Plane calls an error callback when this plugin uses the SDK incorrectly, for example by
+//! passing an invalid dataref handle. X-Plane only checks for these errors in debug builds of
+//! X-Plane, and only calls the callback if a plugin has registered one.
+//!
+//! This module registers the callback when [`set_error_callback`] is called or an
+//! [`ErrorDatarefs`] is created. It then records the number of errors and the most recent
+//! messages, which [`error_count`] and [`recent_errors`] return. Each error is passed to the
+//! callback as an [`ErrorContext`], which contains the message and the SDK function that
+//! reported the error if the message names one.
+//!
+//! [`ErrorDatarefs`] publishes the number of errors and the last error message as datarefs,
+//! so that support tools and other plugins can detect problems without reading Log.txt.
+//!
+//! With the `log-errors` feature, the callback is registered when the plugin starts and every
+//! error is written to Log.txt through the [`log`](crate::log) module.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::error::{self, ErrorDatarefs};
+//!
+//! error::set_error_callback(|error| {
+//!     if error.api() == Some("XPLMGetDatai") {
+//!         xplm::debugln!("[myplugin] Bad dataref read: {}", error.message());
+//!     }
+//! });
+//! // Publishes <signature>/errors/count and <signature>/errors/last_message
+//! let error_datarefs = ErrorDatarefs::for_this_plugin().unwrap();
+//! ```
+//!
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::ffi::CStr;
+use std::fmt;
 use std::os::raw::c_char;
+use std::rc::{Rc, Weak};
 use xplm_sys::XPLMSetErrorCallback;
 
-/// The current handler
-static mut HANDLER: Option<fn(&str)> = None;
+use super::data::owned::{CreateError, OwnedData};
+use super::data::{ArrayReadWrite, DataReadWrite};
+use super::plugin::management;
+
+/// The maximum number of messages kept by recent_errors
+const RECENT_ERROR_LIMIT: usize = 16;
+
+/// The length of the last message dataref in bytes, including the null terminator
+pub const LAST_MESSAGE_LENGTH: usize = 256;
+
+/// A function that receives errors
+type ErrorCallback = Box<dyn FnMut(&ErrorContext)>;
+
+thread_local! {
+    /// The current callback
+    static CALLBACK: RefCell<Option<ErrorCallback>> = const { RefCell::new(None) };
+    /// Recently received error messages, oldest first
+    static RECENT: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+    /// The total number of errors received
+    static COUNT: Cell<u32> = const { Cell::new(0) };
+    /// The datarefs that are updated when an error is received
+    static PUBLISHED: RefCell<Weak<Published>> = const { RefCell::new(Weak::new()) };
+}
+
+/// An error that X-Plane reported
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    /// The message from X-Plane
+    message: String,
+    /// The SDK function named in the message
+    api: Option<String>,
+}
+
+impl ErrorContext {
+    fn new(message: &str) -> Self {
+        ErrorContext {
+            message: message.to_owned(),
+            api: api_name(message).map(str::to_owned),
+        }
+    }
+
+    /// Returns the message from X-Plane
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the name of the SDK function that reported the error, if the message names
+    /// one
+    ///
+    /// This is the first word in the message that starts with `XPLM` or `XP`, such as
+    /// `XPLMGetDatai`.
+    pub fn api(&self) -> Option<&str> {
+        self.api.as_deref()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Returns the first word in a message that looks like the name of an SDK function
+fn api_name(message: &str) -> Option<&str> {
+    message
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .find(|word| {
+            let rest = word
+                .strip_prefix("XPLM")
+                .or_else(|| word.strip_prefix("XP"))
+                .unwrap_or_default();
+            rest.starts_with(|c: char| c.is_ascii_uppercase())
+        })
+}
+
+/// Sets the callback that receives errors
+///
+/// This replaces any callback or handler that was set before. Once a callback is set,
+/// X-Plane's error callback stays registered.
+pub fn set_error_callback<F>(callback: F)
+where
+    F: FnMut(&ErrorContext) + 'static,
+{
+    CALLBACK.with(|current| *current.borrow_mut() = Some(Box::new(callback)));
+    install_callback();
+}
 
 /// Sets the error handler
 ///
-/// Once an error handler is set, it cannot be removed.
+/// This is a simpler form of [`set_error_callback`] that only receives the message. It
+/// replaces any callback or handler that was set before.
 pub fn set_error_handler(handler: fn(&str)) {
-    unsafe {
-        HANDLER = Some(handler);
-        XPLMSetErrorCallback(Some(error_handler));
+    set_error_callback(move |error| handler(error.message()));
+}
+
+/// Returns the number of errors that X-Plane has reported
+///
+/// Errors are only recorded after set_error_callback or set_error_handler has been called
+/// or an ErrorDatarefs or [`HealthMonitor`](crate::health::HealthMonitor) has been created,
+/// or from the start with the `log-errors` feature.
+pub fn error_count() -> u32 {
+    COUNT.with(Cell::get)
+}
+
+/// Returns the most recent error messages, oldest first
+///
+/// Up to 16 messages are kept.
+pub fn recent_errors() -> Vec<String> {
+    RECENT.with(|recent| recent.borrow().iter().cloned().collect())
+}
+
+/// Publishes the error count and last error message as datarefs
+///
+/// The datarefs are removed when this object is dropped.
+pub struct ErrorDatarefs {
+    /// The datarefs, also referenced by the error callback
+    _published: Rc<Published>,
+}
+
+/// Datarefs that describe errors
+struct Published {
+    /// `<prefix>/errors/count`
+    count: RefCell<OwnedData<i32>>,
+    /// `<prefix>/errors/last_message`, null-terminated
+    last_message: RefCell<OwnedData<[u8]>>,
+}
+
+impl ErrorDatarefs {
+    /// Creates datarefs under the signature of this plugin
+    pub fn for_this_plugin() -> Result<Self, CreateError> {
+        let signature = management::this_plugin().signature();
+        Self::new(&signature)
+    }
+
+    /// Creates the datarefs `<prefix>/errors/count` (int) and
+    /// `<prefix>/errors/last_message` (byte array)
+    ///
+    /// The datarefs start with the errors that have already been recorded.
+    pub fn new(prefix: &str) -> Result<Self, CreateError> {
+        let published = Rc::new(Published {
+            count: RefCell::new(OwnedData::create(&format!("{}/errors/count", prefix))?),
+            last_message: RefCell::new(OwnedData::create_with_value(
+                &format!("{}/errors/last_message", prefix),
+                &[0u8; LAST_MESSAGE_LENGTH][..],
+            )?),
+        });
+        if let Some(last) = RECENT.with(|recent| recent.borrow().back().cloned()) {
+            published.update(&last);
+        }
+        PUBLISHED.with(|current| *current.borrow_mut() = Rc::downgrade(&published));
+        install_callback();
+        Ok(ErrorDatarefs {
+            _published: published,
+        })
+    }
+}
+
+impl Published {
+    /// Copies the current count and a message into the datarefs
+    fn update(&self, message: &str) {
+        let count = i32::try_from(error_count()).unwrap_or(i32::MAX);
+        self.count.borrow_mut().set(count);
+        let mut bytes = [0u8; LAST_MESSAGE_LENGTH];
+        // Leave at least one null byte at the end
+        let length = message.len().min(LAST_MESSAGE_LENGTH - 1);
+        bytes[..length].copy_from_slice(&message.as_bytes()[..length]);
+        self.last_message.borrow_mut().set(&bytes);
+    }
+}
+
+/// Registers the error callback with X-Plane
+pub(crate) fn install_callback() {
+    unsafe { XPLMSetErrorCallback(Some(error_handler)) }
+}
+
+/// Registers the error callback so that all errors are written to Log.txt
+///
+/// This is called from XPluginStart.
+#[cfg(feature = "log-errors")]
+pub(crate) fn error_init() {
+    install_callback();
+}
+
+/// Records an error message and updates the published datarefs
+fn record(message: &str) {
+    COUNT.with(|count| count.set(count.get().saturating_add(1)));
+    RECENT.with(|recent| {
+        let mut recent = recent.borrow_mut();
+        if recent.len() == RECENT_ERROR_LIMIT {
+            recent.pop_front();
+        }
+        recent.push_back(message.to_owned());
+    });
+    if let Some(published) = PUBLISHED.with(|current| current.borrow().upgrade()) {
+        published.update(message);
     }
 }
 
@@ -20,10 +242,45 @@ unsafe extern "C" fn error_handler(message: *const c_char) {
     let message_cs = CStr::from_ptr(message);
     match message_cs.to_str() {
         Ok(message_str) => {
-            if let Some(handler) = HANDLER {
-                handler(message_str)
+            record(message_str);
+            let error = ErrorContext::new(message_str);
+            #[cfg(feature = "log-errors")]
+            super::log::write(format!("[xplm] X-Plane reported an error: {}\n", error));
+            // Take the callback out while it runs, in case it causes another error
+            if let Some(mut callback) = CALLBACK.with(|current| current.borrow_mut().take()) {
+                super::panics::contain("error", std::ptr::null::<()>(), (), || callback(&error));
+                CALLBACK.with(|current| {
+                    // Keep any callback that was set while this one was running
+                    let mut current = current.borrow_mut();
+                    if current.is_none() {
+                        *current = Some(callback);
+                    }
+                });
             }
         }
         Err(_) => super::debugln!("[xplm] Error handler called with an invalid message"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::api_name;
+
+    #[test]
+    fn test_api_name() {
+        assert_eq!(
+            api_name("XPLMGetDatai: invalid dataref"),
+            Some("XPLMGetDatai")
+        );
+        assert_eq!(
+            api_name("Plugin passed a null string to XPLMFindDataRef."),
+            Some("XPLMFindDataRef")
+        );
+        assert_eq!(
+            api_name("XPCreateWidget was called with a bad parent"),
+            Some("XPCreateWidget")
+        );
+        assert_eq!(api_name("XPLM or XPlane"), None);
+        assert_eq!(api_name("Something went wrong"), None);
+    }
+}
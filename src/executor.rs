@@ -0,0 +1,157 @@
+//! # Running code on the main thread
+//!
+//! X-Plane functions must only be called from the main thread. Plugins that do work on other
+//! threads, such as network or file access, can use [`spawn_on_main`] to send a closure back
+//! to the main thread. Queued closures run in the next flight loop, in the order they were
+//! queued.
+//!
+//! Functions that must only be called on the main thread can require a [`MainThreadToken`].
+//! A token can only be obtained on the main thread and cannot be sent to other threads.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::thread;
+//! use xplm::executor::{spawn_on_main, MainThreadToken};
+//!
+//! thread::spawn(|| {
+//!     let weather = String::from("METAR KSEA 121853Z 18008KT 10SM FEW045");
+//!     spawn_on_main(move || {
+//!         let _token = MainThreadToken::get().expect("Not on the main thread");
+//!         xplm::debugln!("Downloaded weather: {}", weather);
+//!     });
+//! });
+//! ```
+//!
+
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::{c_float, c_int, c_void};
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+
+use xplm_sys::{XPLMRegisterFlightLoopCallback, XPLMUnregisterFlightLoopCallback};
+
+use super::thread::is_main_thread;
+
+/// Async tasks polled every flight loop
+pub mod tasks;
+
+/// A closure queued to run on the main thread
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Closures that have not run yet
+static TASKS: Mutex<Vec<Task>> = Mutex::new(Vec::new());
+
+/// Queues a closure to run on the main thread in the next flight loop
+///
+/// This can be called from any thread. Closures do not run while the plugin is disabled, and
+/// closures that have not run when the plugin stops are dropped without running.
+pub fn spawn_on_main<F>(task: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    tasks().push(Box::new(task));
+}
+
+/// Runs all queued closures now
+///
+/// Closures queued while this function is running run in the next flight loop. This has no
+/// effect if it is not called from the main thread.
+pub fn run_pending() {
+    if !is_main_thread() {
+        return;
+    }
+    for task in take_tasks() {
+        task();
+    }
+}
+
+/// Proof that the code holding it is running on the main thread
+///
+/// A token cannot be sent to or shared with other threads.
+#[derive(Debug, Copy, Clone)]
+pub struct MainThreadToken {
+    /// Makes this type neither Send nor Sync
+    _not_send: PhantomData<*const ()>,
+}
+
+impl MainThreadToken {
+    /// Returns a token if this function is called from the main thread, or None otherwise
+    pub fn get() -> Option<Self> {
+        if is_main_thread() {
+            Some(MainThreadToken {
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Removes and returns all queued closures
+fn take_tasks() -> Vec<Task> {
+    mem::take(&mut *tasks())
+}
+
+/// Locks the queue, ignoring any panic that happened while another thread held the lock
+fn tasks() -> MutexGuard<'static, Vec<Task>> {
+    TASKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Starts the flight loop callback that runs queued closures
+///
+/// This is called from XPluginStart.
+pub(crate) fn executor_init() {
+    unsafe { XPLMRegisterFlightLoopCallback(Some(run_callback), -1.0, ptr::null_mut()) };
+}
+
+/// Stops the flight loop callback and drops any closures that have not run
+///
+/// This is called from XPluginStop.
+pub(crate) fn executor_stop() {
+    unsafe { XPLMUnregisterFlightLoopCallback(Some(run_callback), ptr::null_mut()) };
+    drop(take_tasks());
+}
+
+/// Flight loop callback that runs queued closures every frame
+unsafe extern "C" fn run_callback(
+    _since_call: c_float,
+    _since_loop: c_float,
+    _counter: c_int,
+    _refcon: *mut c_void,
+) -> c_float {
+    if !super::internal::suspended() {
+        super::panics::contain("executor", ptr::null::<()>(), (), run_pending);
+    }
+    -1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_queue() {
+        // The main thread is not set in tests
+        assert!(super::MainThreadToken::get().is_none());
+        let count = Arc::new(AtomicU32::new(0));
+        let worker_count = Arc::clone(&count);
+        thread::spawn(move || {
+            super::spawn_on_main(move || {
+                worker_count.fetch_add(1, Ordering::SeqCst);
+            })
+        })
+        .join()
+        .unwrap();
+        super::run_pending();
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        for task in super::take_tasks() {
+            task();
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(super::take_tasks().is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+//! # Async tasks
+//!
+//! Procedures with several steps, such as waiting for a dataref to reach a value and then
+//! triggering a command, can be written as async blocks. A [`TaskRunner`] polls each of its
+//! tasks once per flight loop on the main thread.
+//!
+//! Tasks can wait with [`sleep`], [`next_loop`], and [`wait_until`]. Futures from other
+//! runtimes that depend on their wakers, such as network futures, are not supported.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use xplm::command::Command;
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::data::ArrayRead;
+//! use xplm::executor::tasks::{sleep, wait_until, TaskRunner};
+//!
+//! let runner = TaskRunner::new();
+//! runner.spawn(async {
+//!     let n1: DataRef<[f32]> = DataRef::find("sim/flightmodel/engine/ENGN_N1_").unwrap();
+//!     let mut starter = Command::find("sim/starters/engage_starter_1").unwrap();
+//!     let hold = starter.hold_down();
+//!     // Hold the starter until N1 reaches 25%
+//!     wait_until(|| n1.get_element(0).unwrap_or(0.0) >= 25.0).await;
+//!     drop(hold);
+//!     sleep(Duration::from_secs(2)).await;
+//!     xplm::debugln!("Engine started");
+//! });
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use super::super::flight_loop::{FlightLoop, LoopState};
+
+/// Identifies a task so that it can be cancelled
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+/// Polls async tasks once per flight loop
+///
+/// All tasks are dropped when this object is dropped.
+pub struct TaskRunner {
+    /// The tasks, shared with the flight loop
+    inner: Rc<RefCell<Inner>>,
+    /// The flight loop that polls the tasks
+    _flight_loop: FlightLoop,
+}
+
+impl TaskRunner {
+    /// Creates a runner with no tasks and starts its flight loop
+    pub fn new() -> Self {
+        let inner = Rc::new(RefCell::new(Inner::default()));
+        let loop_inner = Rc::clone(&inner);
+        let mut flight_loop = FlightLoop::new(move |_: &mut LoopState| Inner::run(&loop_inner));
+        flight_loop.schedule_immediate();
+        TaskRunner {
+            inner,
+            _flight_loop: flight_loop,
+        }
+    }
+
+    /// Adds a task
+    ///
+    /// The task is first polled in the next flight loop.
+    pub fn spawn<F>(&self, task: F) -> TaskId
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        let mut inner = self.inner.borrow_mut();
+        let id = TaskId(inner.next_id);
+        inner.next_id += 1;
+        inner.tasks.push(Task {
+            id,
+            future: Box::pin(task),
+        });
+        id
+    }
+
+    /// Drops a task without polling it again
+    ///
+    /// This has no effect if the task has already finished.
+    pub fn cancel(&self, id: TaskId) {
+        let mut inner = self.inner.borrow_mut();
+        let before = inner.tasks.len();
+        inner.tasks.retain(|task| task.id != id);
+        if inner.tasks.len() == before {
+            // The task may be running right now
+            inner.cancelled.push(id);
+        }
+    }
+
+    /// Returns true if a task has not finished and has not been cancelled
+    pub fn is_running(&self, id: TaskId) -> bool {
+        let inner = self.inner.borrow();
+        inner.running == Some(id) || inner.tasks.iter().any(|task| task.id == id)
+    }
+
+    /// Returns the number of tasks that have not finished
+    pub fn len(&self) -> usize {
+        self.inner.borrow().tasks.len()
+    }
+
+    /// Returns true if all tasks have finished
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for TaskRunner {
+    fn default() -> Self {
+        TaskRunner::new()
+    }
+}
+
+impl fmt::Debug for TaskRunner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaskRunner")
+            .field("tasks", &self.inner.borrow().tasks.len())
+            .finish()
+    }
+}
+
+/// The tasks
+#[derive(Default)]
+struct Inner {
+    tasks: Vec<Task>,
+    /// Tasks cancelled while the flight loop was polling tasks
+    cancelled: Vec<TaskId>,
+    /// The task being polled
+    running: Option<TaskId>,
+    next_id: u64,
+}
+
+impl Inner {
+    /// Polls all tasks and removes those that finished
+    fn run(inner: &RefCell<Inner>) {
+        // Tasks may spawn or cancel tasks, so the list is not borrowed while they run
+        let tasks = mem::take(&mut inner.borrow_mut().tasks);
+        let mut context = Context::from_waker(Waker::noop());
+        let mut pending = Vec::with_capacity(tasks.len());
+        for mut task in tasks {
+            if inner.borrow().cancelled.contains(&task.id) {
+                continue;
+            }
+            inner.borrow_mut().running = Some(task.id);
+            if task.future.as_mut().poll(&mut context).is_pending() {
+                pending.push(task);
+            }
+        }
+        let mut inner = inner.borrow_mut();
+        inner.running = None;
+        let cancelled = mem::take(&mut inner.cancelled);
+        pending.retain(|task| !cancelled.contains(&task.id));
+        // Keep tasks spawned while polling
+        pending.append(&mut inner.tasks);
+        inner.tasks = pending;
+    }
+}
+
+struct Task {
+    id: TaskId,
+    future: Pin<Box<dyn Future<Output = ()>>>,
+}
+
+/// Returns a future that finishes after at least the provided duration
+///
+/// The duration starts when the future is first polled.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        duration,
+        deadline: None,
+    }
+}
+
+/// A future returned by [`sleep`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct Sleep {
+    duration: Duration,
+    deadline: Option<Instant>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context<'_>) -> Poll<()> {
+        let now = Instant::now();
+        let duration = self.duration;
+        let deadline = *self.deadline.get_or_insert(now + duration);
+        if now >= deadline {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Returns a future that finishes in the next flight loop
+pub fn next_loop() -> NextLoop {
+    NextLoop { polled: false }
+}
+
+/// A future returned by [`next_loop`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct NextLoop {
+    polled: bool,
+}
+
+impl Future for NextLoop {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context<'_>) -> Poll<()> {
+        if self.polled {
+            Poll::Ready(())
+        } else {
+            self.polled = true;
+            Poll::Pending
+        }
+    }
+}
+
+/// Returns a future that finishes when a condition becomes true
+///
+/// The condition is checked once per flight loop, starting when the future is first polled.
+pub fn wait_until<F: FnMut() -> bool>(condition: F) -> WaitUntil<F> {
+    WaitUntil { condition }
+}
+
+/// A future returned by [`wait_until`]
+#[must_use = "futures do nothing unless awaited"]
+pub struct WaitUntil<F> {
+    condition: F,
+}
+
+impl<F: FnMut() -> bool + Unpin> Future for WaitUntil<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context<'_>) -> Poll<()> {
+        if (self.condition)() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<F> fmt::Debug for WaitUntil<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WaitUntil").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_loop, sleep, wait_until};
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Waker};
+    use std::time::Duration;
+
+    #[test]
+    fn test_futures() {
+        let mut context = Context::from_waker(Waker::noop());
+
+        let mut next = pin!(next_loop());
+        assert!(next.as_mut().poll(&mut context).is_pending());
+        assert!(next.as_mut().poll(&mut context).is_ready());
+
+        let mut sleep_zero = pin!(sleep(Duration::ZERO));
+        assert!(sleep_zero.as_mut().poll(&mut context).is_ready());
+        let mut sleep_long = pin!(sleep(Duration::from_secs(3600)));
+        assert!(sleep_long.as_mut().poll(&mut context).is_pending());
+
+        let mut checks = 0;
+        let mut wait = pin!(wait_until(|| {
+            checks += 1;
+            checks == 3
+        }));
+        assert!(wait.as_mut().poll(&mut context).is_pending());
+        assert!(wait.as_mut().poll(&mut context).is_pending());
+        assert!(wait.as_mut().poll(&mut context).is_ready());
+    }
+}
@@ -0,0 +1,138 @@
+//! # Fault injection
+//!
+//! Error handling code in plugins is hard to test, because X-Plane rarely fails. With the
+//! `fault-injection` feature, tests can make some SDK operations fail on purpose:
+//!
+//! * [`DataRef::find`](crate::data::borrowed::DataRef::find) returns
+//!   [`FindError::NotFound`](crate::data::borrowed::FindError::NotFound)
+//! * [`OwnedCommand::new`](crate::command::OwnedCommand::new) returns
+//!   [`CommandCreateError::Failed`](crate::command::CommandCreateError::Failed)
+//! * [`Window::try_new`](crate::window::Window::try_new) returns
+//!   [`WindowCreateError`](crate::window::WindowCreateError)
+//!
+//! A fault stays active until its [`FaultGuard`] is dropped.
+//!
+//! This feature is intended for testing. It should not be enabled in released plugins.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::fault::{self, Fault};
+//!
+//! let _guard = fault::inject(Fault::DataRefNotFound, Some("sim/flightmodel/position/latitude"));
+//! assert!(DataRef::<f64>::find("sim/flightmodel/position/latitude").is_err());
+//! ```
+//!
+
+use std::cell::RefCell;
+
+/// Operations that can be made to fail
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Fault {
+    /// Finding a dataref
+    DataRefNotFound,
+    /// Creating a command
+    CommandCreate,
+    /// Creating a window
+    WindowCreate,
+}
+
+/// An active fault
+#[derive(Debug, Clone)]
+struct Rule {
+    id: u64,
+    fault: Fault,
+    /// The name of the dataref or command that fails, or None if all fail
+    name: Option<String>,
+}
+
+impl Rule {
+    fn matches(&self, fault: Fault, name: Option<&str>) -> bool {
+        self.fault == fault
+            && match self.name {
+                Some(ref rule_name) => name == Some(rule_name.as_str()),
+                None => true,
+            }
+    }
+}
+
+/// The active faults
+#[derive(Debug, Default)]
+struct Rules {
+    rules: Vec<Rule>,
+    next_id: u64,
+}
+
+thread_local! {
+    static RULES: RefCell<Rules> = RefCell::new(Rules::default());
+}
+
+/// Makes an operation fail until the returned guard is dropped
+///
+/// If name is Some, only the dataref or command with that name fails. Windows have no names,
+/// so a window fault with a name never applies.
+#[must_use = "The fault is removed when the guard is dropped"]
+pub fn inject(fault: Fault, name: Option<&str>) -> FaultGuard {
+    RULES.with(|rules| {
+        let mut rules = rules.borrow_mut();
+        let id = rules.next_id;
+        rules.next_id += 1;
+        rules.rules.push(Rule {
+            id,
+            fault,
+            name: name.map(str::to_owned),
+        });
+        FaultGuard { id }
+    })
+}
+
+/// Removes all active faults
+///
+/// Guards for the removed faults have no effect when they are dropped.
+pub fn clear() {
+    RULES.with(|rules| rules.borrow_mut().rules.clear());
+}
+
+/// Returns true if an operation on the provided dataref, command, or window should fail
+pub(crate) fn triggered(fault: Fault, name: Option<&str>) -> bool {
+    RULES.with(|rules| {
+        rules
+            .borrow()
+            .rules
+            .iter()
+            .any(|rule| rule.matches(fault, name))
+    })
+}
+
+/// Removes a fault when dropped
+#[derive(Debug)]
+pub struct FaultGuard {
+    id: u64,
+}
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        RULES.with(|rules| rules.borrow_mut().rules.retain(|rule| rule.id != self.id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inject, triggered, Fault};
+
+    #[test]
+    fn test_inject() {
+        let named = inject(Fault::DataRefNotFound, Some("a/b"));
+        assert!(triggered(Fault::DataRefNotFound, Some("a/b")));
+        assert!(!triggered(Fault::DataRefNotFound, Some("a/c")));
+        assert!(!triggered(Fault::CommandCreate, Some("a/b")));
+        let all = inject(Fault::WindowCreate, None);
+        assert!(triggered(Fault::WindowCreate, None));
+        drop(named);
+        assert!(!triggered(Fault::DataRefNotFound, Some("a/b")));
+        drop(all);
+        assert!(!triggered(Fault::WindowCreate, None));
+    }
+}
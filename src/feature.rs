@@ -1,3 +1,26 @@
+//! # SDK features
+//!
+//! Features change how X-Plane treats a plugin, for example by using native file paths or
+//! calling drawing callbacks for water reflections. [`KnownFeature`] lists the features
+//! that this library knows about. Other features can be found by name with [`find_feature`].
+//!
+//! Features can also be enabled before the plugin starts, with the `features` option of
+//! [`xplane_plugin!`](crate::xplane_plugin).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::feature::{supported_features, KnownFeature};
+//!
+//! if KnownFeature::WantsReflections.is_supported() {
+//!     KnownFeature::WantsReflections.set_enabled(true).unwrap();
+//! }
+//! for feature in supported_features() {
+//!     xplm::debugln!("{} enabled: {}", feature, feature.enabled());
+//! }
+//! ```
+//!
+
 use std::ffi::{CStr, CString};
 use std::fmt;
 use std::os::raw::*;
@@ -17,8 +40,14 @@ impl Feature {
         &self.name
     }
 
+    /// Returns the known feature with this name, if there is one
+    pub fn known(&self) -> Option<KnownFeature> {
+        KnownFeature::from_name(&self.name)
+    }
+
     /// Returns true if this feature is currently enabled
     pub fn enabled(&self) -> bool {
+        crate::debug_assert_main_thread!();
         let name_c = CString::new(&*self.name).unwrap();
         let enabled = unsafe { xplm_sys::XPLMIsFeatureEnabled(name_c.as_ptr()) };
         enabled == 1
@@ -26,6 +55,7 @@ impl Feature {
 
     /// Enables or disables this feature
     pub fn set_enabled(&self, enable: bool) {
+        crate::debug_assert_main_thread!();
         // Because this name was either copied from C with XPLMEnumerateFeatures or
         // checked with XPLMHasFeature, it must be valid as a C string.
         let name_c = CString::new(&*self.name).unwrap();
@@ -41,6 +71,7 @@ impl fmt::Display for Feature {
 
 /// Looks for a feature with the provided name and returns it if it exists
 pub fn find_feature<S: Into<String>>(name: S) -> Option<Feature> {
+    crate::debug_assert_main_thread!();
     match CString::new(name.into()) {
         Ok(name) => {
             let has_feature = unsafe { xplm_sys::XPLMHasFeature(name.as_ptr()) };
@@ -60,6 +91,7 @@ pub fn find_feature<S: Into<String>>(name: S) -> Option<Feature> {
 
 /// Returns all features supported by the X-Plane plugin SDK
 pub fn all_features() -> Vec<Feature> {
+    crate::debug_assert_main_thread!();
     let mut features = Vec::new();
     let features_ptr: *mut _ = &mut features;
     unsafe {
@@ -68,6 +100,11 @@ pub fn all_features() -> Vec<Feature> {
     features
 }
 
+/// Returns an iterator over all features supported by the X-Plane plugin SDK
+pub fn supported_features() -> impl Iterator<Item = Feature> {
+    all_features().into_iter()
+}
+
 /// Interprets refcon as a pointer to a Vec<Feature>.
 /// Allocates a new Feature and adds it to the vector
 unsafe extern "C" fn feature_callback(feature: *const c_char, refcon: *mut c_void) {
@@ -81,3 +118,106 @@ unsafe extern "C" fn feature_callback(feature: *const c_char, refcon: *mut c_voi
         (*features).push(new_feature);
     }
 }
+
+/// SDK features that this library knows about
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KnownFeature {
+    /// `XPLM_USE_NATIVE_PATHS`: File paths use the native format of the operating system
+    ///
+    /// This library always enables this feature.
+    NativePaths,
+    /// `XPLM_USE_NATIVE_WIDGET_WINDOWS`: Widgets are drawn in modern windows
+    NativeWidgetWindows,
+    /// `XPLM_WANTS_REFLECTIONS`: Drawing callbacks are also called for water reflections
+    WantsReflections,
+    /// `XPLM_WANTS_DATAREF_NOTIFICATIONS`: The plugin receives a message when datarefs are
+    /// added
+    WantsDatarefNotifications,
+}
+
+impl KnownFeature {
+    /// All known features
+    pub const ALL: [KnownFeature; 4] = [
+        KnownFeature::NativePaths,
+        KnownFeature::NativeWidgetWindows,
+        KnownFeature::WantsReflections,
+        KnownFeature::WantsDatarefNotifications,
+    ];
+
+    /// Returns the name that the SDK uses for this feature
+    pub fn name(self) -> &'static str {
+        match self {
+            KnownFeature::NativePaths => "XPLM_USE_NATIVE_PATHS",
+            KnownFeature::NativeWidgetWindows => "XPLM_USE_NATIVE_WIDGET_WINDOWS",
+            KnownFeature::WantsReflections => "XPLM_WANTS_REFLECTIONS",
+            KnownFeature::WantsDatarefNotifications => "XPLM_WANTS_DATAREF_NOTIFICATIONS",
+        }
+    }
+
+    /// Returns the known feature with the provided SDK name, if there is one
+    pub fn from_name(name: &str) -> Option<Self> {
+        KnownFeature::ALL
+            .iter()
+            .copied()
+            .find(|feature| feature.name() == name)
+    }
+
+    /// Returns this feature if the running version of X-Plane supports it
+    pub fn find(self) -> Option<Feature> {
+        find_feature(self.name())
+    }
+
+    /// Returns true if the running version of X-Plane supports this feature
+    pub fn is_supported(self) -> bool {
+        self.find().is_some()
+    }
+
+    /// Returns true if this feature is supported and enabled
+    pub fn is_enabled(self) -> bool {
+        self.find().is_some_and(|feature| feature.enabled())
+    }
+
+    /// Enables or disables this feature
+    pub fn set_enabled(self, enable: bool) -> Result<(), UnsupportedFeature> {
+        let feature = self.find().ok_or(UnsupportedFeature(self))?;
+        feature.set_enabled(enable);
+        Ok(())
+    }
+
+    /// Returns an iterator over the known features that the running version of X-Plane
+    /// supports
+    pub fn supported() -> impl Iterator<Item = KnownFeature> {
+        KnownFeature::ALL
+            .into_iter()
+            .filter(|feature| feature.is_supported())
+    }
+}
+
+impl fmt::Display for KnownFeature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An error returned when enabling a feature that X-Plane does not support
+#[derive(thiserror::Error, Debug)]
+#[error("X-Plane does not support feature {0}")]
+pub struct UnsupportedFeature(pub KnownFeature);
+
+#[cfg(test)]
+mod tests {
+    use super::KnownFeature;
+
+    #[test]
+    fn test_known_names() {
+        for feature in KnownFeature::ALL {
+            assert_eq!(KnownFeature::from_name(feature.name()), Some(feature));
+        }
+        assert_eq!(
+            KnownFeature::from_name("XPLM_WANTS_REFLECTIONS"),
+            Some(KnownFeature::WantsReflections)
+        );
+        assert_eq!(KnownFeature::from_name("XPLM_NOT_A_FEATURE"), None);
+    }
+}
@@ -0,0 +1,65 @@
+//! # Feedback hook
+//!
+//! A plugin can set a hook that is called every time a menu item or command created with
+//! this crate is used. This is useful for click sounds or usage statistics, without adding
+//! a call to every handler.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::feedback::{self, Feedback};
+//!
+//! feedback::set_hook(|event: &Feedback| match event {
+//!     Feedback::MenuClick(name) => xplm::debugln!("Menu item {} clicked", name),
+//!     Feedback::Command(name) => xplm::debugln!("Command {} triggered", name),
+//!     _ => {}
+//! });
+//! ```
+//!
+
+use std::cell::RefCell;
+
+/// A hook function
+type Hook = Box<dyn FnMut(&Feedback)>;
+
+thread_local! {
+    /// The current hook
+    static HOOK: RefCell<Option<Hook>> = const { RefCell::new(None) };
+}
+
+/// An event that the hook is called for
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Feedback<'a> {
+    /// A menu item with the provided name was clicked
+    MenuClick(&'a str),
+    /// A command owned by this plugin with the provided name began
+    Command(&'a str),
+}
+
+/// Sets the hook, replacing any existing hook
+///
+/// The hook is called before the menu item or command handler.
+pub fn set_hook<F: FnMut(&Feedback) + 'static>(hook: F) {
+    HOOK.with(|current| *current.borrow_mut() = Some(Box::new(hook)));
+}
+
+/// Removes the hook
+pub fn clear_hook() {
+    HOOK.with(|current| current.borrow_mut().take());
+}
+
+/// Calls the hook, if one is set
+pub(crate) fn notify(event: Feedback) {
+    // The hook is taken out while it runs, so it can trigger commands or replace itself
+    let hook = HOOK.with(|current| current.borrow_mut().take());
+    if let Some(mut hook) = hook {
+        hook(&event);
+        HOOK.with(|current| {
+            let mut current = current.borrow_mut();
+            if current.is_none() {
+                *current = Some(hook);
+            }
+        });
+    }
+}
@@ -30,7 +30,7 @@ impl StringBuffer {
     /// set to null bytes (`\0`).
     pub fn new(length: usize) -> StringBuffer {
         StringBuffer {
-            bytes: iter::repeat(b'\0').take(length).collect(),
+            bytes: iter::repeat_n(b'\0', length).collect(),
         }
     }
 
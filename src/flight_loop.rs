@@ -5,6 +5,9 @@
 //! A FlightLoop object must persist for callbacks to occur. When the FlightLoop is dropped,
 //! its callbacks will stop.
 //!
+//! A [`Timer`] is simpler to use when a closure only needs to run once after a delay or at a
+//! fixed interval.
+//!
 //! # Examples
 //!
 //! Closure handler:
@@ -40,11 +43,13 @@
 
 use xplm_sys;
 
+use std::cell::Cell;
 use std::f32;
 use std::fmt;
 use std::mem;
 use std::ops::DerefMut;
 use std::os::raw::*;
+use std::rc::Rc;
 use std::time::Duration;
 
 /// Tracks a flight loop callback, which can be called by X-Plane periodically for calculations
@@ -60,18 +65,43 @@ impl FlightLoop {
     ///
     /// Provide the callback to be called
     ///
-    /// The callback will not be called until it is scheduled
+    /// The callback will not be called until it is scheduled. It runs after X-Plane
+    /// calculates the flight model.
     pub fn new<C: FlightLoopCallback>(callback: C) -> Self {
+        FlightLoop::with_phase(Phase::AfterFlightModel, callback)
+    }
+
+    /// Creates a new flight loop that runs in the provided phase
+    ///
+    /// Plugins that provide control inputs should use [`Phase::BeforeFlightModel`] so that
+    /// the flight model uses their inputs in the same frame.
+    ///
+    /// The callback will not be called until it is scheduled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::flight_loop::{FlightLoop, LoopState, Phase};
+    ///
+    /// let mut autopilot = FlightLoop::with_phase(Phase::BeforeFlightModel, |_: &mut LoopState| {
+    ///     // Set control surface datarefs
+    /// });
+    /// autopilot.schedule_immediate();
+    /// ```
+    pub fn with_phase<C: FlightLoopCallback>(phase: Phase, callback: C) -> Self {
+        crate::debug_assert_main_thread!();
         let mut data = Box::new(LoopData::new(callback));
         let data_ptr: *mut LoopData = data.deref_mut();
         // Create a flight loop
         let mut config = xplm_sys::XPLMCreateFlightLoop_t {
             structSize: mem::size_of::<xplm_sys::XPLMCreateFlightLoop_t>() as c_int,
-            phase: xplm_sys::xplm_FlightLoop_Phase_AfterFlightModel as i32,
+            phase: phase.to_xplm(),
             callbackFunc: Some(flight_loop_callback::<C>),
             refcon: data_ptr as *mut c_void,
         };
         data.loop_id = unsafe { Some(xplm_sys::XPLMCreateFlightLoop(&mut config)) };
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::created("FlightLoop", data_ptr);
         FlightLoop { data }
     }
 
@@ -105,6 +135,112 @@ impl FlightLoop {
     }
 }
 
+/// Runs a closure once after a delay, or repeatedly at an interval
+///
+/// The timer is cancelled when this object is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use xplm::flight_loop::Timer;
+///
+/// let reminder = Timer::once(Duration::from_secs(5), || {
+///     xplm::debugln!("Five seconds have passed");
+/// });
+/// let mut heartbeat = Timer::every(Duration::from_secs(1), || {
+///     xplm::debugln!("Still running");
+/// });
+/// // Later
+/// heartbeat.cancel();
+/// ```
+#[derive(Debug)]
+pub struct Timer {
+    flight_loop: FlightLoop,
+    /// If the closure will be called again
+    active: Rc<Cell<bool>>,
+}
+
+impl Timer {
+    /// Creates a timer that calls a closure once, after a delay
+    ///
+    /// If the delay is zero, the closure is called in the next flight loop.
+    pub fn once<F: FnOnce() + 'static>(delay: Duration, callback: F) -> Self {
+        let mut callback = Some(callback);
+        Timer::start(delay, move |state: &mut LoopState, active: &Cell<bool>| {
+            state.deactivate();
+            active.set(false);
+            if let Some(callback) = callback.take() {
+                callback();
+            }
+        })
+    }
+
+    /// Creates a timer that calls a closure repeatedly, with the provided interval between
+    /// calls
+    ///
+    /// The first call happens after one interval. If the interval is zero, the closure is
+    /// called every flight loop.
+    pub fn every<F: FnMut() + 'static>(interval: Duration, mut callback: F) -> Self {
+        Timer::start(interval, move |_: &mut LoopState, _: &Cell<bool>| {
+            callback()
+        })
+    }
+
+    fn start<F>(delay: Duration, mut callback: F) -> Self
+    where
+        F: FnMut(&mut LoopState, &Cell<bool>) + 'static,
+    {
+        let active = Rc::new(Cell::new(true));
+        let loop_active = Rc::clone(&active);
+        let mut flight_loop =
+            FlightLoop::new(move |state: &mut LoopState| callback(state, &loop_active));
+        if delay.is_zero() {
+            flight_loop.schedule_immediate();
+        } else {
+            flight_loop.schedule_after(delay);
+        }
+        Timer {
+            flight_loop,
+            active,
+        }
+    }
+
+    /// Stops this timer so that the closure will not be called again
+    pub fn cancel(&mut self) {
+        self.flight_loop.deactivate();
+        self.active.set(false);
+    }
+
+    /// Returns true if the closure will be called again
+    ///
+    /// This returns false after a one-time timer has fired or after the timer was cancelled.
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+}
+
+/// When a flight loop callback runs, relative to the flight model calculations
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Phase {
+    /// Before X-Plane calculates the flight model
+    BeforeFlightModel,
+    /// After X-Plane calculates the flight model
+    #[default]
+    AfterFlightModel,
+}
+
+impl Phase {
+    fn to_xplm(self) -> xplm_sys::XPLMFlightLoopPhaseType {
+        let phase = match self {
+            Phase::BeforeFlightModel => xplm_sys::xplm_FlightLoop_Phase_BeforeFlightModel,
+            Phase::AfterFlightModel => xplm_sys::xplm_FlightLoop_Phase_AfterFlightModel,
+        };
+        phase as xplm_sys::XPLMFlightLoopPhaseType
+    }
+}
+
 /// Data stored as part of a FlightLoop and used as a refcon
 struct LoopData {
     /// The loop result, or None if the loop has not been scheduled
@@ -144,6 +280,9 @@ impl LoopData {
 
 impl Drop for LoopData {
     fn drop(&mut self) {
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::dropped("FlightLoop", self);
+        super::panics::forget(self);
         if let Some(loop_id) = self.loop_id {
             unsafe { xplm_sys::XPLMDestroyFlightLoop(loop_id) }
         }
@@ -240,7 +379,7 @@ impl From<LoopResult> for f32 {
         match lr {
             LoopResult::Deactivate => 0f32,
             LoopResult::Seconds(secs) => secs,
-            LoopResult::Loops(loops) => -1.0f32 * (loops as f32),
+            LoopResult::Loops(loops) => -(loops as f32),
         }
     }
 }
@@ -254,21 +393,33 @@ unsafe extern "C" fn flight_loop_callback<C: FlightLoopCallback>(
     counter: c_int,
     refcon: *mut c_void,
 ) -> c_float {
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("FlightLoop", refcon) {
+        // Deactivate
+        return 0.0;
+    }
     // Get the loop data
     let loop_data = refcon as *mut LoopData;
-    // Create a state
-    let mut state = LoopState {
-        since_call: secs_to_duration(since_last_call),
-        since_loop: secs_to_duration(since_loop),
-        counter,
-        result: (*loop_data).loop_result.as_mut().unwrap(),
-    };
-    let callback_ptr: *mut dyn FlightLoopCallback = (*loop_data).callback.as_mut();
-    let callback = callback_ptr as *mut C;
-    (*callback).flight_loop(&mut state);
+    if super::internal::suspended() {
+        // Keep the current schedule without calling the callback
+        return f32::from((*loop_data).loop_result.clone().unwrap());
+    }
+    // Deactivate a callback that has been disabled after panicking
+    super::panics::contain("flight loop", loop_data, 0.0, || {
+        // Create a state
+        let mut state = LoopState {
+            since_call: secs_to_duration(since_last_call),
+            since_loop: secs_to_duration(since_loop),
+            counter,
+            result: (*loop_data).loop_result.as_mut().unwrap(),
+        };
+        let callback_ptr: *mut dyn FlightLoopCallback = (*loop_data).callback.as_mut();
+        let callback = callback_ptr as *mut C;
+        (*callback).flight_loop(&mut state);
 
-    // Return the next loop time
-    f32::from(state.result.clone())
+        // Return the next loop time
+        f32::from(state.result.clone())
+    })
 }
 
 fn secs_to_duration(time: f32) -> Duration {
@@ -0,0 +1,276 @@
+//! # Per-frame dataref snapshots
+//!
+//! Reading datarefs from a draw callback can be slow, and different draw callbacks that read
+//! the same dataref in one frame may see different values.
+//!
+//! A [`FrameData`] reads a configured set of datarefs once per frame in a flight loop
+//! callback and stores the values. Draw callbacks read the stored values through a
+//! [`FrameReader`], so all drawing in a frame uses the same values.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::draw::{Draw, Phase};
+//! use xplm::frame_data::FrameDataBuilder;
+//!
+//! let mut builder = FrameDataBuilder::new();
+//! let heading = builder.float(DataRef::find("sim/flightmodel/position/psi").unwrap());
+//! let frame_data = builder.build();
+//!
+//! let reader = frame_data.reader();
+//! let draw = Draw::new(Phase::AfterWindows, move || {
+//!     let snapshot = reader.snapshot();
+//!     let heading: f32 = *snapshot.get(heading);
+//!     // Draw using heading
+//! })
+//! .unwrap();
+//! ```
+//!
+
+use std::cell::{Ref, RefCell};
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use super::data::{ArrayRead, DataRead};
+use super::flight_loop::{FlightLoop, LoopState};
+
+/// A function that reads one dataref into a snapshot
+type Source = Box<dyn FnMut(&mut Snapshot)>;
+
+/// Configures the datarefs that a FrameData reads
+#[derive(Default)]
+pub struct FrameDataBuilder {
+    /// The dataref readers
+    sources: Vec<Source>,
+    /// The snapshot, with a slot for each source
+    snapshot: Snapshot,
+}
+
+impl FrameDataBuilder {
+    /// Creates a builder with no datarefs
+    pub fn new() -> Self {
+        FrameDataBuilder::default()
+    }
+
+    /// Adds a floating-point dataref
+    pub fn float<D: DataRead<f32> + 'static>(&mut self, dataref: D) -> FrameKey<f32> {
+        let index = self.snapshot.floats.len();
+        self.snapshot.floats.push(dataref.get());
+        self.sources.push(Box::new(move |snapshot: &mut Snapshot| {
+            snapshot.floats[index] = dataref.get();
+        }));
+        FrameKey::new(index)
+    }
+    /// Adds a double-precision dataref
+    pub fn double<D: DataRead<f64> + 'static>(&mut self, dataref: D) -> FrameKey<f64> {
+        let index = self.snapshot.doubles.len();
+        self.snapshot.doubles.push(dataref.get());
+        self.sources.push(Box::new(move |snapshot: &mut Snapshot| {
+            snapshot.doubles[index] = dataref.get();
+        }));
+        FrameKey::new(index)
+    }
+    /// Adds an integer dataref
+    pub fn int<D: DataRead<i32> + 'static>(&mut self, dataref: D) -> FrameKey<i32> {
+        let index = self.snapshot.ints.len();
+        self.snapshot.ints.push(dataref.get());
+        self.sources.push(Box::new(move |snapshot: &mut Snapshot| {
+            snapshot.ints[index] = dataref.get();
+        }));
+        FrameKey::new(index)
+    }
+    /// Adds a floating-point array dataref
+    ///
+    /// The length of the array is checked every frame.
+    pub fn float_array<D: ArrayRead<[f32]> + 'static>(&mut self, dataref: D) -> FrameKey<Vec<f32>> {
+        let index = self.snapshot.float_arrays.len();
+        self.snapshot.float_arrays.push(dataref.as_vec());
+        self.sources.push(Box::new(move |snapshot: &mut Snapshot| {
+            read_array(&dataref, &mut snapshot.float_arrays[index]);
+        }));
+        FrameKey::new(index)
+    }
+    /// Adds an integer array dataref
+    ///
+    /// The length of the array is checked every frame.
+    pub fn int_array<D: ArrayRead<[i32]> + 'static>(&mut self, dataref: D) -> FrameKey<Vec<i32>> {
+        let index = self.snapshot.int_arrays.len();
+        self.snapshot.int_arrays.push(dataref.as_vec());
+        self.sources.push(Box::new(move |snapshot: &mut Snapshot| {
+            read_array(&dataref, &mut snapshot.int_arrays[index]);
+        }));
+        FrameKey::new(index)
+    }
+
+    /// Starts reading the datarefs every frame
+    ///
+    /// The snapshot contains the values of the datarefs when they were added until the flight
+    /// loop runs for the first time.
+    pub fn build(self) -> FrameData {
+        let snapshot = Rc::new(RefCell::new(self.snapshot));
+        let loop_snapshot = Rc::clone(&snapshot);
+        let mut sources = self.sources;
+        let mut flight_loop = FlightLoop::new(move |_: &mut LoopState| {
+            let mut snapshot = loop_snapshot.borrow_mut();
+            for source in sources.iter_mut() {
+                source(&mut snapshot);
+            }
+            snapshot.frame = snapshot.frame.wrapping_add(1);
+        });
+        flight_loop.schedule_immediate();
+        FrameData {
+            snapshot,
+            _flight_loop: flight_loop,
+        }
+    }
+}
+
+impl fmt::Debug for FrameDataBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FrameDataBuilder")
+            .field("snapshot", &self.snapshot)
+            .finish()
+    }
+}
+
+/// Reads datarefs once per frame
+///
+/// The datarefs are no longer read when this object is dropped.
+#[derive(Debug)]
+pub struct FrameData {
+    /// The most recent values
+    snapshot: Rc<RefCell<Snapshot>>,
+    /// The flight loop that reads the datarefs
+    _flight_loop: FlightLoop,
+}
+
+impl FrameData {
+    /// Returns a reader that can be moved into a draw callback
+    pub fn reader(&self) -> FrameReader {
+        FrameReader {
+            snapshot: Rc::clone(&self.snapshot),
+        }
+    }
+
+    /// Returns the most recent values
+    pub fn snapshot(&self) -> Ref<'_, Snapshot> {
+        self.snapshot.borrow()
+    }
+}
+
+/// Provides read-only access to the values read by a FrameData
+#[derive(Debug, Clone)]
+pub struct FrameReader {
+    snapshot: Rc<RefCell<Snapshot>>,
+}
+
+impl FrameReader {
+    /// Returns the most recent values
+    pub fn snapshot(&self) -> Ref<'_, Snapshot> {
+        self.snapshot.borrow()
+    }
+}
+
+/// The values of datarefs in one frame
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    floats: Vec<f32>,
+    doubles: Vec<f64>,
+    ints: Vec<i32>,
+    float_arrays: Vec<Vec<f32>>,
+    int_arrays: Vec<Vec<i32>>,
+    /// The number of times the values have been read
+    frame: u64,
+}
+
+impl Snapshot {
+    /// Returns a value
+    ///
+    /// The key must have been returned by the builder of the FrameData that provided this
+    /// snapshot. Otherwise, this function may panic or return a different value.
+    pub fn get<T: FrameValue>(&self, key: FrameKey<T>) -> &T {
+        &T::values(self)[key.index]
+    }
+
+    /// Returns the number of times the values have been read
+    ///
+    /// This increases by one every frame.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
+
+/// Identifies a value in a snapshot
+pub struct FrameKey<T> {
+    /// The index of the value in the list for its type
+    index: usize,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> FrameKey<T> {
+    fn new(index: usize) -> Self {
+        FrameKey {
+            index,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for FrameKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for FrameKey<T> {}
+
+impl<T> fmt::Debug for FrameKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FrameKey")
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+/// Types that can be stored in a snapshot
+///
+/// This trait is implemented for f32, f64, i32, `Vec<f32>`, and `Vec<i32>`. It cannot be
+/// implemented outside this crate.
+pub trait FrameValue: private::Sealed + Sized {
+    /// Returns the values of this type in a snapshot
+    #[doc(hidden)]
+    fn values(snapshot: &Snapshot) -> &[Self];
+}
+
+macro_rules! frame_value {
+    ($native_type:ty, $field:ident) => {
+        impl private::Sealed for $native_type {}
+        impl FrameValue for $native_type {
+            fn values(snapshot: &Snapshot) -> &[Self] {
+                &snapshot.$field
+            }
+        }
+    };
+}
+
+frame_value!(f32, floats);
+frame_value!(f64, doubles);
+frame_value!(i32, ints);
+frame_value!(Vec<f32>, float_arrays);
+frame_value!(Vec<i32>, int_arrays);
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Reads an array dataref into a Vec, resizing it if the length has changed
+fn read_array<T, D>(dataref: &D, values: &mut Vec<T>)
+where
+    T: Default + Clone,
+    D: ArrayRead<[T]>,
+    [T]: super::data::ArrayType<Element = T>,
+{
+    values.resize(dataref.len(), T::default());
+    dataref.get(values);
+}
@@ -0,0 +1,392 @@
+//! # Round gauges
+//!
+//! Helpers for drawing steam gauges in windows or in the panel draw phases:
+//!
+//! * A [`Scale`] maps a dataref value to a needle angle
+//! * [`LowPass`] and [`RateLimit`] smooth values so that needles move like real ones
+//! * A [`RotatedQuad`] draws a needle or other textured rectangle rotated around a pivot
+//! * [`draw_arc`] draws colored range markings
+//!
+//! Angles are in degrees clockwise from straight up. Positions are in the coordinates of the
+//! current draw phase.
+//!
+//! This module is available with the `gauges` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::data::DataRead;
+//! use xplm::draw::{Draw, Phase};
+//! use xplm::gauges::{self, LowPass, RotatedQuad, Scale};
+//! use xplm::geometry::Point;
+//!
+//! let airspeed: DataRef<f32> =
+//!     DataRef::find("sim/flightmodel/position/indicated_airspeed").unwrap();
+//! // 40 knots at the bottom left, 200 knots at the bottom right
+//! let scale = Scale::linear(40.0, 200.0, -135.0, 135.0);
+//! let mut filter = LowPass::new(Duration::from_millis(150));
+//! let needle = RotatedQuad::needle(60.0, 4.0);
+//! let center = Point::from_xy(200.0, 200.0);
+//!
+//! let draw = Draw::new(Phase::AfterGauges, move || {
+//!     // Green arc from 60 to 160 knots
+//!     let green = [0.0, 0.8, 0.0, 1.0];
+//!     gauges::draw_arc(center, 70.0, 5.0, scale.angle(60.0), scale.angle(160.0), green);
+//!     let value = filter.update(airspeed.get(), Duration::from_millis(16));
+//!     needle.draw(center, scale.angle(value));
+//! })
+//! .unwrap();
+//! ```
+//!
+
+use std::time::Duration;
+
+use super::draw::{self, GraphicsState};
+use super::geometry::Point;
+use super::gl;
+use super::window::style::Rgba;
+
+/// The number of triangle pairs used for each 90 degrees of an arc
+const ARC_SEGMENTS_PER_QUADRANT: f32 = 16.0;
+
+/// Maps values to needle angles
+///
+/// Values between two points are interpolated linearly. Values outside the points are
+/// clamped to the first or last angle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scale {
+    /// (value, angle) pairs sorted by value
+    points: Vec<(f32, f32)>,
+}
+
+impl Scale {
+    /// Creates a scale with one angle for the minimum value and one for the maximum value
+    pub fn linear(min_value: f32, max_value: f32, min_angle: f32, max_angle: f32) -> Self {
+        Scale::from_points(&[(min_value, min_angle), (max_value, max_angle)])
+    }
+
+    /// Creates a scale from (value, angle) pairs, for gauges with non-linear markings
+    ///
+    /// # Panics
+    ///
+    /// This function panics if points is empty or the values are not in increasing order.
+    pub fn from_points(points: &[(f32, f32)]) -> Self {
+        assert!(!points.is_empty(), "A scale needs at least one point");
+        assert!(
+            points.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Scale values must be in increasing order"
+        );
+        Scale {
+            points: points.to_vec(),
+        }
+    }
+
+    /// Returns the angle for a value
+    pub fn angle(&self, value: f32) -> f32 {
+        let (first_value, first_angle) = self.points[0];
+        if value <= first_value {
+            return first_angle;
+        }
+        for pair in self.points.windows(2) {
+            let ((value0, angle0), (value1, angle1)) = (pair[0], pair[1]);
+            if value <= value1 {
+                let fraction = (value - value0) / (value1 - value0);
+                return angle0 + fraction * (angle1 - angle0);
+            }
+        }
+        self.points[self.points.len() - 1].1
+    }
+}
+
+/// A first-order low-pass filter, for needles that lag behind the value
+#[derive(Debug, Clone)]
+pub struct LowPass {
+    /// The time constant in seconds
+    time_constant: f32,
+    value: Option<f32>,
+}
+
+impl LowPass {
+    /// Creates a filter with the provided time constant
+    ///
+    /// After one time constant, the output has moved about 63% of the way to a new input.
+    pub fn new(time_constant: Duration) -> Self {
+        LowPass {
+            time_constant: time_constant.as_secs_f32(),
+            value: None,
+        }
+    }
+
+    /// Updates the filter with a new input and the time since the last update, and returns
+    /// the output
+    ///
+    /// The first update returns the input unchanged.
+    pub fn update(&mut self, input: f32, elapsed: Duration) -> f32 {
+        let value = match self.value {
+            Some(value) if self.time_constant > 0.0 => {
+                let alpha = 1.0 - (-elapsed.as_secs_f32() / self.time_constant).exp();
+                value + alpha * (input - value)
+            }
+            _ => input,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    /// Returns the latest output, or None if the filter has not been updated
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    /// Sets the output, for example when the aircraft is reloaded
+    pub fn reset(&mut self, value: f32) {
+        self.value = Some(value);
+    }
+}
+
+/// Limits how fast a value can change, for needles driven by slow mechanisms
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    /// The maximum change per second
+    max_rate: f32,
+    value: Option<f32>,
+}
+
+impl RateLimit {
+    /// Creates a limiter with the maximum change per second
+    pub fn new(max_rate: f32) -> Self {
+        RateLimit {
+            max_rate: max_rate.abs(),
+            value: None,
+        }
+    }
+
+    /// Updates the limiter with a new input and the time since the last update, and returns
+    /// the output
+    ///
+    /// The first update returns the input unchanged.
+    pub fn update(&mut self, input: f32, elapsed: Duration) -> f32 {
+        let value = match self.value {
+            Some(value) => {
+                let max_change = self.max_rate * elapsed.as_secs_f32();
+                value + (input - value).clamp(-max_change, max_change)
+            }
+            None => input,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    /// Returns the latest output, or None if the limiter has not been updated
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    /// Sets the output
+    pub fn reset(&mut self, value: f32) {
+        self.value = Some(value);
+    }
+}
+
+/// A rectangle that is drawn rotated around a pivot point, such as a needle
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RotatedQuad {
+    width: f32,
+    height: f32,
+    /// The point that the quad rotates around, relative to its bottom left corner
+    pivot: (f32, f32),
+    /// The texture number, or None to draw a solid color
+    texture: Option<i32>,
+    /// The color, which is multiplied with the texture
+    color: Rgba,
+}
+
+impl RotatedQuad {
+    /// Creates a white quad with the provided size that rotates around its center
+    pub fn new(width: f32, height: f32) -> Self {
+        RotatedQuad {
+            width,
+            height,
+            pivot: (width / 2.0, height / 2.0),
+            texture: None,
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    /// Creates a white needle with the provided length and width that rotates around the
+    /// center of its bottom edge
+    pub fn needle(length: f32, width: f32) -> Self {
+        RotatedQuad::new(width, length).pivot(width / 2.0, 0.0)
+    }
+
+    /// Sets the point the quad rotates around, relative to its bottom left corner
+    pub fn pivot(self, x: f32, y: f32) -> Self {
+        RotatedQuad {
+            pivot: (x, y),
+            ..self
+        }
+    }
+
+    /// Sets a texture, which is stretched over the quad
+    ///
+    /// The texture number must come from
+    /// [`generate_texture_number`](crate::draw::generate_texture_number).
+    pub fn texture(self, texture: i32) -> Self {
+        RotatedQuad {
+            texture: Some(texture),
+            ..self
+        }
+    }
+
+    /// Sets the color, which is multiplied with the texture if there is one
+    pub fn color(self, color: Rgba) -> Self {
+        RotatedQuad { color, ..self }
+    }
+
+    /// Draws this quad with its pivot at the provided point, rotated clockwise by angle degrees
+    ///
+    /// This must only be called from a draw callback. It changes the graphics state.
+    pub fn draw(&self, position: Point<f32>, angle: f32) {
+        let corners = self.corners(position, angle);
+        let textures = if self.texture.is_some() { 1 } else { 0 };
+        draw::set_state(&GraphicsState::new().alpha_blending(true).textures(textures));
+        if let Some(texture) = self.texture {
+            draw::bind_texture(texture, 0);
+        }
+        let [red, green, blue, alpha] = self.color;
+        let texture_coordinates = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        unsafe {
+            gl::glColor4f(red, green, blue, alpha);
+            gl::glBegin(gl::GL_QUADS);
+            for (&(x, y), &(s, t)) in corners.iter().zip(texture_coordinates.iter()) {
+                gl::glTexCoord2f(s, t);
+                gl::glVertex2f(x, y);
+            }
+            gl::glEnd();
+        }
+    }
+
+    /// Returns the corners, counterclockwise from the bottom left, with the pivot at the
+    /// provided position, rotated clockwise by angle degrees
+    fn corners(&self, position: Point<f32>, angle: f32) -> [(f32, f32); 4] {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (pivot_x, pivot_y) = self.pivot;
+        [
+            (0.0, 0.0),
+            (self.width, 0.0),
+            (self.width, self.height),
+            (0.0, self.height),
+        ]
+        .map(|(x, y)| {
+            let (x, y) = (x - pivot_x, y - pivot_y);
+            // Clockwise rotation
+            (
+                position.x() + x * cos + y * sin,
+                position.y() - x * sin + y * cos,
+            )
+        })
+    }
+}
+
+/// Draws an arc, such as a colored range on a gauge face
+///
+/// The arc is centered on the circle with the provided radius and extends thickness / 2 on
+/// each side. It starts at start_angle and ends at end_angle, which are in degrees clockwise
+/// from straight up.
+///
+/// This must only be called from a draw callback. It changes the graphics state.
+pub fn draw_arc(
+    center: Point<f32>,
+    radius: f32,
+    thickness: f32,
+    start_angle: f32,
+    end_angle: f32,
+    color: Rgba,
+) {
+    let points = arc_strip(center, radius, thickness, start_angle, end_angle);
+    draw::set_state(&GraphicsState::new().alpha_blending(true));
+    let [red, green, blue, alpha] = color;
+    unsafe {
+        gl::glColor4f(red, green, blue, alpha);
+        gl::glBegin(gl::GL_TRIANGLE_STRIP);
+        for (x, y) in points {
+            gl::glVertex2f(x, y);
+        }
+        gl::glEnd();
+    }
+}
+
+/// Returns the vertices of a triangle strip that draws an arc, alternating between the
+/// inside and outside edges
+fn arc_strip(
+    center: Point<f32>,
+    radius: f32,
+    thickness: f32,
+    start_angle: f32,
+    end_angle: f32,
+) -> Vec<(f32, f32)> {
+    let segments = ((end_angle - start_angle).abs() / 90.0 * ARC_SEGMENTS_PER_QUADRANT)
+        .ceil()
+        .max(1.0) as usize;
+    let inner = radius - thickness / 2.0;
+    let outer = radius + thickness / 2.0;
+    let mut points = Vec::with_capacity(2 * (segments + 1));
+    for i in 0..=segments {
+        let angle = start_angle + (end_angle - start_angle) * i as f32 / segments as f32;
+        let (sin, cos) = angle.to_radians().sin_cos();
+        for r in [inner, outer] {
+            points.push((center.x() + r * sin, center.y() + r * cos));
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arc_strip, LowPass, RateLimit, RotatedQuad, Scale};
+    use crate::geometry::Point;
+    use std::time::Duration;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_scale() {
+        let scale = Scale::from_points(&[(0.0, -90.0), (10.0, 0.0), (30.0, 90.0)]);
+        assert_eq!(scale.angle(-5.0), -90.0);
+        assert_eq!(scale.angle(5.0), -45.0);
+        assert_eq!(scale.angle(20.0), 45.0);
+        assert_eq!(scale.angle(50.0), 90.0);
+    }
+
+    #[test]
+    fn test_filters() {
+        let step = Duration::from_millis(100);
+        let mut low_pass = LowPass::new(Duration::from_millis(100));
+        assert_eq!(low_pass.update(0.0, step), 0.0);
+        let value = low_pass.update(1.0, step);
+        assert!((value - 0.632).abs() < 0.001);
+
+        let mut rate = RateLimit::new(10.0);
+        assert_eq!(rate.update(0.0, step), 0.0);
+        assert_eq!(rate.update(5.0, step), 1.0);
+        assert_eq!(rate.update(-5.0, step), 0.0);
+    }
+
+    #[test]
+    fn test_geometry() {
+        let needle = RotatedQuad::needle(10.0, 2.0);
+        let corners = needle.corners(Point::from_xy(100.0, 50.0), 90.0);
+        // Pointing right
+        assert!(close(corners[0], (100.0, 51.0)));
+        assert!(close(corners[2], (110.0, 49.0)));
+
+        let strip = arc_strip(Point::from_xy(0.0, 0.0), 10.0, 2.0, 0.0, 90.0);
+        assert_eq!(strip.len(), 34);
+        assert!(close(strip[0], (0.0, 9.0)));
+        assert!(close(strip[33], (11.0, 0.0)));
+    }
+}
@@ -0,0 +1,162 @@
+//! # Geographic coordinates
+//!
+//! X-Plane draws in local OpenGL coordinates, in meters from a reference point that moves
+//! when the aircraft flies far enough. A [`LatLon`] is a location on the Earth, and a
+//! [`LocalXYZ`] is a point in local coordinates. They can be converted into each other with
+//! [`LatLon::to_local`] and [`LocalXYZ::to_world`].
+//!
+//! This module also converts headings between true and magnetic north.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geo::{self, LatLon};
+//!
+//! let seattle = LatLon::new(47.45, -122.31);
+//! // Draw something 100 meters above Seattle
+//! let point = seattle.to_local(100.0);
+//!
+//! let variation = seattle.magnetic_variation();
+//! let runway_heading = geo::true_to_magnetic(179.9);
+//! ```
+//!
+
+use super::navigation;
+
+/// A location on the Earth
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct LatLon {
+    /// The latitude in degrees
+    pub latitude: f64,
+    /// The longitude in degrees
+    pub longitude: f64,
+}
+
+impl LatLon {
+    /// Creates a location from a latitude and longitude in degrees
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        LatLon {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Converts this location and an altitude in meters above mean sea level into local
+    /// coordinates
+    pub fn to_local(self, altitude: f64) -> LocalXYZ {
+        crate::debug_assert_main_thread!();
+        let mut local = LocalXYZ::default();
+        unsafe {
+            xplm_sys::XPLMWorldToLocal(
+                self.latitude,
+                self.longitude,
+                altitude,
+                &mut local.x,
+                &mut local.y,
+                &mut local.z,
+            );
+        }
+        local
+    }
+
+    /// Returns the magnetic variation at this location, in degrees
+    ///
+    /// The variation is positive when magnetic north is east of true north.
+    pub fn magnetic_variation(self) -> f32 {
+        crate::debug_assert_main_thread!();
+        unsafe { xplm_sys::XPLMGetMagneticVariation(self.latitude, self.longitude) }
+    }
+
+    /// Returns the great-circle distance in meters from this location to another
+    pub fn distance_to(self, other: LatLon) -> f64 {
+        navigation::distance(
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        )
+    }
+
+    /// Returns the initial true bearing in degrees from this location to another
+    pub fn bearing_to(self, other: LatLon) -> f64 {
+        navigation::bearing(
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        )
+    }
+}
+
+/// A point in local OpenGL coordinates
+///
+/// The units are meters. Positive X is east, positive Y is up, and positive Z is south.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct LocalXYZ {
+    /// The X coordinate
+    pub x: f64,
+    /// The Y coordinate
+    pub y: f64,
+    /// The Z coordinate
+    pub z: f64,
+}
+
+impl LocalXYZ {
+    /// Creates a point from local coordinates
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        LocalXYZ { x, y, z }
+    }
+
+    /// Converts this point into a location and an altitude in meters above mean sea level
+    pub fn to_world(self) -> (LatLon, f64) {
+        crate::debug_assert_main_thread!();
+        let mut location = LatLon::default();
+        let mut altitude = 0.0;
+        unsafe {
+            xplm_sys::XPLMLocalToWorld(
+                self.x,
+                self.y,
+                self.z,
+                &mut location.latitude,
+                &mut location.longitude,
+                &mut altitude,
+            );
+        }
+        (location, altitude)
+    }
+
+    /// Returns the straight-line distance in meters from this point to another
+    pub fn distance_to(self, other: LocalXYZ) -> f64 {
+        let (dx, dy, dz) = (other.x - self.x, other.y - self.y, other.z - self.z);
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+}
+
+/// Converts a heading in degrees from true north to magnetic north at the user aircraft's
+/// location
+pub fn true_to_magnetic(heading: f32) -> f32 {
+    crate::debug_assert_main_thread!();
+    unsafe { xplm_sys::XPLMDegTrueToDegMagnetic(heading) }
+}
+
+/// Converts a heading in degrees from magnetic north to true north at the user aircraft's
+/// location
+pub fn magnetic_to_true(heading: f32) -> f32 {
+    crate::debug_assert_main_thread!();
+    unsafe { xplm_sys::XPLMDegMagneticToDegTrue(heading) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LatLon, LocalXYZ};
+
+    #[test]
+    fn test_distances() {
+        let a = LocalXYZ::new(1.0, 2.0, 3.0);
+        assert_eq!(a.distance_to(LocalXYZ::new(4.0, 6.0, 3.0)), 5.0);
+        let start = LatLon::new(10.0, 20.0);
+        let north = LatLon::new(11.0, 20.0);
+        assert!((start.distance_to(north) - 111_195.0).abs() < 1.0);
+        assert!(start.bearing_to(north).abs() < 1e-9);
+    }
+}
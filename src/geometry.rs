@@ -126,3 +126,39 @@ impl<N> From<(N, N)> for Point<N> {
         Point::from_xy(x, y)
     }
 }
+
+/// A 2D size
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Size<N> {
+    /// The width
+    width: N,
+    /// The height
+    height: N,
+}
+
+impl<N> Size<N> {
+    /// Creates a size from a width and height
+    pub fn new(width: N, height: N) -> Self {
+        Size { width, height }
+    }
+    /// Consumes this size and returns its width and height
+    pub fn into_width_height(self) -> (N, N) {
+        (self.width, self.height)
+    }
+}
+
+impl<N: Clone> Size<N> {
+    pub fn width(&self) -> N {
+        self.width.clone()
+    }
+    pub fn height(&self) -> N {
+        self.height.clone()
+    }
+}
+
+impl<N> From<(N, N)> for Size<N> {
+    /// Converts a (width, height) pair into a size
+    fn from((width, height): (N, N)) -> Self {
+        Size::new(width, height)
+    }
+}
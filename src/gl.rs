@@ -0,0 +1,197 @@
+//! OpenGL functions and constants used by drawing utilities
+//!
+//! X-Plane provides the OpenGL context, so plugins do not need to create one. Functions from
+//! OpenGL 1.1 are linked directly. Later functions are not exported by the Windows OpenGL
+//! library, so they are looked up and may be unavailable.
+//!
+//! The extension functions are loaded when the plugin starts, while X-Plane's context is
+//! current. [`function`] looks up any OpenGL function for plugins that use their own
+//! bindings.
+//!
+//! This loader only uses platform functions, so it does not include any code generated from
+//! the OpenGL registry.
+
+// Some items are only used by modules that require optional features
+#![allow(dead_code)]
+
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_int, c_uchar, c_uint, c_void};
+use std::sync::OnceLock;
+
+pub const GL_FALSE: c_uchar = 0;
+pub const GL_TEXTURE_2D: c_uint = 0x0DE1;
+pub const GL_TEXTURE_BINDING_2D: c_uint = 0x8069;
+pub const GL_VIEWPORT: c_uint = 0x0BA2;
+pub const GL_SCISSOR_BOX: c_uint = 0x0C10;
+pub const GL_SCISSOR_TEST: c_uint = 0x0C11;
+pub const GL_DEPTH_WRITEMASK: c_uint = 0x0B72;
+pub const GL_PACK_ALIGNMENT: c_uint = 0x0D05;
+pub const GL_UNPACK_ALIGNMENT: c_uint = 0x0CF5;
+pub const GL_RGBA: c_uint = 0x1908;
+pub const GL_UNSIGNED_BYTE: c_uint = 0x1401;
+pub const GL_TEXTURE0: c_uint = 0x84C0;
+pub const GL_ACTIVE_TEXTURE: c_uint = 0x84E0;
+pub const GL_ARRAY_BUFFER: c_uint = 0x8892;
+pub const GL_ARRAY_BUFFER_BINDING: c_uint = 0x8894;
+pub const GL_CURRENT_PROGRAM: c_uint = 0x8B8D;
+pub const GL_VERTEX_ARRAY_BINDING: c_uint = 0x85B5;
+pub const GL_DRAW_FRAMEBUFFER: c_uint = 0x8CA9;
+pub const GL_READ_FRAMEBUFFER: c_uint = 0x8CA8;
+pub const GL_DRAW_FRAMEBUFFER_BINDING: c_uint = 0x8CA6;
+pub const GL_READ_FRAMEBUFFER_BINDING: c_uint = 0x8CAA;
+pub const GL_LINES: c_uint = 0x0001;
+pub const GL_LINE_LOOP: c_uint = 0x0002;
+pub const GL_TRIANGLES: c_uint = 0x0004;
+pub const GL_TRIANGLE_STRIP: c_uint = 0x0005;
+pub const GL_TRIANGLE_FAN: c_uint = 0x0006;
+pub const GL_QUADS: c_uint = 0x0007;
+pub const GL_TEXTURE_MAG_FILTER: c_uint = 0x2800;
+pub const GL_TEXTURE_MIN_FILTER: c_uint = 0x2801;
+pub const GL_TEXTURE_WRAP_S: c_uint = 0x2802;
+pub const GL_TEXTURE_WRAP_T: c_uint = 0x2803;
+pub const GL_NEAREST: c_int = 0x2600;
+pub const GL_LINEAR: c_int = 0x2601;
+pub const GL_CLAMP_TO_EDGE: c_int = 0x812F;
+
+#[cfg_attr(target_os = "windows", link(name = "opengl32"))]
+#[cfg_attr(target_os = "macos", link(name = "OpenGL", kind = "framework"))]
+extern "system" {
+    pub fn glGetIntegerv(pname: c_uint, data: *mut c_int);
+    pub fn glGetBooleanv(pname: c_uint, data: *mut c_uchar);
+    pub fn glIsEnabled(capability: c_uint) -> c_uchar;
+    pub fn glEnable(capability: c_uint);
+    pub fn glDisable(capability: c_uint);
+    pub fn glViewport(x: c_int, y: c_int, width: c_int, height: c_int);
+    pub fn glScissor(x: c_int, y: c_int, width: c_int, height: c_int);
+    pub fn glDepthMask(flag: c_uchar);
+    pub fn glPixelStorei(pname: c_uint, param: c_int);
+    pub fn glReadPixels(
+        x: c_int,
+        y: c_int,
+        width: c_int,
+        height: c_int,
+        format: c_uint,
+        pixel_type: c_uint,
+        data: *mut c_void,
+    );
+    pub fn glBegin(mode: c_uint);
+    pub fn glEnd();
+    pub fn glVertex2f(x: f32, y: f32);
+    pub fn glTexCoord2f(s: f32, t: f32);
+    pub fn glColor4f(red: f32, green: f32, blue: f32, alpha: f32);
+    pub fn glLineWidth(width: f32);
+    pub fn glTexImage2D(
+        target: c_uint,
+        level: c_int,
+        internal_format: c_int,
+        width: c_int,
+        height: c_int,
+        border: c_int,
+        format: c_uint,
+        pixel_type: c_uint,
+        data: *const c_void,
+    );
+    pub fn glTexSubImage2D(
+        target: c_uint,
+        level: c_int,
+        x_offset: c_int,
+        y_offset: c_int,
+        width: c_int,
+        height: c_int,
+        format: c_uint,
+        pixel_type: c_uint,
+        data: *const c_void,
+    );
+    pub fn glTexParameteri(target: c_uint, pname: c_uint, param: c_int);
+    pub fn glDeleteTextures(count: c_int, textures: *const c_uint);
+}
+
+/// OpenGL functions newer than version 1.1
+pub struct Extensions {
+    pub active_texture: Option<unsafe extern "system" fn(texture: c_uint)>,
+    pub bind_buffer: Option<unsafe extern "system" fn(target: c_uint, buffer: c_uint)>,
+    pub use_program: Option<unsafe extern "system" fn(program: c_uint)>,
+    pub bind_vertex_array: Option<unsafe extern "system" fn(array: c_uint)>,
+    pub bind_framebuffer: Option<unsafe extern "system" fn(target: c_uint, framebuffer: c_uint)>,
+}
+
+/// Returns the OpenGL functions newer than version 1.1, loading them on first use
+///
+/// This must be called while X-Plane's OpenGL context is current, for example from a draw
+/// callback.
+pub fn extensions() -> &'static Extensions {
+    static EXTENSIONS: OnceLock<Extensions> = OnceLock::new();
+    EXTENSIONS.get_or_init(|| unsafe {
+        Extensions {
+            active_texture: load(c"glActiveTexture"),
+            bind_buffer: load(c"glBindBuffer"),
+            use_program: load(c"glUseProgram"),
+            bind_vertex_array: load(c"glBindVertexArray"),
+            bind_framebuffer: load(c"glBindFramebuffer"),
+        }
+    })
+}
+
+/// Looks up an OpenGL function and converts it into a function pointer of type F
+///
+/// F must be a function pointer type with the correct signature.
+unsafe fn load<F: Copy>(name: &CStr) -> Option<F> {
+    let address = function(name);
+    if address.is_null() {
+        None
+    } else {
+        Some(mem::transmute_copy::<*const c_void, F>(&address))
+    }
+}
+
+/// Returns the address of an OpenGL function, or null if it is not available
+///
+/// This works for functions from all OpenGL versions, including 1.1.
+pub fn function(name: &CStr) -> *const c_void {
+    unsafe { proc_address(name.as_ptr()) }
+}
+
+#[cfg(target_os = "windows")]
+unsafe fn proc_address(name: *const c_char) -> *const c_void {
+    #[link(name = "opengl32")]
+    extern "system" {
+        fn wglGetProcAddress(name: *const c_char) -> *const c_void;
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleA(name: *const c_char) -> *mut c_void;
+        fn GetProcAddress(module: *mut c_void, name: *const c_char) -> *const c_void;
+    }
+    let address = wglGetProcAddress(name);
+    // Some drivers return small integers instead of null on failure
+    match address as isize {
+        -1..=3 => {
+            // OpenGL 1.1 functions are only exported by opengl32.dll
+            let module = GetModuleHandleA(c"opengl32.dll".as_ptr());
+            if module.is_null() {
+                std::ptr::null()
+            } else {
+                GetProcAddress(module, name)
+            }
+        }
+        _ => address,
+    }
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn proc_address(name: *const c_char) -> *const c_void {
+    extern "C" {
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *const c_void;
+    }
+    const RTLD_DEFAULT: *mut c_void = -2isize as *mut c_void;
+    dlsym(RTLD_DEFAULT, name)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+unsafe fn proc_address(name: *const c_char) -> *const c_void {
+    extern "C" {
+        fn glXGetProcAddressARB(name: *const c_uchar) -> *const c_void;
+    }
+    glXGetProcAddressARB(name as *const c_uchar)
+}
@@ -0,0 +1,184 @@
+//! # Plugin health monitoring
+//!
+//! A [`HealthMonitor`] publishes datarefs that describe the state of this plugin, so that
+//! external tools and other plugins can check that it is working:
+//!
+//! * `<prefix>/health/last_loop_time` (float): The X-Plane elapsed time, in seconds, when the
+//!   plugin last called [`HealthMonitor::heartbeat`]
+//! * `<prefix>/health/error_count` (int): The number of SDK errors that X-Plane has reported,
+//!   as returned by [`error::error_count`]
+//! * `<prefix>/health/enabled` (int): 1 if the plugin is enabled, otherwise 0
+//!
+//! The prefix is normally the plugin signature.
+//!
+//! The monitor also runs a watchdog. If the plugin does not call `heartbeat` within the
+//! configured timeout (for example, because its flight loop was deactivated by mistake),
+//! a message is written to the log.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::rc::Rc;
+//! use std::time::Duration;
+//! use xplm::flight_loop::{FlightLoop, LoopState};
+//! use xplm::health::HealthMonitor;
+//!
+//! let health = Rc::new(HealthMonitor::for_this_plugin(Duration::from_secs(5)).unwrap());
+//! let loop_health = Rc::clone(&health);
+//! let mut flight_loop = FlightLoop::new(move |_: &mut LoopState| {
+//!     loop_health.heartbeat();
+//! });
+//! flight_loop.schedule_immediate();
+//! ```
+//!
+
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use xplm_sys;
+
+use super::data::owned::{CreateError, OwnedData};
+use super::data::{DataRead, DataReadWrite};
+use super::debugln;
+use super::error;
+use super::flight_loop::{FlightLoop, LoopState};
+use super::plugin::management;
+
+/// How often the watchdog checks for heartbeats
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+thread_local! {
+    /// The monitor that receives plugin enable and disable notifications
+    static CURRENT: RefCell<Weak<Shared>> = const { RefCell::new(Weak::new()) };
+}
+
+/// Publishes health datarefs for this plugin and watches for missed heartbeats
+///
+/// The datarefs are removed when this object is dropped.
+pub struct HealthMonitor {
+    /// State shared with the watchdog
+    shared: Rc<Shared>,
+    /// The watchdog flight loop
+    _watchdog: FlightLoop,
+}
+
+/// Health state shared between the monitor and its watchdog
+struct Shared {
+    /// The time of the last heartbeat
+    last_loop_time: RefCell<OwnedData<f32>>,
+    /// A copy of the error module's count
+    error_count: RefCell<OwnedData<i32>>,
+    /// If the plugin is enabled
+    enabled: RefCell<OwnedData<bool>>,
+    /// The maximum time between heartbeats
+    timeout: Duration,
+    /// If the watchdog has reported the current missed heartbeat
+    reported: Cell<bool>,
+}
+
+impl HealthMonitor {
+    /// Creates a monitor that publishes datarefs under the signature of this plugin
+    pub fn for_this_plugin(timeout: Duration) -> Result<Self, CreateError> {
+        let signature = management::this_plugin().signature();
+        Self::new(&signature, timeout)
+    }
+
+    /// Creates a monitor that publishes datarefs under `<prefix>/health/`
+    ///
+    /// The watchdog logs a message if heartbeat is not called at least once every timeout.
+    pub fn new(prefix: &str, timeout: Duration) -> Result<Self, CreateError> {
+        let shared = Rc::new(Shared {
+            last_loop_time: RefCell::new(OwnedData::create_with_value(
+                &format!("{}/health/last_loop_time", prefix),
+                &elapsed_time(),
+            )?),
+            error_count: RefCell::new(OwnedData::create_with_value(
+                &format!("{}/health/error_count", prefix),
+                &published_error_count(),
+            )?),
+            enabled: RefCell::new(OwnedData::create_with_value(
+                &format!("{}/health/enabled", prefix),
+                &true,
+            )?),
+            timeout,
+            reported: Cell::new(false),
+        });
+        CURRENT.with(|current| *current.borrow_mut() = Rc::downgrade(&shared));
+        // Errors are only counted while the error callback is registered
+        error::install_callback();
+
+        let watchdog_shared = Rc::clone(&shared);
+        let mut watchdog = FlightLoop::new(move |_: &mut LoopState| watchdog_shared.check());
+        watchdog.schedule_after(WATCHDOG_INTERVAL);
+
+        Ok(HealthMonitor {
+            shared,
+            _watchdog: watchdog,
+        })
+    }
+
+    /// Records that the plugin is running normally
+    ///
+    /// This should be called from the plugin's main flight loop callback.
+    pub fn heartbeat(&self) {
+        self.shared.last_loop_time.borrow_mut().set(elapsed_time());
+        self.shared.update_error_count();
+        if self.shared.reported.replace(false) {
+            debugln!("[xplm] Health: heartbeats have resumed");
+        }
+    }
+
+    /// Returns the number of SDK errors that X-Plane has reported
+    ///
+    /// This is the same as [`error::error_count`].
+    pub fn error_count(&self) -> u32 {
+        error::error_count()
+    }
+}
+
+impl Shared {
+    /// Copies the error module's count into the error count dataref
+    fn update_error_count(&self) {
+        self.error_count.borrow_mut().set(published_error_count());
+    }
+
+    /// Updates the error count and logs a message if the last heartbeat is too old
+    fn check(&self) {
+        self.update_error_count();
+        let last = self.last_loop_time.borrow().get();
+        let since_last = elapsed_time() - last;
+        if since_last > self.timeout.as_secs_f32() && !self.reported.replace(true) {
+            debugln!(
+                "[xplm] Health: no heartbeat for {:.1} seconds; the plugin flight loop may have stopped",
+                since_last
+            );
+        }
+    }
+}
+
+/// Updates the enabled dataref of the current monitor, if any
+///
+/// This is called when X-Plane enables or disables the plugin.
+pub(crate) fn set_plugin_enabled(enabled: bool) {
+    CURRENT.with(|current| {
+        if let Some(shared) = current.borrow().upgrade() {
+            shared.enabled.borrow_mut().set(enabled);
+            // Heartbeats stop while the plugin is disabled
+            shared.reported.set(false);
+            if enabled {
+                shared.last_loop_time.borrow_mut().set(elapsed_time());
+            }
+        }
+    });
+}
+
+/// Returns the error count as a dataref value
+fn published_error_count() -> i32 {
+    i32::try_from(error::error_count()).unwrap_or(i32::MAX)
+}
+
+/// Returns the time since X-Plane started, in seconds
+fn elapsed_time() -> f32 {
+    unsafe { xplm_sys::XPLMGetElapsedTime() }
+}
@@ -0,0 +1,160 @@
+//! # Hot keys
+//!
+//! A hot key calls a handler when the user presses a key combination anywhere in X-Plane.
+//! Hot keys are listed in the X-Plane keyboard settings, where users can see their
+//! descriptions.
+//!
+//! The key combination can be changed while the hot key exists, for example after the user
+//! picks a new one with a [`KeyCapture`](crate::key_binding::KeyCapture).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::hotkey::HotKey;
+//! use xplm::key_binding::KeyBinding;
+//!
+//! let binding: KeyBinding = "Ctrl+Shift+F8".parse().unwrap();
+//! let hot_key = HotKey::register(&binding, "Toggle the example window", || {
+//!     xplm::debugln!("Hot key pressed");
+//! })
+//! .unwrap();
+//! ```
+//!
+
+use std::ffi::{CString, NulError};
+use std::fmt;
+use std::os::raw::c_void;
+
+use xplm_sys::{self, XPLMHotKeyID, XPLMKeyFlags};
+
+use super::key_binding::KeyBinding;
+
+/// Trait for things that can handle hot key presses
+pub trait HotKeyHandler: 'static {
+    /// Called when the key combination is pressed
+    fn hot_key(&mut self);
+}
+
+impl<F> HotKeyHandler for F
+where
+    F: FnMut() + 'static,
+{
+    fn hot_key(&mut self) {
+        self()
+    }
+}
+
+/// A registered hot key
+///
+/// The hot key is unregistered when this object is dropped.
+pub struct HotKey {
+    id: XPLMHotKeyID,
+    binding: KeyBinding,
+    description: String,
+    /// The handler, boxed so that it has a constant address to use as the refcon
+    _handler: Box<Box<dyn HotKeyHandler>>,
+}
+
+impl HotKey {
+    /// Registers a hot key that calls a handler when a key combination is pressed
+    pub fn register<H: HotKeyHandler>(
+        binding: &KeyBinding,
+        description: &str,
+        handler: H,
+    ) -> Result<Self, HotKeyError> {
+        crate::debug_assert_main_thread!();
+        let description_c = CString::new(description)?;
+        let mut handler: Box<Box<dyn HotKeyHandler>> = Box::new(Box::new(handler));
+        let handler_ptr: *mut Box<dyn HotKeyHandler> = &mut *handler;
+        let id = unsafe {
+            xplm_sys::XPLMRegisterHotKey(
+                binding.key.to_xplm(),
+                flags(binding),
+                description_c.as_ptr(),
+                Some(hot_key_callback),
+                handler_ptr as *mut c_void,
+            )
+        };
+        if id.is_null() {
+            return Err(HotKeyError::Register(description.to_owned()));
+        }
+        Ok(HotKey {
+            id,
+            binding: binding.clone(),
+            description: description.to_owned(),
+            _handler: handler,
+        })
+    }
+
+    /// Returns the key combination
+    pub fn binding(&self) -> &KeyBinding {
+        &self.binding
+    }
+
+    /// Changes the key combination
+    pub fn set_binding(&mut self, binding: &KeyBinding) {
+        crate::debug_assert_main_thread!();
+        unsafe {
+            xplm_sys::XPLMSetHotKeyCombination(self.id, binding.key.to_xplm(), flags(binding));
+        }
+        self.binding = binding.clone();
+    }
+
+    /// Returns the description
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Drop for HotKey {
+    fn drop(&mut self) {
+        unsafe { xplm_sys::XPLMUnregisterHotKey(self.id) }
+        super::panics::forget(&*self._handler);
+    }
+}
+
+impl fmt::Debug for HotKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HotKey")
+            .field("binding", &self.binding)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// Returns the key flags for a binding, for a key press
+fn flags(binding: &KeyBinding) -> XPLMKeyFlags {
+    let mut flags = xplm_sys::xplm_DownFlag as XPLMKeyFlags;
+    if binding.control {
+        flags |= xplm_sys::xplm_ControlFlag as XPLMKeyFlags;
+    }
+    if binding.option {
+        flags |= xplm_sys::xplm_OptionAltFlag as XPLMKeyFlags;
+    }
+    if binding.shift {
+        flags |= xplm_sys::xplm_ShiftFlag as XPLMKeyFlags;
+    }
+    flags
+}
+
+/// The hot key callback provided to X-Plane
+unsafe extern "C" fn hot_key_callback(refcon: *mut c_void) {
+    if super::internal::suspended() {
+        return;
+    }
+    let handler = refcon as *mut Box<dyn HotKeyHandler>;
+    super::panics::contain("hot key", handler, (), || (*handler).hot_key());
+}
+
+/// Errors that can occur when registering a hot key
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum HotKeyError {
+    /// The description contained a null byte
+    #[error("Null byte in hot key description")]
+    Null(#[from] NulError),
+
+    /// X-Plane could not register the hot key
+    #[error("Could not register hot key {0}")]
+    Register(String),
+}
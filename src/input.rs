@@ -0,0 +1,7 @@
+//! # Input devices
+//!
+//! Access to input hardware that X-Plane manages.
+//!
+
+/// Joystick axes and buttons
+pub mod joystick;
@@ -0,0 +1,274 @@
+//! # Joysticks
+//!
+//! X-Plane combines the axes and buttons of all connected joysticks, yokes, pedals, and
+//! throttle quadrants into arrays of axis values and button states. A [`Joystick`] reads
+//! these arrays and changes what each axis controls.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::input::joystick::{AxisAssignment, Joystick};
+//!
+//! let mut joystick = Joystick::new().unwrap();
+//! for axis in joystick.axes_assigned_to(AxisAssignment::Throttle) {
+//!     xplm::debugln!("Throttle axis {}: {:?}", axis, joystick.axis_value(axis));
+//! }
+//! // Use axis 3 for the speed brakes
+//! joystick.assign_axis(3, AxisAssignment::SpeedBrakes);
+//! for button in joystick.pressed_buttons() {
+//!     xplm::debugln!("Button {} is pressed", button);
+//! }
+//! ```
+//!
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use super::super::data::borrowed::{DataRef, FindError};
+use super::super::data::{ArrayRead, ArrayReadWrite, DataRead, ReadWrite};
+
+/// What a joystick axis controls
+///
+/// Assignments are compared by their raw values, so `Other(4)` is equal to `Throttle`.
+#[derive(Debug, Copy, Clone, Eq)]
+#[non_exhaustive]
+pub enum AxisAssignment {
+    /// Nothing
+    None,
+    /// Pitch
+    Pitch,
+    /// Roll
+    Roll,
+    /// Yaw
+    Yaw,
+    /// All throttles
+    Throttle,
+    /// Helicopter collective
+    Collective,
+    /// The left toe brake
+    LeftToeBrake,
+    /// The right toe brake
+    RightToeBrake,
+    /// All propeller controls
+    Prop,
+    /// All mixture controls
+    Mixture,
+    /// Carburetor heat
+    CarbHeat,
+    /// Flaps
+    Flaps,
+    /// Thrust vectoring
+    ThrustVector,
+    /// Wing sweep
+    WingSweep,
+    /// Speed brakes
+    SpeedBrakes,
+    /// Displacement
+    Displacement,
+    /// Thrust reversers
+    Reverse,
+    /// Elevator trim
+    ElevatorTrim,
+    /// Aileron trim
+    AileronTrim,
+    /// Rudder trim
+    RudderTrim,
+    /// Another assignment, with the value that X-Plane uses for it
+    ///
+    /// [`from_raw`](AxisAssignment::from_raw) only returns this for values that do not have
+    /// their own variant.
+    Other(i32),
+}
+
+/// The assignments in the order of their values
+const ASSIGNMENTS: [AxisAssignment; 20] = [
+    AxisAssignment::None,
+    AxisAssignment::Pitch,
+    AxisAssignment::Roll,
+    AxisAssignment::Yaw,
+    AxisAssignment::Throttle,
+    AxisAssignment::Collective,
+    AxisAssignment::LeftToeBrake,
+    AxisAssignment::RightToeBrake,
+    AxisAssignment::Prop,
+    AxisAssignment::Mixture,
+    AxisAssignment::CarbHeat,
+    AxisAssignment::Flaps,
+    AxisAssignment::ThrustVector,
+    AxisAssignment::WingSweep,
+    AxisAssignment::SpeedBrakes,
+    AxisAssignment::Displacement,
+    AxisAssignment::Reverse,
+    AxisAssignment::ElevatorTrim,
+    AxisAssignment::AileronTrim,
+    AxisAssignment::RudderTrim,
+];
+
+impl AxisAssignment {
+    /// Converts a value of `sim/joystick/joystick_axis_assignments` into an assignment
+    pub fn from_raw(value: i32) -> Self {
+        usize::try_from(value)
+            .ok()
+            .and_then(|index| ASSIGNMENTS.get(index))
+            .copied()
+            .unwrap_or(AxisAssignment::Other(value))
+    }
+
+    /// Returns the value of `sim/joystick/joystick_axis_assignments` for this assignment
+    pub fn raw(self) -> i32 {
+        match self {
+            AxisAssignment::Other(value) => value,
+            known => ASSIGNMENTS
+                .iter()
+                .position(|assignment| mem::discriminant(assignment) == mem::discriminant(&known))
+                .expect("Assignment not in list") as i32,
+        }
+    }
+}
+
+impl PartialEq for AxisAssignment {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw() == other.raw()
+    }
+}
+
+impl Hash for AxisAssignment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw().hash(state);
+    }
+}
+
+/// The joystick axes and buttons that X-Plane knows about
+pub struct Joystick {
+    has_joystick: DataRef<bool>,
+    axis_values: DataRef<[f32]>,
+    axis_assignments: DataRef<[i32], ReadWrite>,
+    axis_reverse: DataRef<[i32], ReadWrite>,
+    button_values: DataRef<[i32]>,
+}
+
+impl Joystick {
+    /// Finds the joystick datarefs
+    pub fn new() -> Result<Self, FindError> {
+        Ok(Joystick {
+            has_joystick: DataRef::find("sim/joystick/has_joystick")?,
+            axis_values: DataRef::find("sim/joystick/joystick_axis_values")?,
+            axis_assignments: DataRef::find("sim/joystick/joystick_axis_assignments")?
+                .writeable()?,
+            axis_reverse: DataRef::find("sim/joystick/joystick_axis_reverse")?.writeable()?,
+            button_values: DataRef::find("sim/joystick/joystick_button_values")?,
+        })
+    }
+
+    /// Returns true if any joystick is connected
+    pub fn is_connected(&self) -> bool {
+        self.has_joystick.get()
+    }
+
+    /// Returns the number of axes that X-Plane supports
+    ///
+    /// This includes axes that no connected device has.
+    pub fn axis_count(&self) -> usize {
+        self.axis_values.len()
+    }
+
+    /// Returns the value of an axis, from 0 to 1, or None if the index is out of range
+    pub fn axis_value(&self, axis: usize) -> Option<f32> {
+        self.axis_values.get_element(axis)
+    }
+
+    /// Returns the values of all axes
+    pub fn axis_values(&self) -> Vec<f32> {
+        self.axis_values.as_vec()
+    }
+
+    /// Returns what an axis controls, or None if the index is out of range
+    pub fn axis_assignment(&self, axis: usize) -> Option<AxisAssignment> {
+        self.axis_assignments
+            .get_element(axis)
+            .map(AxisAssignment::from_raw)
+    }
+
+    /// Returns the indices of the axes that control something
+    pub fn axes_assigned_to(&self, assignment: AxisAssignment) -> Vec<usize> {
+        indices_where(&self.axis_assignments.as_vec(), |value| {
+            value == assignment.raw()
+        })
+    }
+
+    /// Changes what an axis controls
+    ///
+    /// If the index is out of range, nothing is changed.
+    pub fn assign_axis(&mut self, axis: usize, assignment: AxisAssignment) {
+        self.axis_assignments.set_element(axis, assignment.raw());
+    }
+
+    /// Returns true if an axis is reversed, or None if the index is out of range
+    pub fn axis_reversed(&self, axis: usize) -> Option<bool> {
+        self.axis_reverse.get_element(axis).map(|value| value != 0)
+    }
+
+    /// Reverses an axis or returns it to normal
+    ///
+    /// If the index is out of range, nothing is changed.
+    pub fn set_axis_reversed(&mut self, axis: usize, reversed: bool) {
+        self.axis_reverse.set_element(axis, i32::from(reversed));
+    }
+
+    /// Returns the number of buttons that X-Plane supports
+    pub fn button_count(&self) -> usize {
+        self.button_values.len()
+    }
+
+    /// Returns true if a button is pressed, or None if the index is out of range
+    pub fn button_pressed(&self, button: usize) -> Option<bool> {
+        self.button_values
+            .get_element(button)
+            .map(|value| value != 0)
+    }
+
+    /// Returns the indices of all pressed buttons
+    pub fn pressed_buttons(&self) -> Vec<usize> {
+        indices_where(&self.button_values.as_vec(), |value| value != 0)
+    }
+}
+
+impl fmt::Debug for Joystick {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Joystick")
+            .field("axes", &self.axis_count())
+            .field("buttons", &self.button_count())
+            .finish()
+    }
+}
+
+/// Returns the indices of the values that match a condition
+fn indices_where<F: Fn(i32) -> bool>(values: &[i32], condition: F) -> Vec<usize> {
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| condition(**value))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{indices_where, AxisAssignment};
+
+    #[test]
+    fn test_assignments() {
+        for value in -1..25 {
+            assert_eq!(AxisAssignment::from_raw(value).raw(), value);
+        }
+        assert_eq!(AxisAssignment::from_raw(4), AxisAssignment::Throttle);
+        assert_eq!(AxisAssignment::from_raw(40), AxisAssignment::Other(40));
+        assert_eq!(AxisAssignment::Other(4), AxisAssignment::Throttle);
+        assert_ne!(AxisAssignment::Other(5), AxisAssignment::Throttle);
+        assert_eq!(
+            indices_where(&[0, 1, 0, 1, 1], |value| value != 0),
+            [1, 3, 4]
+        );
+    }
+}
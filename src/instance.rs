@@ -0,0 +1,290 @@
+//! # Object instances
+//!
+//! Instancing is the way to draw 3D objects in X-Plane 11 and 12. A plugin loads an
+//! [`Object`] from an OBJ file and creates an [`Instance`] for each copy of the object in the
+//! world. X-Plane draws instances without a draw callback.
+//!
+//! Loading a large object can take a long time. [`Object::load_async`] loads an object in the
+//! background and provides it to a callback when it is ready.
+//!
+//! An instance can animate the object with its own values for a list of datarefs, which are
+//! provided when the instance is created and each time it is moved.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::instance::{Instance, Object, Position};
+//!
+//! let object = Object::load("lib/airport/vehicles/pushback/tug.obj").unwrap();
+//! let wheel_angle = "sim/graphics/animation/ground_traffic/tire_rotation_angle_deg";
+//! let mut tug = Instance::new(&object, &[wheel_angle]).unwrap();
+//! tug.set_position(
+//!     &Position {
+//!         x: 100.0,
+//!         y: 10.0,
+//!         z: -250.0,
+//!         heading: 90.0,
+//!         ..Position::default()
+//!     },
+//!     &[45.0],
+//! );
+//! ```
+//!
+
+use std::ffi::{CString, NulError};
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::rc::Rc;
+
+use xplm_sys::{self, XPLMInstanceRef, XPLMObjectRef};
+
+/// A loaded 3D object
+///
+/// Objects can be cloned cheaply. The object is unloaded when all clones and all instances
+/// of it have been dropped.
+#[derive(Debug, Clone)]
+pub struct Object {
+    inner: Rc<ObjectRef>,
+}
+
+impl Object {
+    /// Loads an object
+    ///
+    /// The path can be a library path or a path to an OBJ file relative to the X-Plane
+    /// folder.
+    pub fn load(path: &str) -> Result<Self, LoadError> {
+        crate::debug_assert_main_thread!();
+        let path_c = CString::new(path)?;
+        let object = unsafe { xplm_sys::XPLMLoadObject(path_c.as_ptr()) };
+        if object.is_null() {
+            Err(LoadError::NotFound(path.to_owned()))
+        } else {
+            Ok(Object::from_ref(object))
+        }
+    }
+
+    /// Starts loading an object in the background
+    ///
+    /// The callback is called from a later flight loop with the object, or an error if the
+    /// object could not be loaded. If the plugin is disabled before the object is loaded, the
+    /// object is unloaded and the callback is not called.
+    ///
+    /// Returns an error if the path contains a null byte. In that case the callback is not
+    /// called.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::instance::{Instance, Object};
+    ///
+    /// Object::load_async("lib/airport/vehicles/pushback/tug.obj", |result| match result {
+    ///     Ok(object) => {
+    ///         let _instance = Instance::new(&object, &[]);
+    ///     }
+    ///     Err(e) => xplm::debugln!("{}", e),
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn load_async<F>(path: &str, callback: F) -> Result<(), NulError>
+    where
+        F: FnOnce(Result<Object, LoadError>) + 'static,
+    {
+        crate::debug_assert_main_thread!();
+        let path_c = CString::new(path)?;
+        let pending = Box::new(PendingLoad {
+            path: path.to_owned(),
+            callback: Box::new(callback),
+        });
+        unsafe {
+            xplm_sys::XPLMLoadObjectAsync(
+                path_c.as_ptr(),
+                Some(object_loaded),
+                Box::into_raw(pending) as *mut c_void,
+            );
+        }
+        Ok(())
+    }
+
+    /// Creates an object from an object reference that this object will unload
+    pub(crate) fn from_ref(object: XPLMObjectRef) -> Self {
+        Object {
+            inner: Rc::new(ObjectRef(object)),
+        }
+    }
+
+    /// Returns the object reference
+    pub fn as_raw(&self) -> XPLMObjectRef {
+        self.inner.0
+    }
+}
+
+/// An object that is being loaded and the callback to call when it is ready
+struct PendingLoad {
+    path: String,
+    callback: Box<dyn FnOnce(Result<Object, LoadError>)>,
+}
+
+/// The load callback provided to X-Plane
+///
+/// The refcon is a PendingLoad that was leaked in load_async.
+unsafe extern "C" fn object_loaded(object: XPLMObjectRef, refcon: *mut c_void) {
+    let PendingLoad { path, callback } = *Box::from_raw(refcon as *mut PendingLoad);
+    let result = if object.is_null() {
+        Err(LoadError::NotFound(path))
+    } else {
+        Ok(Object::from_ref(object))
+    };
+    // When suspended, the object is unloaded as the result is dropped
+    if !super::internal::suspended() {
+        super::panics::contain("object load", refcon, (), || callback(result));
+    }
+}
+
+/// Unloads an object when dropped
+#[derive(Debug)]
+struct ObjectRef(XPLMObjectRef);
+
+impl Drop for ObjectRef {
+    fn drop(&mut self) {
+        unsafe { xplm_sys::XPLMUnloadObject(self.0) }
+    }
+}
+
+/// The position and orientation of an instance
+///
+/// The position is in meters in local OpenGL coordinates. The angles are in degrees.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Position {
+    /// East
+    pub x: f32,
+    /// Up
+    pub y: f32,
+    /// South
+    pub z: f32,
+    /// Pitch up
+    pub pitch: f32,
+    /// Heading clockwise from true north
+    pub heading: f32,
+    /// Roll right
+    pub roll: f32,
+}
+
+impl Position {
+    fn to_xplm(self) -> xplm_sys::XPLMDrawInfo_t {
+        xplm_sys::XPLMDrawInfo_t {
+            structSize: mem::size_of::<xplm_sys::XPLMDrawInfo_t>() as _,
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            pitch: self.pitch,
+            heading: self.heading,
+            roll: self.roll,
+        }
+    }
+}
+
+/// An instance of an object that X-Plane draws
+///
+/// The instance is destroyed when this object is dropped.
+#[derive(Debug)]
+pub struct Instance {
+    id: XPLMInstanceRef,
+    /// The number of datarefs that this instance provides values for
+    datarefs: usize,
+    /// The object, kept loaded while this instance exists
+    object: Object,
+}
+
+impl Instance {
+    /// Creates an instance of an object
+    ///
+    /// The datarefs are the names of the datarefs that the object uses for animation. Values
+    /// for these datarefs are provided to [`set_position`](Instance::set_position).
+    ///
+    /// The instance is not drawn until its position is set.
+    pub fn new(object: &Object, datarefs: &[&str]) -> Result<Self, InstanceError> {
+        crate::debug_assert_main_thread!();
+        let names = datarefs
+            .iter()
+            .map(|&name| CString::new(name))
+            .collect::<Result<Vec<CString>, NulError>>()?;
+        // The list of names is terminated with a null pointer
+        let mut name_ptrs: Vec<*const c_char> = names
+            .iter()
+            .map(|name| name.as_ptr())
+            .chain(Some(ptr::null()))
+            .collect();
+        let id = unsafe { xplm_sys::XPLMCreateInstance(object.as_raw(), name_ptrs.as_mut_ptr()) };
+        if id.is_null() {
+            Err(InstanceError::Create)
+        } else {
+            Ok(Instance {
+                id,
+                datarefs: datarefs.len(),
+                object: object.clone(),
+            })
+        }
+    }
+
+    /// Returns the object that this is an instance of
+    pub fn object(&self) -> &Object {
+        &self.object
+    }
+
+    /// Moves this instance and sets the values of its datarefs
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of values is not equal to the number of datarefs
+    /// this instance was created with.
+    pub fn set_position(&mut self, position: &Position, values: &[f32]) {
+        crate::debug_assert_main_thread!();
+        assert_eq!(
+            values.len(),
+            self.datarefs,
+            "Instance has {} datarefs but {} values were provided",
+            self.datarefs,
+            values.len()
+        );
+        let position = position.to_xplm();
+        let values_ptr = if values.is_empty() {
+            ptr::null()
+        } else {
+            values.as_ptr()
+        };
+        unsafe { xplm_sys::XPLMInstanceSetPosition(self.id, &position, values_ptr) }
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        unsafe { xplm_sys::XPLMDestroyInstance(self.id) }
+    }
+}
+
+/// Errors that can occur when loading an object
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum LoadError {
+    /// The provided path contained a null byte
+    #[error("Null byte in object path")]
+    Null(#[from] NulError),
+
+    /// The object could not be found or loaded
+    #[error("Could not load object {0}")]
+    NotFound(String),
+}
+
+/// Errors that can occur when creating an instance
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum InstanceError {
+    /// A dataref name contained a null byte
+    #[error("Null byte in dataref name")]
+    Null(#[from] NulError),
+
+    /// X-Plane could not create the instance
+    #[error("Could not create instance")]
+    Create,
+}
@@ -1,6 +1,12 @@
 use std::ffi::CString;
 use std::os::raw::c_char;
 use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// If callbacks into plugin code are suspended because the plugin is not enabled
+///
+/// This starts out true because a plugin is disabled between XPluginStart and XPluginEnable.
+static SUSPENDED: AtomicBool = AtomicBool::new(true);
 
 /// Copies up to 256 bytes (including null termination) to
 /// the provided destination. If the provided source string is too long, it will be
@@ -16,5 +22,34 @@ pub unsafe fn copy_to_c_buffer(mut src: String, dest: *mut c_char) {
 
 /// Performs initialization required for the XPLM crate to work correctly
 pub fn xplm_init() {
+    super::thread::thread_init();
     super::paths::path_init();
+    super::log::log_init();
+    super::executor::executor_init();
+    #[cfg(feature = "log-errors")]
+    super::error::error_init();
+    // Lazy datarefs retry lookups when X-Plane reports that datarefs were added. X-Plane only
+    // sends that message to plugins that enable this feature.
+    if let Some(feature) = super::feature::KnownFeature::WantsDatarefNotifications.find() {
+        feature.set_enabled(true);
+    }
+    // X-Plane's OpenGL context is current during XPluginStart. On Windows, functions cannot
+    // be looked up without a current context.
+    super::gl::extensions();
+}
+
+/// Returns true if callbacks into plugin code are suspended
+///
+/// Callback functions that X-Plane calls check this before calling a window delegate, draw
+/// callback, flight loop callback, menu item handler, or command handler. Owned datarefs
+/// still respond to reads and writes from other plugins while suspended.
+pub fn suspended() -> bool {
+    SUSPENDED.load(Ordering::Relaxed)
+}
+
+/// Suspends or resumes callbacks into plugin code
+///
+/// This is called when the plugin is enabled and disabled.
+pub fn set_suspended(suspended: bool) {
+    SUSPENDED.store(suspended, Ordering::Relaxed);
 }
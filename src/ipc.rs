@@ -0,0 +1,301 @@
+//! # Typed messages between plugins
+//!
+//! Plugins can send each other messages with `XPLMSendMessageToPlugin`, but the parameter is
+//! an untyped pointer. This module sends a [`Message`] as bytes wrapped in an envelope that
+//! the receiving plugin checks before decoding it. Both plugins must use this module and agree
+//! on the message number and encoding.
+//!
+//! Messages are delivered immediately, before [`send`] returns.
+//!
+//! # Examples
+//!
+//! Define a message type that both plugins share:
+//!
+//! ```
+//! use xplm::ipc::Message;
+//!
+//! pub struct SetFrequency {
+//!     pub hz: u32,
+//! }
+//!
+//! impl Message for SetFrequency {
+//!     const ID: i32 = 0x4d59_0001;
+//!     fn encode(&self) -> Vec<u8> {
+//!         self.hz.to_le_bytes().to_vec()
+//!     }
+//!     fn decode(bytes: &[u8]) -> Option<Self> {
+//!         Some(SetFrequency {
+//!             hz: u32::from_le_bytes(bytes.try_into().ok()?),
+//!         })
+//!     }
+//! }
+//! ```
+//!
+//! The sending plugin:
+//!
+//! ```no_run
+//! # use xplm::ipc::Message;
+//! # struct SetFrequency { hz: u32 }
+//! # impl Message for SetFrequency {
+//! #     const ID: i32 = 0x4d59_0001;
+//! #     fn encode(&self) -> Vec<u8> { self.hz.to_le_bytes().to_vec() }
+//! #     fn decode(bytes: &[u8]) -> Option<Self> { None }
+//! # }
+//! xplm::ipc::send("com.example.radio", &SetFrequency { hz: 118_100_000 }).unwrap();
+//! ```
+//!
+//! The receiving plugin registers a handler and dispatches messages from
+//! [`Plugin::receive_message`](crate::plugin::Plugin::receive_message):
+//!
+//! ```no_run
+//! # use xplm::ipc::Message;
+//! # struct SetFrequency { hz: u32 }
+//! # impl Message for SetFrequency {
+//! #     const ID: i32 = 0x4d59_0001;
+//! #     fn encode(&self) -> Vec<u8> { Vec::new() }
+//! #     fn decode(bytes: &[u8]) -> Option<Self> { None }
+//! # }
+//! use std::os::raw::c_void;
+//! use xplm::ipc::Handlers;
+//! use xplm::plugin::messages::{PluginId, PluginMessage};
+//!
+//! let mut handlers = Handlers::new();
+//! handlers.on(|_from: PluginId, message: SetFrequency| {
+//!     xplm::debugln!("Tuning to {} Hz", message.hz);
+//! });
+//!
+//! // In receive_message
+//! # let (from, message) = (PluginId::XPLANE, PluginMessage::Other(0));
+//! # let param = std::ptr::null_mut();
+//! unsafe { handlers.dispatch(from, message, param) };
+//! ```
+//!
+
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::c_void;
+use std::slice;
+
+use super::plugin::management::{all_plugins, plugin_with_signature};
+use super::plugin::messages::{PluginId, PluginMessage};
+
+/// The largest message number that X-Plane reserves for its own messages
+const RESERVED_MAX: i32 = 0x00FF_FFFF;
+
+/// Identifies an envelope sent by this module
+const MAGIC: u64 = 0x786c_706d_6970_6331;
+
+/// A message that can be sent to other plugins
+pub trait Message: Sized + 'static {
+    /// The message number
+    ///
+    /// This must be greater than 0x00FFFFFF, because X-Plane reserves smaller numbers, and
+    /// must be different from the numbers of other messages that the receiving plugin
+    /// handles.
+    const ID: i32;
+
+    /// Converts this message into bytes
+    fn encode(&self) -> Vec<u8>;
+
+    /// Converts bytes into a message, or returns None if the bytes are not valid
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+/// The parameter of a message sent by this module
+#[repr(C)]
+struct Envelope {
+    magic: u64,
+    data: *const u8,
+    length: usize,
+}
+
+/// Sends a message to the plugin with the provided signature
+pub fn send<M: Message>(signature: &str, message: &M) -> Result<(), SendError> {
+    let plugin =
+        plugin_with_signature(signature).ok_or_else(|| SendError::NotFound(signature.into()))?;
+    deliver(plugin.id(), message);
+    Ok(())
+}
+
+/// Sends a message to all other enabled plugins
+pub fn broadcast<M: Message>(message: &M) {
+    let this_plugin = super::plugin::management::this_plugin().id();
+    for plugin in all_plugins() {
+        if plugin.id() != this_plugin && plugin.is_enabled() {
+            deliver(plugin.id(), message);
+        }
+    }
+}
+
+fn deliver<M: Message>(to: PluginId, message: &M) {
+    check_id(M::ID);
+    let bytes = message.encode();
+    let mut envelope = Envelope {
+        magic: MAGIC,
+        data: bytes.as_ptr(),
+        length: bytes.len(),
+    };
+    let envelope_ptr: *mut Envelope = &mut envelope;
+    unsafe { xplm_sys::XPLMSendMessageToPlugin(to.raw(), M::ID, envelope_ptr.cast()) };
+}
+
+fn check_id(id: i32) {
+    assert!(
+        !(0..=RESERVED_MAX).contains(&id),
+        "Message ID {:#x} is reserved for X-Plane",
+        id
+    );
+}
+
+/// A function that decodes and handles a message, returning false if the message could not be
+/// decoded
+type Handler = Box<dyn FnMut(PluginId, &[u8]) -> bool>;
+
+/// Handlers for messages from other plugins
+#[derive(Default)]
+pub struct Handlers {
+    /// Handlers by message number
+    handlers: HashMap<i32, Handler>,
+}
+
+impl Handlers {
+    /// Creates an object with no handlers
+    pub fn new() -> Self {
+        Handlers::default()
+    }
+
+    /// Sets the handler for a message type
+    ///
+    /// This replaces any handler for the same message number.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the message number is reserved for X-Plane.
+    pub fn on<M, F>(&mut self, mut handler: F)
+    where
+        M: Message,
+        F: FnMut(PluginId, M) + 'static,
+    {
+        check_id(M::ID);
+        self.handlers.insert(
+            M::ID,
+            Box::new(move |from, bytes| match M::decode(bytes) {
+                Some(message) => {
+                    handler(from, message);
+                    true
+                }
+                None => false,
+            }),
+        );
+    }
+
+    /// Calls the handler for a message, if this object has one
+    ///
+    /// This returns true if a handler was called.
+    ///
+    /// # Safety
+    ///
+    /// The parameters must come from
+    /// [`Plugin::receive_message`](crate::plugin::Plugin::receive_message). If the message
+    /// number matches a handler, param must be null or point to readable memory of at least
+    /// 24 bytes (a `u64`, a pointer, and a `usize`) that is aligned to 8 bytes. This is true
+    /// for messages sent with this module, but may not be true for other messages with the same
+    /// number.
+    pub unsafe fn dispatch(
+        &mut self,
+        from: PluginId,
+        message: PluginMessage,
+        param: *mut c_void,
+    ) -> bool {
+        let PluginMessage::Other(id) = message else {
+            return false;
+        };
+        let Some(handler) = self.handlers.get_mut(&id) else {
+            return false;
+        };
+        match open_envelope(param) {
+            Some(bytes) => handler(from, bytes),
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for Handlers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
+
+/// Returns the bytes in an envelope, or None if param is not an envelope
+unsafe fn open_envelope<'a>(param: *mut c_void) -> Option<&'a [u8]> {
+    if param.is_null() {
+        return None;
+    }
+    let envelope = param.cast::<Envelope>();
+    if (*envelope).magic != MAGIC {
+        return None;
+    }
+    if (*envelope).length == 0 {
+        return Some(&[]);
+    }
+    Some(slice::from_raw_parts((*envelope).data, (*envelope).length))
+}
+
+/// An error returned when a message cannot be sent
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum SendError {
+    /// No plugin with the signature is loaded
+    #[error("No plugin with signature {0}")]
+    NotFound(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open_envelope, Envelope, Handlers, Message, MAGIC};
+    use crate::plugin::messages::{PluginId, PluginMessage};
+    use std::cell::Cell;
+    use std::ptr;
+    use std::rc::Rc;
+
+    struct Ping(u8);
+
+    impl Message for Ping {
+        const ID: i32 = 0x1000_0000;
+        fn encode(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+        fn decode(bytes: &[u8]) -> Option<Self> {
+            match bytes {
+                [value] => Some(Ping(*value)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let received = Rc::new(Cell::new(0));
+        let handler_received = Rc::clone(&received);
+        let mut handlers = Handlers::new();
+        handlers.on(move |_, ping: Ping| handler_received.set(ping.0));
+
+        let bytes = Ping(42).encode();
+        let mut envelope = Envelope {
+            magic: MAGIC,
+            data: bytes.as_ptr(),
+            length: bytes.len(),
+        };
+        let param: *mut Envelope = &mut envelope;
+        let from = PluginId::from_raw(3);
+        unsafe {
+            assert_eq!(open_envelope(param.cast()), Some(&[42u8][..]));
+            assert!(!handlers.dispatch(from, PluginMessage::Other(Ping::ID), ptr::null_mut()));
+            assert!(!handlers.dispatch(from, PluginMessage::Other(7), param.cast()));
+            assert!(handlers.dispatch(from, PluginMessage::Other(Ping::ID), param.cast()));
+        }
+        assert_eq!(received.get(), 42);
+        envelope.magic = 0;
+        assert!(unsafe { open_envelope(ptr::addr_of_mut!(envelope).cast()) }.is_none());
+    }
+}
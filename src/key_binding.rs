@@ -0,0 +1,406 @@
+//! # Key bindings
+//!
+//! Plugins that offer keyboard shortcuts often let users choose the keys. A [`KeyBinding`]
+//! is a key with modifiers that can be converted to and from text, such as `Ctrl+Shift+F5`.
+//! A [`KeyBindings`] object maps action names to bindings and can be saved to and loaded
+//! from a file.
+//!
+//! A [`KeyCapture`] waits for the user to press a key and reports it as a binding, so a
+//! plugin can provide a "press a key to bind" option.
+//!
+//! A binding can be registered as a global shortcut with a [`HotKey`](crate::hotkey::HotKey).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use xplm::key_binding::{KeyBinding, KeyBindings, KeyCapture};
+//!
+//! let bindings = Rc::new(RefCell::new(KeyBindings::load()));
+//! if bindings.borrow().get("toggle_panel").is_none() {
+//!     bindings
+//!         .borrow_mut()
+//!         .set("toggle_panel", "Ctrl+P".parse().unwrap());
+//! }
+//!
+//! // Let the user choose a new key
+//! let capture_bindings = Rc::clone(&bindings);
+//! let capture = KeyCapture::start(move |binding: Option<KeyBinding>| {
+//!     // binding is None if the user pressed Escape
+//!     if let Some(binding) = binding {
+//!         let mut bindings = capture_bindings.borrow_mut();
+//!         bindings.set("toggle_panel", binding);
+//!         bindings.save().unwrap();
+//!     }
+//! });
+//! ```
+//!
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use xplm_sys;
+
+use super::debug_config::plugin_folder;
+use super::debugln;
+use super::window::{Key, KeyAction, KeyEvent};
+
+/// The name of the file that key bindings are saved in, in the plugin folder
+pub const FILE_NAME: &str = "key_bindings.ini";
+
+/// A key and the modifier keys that must be held down with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    /// The key
+    pub key: Key,
+    /// If the control key must be held down
+    pub control: bool,
+    /// If the option/alt key must be held down
+    pub option: bool,
+    /// If a shift key must be held down
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    /// Creates a binding for a key with no modifiers
+    pub fn new(key: Key) -> Self {
+        KeyBinding {
+            key,
+            control: false,
+            option: false,
+            shift: false,
+        }
+    }
+
+    /// Returns the binding for the key and modifiers in a key event
+    pub fn from_event(event: &KeyEvent) -> Self {
+        KeyBinding {
+            key: event.key(),
+            control: event.control_pressed(),
+            option: event.option_pressed(),
+            shift: event.shift_pressed(),
+        }
+    }
+
+    /// Returns true if an event has the same key and modifiers as this binding
+    ///
+    /// The key action (press or release) is not checked.
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        *self == KeyBinding::from_event(event)
+    }
+}
+
+/// The binding is written as the modifiers and the key separated by `+`, for example
+/// `Ctrl+Alt+Shift+F5`. The key names are the names of the [`Key`] variants.
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.control {
+            f.write_str("Ctrl+")?;
+        }
+        if self.option {
+            f.write_str("Alt+")?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = ParseKeyBindingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseKeyBindingError(s.to_owned());
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key_name = parts.pop().ok_or_else(error)?;
+        let mut binding = KeyBinding::new(key_by_name(key_name).ok_or_else(error)?);
+        for modifier in parts {
+            let flag = match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut binding.control,
+                "alt" | "option" => &mut binding.option,
+                "shift" => &mut binding.shift,
+                _ => return Err(error()),
+            };
+            *flag = true;
+        }
+        Ok(binding)
+    }
+}
+
+/// Finds the key with a name, ignoring case
+fn key_by_name(name: &str) -> Option<Key> {
+    (0..=u8::MAX)
+        .filter_map(|code| Key::from_xplm(code as c_char))
+        .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+}
+
+/// An error returned when a key binding cannot be parsed
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid key binding: {0}")]
+pub struct ParseKeyBindingError(String);
+
+/// Key bindings for named actions
+///
+/// The bindings can be converted into text with [`ToString`] and parsed with [`str::parse`].
+/// The text contains one `name = binding` line for each action. Lines that start with `#` are
+/// comments.
+#[derive(Debug, Clone, Default)]
+pub struct KeyBindings {
+    bindings: BTreeMap<String, KeyBinding>,
+}
+
+impl KeyBindings {
+    /// Creates an empty set of bindings
+    pub fn new() -> Self {
+        KeyBindings::default()
+    }
+
+    /// Reads bindings from `key_bindings.ini` in this plugin's folder
+    ///
+    /// A missing file is not an error. Other problems reading the file are written to the log,
+    /// and invalid lines are skipped.
+    pub fn load() -> Self {
+        Self::load_from(&default_path())
+    }
+
+    /// Reads bindings from a file
+    ///
+    /// A missing file is not an error. Other problems reading the file are written to the log,
+    /// and invalid lines are skipped.
+    pub fn load_from(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let (bindings, invalid) = KeyBindings::parse_lines(&text);
+                for line in invalid {
+                    debugln!("[xplm] Invalid key binding in {}: {}", path.display(), line);
+                }
+                bindings
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => KeyBindings::default(),
+            Err(e) => {
+                debugln!("[xplm] Failed to read {}: {}", path.display(), e);
+                KeyBindings::default()
+            }
+        }
+    }
+
+    /// Writes the bindings to `key_bindings.ini` in this plugin's folder
+    pub fn save(&self) -> io::Result<()> {
+        self.save_to(&default_path())
+    }
+
+    /// Writes the bindings to a file
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Returns the binding for an action
+    pub fn get(&self, name: &str) -> Option<&KeyBinding> {
+        self.bindings.get(name)
+    }
+
+    /// Sets the binding for an action
+    pub fn set<S: Into<String>>(&mut self, name: S, binding: KeyBinding) {
+        self.bindings.insert(name.into(), binding);
+    }
+
+    /// Removes the binding for an action
+    pub fn remove(&mut self, name: &str) -> Option<KeyBinding> {
+        self.bindings.remove(name)
+    }
+
+    /// Returns the name of the action that a key event matches, if any
+    pub fn find(&self, event: &KeyEvent) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|(_, binding)| binding.matches(event))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns an iterator over action names and bindings, ordered by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &KeyBinding)> {
+        self.bindings
+            .iter()
+            .map(|(name, binding)| (name.as_str(), binding))
+    }
+
+    /// Parses bindings and returns them with the lines that could not be parsed
+    fn parse_lines(text: &str) -> (Self, Vec<&str>) {
+        let mut bindings = KeyBindings::default();
+        let mut invalid = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line.split_once('=').and_then(|(name, binding)| {
+                let binding = binding.trim().parse().ok()?;
+                Some((name.trim().to_owned(), binding))
+            });
+            match parsed {
+                Some((name, binding)) => {
+                    bindings.bindings.insert(name, binding);
+                }
+                None => invalid.push(line),
+            }
+        }
+        (bindings, invalid)
+    }
+}
+
+impl fmt::Display for KeyBindings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, binding) in &self.bindings {
+            writeln!(f, "{} = {}", name, binding)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeyBindings {
+    type Err = ParseKeyBindingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match KeyBindings::parse_lines(s) {
+            (bindings, ref invalid) if invalid.is_empty() => Ok(bindings),
+            (_, invalid) => Err(ParseKeyBindingError(invalid[0].to_owned())),
+        }
+    }
+}
+
+/// Returns the path to the key bindings file in this plugin's folder
+fn default_path() -> PathBuf {
+    plugin_folder().join(FILE_NAME)
+}
+
+/// Waits for the user to press a key
+///
+/// While waiting, all key presses are intercepted before they reach X-Plane or windows.
+/// When the user presses a key, the callback is called once with its binding, or with
+/// None if the key was Escape. Keys are then passed on normally.
+///
+/// Keys are no longer intercepted when this object is dropped.
+pub struct KeyCapture {
+    data: Box<CaptureData>,
+}
+
+/// A callback that receives a captured key
+type CaptureCallback = Box<dyn FnOnce(Option<KeyBinding>)>;
+
+/// The callback and state of a key capture, used as a refcon
+struct CaptureData {
+    /// If a key has been captured
+    done: Cell<bool>,
+    /// The callback, called at most once
+    callback: Cell<Option<CaptureCallback>>,
+}
+
+impl KeyCapture {
+    /// Starts waiting for a key press
+    pub fn start<F>(callback: F) -> Self
+    where
+        F: FnOnce(Option<KeyBinding>) + 'static,
+    {
+        crate::debug_assert_main_thread!();
+        let data = Box::new(CaptureData {
+            done: Cell::new(false),
+            callback: Cell::new(Some(Box::new(callback))),
+        });
+        let data_ptr: *const CaptureData = data.as_ref();
+        unsafe {
+            xplm_sys::XPLMRegisterKeySniffer(Some(capture_sniffer), 1, data_ptr as *mut c_void);
+        }
+        KeyCapture { data }
+    }
+
+    /// Returns true if a key has been captured or the capture was cancelled
+    pub fn is_done(&self) -> bool {
+        self.data.done.get()
+    }
+}
+
+impl Drop for KeyCapture {
+    fn drop(&mut self) {
+        let data_ptr: *const CaptureData = self.data.as_ref();
+        unsafe {
+            xplm_sys::XPLMUnregisterKeySniffer(Some(capture_sniffer), 1, data_ptr as *mut c_void);
+        }
+    }
+}
+
+impl fmt::Debug for KeyCapture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyCapture")
+            .field("done", &self.is_done())
+            .finish()
+    }
+}
+
+/// Key sniffer callback for key captures
+unsafe extern "C" fn capture_sniffer(
+    key: c_char,
+    flags: xplm_sys::XPLMKeyFlags,
+    virtual_key: c_char,
+    refcon: *mut c_void,
+) -> c_int {
+    let data = refcon as *const CaptureData;
+    if (*data).done.get() || super::internal::suspended() {
+        // Pass the key on
+        return 1;
+    }
+    match KeyEvent::from_xplm(key, flags, virtual_key) {
+        Ok(event) => {
+            if let KeyAction::Press = event.action() {
+                (*data).done.set(true);
+                let binding = match event.key() {
+                    Key::Escape => None,
+                    _ => Some(KeyBinding::from_event(&event)),
+                };
+                if let Some(callback) = (*data).callback.take() {
+                    super::panics::contain("key capture", data, (), || callback(binding));
+                }
+            }
+            // Consume the key
+            0
+        }
+        // Modifier keys and unsupported keys
+        Err(_) => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyBinding, KeyBindings};
+    use crate::window::Key;
+
+    #[test]
+    fn test_binding_round_trip() {
+        let binding: KeyBinding = "ctrl+Shift+f5".parse().unwrap();
+        assert_eq!(binding.key, Key::F5);
+        assert!(binding.control && binding.shift && !binding.option);
+        assert_eq!(binding.to_string(), "Ctrl+Shift+F5");
+        assert_eq!("Key1".parse::<KeyBinding>().unwrap().key, Key::Key1);
+        assert!("Ctrl+".parse::<KeyBinding>().is_err());
+        assert!("Hyper+A".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn test_bindings_parse() {
+        let text = "# Shortcuts\ntoggle_panel = Ctrl+P\nreset=Alt+Shift+R\nbad line\n";
+        let (bindings, invalid) = KeyBindings::parse_lines(text);
+        assert_eq!(invalid, vec!["bad line"]);
+        assert_eq!(bindings.get("reset").unwrap().to_string(), "Alt+Shift+R");
+        assert_eq!(
+            bindings.to_string(),
+            "reset = Alt+Shift+R\ntoggle_panel = Ctrl+P\n"
+        );
+        assert!(text.parse::<KeyBindings>().is_err());
+    }
+}
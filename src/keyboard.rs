@@ -0,0 +1,72 @@
+//! # Keyboard focus
+//!
+//! Key presses go to whatever has keyboard focus. Normally X-Plane has focus and uses keys
+//! for its own commands. A window that accepts text, such as a search box, takes focus with
+//! [`Window::take_keyboard_focus`] and receives key events until it gives up focus with
+//! [`Window::give_up_focus`] or something else takes focus. When the user clicks back into
+//! the simulator, X-Plane takes focus again and the window's delegate is notified through
+//! [`WindowDelegate::focus_lost`].
+//!
+//! [`focus`] tells where keyboard focus is now.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::keyboard::{self, Focus};
+//!
+//! match keyboard::focus() {
+//!     Focus::XPlane => xplm::debugln!("X-Plane has keyboard focus"),
+//!     Focus::Window(window) => xplm::debugln!("Window {:?} has keyboard focus", window),
+//!     _ => xplm::debugln!("Another plugin has keyboard focus"),
+//! }
+//! ```
+//!
+//! [`Window::take_keyboard_focus`]: crate::window::Window::take_keyboard_focus
+//! [`Window::give_up_focus`]: crate::window::Window::give_up_focus
+//! [`WindowDelegate::focus_lost`]: crate::window::WindowDelegate::focus_lost
+//!
+
+use std::ptr;
+
+use super::window::{self, WindowId};
+
+/// The things that can have keyboard focus
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Focus {
+    /// X-Plane has focus and uses key presses for its own commands
+    XPlane,
+    /// A window created by this plugin has focus
+    Window(WindowId),
+    /// A window or widget created by another plugin, or a widget created by this plugin, has
+    /// focus
+    Other,
+}
+
+/// Returns the thing that has keyboard focus
+pub fn focus() -> Focus {
+    crate::debug_assert_main_thread!();
+    if 1 == unsafe { xplm_sys::XPLMHasKeyboardFocus(ptr::null_mut()) } {
+        Focus::XPlane
+    } else if let Some(window) = window::focused_window() {
+        Focus::Window(window)
+    } else {
+        Focus::Other
+    }
+}
+
+/// Returns keyboard focus to X-Plane if a window created by this plugin has it
+///
+/// Returns true if a window created by this plugin had focus. Focus is not taken from other
+/// plugins.
+pub fn give_up_focus() -> bool {
+    crate::debug_assert_main_thread!();
+    if window::focused_window().is_some() {
+        unsafe {
+            xplm_sys::XPLMTakeKeyboardFocus(ptr::null_mut());
+        }
+        true
+    } else {
+        false
+    }
+}
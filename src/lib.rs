@@ -8,8 +8,8 @@ use std::ffi::CString;
 
 /// FFI utilities
 mod ffi;
-/// Path conversion
-mod paths;
+/// OpenGL functions
+mod gl;
 /// Plugin macro
 mod plugin_macro;
 
@@ -17,30 +17,101 @@ mod plugin_macro;
 ///
 mod internal;
 
+/// Plugin information window
+pub mod about;
+/// Commands with associated menu items
+pub mod action;
 /// Commands
 pub mod command;
 /// Datarefs
 pub mod data;
+/// Debug options from a file or environment variables
+pub mod debug_config;
+/// Drop order logging and detection of callbacks for dropped objects
+#[cfg(feature = "drop-diagnostics")]
+pub mod diagnostics;
+/// Mouse location and screen size
+pub mod display;
 /// Low-level drawing callbacks
 pub mod draw;
 /// Error detection
 pub mod error;
+/// Running code on the main thread
+pub mod executor;
+/// Simulated SDK failures for testing
+#[cfg(feature = "fault-injection")]
+pub mod fault;
 /// SDK feature management
 pub mod feature;
+/// Hook for menu clicks and commands
+pub mod feedback;
 /// Flight loop callbacks
 // TODO: Flight loop implementation that supports SDK 1.0
 pub mod flight_loop;
+/// Per-frame dataref snapshots for drawing
+pub mod frame_data;
+/// Round gauge and needle drawing
+#[cfg(feature = "gauges")]
+pub mod gauges;
+/// Geographic coordinates and magnetic variation
+pub mod geo;
 /// 2D user interface geometry
 pub mod geometry;
+/// Plugin health datarefs and watchdog
+pub mod health;
+/// Global keyboard shortcuts
+pub mod hotkey;
+/// Joysticks and other input devices
+pub mod input;
+/// 3D object loading and instancing
+pub mod instance;
+/// Typed messages between plugins
+pub mod ipc;
+/// Rebindable keyboard shortcuts
+pub mod key_binding;
+/// Keyboard focus
+pub mod keyboard;
+/// Logging from threads other than the main thread
+pub mod log;
 /// User interface menus
 pub mod menu;
+/// Navigation database and great-circle calculations
+pub mod navigation;
+/// SDK functions that may not be available in all X-Plane versions
+pub mod optional;
+/// Overriding X-Plane systems
+pub mod overrides;
+/// Catching panics in callbacks
+pub mod panics;
+/// X-Plane and plugin folders
+pub mod paths;
+/// The user's aircraft
+pub mod planes;
 /// Plugin creation and management
 pub mod plugin;
+/// Batch creation of datarefs and commands
+pub mod registration;
+/// Terrain probes
+pub mod scenery;
+/// Frame and window capture
+#[cfg(feature = "screenshot")]
+pub mod screenshot;
+/// Serial port bridge for cockpit hardware
+#[cfg(feature = "serial")]
+pub mod serial;
+/// In-simulator test cases
+pub mod testing;
+/// Checking that code runs on X-Plane's main thread
+pub mod thread;
 /// X-Plane and XPLM version info
 pub mod versions;
+/// Native X-Plane widgets
+pub mod widgets;
 /// Relatively low-level windows
 pub mod window;
 
+pub use self::versions::versions;
+
 /// Writes a message to the developer console and Log.txt file
 ///
 /// No line terminator is added.
@@ -48,9 +119,7 @@ pub mod window;
 pub fn debug<S: Into<String>>(message: S) {
     match CString::new(message.into()) {
         Ok(message_c) => unsafe { XPLMDebugString(message_c.as_ptr()) },
-        Err(_) => unsafe {
-            XPLMDebugString("[xplm] Invalid debug message\n\0".as_ptr() as *const _)
-        },
+        Err(_) => unsafe { XPLMDebugString(c"[xplm] Invalid debug message\n".as_ptr()) },
     }
 }
 
@@ -88,7 +157,33 @@ macro_rules! debugln {
     });
 }
 
+/// Writes a message to the developer console and Log.txt file from any thread
+///
+/// Messages from threads other than the main thread are written in the next flight loop.
+/// See the [`log`](crate::log) module for details.
+#[macro_export]
+macro_rules! debug_any_thread {
+    ($($arg:tt)*) => ({
+        $crate::log::write(std::fmt::format(std::format_args!($($arg)*)));
+    });
+}
+
+/// Writes a message to the developer console and Log.txt file from any thread, with a newline
+///
+/// Messages from threads other than the main thread are written in the next flight loop.
+/// See the [`log`](crate::log) module for details.
+#[macro_export]
+macro_rules! debugln_any_thread {
+    () => ($crate::debug_any_thread!("\n"));
+    ($($arg:tt)*) => ({
+        let mut formatted_string: String = std::fmt::format(std::format_args!($($arg)*));
+        formatted_string.push_str("\n");
+        $crate::log::write(formatted_string);
+    });
+}
+
 /// Attempts to locate a symbol. If it exists, returns a pointer to it
+#[deprecated(note = "Please use the typed symbol function instead")]
 pub fn find_symbol<S: Into<String>>(name: S) -> *mut std::os::raw::c_void {
     use std::ptr;
     match std::ffi::CString::new(name.into()) {
@@ -97,14 +192,43 @@ pub fn find_symbol<S: Into<String>>(name: S) -> *mut std::os::raw::c_void {
     }
 }
 
+/// Looks up an SDK function by name and returns it as a function pointer of type T
+///
+/// Returns None if the function does not exist or the name contains a null byte.
+///
+/// The [`optional`] module has typed lookups for some SDK functions that are only available
+/// in newer versions of X-Plane.
+///
+/// # Safety
+///
+/// T must have the same signature as the function.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::os::raw::c_int;
+///
+/// let count_data_refs: Option<unsafe extern "C" fn() -> c_int> =
+///     unsafe { xplm::symbol("XPLMCountDataRefs") };
+/// ```
+pub unsafe fn symbol<T: optional::FnPtr>(name: &str) -> Option<T> {
+    let name_c = std::ffi::CString::new(name).ok()?;
+    let address = xplm_sys::XPLMFindSymbol(name_c.as_ptr());
+    if address.is_null() {
+        None
+    } else {
+        Some(std::mem::transmute_copy::<*mut std::os::raw::c_void, T>(
+            &address,
+        ))
+    }
+}
+
 /// Speak the string and/or display it onscreen/in the ATC history window
 pub fn speak<S: Into<String>>(msg: S) {
     match std::ffi::CString::new(msg.into()) {
         Ok(msg) => unsafe {
             xplm_sys::XPLMSpeakString(msg.as_ptr());
         },
-        Err(_) => unsafe {
-            crate::XPLMDebugString("[xplm] Invalid speak message\n\0".as_ptr() as *const _)
-        },
+        Err(_) => unsafe { crate::XPLMDebugString(c"[xplm] Invalid speak message\n".as_ptr()) },
     }
 }
@@ -0,0 +1,128 @@
+//! # Logging from any thread
+//!
+//! [`XPLMDebugString`](crate::XPLMDebugString), which the [`debug!`](crate::debug) and
+//! [`debugln!`](crate::debugln) macros call, must only be called from the main thread.
+//! The [`debug_any_thread!`](crate::debug_any_thread) and
+//! [`debugln_any_thread!`](crate::debugln_any_thread) macros can be used from any thread.
+//! On the main thread they write messages immediately. On other threads they queue messages,
+//! which are written in the next flight loop.
+//!
+//! The main thread is the thread that started the plugin. See the [`thread`](crate::thread)
+//! module for details.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::thread;
+//!
+//! thread::spawn(|| {
+//!     xplm::debugln_any_thread!("[myplugin] Connected to server");
+//! });
+//! ```
+//!
+
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::{c_float, c_int, c_void};
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+
+use xplm_sys::{XPLMDebugString, XPLMRegisterFlightLoopCallback, XPLMUnregisterFlightLoopCallback};
+
+/// Messages from other threads that have not been written yet
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub use super::thread::is_main_thread;
+
+/// Writes a message to the developer console and Log.txt file, or queues it to be written
+/// if this is not the main thread
+///
+/// No line terminator is added.
+pub fn write(message: String) {
+    if is_main_thread() {
+        flush();
+        write_now(message);
+    } else {
+        queue(message);
+    }
+}
+
+/// Writes all queued messages now
+///
+/// This has no effect if it is not called from the main thread.
+pub fn flush() {
+    if !is_main_thread() {
+        return;
+    }
+    for message in take_pending() {
+        write_now(message);
+    }
+}
+
+/// Adds a message to the queue
+fn queue(message: String) {
+    pending().push(message);
+}
+
+/// Removes and returns all queued messages
+fn take_pending() -> Vec<String> {
+    mem::take(&mut *pending())
+}
+
+/// Locks the queue, ignoring any panic that happened while another thread held the lock
+fn pending() -> MutexGuard<'static, Vec<String>> {
+    PENDING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Writes a message
+fn write_now(message: String) {
+    match CString::new(message) {
+        Ok(message) => unsafe { XPLMDebugString(message.as_ptr()) },
+        Err(_) => unsafe { XPLMDebugString(c"[xplm] Invalid debug message\n".as_ptr()) },
+    }
+}
+
+/// Starts the flight loop callback that writes queued messages
+///
+/// This is called from XPluginStart.
+pub(crate) fn log_init() {
+    unsafe { XPLMRegisterFlightLoopCallback(Some(flush_callback), -1.0, ptr::null_mut()) };
+}
+
+/// Writes any queued messages and stops the flight loop callback
+///
+/// This is called from XPluginStop.
+pub(crate) fn log_stop() {
+    flush();
+    unsafe { XPLMUnregisterFlightLoopCallback(Some(flush_callback), ptr::null_mut()) };
+}
+
+/// Flight loop callback that writes queued messages every frame
+///
+/// Messages are written even when the plugin is disabled.
+unsafe extern "C" fn flush_callback(
+    _since_call: c_float,
+    _since_loop: c_float,
+    _counter: c_int,
+    _refcon: *mut c_void,
+) -> c_float {
+    flush();
+    -1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    #[test]
+    fn test_queue() {
+        // The main thread is not set in tests
+        assert!(!super::is_main_thread());
+        thread::spawn(|| super::queue("from worker\n".into()))
+            .join()
+            .unwrap();
+        super::queue("second\n".into());
+        assert_eq!(super::take_pending(), ["from worker\n", "second\n"]);
+        assert!(super::take_pending().is_empty());
+    }
+}
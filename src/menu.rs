@@ -6,8 +6,13 @@ use std::ptr;
 use std::rc::Rc;
 use xplm_sys;
 
+use super::command::Command;
+use super::feedback::{self, Feedback};
+use super::optional;
+
 /// Something that can be added to a menu
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Item {
     /// A submenu
     Submenu(Rc<Menu>),
@@ -23,7 +28,7 @@ impl Item {
     /// Called when this item is added to a parent menu
     fn add_to_menu(&self, parent_id: xplm_sys::XPLMMenuID) {
         match *self {
-            Item::Submenu(ref menu) => menu.add_to_menu(parent_id),
+            Item::Submenu(ref menu) => menu.add_to_menu_id(parent_id),
             // Pass the address of this Item as a reference for the callback
             Item::Action(ref action) => action.add_to_menu(parent_id, self),
             Item::Check(ref check) => check.add_to_menu(parent_id, self),
@@ -52,8 +57,14 @@ impl Item {
     /// Called when the user clicks on this menu item
     fn handle_click(&self) {
         match *self {
-            Item::Action(ref action) => action.handle_click(),
-            Item::Check(ref check) => check.handle_click(),
+            Item::Action(ref action) => {
+                feedback::notify(Feedback::MenuClick(&action.name()));
+                action.handle_click();
+            }
+            Item::Check(ref check) => {
+                feedback::notify(Feedback::MenuClick(&check.name()));
+                check.handle_click();
+            }
             _ => {}
         }
     }
@@ -95,6 +106,7 @@ pub struct Menu {
     /// The items, separators, and submenus in this menu
     ///
     /// Each item is in a Box, to allow callbacks to reference it.
+    #[allow(clippy::vec_box)]
     children: RefCell<Vec<Box<Item>>>,
     /// The status of this menu
     state: Cell<MenuState>,
@@ -138,31 +150,159 @@ impl Menu {
         R: Into<Rc<C>>,
         Rc<C>: Into<Item>,
     {
+        let length = self.child_count();
+        self.insert_child(length, child);
+    }
+    /// Inserts a child into this menu at the provided index
+    ///
+    /// The child argument may be a Menu, ActionItem, CheckItem, or Separator,
+    /// or an Rc containing one of these types.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if index is greater than the number of children in this menu.
+    pub fn insert_child<R, C>(&self, index: usize, child: R)
+    where
+        R: Into<Rc<C>>,
+        Rc<C>: Into<Item>,
+    {
+        // Check before removing any items from the X-Plane menu
+        let length = self.child_count();
+        assert!(
+            index <= length,
+            "insert_child index {} out of range (length {})",
+            index,
+            length
+        );
+        let child = Box::new(child.into().into());
+        self.rebuild_from(index, move |children| children.insert(index, child));
+    }
+    /// Moves the child at index from so that it is at index to
+    ///
+    /// The other children keep their order.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if from or to is not less than the number of children in this menu.
+    pub fn move_child(&self, from: usize, to: usize) {
+        let length = self.child_count();
+        assert!(from < length, "move_child from index {} out of range", from);
+        assert!(to < length, "move_child to index {} out of range", to);
+        if from == to {
+            return;
+        }
+        self.rebuild_from(from.min(to), move |children| {
+            let child = children.remove(from);
+            children.insert(to, child);
+        });
+    }
+    /// Removes the child at the provided index from this menu and returns it
+    ///
+    /// The removed child can be added to a menu again.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if index is not less than the number of children in this menu.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::menu::{ActionItem, Menu, Separator};
+    ///
+    /// let menu = Menu::new("Recent files").unwrap();
+    /// menu.add_to_plugins_menu();
+    /// menu.add_child(ActionItem::new("first.txt", |_: &ActionItem| {}).unwrap());
+    /// menu.add_child(Separator);
+    /// menu.insert_child(0, ActionItem::new("second.txt", |_: &ActionItem| {}).unwrap());
+    /// // Remove first.txt
+    /// let _removed = menu.remove_child(1);
+    /// menu.clear();
+    /// ```
+    pub fn remove_child(&self, index: usize) -> Item {
         let mut borrow = self.children.borrow_mut();
-        borrow.push(Box::new(child.into().into()));
+        assert!(
+            index < borrow.len(),
+            "remove_child index {} out of range",
+            index
+        );
+        if let MenuState::InMenu { id, .. } = self.state.get() {
+            borrow[index].remove_from_menu(id, index as c_int);
+            // X-Plane moves the later items up by one
+            for (later_index, later) in borrow.iter().enumerate().skip(index + 1) {
+                later.update_index(later_index as c_int - 1);
+            }
+        }
+        *borrow.remove(index)
+    }
+    /// Removes all children from this menu
+    pub fn clear(&self) {
+        self.rebuild_from(0, Vec::clear);
+    }
+    /// Returns the number of children in this menu, including separators
+    pub fn child_count(&self) -> usize {
+        self.children.borrow().len()
     }
 
     /// Adds this menu as a child of the plugins menu
     pub fn add_to_plugins_menu(&self) {
         let plugins_menu = unsafe { xplm_sys::XPLMFindPluginsMenu() };
-        self.add_to_menu(plugins_menu);
+        self.add_to_menu_id(plugins_menu);
     }
     /// Removes this menu from the plugins menu
     pub fn remove_from_plugins_menu(&self) {
         let plugins_menu = unsafe { xplm_sys::XPLMFindPluginsMenu() };
-        if let MenuState::InMenu {
-            id: _id,
-            parent,
-            index_in_parent,
-        } = self.state.get()
-        {
-            if parent == plugins_menu {
-                self.remove_from_menu(plugins_menu, index_in_parent);
-            }
+        self.remove_from_menu_id(plugins_menu);
+    }
+
+    /// Adds this menu as a child of the menu of the user's aircraft
+    ///
+    /// X-Plane shows the aircraft menu in the menu bar while the aircraft is loaded. Only
+    /// plugins that are part of the user's aircraft have this menu, so this returns an error
+    /// in other plugins.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::menu::{ActionItem, Menu};
+    ///
+    /// let menu = Menu::new("Doors").unwrap();
+    /// menu.add_child(ActionItem::new("Open all", |_: &ActionItem| {}).unwrap());
+    /// if menu.add_to_aircraft_menu().is_err() {
+    ///     // Not loaded with an aircraft
+    ///     menu.add_to_plugins_menu();
+    /// }
+    /// ```
+    pub fn add_to_aircraft_menu(&self) -> Result<(), NoAircraftMenu> {
+        let aircraft_menu = unsafe { xplm_sys::XPLMFindAircraftMenu() };
+        if aircraft_menu.is_null() {
+            return Err(NoAircraftMenu);
+        }
+        self.add_to_menu_id(aircraft_menu);
+        Ok(())
+    }
+    /// Removes this menu from the aircraft menu
+    pub fn remove_from_aircraft_menu(&self) {
+        let aircraft_menu = unsafe { xplm_sys::XPLMFindAircraftMenu() };
+        if !aircraft_menu.is_null() {
+            self.remove_from_menu_id(aircraft_menu);
         }
     }
+
+    /// Adds this menu as the last child of another menu
+    ///
+    /// This is equivalent to passing a clone of this `Rc` to
+    /// [`add_child`](Menu::add_child) of the parent. The parent keeps this menu in its list
+    /// of children, so the menu can be removed with [`remove_child`](Menu::remove_child).
+    pub fn add_to_menu(self: &Rc<Self>, parent: &Menu) {
+        parent.add_child::<_, Menu>(Rc::clone(self));
+    }
 }
 
+/// An error returned when the aircraft menu is not available
+#[derive(thiserror::Error, Debug)]
+#[error("No aircraft menu is available")]
+pub struct NoAircraftMenu;
+
 /// Status that a menu can have
 #[derive(Debug, Copy, Clone)]
 enum MenuState {
@@ -180,7 +320,8 @@ enum MenuState {
 }
 
 impl Menu {
-    fn add_to_menu(&self, parent_id: xplm_sys::XPLMMenuID) {
+    fn add_to_menu_id(&self, parent_id: xplm_sys::XPLMMenuID) {
+        crate::debug_assert_main_thread!();
         if let MenuState::Free = self.state.get() {
             let name_c = CString::new(self.name()).unwrap();
             // A submenu requires a menu item to open it
@@ -211,6 +352,45 @@ impl Menu {
             }
         }
     }
+    /// Changes the children of this menu, keeping X-Plane's menu in sync
+    ///
+    /// X-Plane can only append menu items, so if this menu is in a menu, the children at and
+    /// after start are removed from X-Plane, modify is called, and the children at and after
+    /// start are appended again.
+    fn rebuild_from<F>(&self, start: usize, modify: F)
+    where
+        F: FnOnce(&mut Vec<Box<Item>>),
+    {
+        let mut borrow = self.children.borrow_mut();
+        match self.state.get() {
+            MenuState::Free => modify(&mut borrow),
+            MenuState::InMenu { id, .. } => {
+                // Remove from the end so that the indices of earlier children do not change
+                for (index, child) in borrow.iter().enumerate().skip(start).rev() {
+                    child.remove_from_menu(id, index as c_int);
+                }
+                modify(&mut borrow);
+                for child in borrow.iter().skip(start) {
+                    // Memory safety warning: Child must be allocated in a Box to prevent it from
+                    // moving
+                    child.add_to_menu(id);
+                }
+            }
+        }
+    }
+    /// Removes this menu if its parent has the provided ID
+    fn remove_from_menu_id(&self, parent_id: xplm_sys::XPLMMenuID) {
+        if let MenuState::InMenu {
+            id: _id,
+            parent,
+            index_in_parent,
+        } = self.state.get()
+        {
+            if parent == parent_id {
+                self.remove_from_menu(parent_id, index_in_parent);
+            }
+        }
+    }
     fn update_index(&self, index_in_parent: c_int) {
         let mut state = self.state.get();
         if let MenuState::InMenu {
@@ -255,6 +435,8 @@ impl Menu {
 /// a dangling pointer
 impl Drop for Menu {
     fn drop(&mut self) {
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::dropped("Menu", self);
         if let MenuState::InMenu {
             id: _id,
             parent,
@@ -291,8 +473,13 @@ pub struct ActionItem {
     ///
     /// Invariant: this can be converted into a CString
     name: RefCell<String>,
+    /// If this item can be clicked
+    enabled: Cell<bool>,
     /// Information about the menu this item is part of
     in_menu: Cell<Option<InMenu>>,
+    /// The command that X-Plane triggers when this item is clicked, instead of calling the
+    /// handler
+    command: Option<xplm_sys::XPLMCommandRef>,
     /// The item click handler
     handler: Box<RefCell<dyn MenuClickHandler>>,
 }
@@ -309,11 +496,99 @@ impl ActionItem {
         check_c_string(&name)?;
         Ok(ActionItem {
             name: RefCell::new(name),
+            enabled: Cell::new(true),
             in_menu: Cell::new(None),
+            command: None,
             handler: Box::new(RefCell::new(handler)),
         })
     }
+    /// Creates a new item that triggers a command when clicked
+    ///
+    /// X-Plane shows the keyboard shortcuts and joystick buttons that the user has assigned
+    /// to the command next to the item. In X-Plane versions that cannot attach commands to
+    /// menu items, the item triggers the command when clicked but does not show the
+    /// shortcuts.
+    ///
+    /// Returns an error if the name contains a null byte
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xplm::command::Command;
+    /// use xplm::menu::{ActionItem, Menu};
+    ///
+    /// let menu = Menu::new("Example").unwrap();
+    /// let settings = Command::find("myplugin/toggle_settings").unwrap();
+    /// menu.add_child(ActionItem::with_command("Settings", &settings).unwrap());
+    /// menu.add_to_plugins_menu();
+    /// ```
+    pub fn with_command<S: Into<String>>(name: S, command: &Command) -> Result<Self, NulError> {
+        let mut item = ActionItem::new(name, |_: &ActionItem| {})?;
+        item.command = Some(command.id());
+        Ok(item)
+    }
+    /// Creates a new item with a handler that receives a context
+    ///
+    /// The item keeps only a weak reference to the context, so the context can own this
+    /// item without creating a reference cycle. When the user clicks on this item, the
+    /// handler is called with the context. If the context has been dropped, the click is
+    /// ignored.
+    ///
+    /// Returns an error if the name contains a null byte
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use xplm::menu::{ActionItem, Menu};
+    ///
+    /// struct Plugin {
+    ///     menu: Menu,
+    ///     clicks: Cell<u32>,
+    /// }
+    ///
+    /// let plugin = Rc::new(Plugin {
+    ///     menu: Menu::new("Example").unwrap(),
+    ///     clicks: Cell::new(0),
+    /// });
+    /// // The item does not keep the plugin alive
+    /// let item = ActionItem::with_context("Click", &plugin, |plugin: &Plugin, _item| {
+    ///     plugin.clicks.set(plugin.clicks.get() + 1);
+    /// })
+    /// .unwrap();
+    /// plugin.menu.add_child(item);
+    /// ```
+    pub fn with_context<S, T, F>(name: S, context: &Rc<T>, handler: F) -> Result<Self, NulError>
+    where
+        S: Into<String>,
+        T: 'static,
+        F: FnMut(&T, &ActionItem) + 'static,
+    {
+        let context = Rc::downgrade(context);
+        let mut handler = handler;
+        ActionItem::new(name, move |item: &ActionItem| {
+            if let Some(context) = context.upgrade() {
+                handler(&context, item);
+            }
+        })
+    }
 
+    /// Returns true if this item is enabled
+    pub fn enabled(&self) -> bool {
+        self.enabled.get()
+    }
+    /// Enables or disables this item
+    ///
+    /// A disabled item is shown grayed out and cannot be clicked.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+        if let Some(in_menu) = self.in_menu.get() {
+            unsafe {
+                xplm_sys::XPLMEnableMenuItem(in_menu.parent, in_menu.index, enabled as c_int);
+            }
+        }
+    }
     /// Returns the name of this item
     pub fn name(&self) -> String {
         let borrow = self.name.borrow();
@@ -345,14 +620,23 @@ impl ActionItem {
     fn add_to_menu(&self, parent_id: xplm_sys::XPLMMenuID, enclosing_item: *const Item) {
         let name_c = CString::new(self.name()).unwrap();
         let index = unsafe {
-            let index = xplm_sys::XPLMAppendMenuItem(
-                parent_id,
-                name_c.as_ptr(),
-                enclosing_item as *mut _,
-                0,
-            );
+            // X-Plane versions without XPLMAppendMenuItemWithCommand get a normal item that
+            // triggers the command when clicked
+            let append_with_command = self.command.zip(optional::append_menu_item_with_command());
+            let index = match append_with_command {
+                Some((command, append)) => append(parent_id, name_c.as_ptr(), command),
+                None => xplm_sys::XPLMAppendMenuItem(
+                    parent_id,
+                    name_c.as_ptr(),
+                    enclosing_item as *mut _,
+                    0,
+                ),
+            };
             // Ensure item is not checkable
             xplm_sys::XPLMCheckMenuItem(parent_id, index, xplm_sys::xplm_Menu_NoCheck as c_int);
+            if !self.enabled.get() {
+                xplm_sys::XPLMEnableMenuItem(parent_id, index, 0);
+            }
             index
         };
         self.in_menu.set(Some(InMenu::new(parent_id, index)));
@@ -366,11 +650,17 @@ impl ActionItem {
     }
     fn remove_from_menu(&self, parent_id: xplm_sys::XPLMMenuID, index_in_parent: c_int) {
         unsafe { xplm_sys::XPLMRemoveMenuItem(parent_id, index_in_parent as c_int) }
+        self.in_menu.set(None);
     }
 
     fn handle_click(&self) {
+        if let Some(command) = self.command {
+            // Only reached if the item was added without XPLMAppendMenuItemWithCommand
+            unsafe { xplm_sys::XPLMCommandOnce(command) };
+            return;
+        }
         let mut borrow = self.handler.borrow_mut();
-        borrow.item_clicked(&self);
+        borrow.item_clicked(self);
     }
 }
 
@@ -388,12 +678,18 @@ impl fmt::Debug for ActionItem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("ActionItem")
             .field("name", &self.name)
+            .field("enabled", &self.enabled)
             .field("in_menu", &self.in_menu)
+            .field("command", &self.command)
             .finish()
     }
 }
 
 /// Trait for things that can respond when the user clicks on a menu item
+///
+/// A handler is owned by its menu item. If a handler contains an `Rc` of a structure that
+/// also owns the item, neither will ever be dropped. Use [`ActionItem::with_context`], or
+/// store a [`Weak`](std::rc::Weak) reference in the handler, to avoid this.
 pub trait MenuClickHandler: 'static {
     /// Called when the user clicks on a menu item. The clicked item is passed.
     fn item_clicked(&mut self, item: &ActionItem);
@@ -416,6 +712,8 @@ pub struct CheckItem {
     name: RefCell<String>,
     /// If this item is checked
     checked: Cell<bool>,
+    /// If this item can be clicked
+    enabled: Cell<bool>,
     /// Information about the menu this item is part of
     in_menu: Cell<Option<InMenu>>,
     /// The check handler
@@ -436,10 +734,38 @@ impl CheckItem {
         Ok(CheckItem {
             name: RefCell::new(name),
             checked: Cell::new(checked),
+            enabled: Cell::new(true),
             in_menu: Cell::new(None),
             handler: Box::new(RefCell::new(handler)),
         })
     }
+    /// Creates a new item with a handler that receives a context
+    ///
+    /// The item keeps only a weak reference to the context, so the context can own this
+    /// item without creating a reference cycle. When the user checks or unchecks this item,
+    /// the handler is called with the context. If the context has been dropped, the check
+    /// state still changes but the handler is not called.
+    ///
+    /// Returns an error if the name contains a null byte
+    pub fn with_context<S, T, F>(
+        name: S,
+        checked: bool,
+        context: &Rc<T>,
+        handler: F,
+    ) -> Result<Self, NulError>
+    where
+        S: Into<String>,
+        T: 'static,
+        F: FnMut(&T, &CheckItem, bool) + 'static,
+    {
+        let context = Rc::downgrade(context);
+        let mut handler = handler;
+        CheckItem::new(name, checked, move |item: &CheckItem, checked| {
+            if let Some(context) = context.upgrade() {
+                handler(&context, item, checked);
+            }
+        })
+    }
     /// Returns true if this item is checked
     pub fn checked(&self) -> bool {
         if let Some(in_menu) = self.in_menu.get() {
@@ -481,6 +807,21 @@ impl CheckItem {
             }
         }
     }
+    /// Returns true if this item is enabled
+    pub fn enabled(&self) -> bool {
+        self.enabled.get()
+    }
+    /// Enables or disables this item
+    ///
+    /// A disabled item is shown grayed out and cannot be clicked.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+        if let Some(in_menu) = self.in_menu.get() {
+            unsafe {
+                xplm_sys::XPLMEnableMenuItem(in_menu.parent, in_menu.index, enabled as c_int);
+            }
+        }
+    }
     /// Returns the name of this item
     pub fn name(&self) -> String {
         let borrow = self.name.borrow();
@@ -521,6 +862,9 @@ impl CheckItem {
             // Configure check
             let check_state = check_state(self.checked.get());
             xplm_sys::XPLMCheckMenuItem(parent_id, index, check_state);
+            if !self.enabled.get() {
+                xplm_sys::XPLMEnableMenuItem(parent_id, index, 0);
+            }
             index
         };
         self.in_menu.set(Some(InMenu::new(parent_id, index)));
@@ -534,6 +878,7 @@ impl CheckItem {
     }
     fn remove_from_menu(&self, parent_id: xplm_sys::XPLMMenuID, index_in_parent: c_int) {
         unsafe { xplm_sys::XPLMRemoveMenuItem(parent_id, index_in_parent as c_int) }
+        self.in_menu.set(None);
     }
 
     fn handle_click(&self) {
@@ -559,12 +904,16 @@ impl fmt::Debug for CheckItem {
         f.debug_struct("CheckItem")
             .field("name", &self.name)
             .field("checked", &self.checked)
+            .field("enabled", &self.enabled)
             .field("in_menu", &self.in_menu)
             .finish()
     }
 }
 
 /// Trait for things that can respond to check state changes
+///
+/// As with [`MenuClickHandler`], a handler should not contain an `Rc` of a structure that
+/// owns its item. Use [`CheckItem::with_context`] or a [`Weak`](std::rc::Weak) reference instead.
 pub trait CheckHandler: 'static {
     /// Called when the user checks or unchecks an item
     fn item_checked(&mut self, item: &CheckItem, checked: bool);
@@ -611,8 +960,138 @@ fn check_c_string(text: &str) -> Result<(), NulError> {
 
 /// The menu handler callback used for all menu items
 ///
-/// item_ref is a pointer to the relevant Item, allocated in an Rc
+/// item_ref is a pointer to the relevant Item, allocated in a Box in the children of the
+/// parent menu
 unsafe extern "C" fn menu_handler(_menu_ref: *mut c_void, item_ref: *mut c_void) {
+    if super::internal::suspended() {
+        return;
+    }
     let item = item_ref as *const Item;
-    (*item).handle_click();
+    super::panics::contain("menu", item, (), || {
+        // The handler may remove this item from its menu, which frees the Box that item
+        // points to. The clone keeps the item alive until the handler returns.
+        let item = (*item).clone();
+        item.handle_click();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::ptr;
+    use std::rc::{Rc, Weak};
+
+    use super::{menu_handler, ActionItem, Item, Menu, MenuState};
+
+    // Stand-ins for the SDK functions that menus use. Removing items only calls
+    // XPLMRemoveMenuItem, but the others must exist for the tests to link.
+    #[no_mangle]
+    extern "C" fn XPLMRemoveMenuItem(_menu: *mut c_void, _index: c_int) {}
+    #[no_mangle]
+    extern "C" fn XPLMAppendMenuItem(
+        _menu: *mut c_void,
+        _name: *const c_char,
+        _item_ref: *mut c_void,
+        _deprecated: c_int,
+    ) -> c_int {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMAppendMenuSeparator(_menu: *mut c_void) {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMCheckMenuItem(_menu: *mut c_void, _index: c_int, _check: c_int) {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMCheckMenuItemState(_menu: *mut c_void, _index: c_int, _check: *mut c_int) {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMEnableMenuItem(_menu: *mut c_void, _index: c_int, _enabled: c_int) {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMCreateMenu(
+        _name: *const c_char,
+        _parent: *mut c_void,
+        _index: c_int,
+        _handler: *mut c_void,
+        _menu_ref: *mut c_void,
+    ) -> *mut c_void {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMDestroyMenu(_menu: *mut c_void) {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMCommandOnce(_command: *mut c_void) {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMFindSymbol(_name: *const c_char) -> *mut c_void {
+        unreachable!()
+    }
+    #[no_mangle]
+    extern "C" fn XPLMDebugString(_text: *const c_char) {}
+
+    /// Creates a menu that appears to be attached to X-Plane, with one item whose handler
+    /// calls remove on the menu
+    fn menu_with_item(remove: fn(&Menu)) -> (Rc<Menu>, Rc<Cell<u32>>) {
+        let menu = Rc::new(Menu::new("Menu").unwrap());
+        let clicks = Rc::new(Cell::new(0));
+        let weak_menu: Weak<Menu> = Rc::downgrade(&menu);
+        let handler_clicks = Rc::clone(&clicks);
+        menu.add_child(
+            ActionItem::new("Remove me", move |item: &ActionItem| {
+                if let Some(menu) = weak_menu.upgrade() {
+                    remove(&menu);
+                }
+                // The item must still be usable after it has been removed
+                assert_eq!(item.name(), "Remove me");
+                handler_clicks.set(handler_clicks.get() + 1);
+            })
+            .unwrap(),
+        );
+        menu.state.set(MenuState::InMenu {
+            id: ptr::null_mut(),
+            parent: ptr::null_mut(),
+            index_in_parent: 0,
+        });
+        (menu, clicks)
+    }
+
+    /// Calls the menu handler as X-Plane does when the first item of a menu is clicked
+    fn click_first(menu: &Menu) {
+        // Callbacks are suspended until the plugin is enabled
+        crate::internal::set_suspended(false);
+        let item: *const Item = {
+            let children = menu.children.borrow();
+            &*children[0]
+        };
+        unsafe { menu_handler(ptr::null_mut(), item as *mut c_void) };
+    }
+
+    #[test]
+    fn test_handler_removes_own_item() {
+        let (menu, clicks) = menu_with_item(|menu| {
+            menu.remove_child(0);
+        });
+        click_first(&menu);
+        assert_eq!(clicks.get(), 1);
+        assert_eq!(menu.child_count(), 0);
+        menu.state.set(MenuState::Free);
+    }
+
+    #[test]
+    fn test_handler_clears_menu() {
+        let (menu, clicks) = menu_with_item(Menu::clear);
+        click_first(&menu);
+        assert_eq!(clicks.get(), 1);
+        assert_eq!(menu.child_count(), 0);
+        menu.state.set(MenuState::Free);
+    }
 }
@@ -0,0 +1,460 @@
+//! # Navigation
+//!
+//! Navaids, fixes, and airports from the X-Plane navigation database, and distance and
+//! bearing calculations on the surface of the Earth.
+//!
+//! A [`NavSearch`] finds entries by name, identifier, type, frequency, and location.
+//! [`all_navaids`] and [`navaids_of_type`] iterate over the database.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::navigation::{navaids_of_type, NavAid, NavSearch, NavType};
+//!
+//! if let Some(vor) = NavAid::find_nearest(NavType::Vor, 47.45, -122.31) {
+//!     let distance = vor.distance_from(47.45, -122.31);
+//!     xplm::debugln!("{} is {:.1} km away", vor.id, distance / 1000.0);
+//! }
+//!
+//! let seattle = NavSearch::new()
+//!     .id("KSEA")
+//!     .nav_type(NavType::Airport)
+//!     .find();
+//!
+//! let ndb_count = navaids_of_type(NavType::Ndb).count();
+//! ```
+//!
+
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::os::raw::c_int;
+use std::ptr;
+
+use xplm_sys::{self, XPLMNavRef, XPLMNavType};
+
+use super::ffi::StringBuffer;
+
+/// Flight management system flight plans
+pub mod fms;
+/// Nearest navaid tracking for displays
+pub mod nearest;
+
+/// The mean radius of the Earth in meters
+pub const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// The length of the ID buffer passed to XPLMGetNavAidInfo
+const ID_LENGTH: usize = 32;
+/// The length of the name buffer passed to XPLMGetNavAidInfo
+const NAME_LENGTH: usize = 256;
+
+/// Types of entries in the navigation database
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NavType {
+    /// An airport
+    Airport,
+    /// A non-directional beacon
+    Ndb,
+    /// A VHF omnidirectional range
+    Vor,
+    /// An instrument landing system
+    Ils,
+    /// A localizer without a glideslope
+    Localizer,
+    /// A glideslope
+    GlideSlope,
+    /// An outer marker
+    OuterMarker,
+    /// A middle marker
+    MiddleMarker,
+    /// An inner marker
+    InnerMarker,
+    /// A fix
+    Fix,
+    /// Distance measuring equipment
+    Dme,
+}
+
+impl NavType {
+    /// Converts this type into an XPLMNavType
+    fn to_xplm(self) -> XPLMNavType {
+        let nav_type = match self {
+            NavType::Airport => xplm_sys::xplm_Nav_Airport,
+            NavType::Ndb => xplm_sys::xplm_Nav_NDB,
+            NavType::Vor => xplm_sys::xplm_Nav_VOR,
+            NavType::Ils => xplm_sys::xplm_Nav_ILS,
+            NavType::Localizer => xplm_sys::xplm_Nav_Localizer,
+            NavType::GlideSlope => xplm_sys::xplm_Nav_GlideSlope,
+            NavType::OuterMarker => xplm_sys::xplm_Nav_OuterMarker,
+            NavType::MiddleMarker => xplm_sys::xplm_Nav_MiddleMarker,
+            NavType::InnerMarker => xplm_sys::xplm_Nav_InnerMarker,
+            NavType::Fix => xplm_sys::xplm_Nav_Fix,
+            NavType::Dme => xplm_sys::xplm_Nav_DME,
+        };
+        nav_type as XPLMNavType
+    }
+
+    /// Converts an XPLMNavType into a NavType
+    pub(crate) fn from_xplm(nav_type: XPLMNavType) -> Option<Self> {
+        ALL_TYPES
+            .into_iter()
+            .find(|candidate| candidate.to_xplm() == nav_type)
+    }
+}
+
+/// A reference to an entry in the navigation database
+///
+/// References are only valid until X-Plane reloads the navigation database, for example
+/// when scenery is loaded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NavRef(XPLMNavRef, PhantomData<*const ()>);
+
+impl NavRef {
+    /// Converts an XPLMNavRef into a NavRef, returning None if it is XPLM_NAV_NOT_FOUND
+    pub(crate) fn from_xplm(nav_ref: XPLMNavRef) -> Option<Self> {
+        if nav_ref == xplm_sys::XPLM_NAV_NOT_FOUND as XPLMNavRef {
+            None
+        } else {
+            Some(NavRef(nav_ref, PhantomData))
+        }
+    }
+
+    /// Returns the XPLMNavRef for this reference
+    pub fn raw(self) -> XPLMNavRef {
+        self.0
+    }
+
+    /// Reads the information about this entry
+    ///
+    /// Returns None if the entry has a type that this library does not know about.
+    pub fn info(self) -> Option<NavAid> {
+        NavAid::from_ref(self.0)
+    }
+}
+
+/// An entry in the navigation database
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct NavAid {
+    /// The reference to this entry
+    pub nav_ref: NavRef,
+    /// The type of this entry
+    pub nav_type: NavType,
+    /// The identifier, such as `KSEA` or `SEA`
+    pub id: String,
+    /// The name
+    pub name: String,
+    /// The latitude in degrees
+    pub latitude: f32,
+    /// The longitude in degrees
+    pub longitude: f32,
+    /// The elevation in meters above mean sea level
+    pub elevation: f32,
+    /// The frequency, in the units X-Plane uses for this type
+    ///
+    /// NDB frequencies are in kHz. Other frequencies are in units of 10 kHz.
+    pub frequency: i32,
+    /// The heading in degrees, for navaids that have one, such as localizers
+    pub heading: f32,
+}
+
+impl NavAid {
+    /// Finds the entry of the provided type that is nearest to a location
+    ///
+    /// Returns None if the database has no entries of the provided type.
+    pub fn find_nearest(nav_type: NavType, latitude: f32, longitude: f32) -> Option<NavAid> {
+        NavSearch::new()
+            .nav_type(nav_type)
+            .near(latitude, longitude)
+            .find()
+    }
+
+    /// Reads the information about a database entry
+    fn from_ref(nav_ref: XPLMNavRef) -> Option<NavAid> {
+        let nav_ref = NavRef::from_xplm(nav_ref)?;
+        let mut nav_type: XPLMNavType = 0;
+        let mut latitude = 0.0;
+        let mut longitude = 0.0;
+        let mut elevation = 0.0;
+        let mut frequency: c_int = 0;
+        let mut heading = 0.0;
+        let mut id = StringBuffer::new(ID_LENGTH);
+        let mut name = StringBuffer::new(NAME_LENGTH);
+        unsafe {
+            xplm_sys::XPLMGetNavAidInfo(
+                nav_ref.0,
+                &mut nav_type,
+                &mut latitude,
+                &mut longitude,
+                &mut elevation,
+                &mut frequency,
+                &mut heading,
+                id.as_mut_ptr(),
+                name.as_mut_ptr(),
+                ptr::null_mut(),
+            );
+        }
+        Some(NavAid {
+            nav_ref,
+            nav_type: NavType::from_xplm(nav_type)?,
+            id: id.as_str().unwrap_or_default().to_owned(),
+            name: name.as_str().unwrap_or_default().to_owned(),
+            latitude,
+            longitude,
+            elevation,
+            frequency,
+            heading,
+        })
+    }
+
+    /// Returns the distance in meters from a location to this entry
+    pub fn distance_from(&self, latitude: f64, longitude: f64) -> f64 {
+        distance(
+            latitude,
+            longitude,
+            f64::from(self.latitude),
+            f64::from(self.longitude),
+        )
+    }
+
+    /// Returns the initial true bearing in degrees from a location to this entry
+    pub fn bearing_from(&self, latitude: f64, longitude: f64) -> f64 {
+        bearing(
+            latitude,
+            longitude,
+            f64::from(self.latitude),
+            f64::from(self.longitude),
+        )
+    }
+}
+
+/// Search criteria for the navigation database
+///
+/// All criteria are optional. If no types are added, entries of all types match.
+#[derive(Debug, Clone, Default)]
+pub struct NavSearch {
+    /// A fragment of the name
+    name: Option<CString>,
+    /// A fragment of the identifier
+    id: Option<CString>,
+    /// The types to find, combined into a bit field
+    types: XPLMNavType,
+    /// The latitude and longitude to search near
+    location: Option<(f32, f32)>,
+    /// The frequency, in the units X-Plane uses for the type
+    frequency: Option<c_int>,
+}
+
+impl NavSearch {
+    /// Creates a search that matches every entry
+    pub fn new() -> Self {
+        NavSearch::default()
+    }
+
+    /// Finds entries with names that contain the provided fragment
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the fragment contains a null byte.
+    pub fn name(self, fragment: &str) -> Self {
+        NavSearch {
+            name: Some(CString::new(fragment).expect("Name fragment contains a null byte")),
+            ..self
+        }
+    }
+
+    /// Finds entries with identifiers that contain the provided fragment
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the fragment contains a null byte.
+    pub fn id(self, fragment: &str) -> Self {
+        NavSearch {
+            id: Some(CString::new(fragment).expect("ID fragment contains a null byte")),
+            ..self
+        }
+    }
+
+    /// Adds a type of entry to find
+    pub fn nav_type(self, nav_type: NavType) -> Self {
+        NavSearch {
+            types: self.types | nav_type.to_xplm(),
+            ..self
+        }
+    }
+
+    /// Finds the matching entry nearest to a location
+    ///
+    /// Without a location, the first matching entry is found.
+    pub fn near(self, latitude: f32, longitude: f32) -> Self {
+        NavSearch {
+            location: Some((latitude, longitude)),
+            ..self
+        }
+    }
+
+    /// Finds entries with the provided frequency
+    ///
+    /// NDB frequencies are in kHz. Other frequencies are in units of 10 kHz.
+    pub fn frequency(self, frequency: i32) -> Self {
+        NavSearch {
+            frequency: Some(frequency),
+            ..self
+        }
+    }
+
+    /// Returns the types to find, with all types if none were added
+    fn type_mask(&self) -> XPLMNavType {
+        if self.types == 0 {
+            ALL_TYPES
+                .iter()
+                .fold(0, |mask, nav_type| mask | nav_type.to_xplm())
+        } else {
+            self.types
+        }
+    }
+
+    /// Returns a reference to the first or nearest matching entry
+    pub fn find_ref(&self) -> Option<NavRef> {
+        crate::debug_assert_main_thread!();
+        let (mut latitude, mut longitude) = self.location.unwrap_or_default();
+        let mut frequency = self.frequency.unwrap_or_default();
+        // Null pointers leave out the location and frequency
+        let (latitude_ptr, longitude_ptr): (*mut f32, *mut f32) = if self.location.is_some() {
+            (&mut latitude, &mut longitude)
+        } else {
+            (ptr::null_mut(), ptr::null_mut())
+        };
+        let frequency_ptr: *mut c_int = if self.frequency.is_some() {
+            &mut frequency
+        } else {
+            ptr::null_mut()
+        };
+        let nav_ref = unsafe {
+            xplm_sys::XPLMFindNavAid(
+                self.name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+                self.id.as_ref().map_or(ptr::null(), |id| id.as_ptr()),
+                latitude_ptr,
+                longitude_ptr,
+                frequency_ptr,
+                self.type_mask(),
+            )
+        };
+        NavRef::from_xplm(nav_ref)
+    }
+
+    /// Returns the first or nearest matching entry
+    pub fn find(&self) -> Option<NavAid> {
+        self.find_ref().and_then(NavRef::info)
+    }
+}
+
+/// All types of entries
+const ALL_TYPES: [NavType; 11] = [
+    NavType::Airport,
+    NavType::Ndb,
+    NavType::Vor,
+    NavType::Ils,
+    NavType::Localizer,
+    NavType::GlideSlope,
+    NavType::OuterMarker,
+    NavType::MiddleMarker,
+    NavType::InnerMarker,
+    NavType::Fix,
+    NavType::Dme,
+];
+
+/// Returns an iterator over all entries in the navigation database
+pub fn all_navaids() -> NavAids {
+    crate::debug_assert_main_thread!();
+    NavAids {
+        next: NavRef::from_xplm(unsafe { xplm_sys::XPLMGetFirstNavAid() }),
+        last: None,
+    }
+}
+
+/// Returns an iterator over all entries of one type in the navigation database
+pub fn navaids_of_type(nav_type: NavType) -> NavAids {
+    crate::debug_assert_main_thread!();
+    let first = unsafe { xplm_sys::XPLMFindFirstNavAidOfType(nav_type.to_xplm()) };
+    let last = unsafe { xplm_sys::XPLMFindLastNavAidOfType(nav_type.to_xplm()) };
+    NavAids {
+        next: NavRef::from_xplm(first),
+        last: NavRef::from_xplm(last),
+    }
+}
+
+/// An iterator over entries in the navigation database
+#[derive(Debug, Clone)]
+pub struct NavAids {
+    /// The next entry to return
+    next: Option<NavRef>,
+    /// The last entry to return, or None to continue to the end of the database
+    last: Option<NavRef>,
+}
+
+impl Iterator for NavAids {
+    type Item = NavRef;
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next?;
+        self.next = if Some(current) == self.last {
+            None
+        } else {
+            NavRef::from_xplm(unsafe { xplm_sys::XPLMGetNextNavAid(current.0) })
+        };
+        Some(current)
+    }
+}
+
+/// Returns the great-circle distance in meters between two locations
+///
+/// The latitudes and longitudes are in degrees. This uses the haversine formula with a
+/// spherical Earth.
+pub fn distance(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let (lat1, lat2) = (latitude1.to_radians(), latitude2.to_radians());
+    let half_dlat = (lat2 - lat1) / 2.0;
+    let half_dlon = (longitude2 - longitude1).to_radians() / 2.0;
+    let a = half_dlat.sin().powi(2) + lat1.cos() * lat2.cos() * half_dlon.sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
+
+/// Returns the initial true bearing in degrees, from 0 to 360, on the great circle from one
+/// location to another
+pub fn bearing(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let (lat1, lat2) = (latitude1.to_radians(), latitude2.to_radians());
+    let dlon = (longitude2 - longitude1).to_radians();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bearing, distance, NavSearch, NavType};
+
+    #[test]
+    fn test_distance_bearing() {
+        // One degree of latitude
+        let degree = distance(10.0, 20.0, 11.0, 20.0);
+        assert!((degree - 111_195.0).abs() < 1.0);
+        assert!((bearing(10.0, 20.0, 11.0, 20.0) - 0.0).abs() < 1e-9);
+        assert!((bearing(0.0, 20.0, 0.0, 19.0) - 270.0).abs() < 1e-9);
+        // Across the antimeridian
+        let short = distance(0.0, 179.5, 0.0, -179.5);
+        assert!((short - 111_195.0).abs() < 1.0);
+        assert_eq!(distance(45.0, 45.0, 45.0, 45.0), 0.0);
+    }
+
+    #[test]
+    fn test_type_mask() {
+        let all = NavSearch::new().type_mask();
+        assert_eq!(all, 0x7ff);
+        let search = NavSearch::new()
+            .nav_type(NavType::Vor)
+            .nav_type(NavType::Dme);
+        assert_eq!(
+            search.type_mask(),
+            NavType::Vor.to_xplm() | NavType::Dme.to_xplm()
+        );
+    }
+}
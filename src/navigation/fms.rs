@@ -0,0 +1,228 @@
+//! # Flight management system
+//!
+//! The X-Plane FMS stores a flight plan as a list of entries. Each entry is a navaid from the
+//! navigation database or a latitude and longitude, with an altitude. One entry is the
+//! destination, which the GPS and autopilot fly to, and one entry is displayed on the FMS.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::navigation::fms::{self, Waypoint};
+//! use xplm::navigation::{NavSearch, NavType};
+//!
+//! for index in 0..fms::entry_count() {
+//!     if let Some(entry) = fms::get_entry(index) {
+//!         xplm::debugln!("{}: {} at {} ft", index, entry.id, entry.altitude);
+//!     }
+//! }
+//!
+//! // Fly to Seattle at 3000 feet
+//! if let Some(seattle) = NavSearch::new().id("KSEA").nav_type(NavType::Airport).find_ref() {
+//!     fms::set_entry(0, Waypoint::NavAid(seattle), 3000);
+//!     fms::set_destination(0);
+//! }
+//! ```
+//!
+
+use std::os::raw::c_int;
+
+use xplm_sys::{self, XPLMNavRef, XPLMNavType};
+
+use super::super::ffi::StringBuffer;
+use super::{NavRef, NavType};
+
+/// The length of the ID buffer passed to XPLMGetFMSEntryInfo
+const ID_LENGTH: usize = 80;
+
+/// A place that an FMS entry refers to
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Waypoint {
+    /// An entry in the navigation database
+    NavAid(NavRef),
+    /// A latitude and longitude in degrees
+    LatLon {
+        /// The latitude in degrees
+        latitude: f32,
+        /// The longitude in degrees
+        longitude: f32,
+    },
+}
+
+/// An entry in the FMS flight plan
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct FmsEntry {
+    /// The place this entry refers to
+    pub waypoint: Waypoint,
+    /// The type of navaid, or None if this entry is a latitude and longitude
+    pub nav_type: Option<NavType>,
+    /// The identifier of the navaid, or an empty string for a latitude and longitude
+    pub id: String,
+    /// The altitude in feet
+    pub altitude: i32,
+    /// The latitude in degrees
+    pub latitude: f32,
+    /// The longitude in degrees
+    pub longitude: f32,
+}
+
+/// Returns the number of entries in the FMS
+pub fn entry_count() -> usize {
+    crate::debug_assert_main_thread!();
+    let count = unsafe { xplm_sys::XPLMCountFMSEntries() };
+    usize::try_from(count).unwrap_or(0)
+}
+
+/// Returns an entry in the FMS
+///
+/// Returns None if the index is out of range or the entry is empty.
+pub fn get_entry(index: usize) -> Option<FmsEntry> {
+    crate::debug_assert_main_thread!();
+    if index >= entry_count() {
+        return None;
+    }
+    let mut nav_type: XPLMNavType = 0;
+    let mut id = StringBuffer::new(ID_LENGTH);
+    let mut nav_ref: XPLMNavRef = 0;
+    let mut altitude: c_int = 0;
+    let mut latitude = 0.0;
+    let mut longitude = 0.0;
+    unsafe {
+        xplm_sys::XPLMGetFMSEntryInfo(
+            index as c_int,
+            &mut nav_type,
+            id.as_mut_ptr(),
+            &mut nav_ref,
+            &mut altitude,
+            &mut latitude,
+            &mut longitude,
+        );
+    }
+    decode_entry(
+        nav_type,
+        id.as_str().unwrap_or_default(),
+        nav_ref,
+        altitude,
+        latitude,
+        longitude,
+    )
+}
+
+/// Converts the values from XPLMGetFMSEntryInfo into an entry
+fn decode_entry(
+    nav_type: XPLMNavType,
+    id: &str,
+    nav_ref: XPLMNavRef,
+    altitude: c_int,
+    latitude: f32,
+    longitude: f32,
+) -> Option<FmsEntry> {
+    let (waypoint, nav_type) = if nav_type == xplm_sys::xplm_Nav_LatLon as XPLMNavType {
+        (
+            Waypoint::LatLon {
+                latitude,
+                longitude,
+            },
+            None,
+        )
+    } else {
+        // Empty entries have type xplm_Nav_Unknown, which is not a NavType
+        let nav_type = NavType::from_xplm(nav_type)?;
+        (
+            Waypoint::NavAid(NavRef::from_xplm(nav_ref)?),
+            Some(nav_type),
+        )
+    };
+    Some(FmsEntry {
+        waypoint,
+        nav_type,
+        id: id.to_owned(),
+        altitude,
+        latitude,
+        longitude,
+    })
+}
+
+/// Sets an entry in the FMS
+///
+/// The altitude is in feet.
+pub fn set_entry(index: usize, waypoint: Waypoint, altitude: i32) {
+    crate::debug_assert_main_thread!();
+    match waypoint {
+        Waypoint::NavAid(nav_ref) => unsafe {
+            xplm_sys::XPLMSetFMSEntryInfo(index as c_int, nav_ref.raw(), altitude)
+        },
+        Waypoint::LatLon {
+            latitude,
+            longitude,
+        } => unsafe {
+            xplm_sys::XPLMSetFMSEntryLatLon(index as c_int, latitude, longitude, altitude)
+        },
+    }
+}
+
+/// Clears an entry in the FMS
+pub fn clear_entry(index: usize) {
+    crate::debug_assert_main_thread!();
+    unsafe { xplm_sys::XPLMClearFMSEntry(index as c_int) }
+}
+
+/// Returns the index of the entry that the FMS displays
+pub fn displayed_entry() -> usize {
+    crate::debug_assert_main_thread!();
+    let index = unsafe { xplm_sys::XPLMGetDisplayedFMSEntry() };
+    usize::try_from(index).unwrap_or(0)
+}
+
+/// Sets the entry that the FMS displays
+pub fn set_displayed_entry(index: usize) {
+    crate::debug_assert_main_thread!();
+    unsafe { xplm_sys::XPLMSetDisplayedFMSEntry(index as c_int) }
+}
+
+/// Returns the index of the entry that the aircraft is flying to
+pub fn destination() -> usize {
+    crate::debug_assert_main_thread!();
+    let index = unsafe { xplm_sys::XPLMGetDestinationFMSEntry() };
+    usize::try_from(index).unwrap_or(0)
+}
+
+/// Sets the entry that the aircraft flies to
+pub fn set_destination(index: usize) {
+    crate::debug_assert_main_thread!();
+    unsafe { xplm_sys::XPLMSetDestinationFMSEntry(index as c_int) }
+}
+
+/// Returns the navaid that the GPS is flying to, if it is flying to a navaid
+pub fn gps_destination() -> Option<NavRef> {
+    crate::debug_assert_main_thread!();
+    NavRef::from_xplm(unsafe { xplm_sys::XPLMGetGPSDestination() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_entry, Waypoint};
+    use crate::navigation::NavType;
+
+    #[test]
+    fn test_decode_entry() {
+        let vor =
+            decode_entry(xplm_sys::xplm_Nav_VOR as i32, "SEA", 42, 5000, 47.4, -122.3).unwrap();
+        assert_eq!(vor.nav_type, Some(NavType::Vor));
+        assert!(matches!(vor.waypoint, Waypoint::NavAid(nav_ref) if nav_ref.raw() == 42));
+        assert_eq!(vor.altitude, 5000);
+
+        let lat_lon =
+            decode_entry(xplm_sys::xplm_Nav_LatLon as i32, "", -1, 0, 10.0, 20.0).unwrap();
+        assert_eq!(lat_lon.nav_type, None);
+        assert_eq!(
+            lat_lon.waypoint,
+            Waypoint::LatLon {
+                latitude: 10.0,
+                longitude: 20.0
+            }
+        );
+
+        assert!(decode_entry(xplm_sys::xplm_Nav_Unknown as i32, "", -1, 0, 0.0, 0.0).is_none());
+    }
+}
@@ -0,0 +1,215 @@
+//! # Nearest navaids
+//!
+//! Head-up displays and map overlays often show the nearest VOR or airport. Searching the
+//! navigation database in every draw callback is too slow, so a [`NearestNavaidTracker`]
+//! searches from a flight loop at a configurable rate and stores the results. Draw callbacks
+//! can then read the results cheaply.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::draw::{Draw, Phase};
+//! use xplm::navigation::nearest::NearestNavaidTracker;
+//! use xplm::navigation::NavType;
+//!
+//! // Search twice per second
+//! let tracker = NearestNavaidTracker::new(&[NavType::Vor, NavType::Airport], 2.0).unwrap();
+//! let draw = Draw::new(Phase::AfterWindows, move || {
+//!     if let Some(vor) = tracker.nearest(NavType::Vor) {
+//!         let _label = format!("{} {:.0} NM", vor.navaid.id, vor.distance / 1852.0);
+//!         // Draw the label
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::super::data::borrowed::{DataRef, FindError};
+use super::super::data::DataRead;
+use super::super::flight_loop::{FlightLoop, LoopState};
+use super::{NavAid, NavType};
+
+/// A navaid near the user aircraft
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Nearby {
+    /// The navaid
+    pub navaid: NavAid,
+    /// The distance in meters from the aircraft to the navaid
+    pub distance: f64,
+    /// The initial true bearing in degrees from the aircraft to the navaid
+    pub bearing: f64,
+}
+
+/// Finds the navaids nearest to the user aircraft at a fixed rate
+///
+/// The search stops when this object is dropped.
+pub struct NearestNavaidTracker {
+    /// The positions and results, shared with the flight loop
+    shared: Rc<RefCell<Shared>>,
+    /// The flight loop that searches the database
+    _flight_loop: FlightLoop,
+}
+
+impl NearestNavaidTracker {
+    /// Creates a tracker that searches for the provided types rate times per second
+    ///
+    /// The first search happens in the next flight loop.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if rate is not greater than zero.
+    pub fn new(nav_types: &[NavType], rate: f32) -> Result<Self, FindError> {
+        let shared = Rc::new(RefCell::new(Shared {
+            latitude: DataRef::find("sim/flightmodel/position/latitude")?,
+            longitude: DataRef::find("sim/flightmodel/position/longitude")?,
+            period: period(rate),
+            nearest: nav_types.iter().map(|&nav_type| (nav_type, None)).collect(),
+        }));
+        let loop_shared = Rc::clone(&shared);
+        // The interval requested at the end of the previous search
+        let mut requested = Duration::ZERO;
+        let mut flight_loop = FlightLoop::new(move |state: &mut LoopState| {
+            let mut shared = loop_shared.borrow_mut();
+            shared.update();
+            requested = next_interval(shared.period, requested, state.since_last_call());
+            if requested.is_zero() {
+                state.call_next_loop();
+            } else {
+                state.call_after(requested);
+            }
+        });
+        flight_loop.schedule_immediate();
+        Ok(NearestNavaidTracker {
+            shared,
+            _flight_loop: flight_loop,
+        })
+    }
+
+    /// Sets the number of searches per second
+    ///
+    /// The new rate is used after the next search.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if rate is not greater than zero.
+    pub fn set_rate(&self, rate: f32) {
+        self.shared.borrow_mut().period = period(rate);
+    }
+
+    /// Searches for the nearest navaids now
+    pub fn update(&self) {
+        self.shared.borrow_mut().update();
+    }
+
+    /// Returns the nearest navaid of the provided type found in the latest search
+    ///
+    /// Returns None if no search has completed, the type is not tracked, or the database has
+    /// no navaids of the type.
+    pub fn nearest(&self, nav_type: NavType) -> Option<Nearby> {
+        self.shared
+            .borrow()
+            .nearest
+            .iter()
+            .find(|(tracked, _)| *tracked == nav_type)
+            .and_then(|(_, nearby)| nearby.clone())
+    }
+}
+
+impl fmt::Debug for NearestNavaidTracker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("NearestNavaidTracker")
+            .field("period", &shared.period)
+            .field("nearest", &shared.nearest)
+            .finish()
+    }
+}
+
+/// Converts a rate in searches per second into the time between searches
+///
+/// Rates too small to represent as a duration are treated as the longest possible period.
+fn period(rate: f32) -> Duration {
+    // This also rejects NaN
+    assert!(rate > 0.0, "Search rate must be greater than zero");
+    Duration::try_from_secs_f32(1.0 / rate).unwrap_or(Duration::MAX)
+}
+
+/// Returns the interval until the next search
+///
+/// The callback usually runs a little later than requested. Subtracting that lateness from
+/// the period schedules each search from the previous deadline instead of from the time the
+/// callback actually ran, so the searches do not drift. If the callback is more than one
+/// period late, this returns zero and the next search happens in the next flight loop.
+fn next_interval(period: Duration, requested: Duration, elapsed: Duration) -> Duration {
+    let late = elapsed.saturating_sub(requested);
+    period.saturating_sub(late)
+}
+
+/// The aircraft position datarefs and the search results
+struct Shared {
+    latitude: DataRef<f64>,
+    longitude: DataRef<f64>,
+    /// The time between searches
+    period: Duration,
+    /// Each tracked type and the nearest navaid of that type
+    nearest: Vec<(NavType, Option<Nearby>)>,
+}
+
+impl Shared {
+    fn update(&mut self) {
+        let latitude = self.latitude.get();
+        let longitude = self.longitude.get();
+        for (nav_type, nearby) in self.nearest.iter_mut() {
+            *nearby =
+                NavAid::find_nearest(*nav_type, latitude as f32, longitude as f32).map(|navaid| {
+                    Nearby {
+                        distance: navaid.distance_from(latitude, longitude),
+                        bearing: navaid.bearing_from(latitude, longitude),
+                        navaid,
+                    }
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{next_interval, period};
+
+    #[test]
+    fn test_period() {
+        assert_eq!(period(4.0), Duration::from_millis(250));
+        assert_eq!(period(f32::MIN_POSITIVE), Duration::MAX);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_period_nan() {
+        period(f32::NAN);
+    }
+
+    #[test]
+    fn test_next_interval() {
+        let period = Duration::from_millis(500);
+        // On time
+        assert_eq!(next_interval(period, period, period), period);
+        // 20 ms late
+        assert_eq!(
+            next_interval(period, period, Duration::from_millis(520)),
+            Duration::from_millis(480)
+        );
+        // More than one period late
+        assert_eq!(
+            next_interval(period, period, Duration::from_secs(2)),
+            Duration::ZERO
+        );
+    }
+}
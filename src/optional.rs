@@ -0,0 +1,116 @@
+//! # Optional SDK functions
+//!
+//! Some SDK functions are only available in newer versions of X-Plane. A plugin that links
+//! to them directly cannot load in older versions. The functions in this module look up
+//! these SDK functions at runtime with [`symbol`](crate::symbol) and return None if they are
+//! not available.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::optional;
+//!
+//! match optional::count_data_refs() {
+//!     Some(count_data_refs) => {
+//!         let count = unsafe { count_data_refs() };
+//!         xplm::debugln!("{} datarefs", count);
+//!     }
+//!     None => xplm::debugln!("Dataref enumeration requires X-Plane 12"),
+//! }
+//! ```
+//!
+
+use std::os::raw::{c_char, c_int};
+
+use xplm_sys::{
+    XPLMCommandRef, XPLMDataRef, XPLMDataTypeID, XPLMMenuID, XPLMPluginID, XPLMWindowID,
+};
+
+use super::symbol;
+
+/// Function pointer types that can be returned by [`symbol`](crate::symbol)
+///
+/// This is implemented for `extern "C"` function pointers with up to 8 arguments.
+///
+/// # Safety
+///
+/// Implementations must be function pointer types with the same size as a data pointer.
+pub unsafe trait FnPtr: Copy {}
+
+macro_rules! fn_ptr {
+    ($($arg:ident),*) => {
+        unsafe impl<R, $($arg),*> FnPtr for extern "C" fn($($arg),*) -> R {}
+        unsafe impl<R, $($arg),*> FnPtr for unsafe extern "C" fn($($arg),*) -> R {}
+    };
+}
+
+fn_ptr!();
+fn_ptr!(A);
+fn_ptr!(A, B);
+fn_ptr!(A, B, C);
+fn_ptr!(A, B, C, D);
+fn_ptr!(A, B, C, D, E);
+fn_ptr!(A, B, C, D, E, F);
+fn_ptr!(A, B, C, D, E, F, G);
+fn_ptr!(A, B, C, D, E, F, G, H);
+
+/// The signature of `XPLMCountDataRefs`
+pub type CountDataRefsFn = unsafe extern "C" fn() -> c_int;
+/// The signature of `XPLMGetDataRefsByIndex`
+pub type GetDataRefsByIndexFn = unsafe extern "C" fn(c_int, c_int, *mut XPLMDataRef);
+/// The signature of `XPLMGetDataRefInfo`
+pub type GetDataRefInfoFn = unsafe extern "C" fn(XPLMDataRef, *mut RawDataRefInfo);
+/// The signature of `XPLMSetWindowTitle`
+pub type SetWindowTitleFn = unsafe extern "C" fn(XPLMWindowID, *const c_char);
+/// The signature of `XPLMWindowIsPoppedOut`
+pub type WindowIsPoppedOutFn = unsafe extern "C" fn(XPLMWindowID) -> c_int;
+/// The signature of `XPLMAppendMenuItemWithCommand`
+pub type AppendMenuItemWithCommandFn =
+    unsafe extern "C" fn(XPLMMenuID, *const c_char, XPLMCommandRef) -> c_int;
+
+/// Returns `XPLMCountDataRefs`, which is available in X-Plane 12 and later
+pub fn count_data_refs() -> Option<CountDataRefsFn> {
+    unsafe { symbol("XPLMCountDataRefs") }
+}
+
+/// Returns `XPLMGetDataRefsByIndex`, which is available in X-Plane 12 and later
+pub fn get_data_refs_by_index() -> Option<GetDataRefsByIndexFn> {
+    unsafe { symbol("XPLMGetDataRefsByIndex") }
+}
+
+/// Returns `XPLMGetDataRefInfo`, which is available in X-Plane 12 and later
+pub fn get_data_ref_info() -> Option<GetDataRefInfoFn> {
+    unsafe { symbol("XPLMGetDataRefInfo") }
+}
+
+/// Returns `XPLMSetWindowTitle`, which is available in X-Plane 11 and later
+pub fn set_window_title() -> Option<SetWindowTitleFn> {
+    unsafe { symbol("XPLMSetWindowTitle") }
+}
+
+/// Returns `XPLMWindowIsPoppedOut`, which is available in X-Plane 11 and later
+pub fn window_is_popped_out() -> Option<WindowIsPoppedOutFn> {
+    unsafe { symbol("XPLMWindowIsPoppedOut") }
+}
+
+/// Returns `XPLMAppendMenuItemWithCommand`, which is available in X-Plane 11 and later
+pub fn append_menu_item_with_command() -> Option<AppendMenuItemWithCommandFn> {
+    unsafe { symbol("XPLMAppendMenuItemWithCommand") }
+}
+
+/// The `XPLMDataRefInfo_t` structure that `XPLMGetDataRefInfo` fills in
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawDataRefInfo {
+    /// The size of this structure in bytes, which must be set before calling
+    /// `XPLMGetDataRefInfo`
+    pub struct_size: c_int,
+    /// The name of the dataref, owned by X-Plane
+    pub name: *const c_char,
+    /// The types that the dataref supports
+    pub data_type: XPLMDataTypeID,
+    /// Nonzero if the dataref can be written
+    pub writable: c_int,
+    /// The plugin that created the dataref
+    pub owner: XPLMPluginID,
+}
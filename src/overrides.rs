@@ -0,0 +1,256 @@
+//! # Overriding X-Plane systems
+//!
+//! Plugins can take over parts of the simulation, such as the joystick inputs, throttles, or
+//! the flight path of an AI aircraft, by setting override datarefs. X-Plane then stops
+//! updating the related datarefs so that the plugin can write them.
+//!
+//! An [`OverrideGuard`] sets an override when it is created and clears it when it is dropped.
+//! Storing the guard in the plugin, and dropping it when the plugin is disabled, makes sure
+//! that an override does not stay engaged after the plugin stops controlling the system.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::overrides::Override;
+//!
+//! let throttles = Override::Throttles.engage().unwrap();
+//! // Write sim/flightmodel/engine/ENGN_thro_use
+//!
+//! // Dropping the guard gives control back to X-Plane
+//! drop(throttles);
+//!
+//! // Control the flight path of the first AI aircraft
+//! let path = Override::engage_plane_path(1).unwrap();
+//! ```
+//!
+
+use std::fmt;
+
+use super::data::borrowed::{DataRef, FindError};
+use super::data::{ArrayRead, ArrayReadWrite, DataRead, DataReadWrite, ReadWrite};
+
+/// Standard X-Plane overrides
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Override {
+    /// All joystick axes
+    Joystick,
+    /// The joystick yaw axis
+    JoystickHeading,
+    /// The joystick pitch axis
+    JoystickPitch,
+    /// The joystick roll axis
+    JoystickRoll,
+    /// The throttles
+    Throttles,
+    /// The propeller pitch
+    PropPitch,
+    /// The mixture controls
+    Mixture,
+    /// The toe brakes
+    ToeBrakes,
+    /// Nosewheel steering
+    WheelSteer,
+    /// The landing gear and brakes
+    GearBrake,
+    /// The control surface deflections
+    ControlSurfaces,
+    /// The autopilot
+    Autopilot,
+    /// The flight director
+    FlightDirector,
+    /// The navigation needles
+    NavNeedles,
+    /// The engine model
+    Engines,
+    /// The fuel flow
+    FuelFlow,
+    /// The traffic collision avoidance system targets
+    Tcas,
+}
+
+impl Override {
+    /// Returns the name of the dataref that controls this override
+    pub fn dataref_name(self) -> &'static str {
+        match self {
+            Override::Joystick => "sim/operation/override/override_joystick",
+            Override::JoystickHeading => "sim/operation/override/override_joystick_heading",
+            Override::JoystickPitch => "sim/operation/override/override_joystick_pitch",
+            Override::JoystickRoll => "sim/operation/override/override_joystick_roll",
+            Override::Throttles => "sim/operation/override/override_throttles",
+            Override::PropPitch => "sim/operation/override/override_prop_pitch",
+            Override::Mixture => "sim/operation/override/override_mixture",
+            Override::ToeBrakes => "sim/operation/override/override_toe_brakes",
+            Override::WheelSteer => "sim/operation/override/override_wheel_steer",
+            Override::GearBrake => "sim/operation/override/override_gearbrake",
+            Override::ControlSurfaces => "sim/operation/override/override_control_surfaces",
+            Override::Autopilot => "sim/operation/override/override_autopilot",
+            Override::FlightDirector => "sim/operation/override/override_flightdir",
+            Override::NavNeedles => "sim/operation/override/override_navneedles",
+            Override::Engines => "sim/operation/override/override_engines",
+            Override::FuelFlow => "sim/operation/override/override_fuel_flow",
+            Override::Tcas => "sim/operation/override/override_TCAS",
+        }
+    }
+
+    /// Returns true if this override is currently set, by this or any other plugin
+    pub fn is_engaged(self) -> Result<bool, FindError> {
+        let dataref: DataRef<i32> = DataRef::find(self.dataref_name())?;
+        Ok(dataref.get() != 0)
+    }
+
+    /// Sets this override and returns a guard that clears it when dropped
+    pub fn engage(self) -> Result<OverrideGuard, OverrideError> {
+        let dataref: DataRef<i32, ReadWrite> = DataRef::find(self.dataref_name())?.writeable()?;
+        Ok(OverrideGuard::new(
+            Box::new(dataref),
+            self.dataref_name(),
+            None,
+        ))
+    }
+
+    /// Takes control of the flight path of an aircraft and returns a guard that gives control
+    /// back to X-Plane when dropped
+    ///
+    /// The user aircraft has index 0.
+    pub fn engage_plane_path(plane: usize) -> Result<OverrideGuard, OverrideError> {
+        Override::engage_element(PLANE_PATH, plane)
+    }
+
+    /// Takes control of the autopilot of an AI aircraft and returns a guard that gives control
+    /// back to X-Plane when dropped
+    ///
+    /// AI aircraft have indexes starting at 1. This returns an error if plane is 0, the user
+    /// aircraft, which is not flown by the AI.
+    pub fn engage_ai_autopilot(plane: usize) -> Result<OverrideGuard, OverrideError> {
+        if plane == 0 {
+            return Err(OverrideError::UserPlane);
+        }
+        Override::engage_element(AI_AUTOPILOT, plane)
+    }
+
+    /// Sets one element of an array override
+    fn engage_element(name: &'static str, plane: usize) -> Result<OverrideGuard, OverrideError> {
+        let dataref: DataRef<[i32], ReadWrite> = DataRef::find(name)?.writeable()?;
+        if plane >= dataref.len() {
+            return Err(OverrideError::NoPlane(plane));
+        }
+        Ok(OverrideGuard::new(
+            Box::new((dataref, plane)),
+            name,
+            Some(plane),
+        ))
+    }
+}
+
+/// The per-aircraft flight path override
+const PLANE_PATH: &str = "sim/operation/override/override_planepath";
+/// The per-aircraft AI autopilot override
+const AI_AUTOPILOT: &str = "sim/operation/override/override_plane_ai_autopilot";
+
+/// Keeps an override set until it is dropped
+///
+/// If more than one guard sets the same override, the override is cleared when the first of
+/// them is dropped.
+pub struct OverrideGuard {
+    target: Box<dyn Target>,
+    /// The dataref name, for debugging
+    name: &'static str,
+    /// The array index, for debugging
+    index: Option<usize>,
+}
+
+/// The dataref or array element that an override guard controls
+trait Target {
+    fn set_engaged(&mut self, engaged: bool);
+}
+
+impl Target for DataRef<i32, ReadWrite> {
+    fn set_engaged(&mut self, engaged: bool) {
+        self.set(i32::from(engaged));
+    }
+}
+
+impl Target for (DataRef<[i32], ReadWrite>, usize) {
+    fn set_engaged(&mut self, engaged: bool) {
+        self.0.set_element(self.1, i32::from(engaged));
+    }
+}
+
+impl OverrideGuard {
+    /// Sets an override and returns a guard that clears it
+    fn new(mut target: Box<dyn Target>, name: &'static str, index: Option<usize>) -> Self {
+        target.set_engaged(true);
+        OverrideGuard {
+            target,
+            name,
+            index,
+        }
+    }
+
+    /// Returns the name of the override dataref
+    pub fn dataref_name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl Drop for OverrideGuard {
+    fn drop(&mut self) {
+        self.target.set_engaged(false);
+    }
+}
+
+impl fmt::Debug for OverrideGuard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("OverrideGuard");
+        debug.field("dataref", &self.name);
+        if let Some(index) = self.index {
+            debug.field("index", &index);
+        }
+        debug.finish()
+    }
+}
+
+/// Errors that can occur when setting an override
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum OverrideError {
+    /// The override dataref could not be found or written
+    #[error(transparent)]
+    Find(#[from] FindError),
+    /// The aircraft index is larger than X-Plane supports
+    #[error("No aircraft with index {0}")]
+    NoPlane(usize),
+    /// The user aircraft was provided where an AI aircraft is required
+    #[error("The user aircraft is not flown by the AI")]
+    UserPlane,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::{OverrideGuard, Target};
+
+    struct FakeTarget(Rc<Cell<bool>>);
+
+    impl Target for FakeTarget {
+        fn set_engaged(&mut self, engaged: bool) {
+            self.0.set(engaged);
+        }
+    }
+
+    #[test]
+    fn test_guard_released_on_drop() {
+        let engaged = Rc::new(Cell::new(false));
+        let guard = OverrideGuard::new(
+            Box::new(FakeTarget(Rc::clone(&engaged))),
+            "override",
+            Some(3),
+        );
+        assert!(engaged.get());
+        drop(guard);
+        assert!(!engaged.get());
+    }
+}
@@ -0,0 +1,303 @@
+//! # Panics in callbacks
+//!
+//! A panic must not unwind out of a function that X-Plane calls, because X-Plane is not
+//! written in Rust. This library catches panics in every callback that runs plugin code,
+//! including window, menu, command, flight loop, draw, widget, and dataref callbacks. The
+//! panic message is written to Log.txt, and the callback returns a value that lets X-Plane
+//! continue normally, such as passing a mouse click or command on to other plugins.
+//!
+//! A callback that keeps panicking can fill Log.txt with messages every frame. With
+//! [`set_disable_limit`], a callback is disabled after it has panicked a number of times.
+//! A disabled callback is not called again until the object that it belongs to is dropped.
+//! Callbacks are never disabled by default.
+//!
+//! # Panic hook
+//!
+//! When the plugin starts, the [`xplane_plugin!`](crate::xplane_plugin) macro installs a panic
+//! hook that writes the thread, source location, and message of every panic to Log.txt,
+//! including panics on other threads. A backtrace is added if [`set_backtraces`] has enabled
+//! them or the `RUST_BACKTRACE` environment variable is set. With [`set_crash_report_path`],
+//! the same information is also appended to a file, which users can send with bug reports.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::panics;
+//!
+//! // Stop calling any callback that has panicked three times
+//! panics::set_disable_limit(Some(3));
+//! // Write panics with backtraces to a file in the plugin folder
+//! panics::set_backtraces(true);
+//! panics::set_crash_report_path(Some(xplm::paths::plugin_folder().join("crash.txt")));
+//! // When the plugin stops
+//! if panics::panic_count() != 0 {
+//!     xplm::debugln!("{} panics in callbacks", panics::panic_count());
+//! }
+//! ```
+//!
+
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::debugln;
+
+/// The file that panics are appended to, if any
+static CRASH_REPORT_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// If backtraces are always captured
+static BACKTRACES: AtomicBool = AtomicBool::new(false);
+/// If the panic hook has been installed
+static HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+struct State {
+    /// The number of panics after which a callback is disabled
+    limit: Option<u32>,
+    /// The number of panics in the callbacks for each object address
+    counts: HashMap<usize, u32>,
+    /// The total number of panics caught
+    total: u64,
+}
+
+impl State {
+    /// Returns true if callbacks for an address have been disabled
+    fn is_disabled(&self, address: usize) -> bool {
+        match (self.limit, self.counts.get(&address)) {
+            (Some(limit), Some(count)) => *count >= limit,
+            _ => false,
+        }
+    }
+
+    /// Records a panic in a callback for an address and returns the number of panics for that
+    /// address
+    fn record(&mut self, address: usize) -> u32 {
+        self.total = self.total.saturating_add(1);
+        let count = self.counts.entry(address).or_insert(0);
+        *count = count.saturating_add(1);
+        *count
+    }
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Sets the number of times a callback can panic before it is disabled
+///
+/// With None, callbacks are never disabled. This is the default.
+pub fn set_disable_limit(limit: Option<u32>) {
+    STATE.with(|state| state.borrow_mut().limit = limit);
+}
+
+/// Returns the number of times a callback can panic before it is disabled
+pub fn disable_limit() -> Option<u32> {
+    STATE.with(|state| state.borrow().limit)
+}
+
+/// Returns the total number of panics that have been caught in callbacks
+pub fn panic_count() -> u64 {
+    STATE.with(|state| state.borrow().total)
+}
+
+/// Enables or disables backtraces in panic reports
+///
+/// When disabled, backtraces are only captured if the `RUST_BACKTRACE` environment variable
+/// is set. This is the default.
+pub fn set_backtraces(enabled: bool) {
+    BACKTRACES.store(enabled, Ordering::Relaxed);
+}
+
+/// Sets the file that panic reports are appended to
+///
+/// With None, panics are only written to Log.txt. This is the default.
+pub fn set_crash_report_path(path: Option<PathBuf>) {
+    *CRASH_REPORT_PATH.lock().unwrap_or_else(|e| e.into_inner()) = path;
+}
+
+/// Installs the panic hook
+///
+/// This is called from XPluginStart. The hook is only installed once, even if the plugin is
+/// reloaded.
+pub(crate) fn install_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        panic::set_hook(Box::new(panic_hook));
+        HOOK_INSTALLED.store(true, Ordering::Relaxed);
+    });
+}
+
+/// Writes a report of a panic to the log and the crash report file
+fn panic_hook(info: &PanicHookInfo) {
+    let backtrace = if BACKTRACES.load(Ordering::Relaxed) {
+        Backtrace::force_capture()
+    } else {
+        Backtrace::capture()
+    };
+    let location = info.location().map(ToString::to_string);
+    let report = format_report(
+        thread::current().name(),
+        location.as_deref(),
+        panic_message(info.payload()),
+        &backtrace,
+    );
+    let path = CRASH_REPORT_PATH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(path) = path {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| write!(file, "Time: {}\n{}\n", seconds, report));
+        if let Err(e) = written {
+            super::log::write(format!(
+                "[xplm] Failed to write crash report to {}: {}\n",
+                path.display(),
+                e
+            ));
+        }
+    }
+    super::log::write(report);
+}
+
+/// Formats a panic report
+fn format_report(
+    thread: Option<&str>,
+    location: Option<&str>,
+    message: &str,
+    backtrace: &Backtrace,
+) -> String {
+    let mut report = format!(
+        "[xplm] Thread '{}' panicked at {}: {}\n",
+        thread.unwrap_or("<unnamed>"),
+        location.unwrap_or("<unknown>"),
+        message
+    );
+    if backtrace.status() == BacktraceStatus::Captured {
+        report.push_str(&format!("{}\n", backtrace));
+    }
+    report
+}
+
+/// Runs the code of a callback and catches any panic
+///
+/// kind describes the callback in log messages. address is the address of the object that
+/// the callback belongs to, which is usually the refcon. If the callback panics or has been
+/// disabled, this returns default.
+pub(crate) fn contain<T: ?Sized, R>(
+    kind: &'static str,
+    address: *const T,
+    default: R,
+    f: impl FnOnce() -> R,
+) -> R {
+    let address = address.cast::<()>() as usize;
+    if STATE.with(|state| state.borrow().is_disabled(address)) {
+        return default;
+    }
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            // The panic hook has already written the message and location
+            if HOOK_INSTALLED.load(Ordering::Relaxed) {
+                debugln!("[xplm] Caught panic in {} callback", kind);
+            } else {
+                debugln!(
+                    "[xplm] Panic in {} callback: {}",
+                    kind,
+                    panic_message(payload.as_ref())
+                );
+            }
+            let disabled = STATE.with(|state| {
+                let mut state = state.borrow_mut();
+                let count = state.record(address);
+                state.limit.filter(|limit| count == *limit)
+            });
+            if let Some(limit) = disabled {
+                debugln!("[xplm] Disabling {} callback after {} panics", kind, limit);
+            }
+            default
+        }
+    }
+}
+
+/// Forgets the panics for an object that is being dropped
+///
+/// This allows another object at the same address to run its callbacks.
+pub(crate) fn forget<T: ?Sized>(address: *const T) {
+    let address = address.cast::<()>() as usize;
+    // The state may already be destroyed if this is called when the thread exits
+    let _ = STATE.try_with(|state| state.borrow_mut().counts.remove(&address));
+}
+
+/// Returns the message of a panic
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "unknown"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_report, panic_message, State};
+    use std::backtrace::Backtrace;
+
+    #[test]
+    fn test_panic_message() {
+        let payload = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "static");
+        let payload = std::panic::catch_unwind(|| panic!("value {}", 3)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "value 3");
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(3)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "unknown");
+    }
+
+    #[test]
+    fn test_format_report() {
+        let report = format_report(
+            Some("main"),
+            Some("src/lib.rs:10:5"),
+            "oops",
+            &Backtrace::disabled(),
+        );
+        assert_eq!(
+            report,
+            "[xplm] Thread 'main' panicked at src/lib.rs:10:5: oops\n"
+        );
+        let report = format_report(None, None, "oops", &Backtrace::disabled());
+        assert_eq!(
+            report,
+            "[xplm] Thread '<unnamed>' panicked at <unknown>: oops\n"
+        );
+    }
+
+    #[test]
+    fn test_disable() {
+        let mut state = State::default();
+        assert_eq!(state.record(8), 1);
+        assert_eq!(state.record(8), 2);
+        assert!(!state.is_disabled(8));
+        state.limit = Some(2);
+        assert!(state.is_disabled(8));
+        assert!(!state.is_disabled(16));
+        assert_eq!(state.record(16), 1);
+        assert_eq!(state.total, 3);
+    }
+}
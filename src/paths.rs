@@ -1,9 +1,113 @@
-use super::feature;
+//! # X-Plane and plugin folders
+//!
+//! These functions return paths in the native format of the operating system. This crate
+//! enables native paths when the plugin starts.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::paths;
+//!
+//! // Resources installed next to the plugin
+//! let sounds = paths::plugin_folder().join("sounds");
+//! // A file in the X-Plane output folder
+//! let log = paths::xplane_system_path().join("Output").join("my_plugin.log");
+//! ```
+//!
+
+use std::ffi::CStr;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use super::feature::KnownFeature;
+use super::ffi::StringBuffer;
+use super::plugin::management::this_plugin;
 
 /// Enables native paths
-pub fn path_init() {
+pub(crate) fn path_init() {
     // Feature specified to exist in SDK 2.1
-    let native_path_feature =
-        feature::find_feature("XPLM_USE_NATIVE_PATHS").expect("No native paths feature");
-    native_path_feature.set_enabled(true);
+    KnownFeature::NativePaths
+        .set_enabled(true)
+        .expect("No native paths feature");
+}
+
+/// Returns the folder that contains X-Plane
+pub fn xplane_system_path() -> PathBuf {
+    crate::debug_assert_main_thread!();
+    let mut buffer = StringBuffer::new(512);
+    unsafe { xplm_sys::XPLMGetSystemPath(buffer.as_mut_ptr()) };
+    PathBuf::from(buffer.as_str().unwrap_or_default())
+}
+
+/// Returns the path of the X-Plane preferences file
+pub fn preferences_path() -> PathBuf {
+    crate::debug_assert_main_thread!();
+    let mut buffer = StringBuffer::new(512);
+    unsafe { xplm_sys::XPLMGetPrefsPath(buffer.as_mut_ptr()) };
+    PathBuf::from(buffer.as_str().unwrap_or_default())
+}
+
+/// Returns the folder that contains the X-Plane preferences files
+///
+/// Plugins can store their own preferences files in this folder.
+pub fn preferences_folder() -> PathBuf {
+    let prefs_file = preferences_path();
+    prefs_file.parent().map(Path::to_owned).unwrap_or_default()
+}
+
+/// Returns the path of this plugin's binary file, for example
+/// `Resources/plugins/MyPlugin/64/lin.xpl`
+pub fn plugin_path() -> PathBuf {
+    this_plugin().path()
+}
+
+/// Returns the folder that contains this plugin, for example `Resources/plugins/MyPlugin`
+///
+/// If the binary file is in a platform-specific folder such as `64` or `lin_x64`, this
+/// returns the folder above it.
+pub fn plugin_folder() -> PathBuf {
+    plugin_folder_of(&plugin_path())
+}
+
+/// Returns the character that X-Plane uses to separate path components
+pub fn separator() -> char {
+    crate::debug_assert_main_thread!();
+    let separator = unsafe { CStr::from_ptr(xplm_sys::XPLMGetDirectorySeparator()) };
+    separator
+        .to_str()
+        .ok()
+        .and_then(|separator| separator.chars().next())
+        .unwrap_or(std::path::MAIN_SEPARATOR)
+}
+
+/// Returns the plugin folder for a plugin binary path
+fn plugin_folder_of(binary: &Path) -> PathBuf {
+    let Some(folder) = binary.parent() else {
+        return PathBuf::new();
+    };
+    let platform_folders = ["32", "64", "win_x64", "mac_x64", "lin_x64", "lin_arm64"];
+    match folder.file_name().and_then(OsStr::to_str) {
+        Some(name) if platform_folders.contains(&name) => {
+            folder.parent().unwrap_or(folder).to_owned()
+        }
+        _ => folder.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plugin_folder_of;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_plugin_folder() {
+        let folder = PathBuf::from("Resources").join("plugins").join("MyPlugin");
+        assert_eq!(plugin_folder_of(&folder.join("64").join("lin.xpl")), folder);
+        assert_eq!(
+            plugin_folder_of(&folder.join("lin_x64").join("MyPlugin.xpl")),
+            folder
+        );
+        assert_eq!(plugin_folder_of(&folder.join("MyPlugin.xpl")), folder);
+        assert_eq!(plugin_folder_of(Path::new("")), PathBuf::new());
+    }
 }
@@ -0,0 +1,240 @@
+//! # The user's aircraft
+//!
+//! A [`UserAircraft`] provides commonly used information about the user's aircraft without
+//! requiring plugins to know the dataref names. Datarefs are found the first time they are
+//! needed.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::planes;
+//!
+//! let aircraft = planes::user_aircraft();
+//! if let Some(agl) = aircraft.agl() {
+//!     if agl < 30.0 {
+//!         xplm::debugln!("[myplugin] Thirty meters");
+//!     }
+//! }
+//! if let (Some(position), Some(attitude)) = (aircraft.position(), aircraft.attitude()) {
+//!     xplm::debugln!(
+//!         "[myplugin] {:.4}, {:.4} heading {:.0}",
+//!         position.latitude,
+//!         position.longitude,
+//!         attitude.heading
+//!     );
+//! }
+//! ```
+//!
+
+use std::cell::{Cell, RefCell};
+
+use super::data::borrowed::DataRef;
+use super::data::lazy::LazyDataRef;
+use super::data::{ArrayRead, DataRead, DataType};
+use super::scenery::TerrainProbe;
+
+/// Returns an object that provides information about the user's aircraft
+pub fn user_aircraft() -> UserAircraft {
+    UserAircraft::new()
+}
+
+/// Information about the user's aircraft
+///
+/// Each function returns None if X-Plane does not provide the information.
+#[derive(Debug)]
+pub struct UserAircraft {
+    agl: LazyDataRef<f32>,
+    local_x: LazyDataRef<f64>,
+    local_y: LazyDataRef<f64>,
+    local_z: LazyDataRef<f64>,
+    latitude: LazyDataRef<f64>,
+    longitude: LazyDataRef<f64>,
+    elevation: LazyDataRef<f64>,
+    pitch: LazyDataRef<f32>,
+    roll: LazyDataRef<f32>,
+    heading: LazyDataRef<f32>,
+    magnetic_heading: LazyDataRef<f32>,
+    indicated_airspeed: LazyDataRef<f32>,
+    true_airspeed: LazyDataRef<f32>,
+    ground_speed: LazyDataRef<f32>,
+    vertical_speed: LazyDataRef<f32>,
+    on_ground: LazyDataRef<bool>,
+    gear_handle_down: LazyDataRef<bool>,
+    gear_deploy: LazyDataRef<[f32]>,
+    flap_handle: LazyDataRef<f32>,
+    flap_deploy: LazyDataRef<f32>,
+    /// The probe used when the AGL dataref is not available, created when first needed
+    probe: RefCell<Option<TerrainProbe>>,
+    /// The cycle number and result of the most recent terrain probe
+    probe_cache: Cell<Option<(i32, f32)>>,
+}
+
+impl UserAircraft {
+    /// Creates an object that provides information about the user's aircraft
+    pub fn new() -> Self {
+        UserAircraft {
+            agl: lazy("sim/flightmodel/position/y_agl"),
+            local_x: lazy("sim/flightmodel/position/local_x"),
+            local_y: lazy("sim/flightmodel/position/local_y"),
+            local_z: lazy("sim/flightmodel/position/local_z"),
+            latitude: lazy("sim/flightmodel/position/latitude"),
+            longitude: lazy("sim/flightmodel/position/longitude"),
+            elevation: lazy("sim/flightmodel/position/elevation"),
+            pitch: lazy("sim/flightmodel/position/theta"),
+            roll: lazy("sim/flightmodel/position/phi"),
+            heading: lazy("sim/flightmodel/position/psi"),
+            magnetic_heading: lazy("sim/flightmodel/position/mag_psi"),
+            indicated_airspeed: lazy("sim/flightmodel/position/indicated_airspeed"),
+            true_airspeed: lazy("sim/flightmodel/position/true_airspeed"),
+            ground_speed: lazy("sim/flightmodel/position/groundspeed"),
+            vertical_speed: lazy("sim/flightmodel/position/vh_ind_fpm"),
+            on_ground: lazy("sim/flightmodel/failures/onground_any"),
+            gear_handle_down: lazy("sim/cockpit2/controls/gear_handle_down"),
+            gear_deploy: lazy("sim/flightmodel2/gear/deploy_ratio"),
+            flap_handle: lazy("sim/cockpit2/controls/flap_ratio"),
+            flap_deploy: lazy("sim/flightmodel2/controls/flap_handle_deploy_ratio"),
+            probe: RefCell::new(None),
+            probe_cache: Cell::new(None),
+        }
+    }
+
+    /// Returns the height of the aircraft above the ground, in meters
+    ///
+    /// This reads X-Plane's height above ground dataref. If it is not available, this probes
+    /// the terrain below the aircraft like [`probe_agl`](UserAircraft::probe_agl).
+    pub fn agl(&self) -> Option<f32> {
+        read(&self.agl).or_else(|| self.probe_agl())
+    }
+
+    /// Returns the height of the aircraft above the terrain below it, in meters, using a
+    /// terrain probe
+    ///
+    /// The result is cached, so calling this more than once per frame probes the terrain only
+    /// once.
+    pub fn probe_agl(&self) -> Option<f32> {
+        crate::debug_assert_main_thread!();
+        let cycle = unsafe { xplm_sys::XPLMGetCycleNumber() };
+        if let Some((cached_cycle, agl)) = self.probe_cache.get() {
+            if cached_cycle == cycle {
+                return Some(agl);
+            }
+        }
+        let x = read(&self.local_x)? as f32;
+        let y = read(&self.local_y)? as f32;
+        let z = read(&self.local_z)? as f32;
+        let mut probe = self.probe.borrow_mut();
+        let hit = probe.get_or_insert_with(TerrainProbe::new).probe(x, y, z)?;
+        let agl = y - hit.y;
+        self.probe_cache.set(Some((cycle, agl)));
+        Some(agl)
+    }
+
+    /// Returns the latitude, longitude, and elevation of the aircraft
+    pub fn position(&self) -> Option<Position> {
+        Some(Position {
+            latitude: read(&self.latitude)?,
+            longitude: read(&self.longitude)?,
+            elevation: read(&self.elevation)?,
+        })
+    }
+
+    /// Returns the pitch, roll, and true heading of the aircraft
+    pub fn attitude(&self) -> Option<Attitude> {
+        Some(Attitude {
+            pitch: read(&self.pitch)?,
+            roll: read(&self.roll)?,
+            heading: read(&self.heading)?,
+        })
+    }
+
+    /// Returns the magnetic heading of the aircraft, in degrees
+    pub fn magnetic_heading(&self) -> Option<f32> {
+        read(&self.magnetic_heading)
+    }
+
+    /// Returns the indicated airspeed, in knots
+    pub fn indicated_airspeed(&self) -> Option<f32> {
+        read(&self.indicated_airspeed)
+    }
+
+    /// Returns the true airspeed, in meters per second
+    pub fn true_airspeed(&self) -> Option<f32> {
+        read(&self.true_airspeed)
+    }
+
+    /// Returns the speed over the ground, in meters per second
+    pub fn ground_speed(&self) -> Option<f32> {
+        read(&self.ground_speed)
+    }
+
+    /// Returns the indicated vertical speed, in feet per minute
+    pub fn vertical_speed(&self) -> Option<f32> {
+        read(&self.vertical_speed)
+    }
+
+    /// Returns true if any wheel of the aircraft is on the ground
+    pub fn on_ground(&self) -> Option<bool> {
+        read(&self.on_ground)
+    }
+
+    /// Returns true if the landing gear handle is down
+    pub fn gear_handle_down(&self) -> Option<bool> {
+        read(&self.gear_handle_down)
+    }
+
+    /// Returns how far each landing gear is extended, from 0 (retracted) to 1 (extended)
+    pub fn gear_deploy_ratios(&self) -> Option<Vec<f32>> {
+        self.gear_deploy.dataref().map(ArrayRead::as_vec)
+    }
+
+    /// Returns the position of the flap handle, from 0 (retracted) to 1 (fully extended)
+    pub fn flap_handle(&self) -> Option<f32> {
+        read(&self.flap_handle)
+    }
+
+    /// Returns how far the flaps are extended, from 0 (retracted) to 1 (fully extended)
+    pub fn flap_deploy_ratio(&self) -> Option<f32> {
+        read(&self.flap_deploy)
+    }
+}
+
+/// The location of an aircraft
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Position {
+    /// The latitude in degrees
+    pub latitude: f64,
+    /// The longitude in degrees
+    pub longitude: f64,
+    /// The elevation in meters above mean sea level
+    pub elevation: f64,
+}
+
+/// The orientation of an aircraft
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Attitude {
+    /// The pitch in degrees, positive nose up
+    pub pitch: f32,
+    /// The roll in degrees, positive right wing down
+    pub roll: f32,
+    /// The true heading in degrees
+    pub heading: f32,
+}
+
+impl Default for UserAircraft {
+    fn default() -> Self {
+        UserAircraft::new()
+    }
+}
+
+/// Creates a lazy dataref for one of the datarefs that this module reads
+fn lazy<T: DataType + ?Sized>(name: &str) -> LazyDataRef<T> {
+    LazyDataRef::new(name).expect("Dataref name contains a null byte")
+}
+
+/// Returns the value of a lazy dataref, or None if it has not been found
+fn read<T: DataType>(dataref: &LazyDataRef<T>) -> Option<T>
+where
+    DataRef<T>: DataRead<T>,
+{
+    dataref.dataref().map(DataRead::get)
+}
@@ -1,16 +1,37 @@
 use std::os::raw::c_void;
 
+use self::messages::{PluginId, PluginMessage};
+use self::state::PluginState;
+
 /// Accessing and communicating with other plugins
 pub mod management;
 
 /// Inter-plugin messaging
 pub mod messages;
 
+/// Saving plugin state between sessions
+pub mod state;
+
 /// Items used by the xplane_plugin! macro, which must be public
 #[doc(hidden)]
 pub mod internal;
 
 /// Information about a plugin
+///
+/// # Examples
+///
+/// ```
+/// use xplm::plugin::PluginInfo;
+///
+/// let info = PluginInfo::new(
+///     "Example Plugin",
+///     "org.example.plugin",
+///     "A plugin written in Rust",
+/// )
+/// .version(xplm::plugin_version!());
+/// assert_eq!(info.name, "Example Plugin");
+/// ```
+#[non_exhaustive]
 pub struct PluginInfo {
     /// The plugin name
     pub name: String,
@@ -18,6 +39,65 @@ pub struct PluginInfo {
     pub signature: String,
     /// A description of the plugin
     pub description: String,
+    /// The version of the plugin
+    ///
+    /// If this is set, the version is written to the log when the plugin starts and published
+    /// in the byte array dataref `<signature>/version`. The [`plugin_version!`](crate::plugin_version)
+    /// macro provides the version from Cargo.toml.
+    pub version: Option<String>,
+}
+
+impl PluginInfo {
+    /// Creates information with a name, signature, and description, and no version
+    pub fn new<N, S, D>(name: N, signature: S, description: D) -> Self
+    where
+        N: Into<String>,
+        S: Into<String>,
+        D: Into<String>,
+    {
+        PluginInfo {
+            name: name.into(),
+            signature: signature.into(),
+            description: description.into(),
+            version: None,
+        }
+    }
+
+    /// Sets the version
+    pub fn version<V: Into<Option<String>>>(self, version: V) -> Self {
+        PluginInfo {
+            version: version.into(),
+            ..self
+        }
+    }
+}
+
+/// Returns the version of the crate that uses this macro, for use in [`PluginInfo`]
+///
+/// The version is the package version from Cargo.toml. If the environment variable
+/// `XPLM_GIT_HASH` was set when the plugin was compiled, its value is added in parentheses,
+/// for example `1.2.0 (3f2a9c1)`. A build script can set it:
+///
+/// ```no_run
+/// // build.rs
+/// use std::process::Command;
+///
+/// let output = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output();
+/// if let Ok(output) = output {
+///     let hash = String::from_utf8_lossy(&output.stdout);
+///     println!("cargo:rustc-env=XPLM_GIT_HASH={}", hash.trim());
+/// }
+/// ```
+#[macro_export]
+macro_rules! plugin_version {
+    () => {
+        match option_env!("XPLM_GIT_HASH") {
+            Some(hash) if !hash.is_empty() => {
+                Some(format!("{} ({})", env!("CARGO_PKG_VERSION"), hash))
+            }
+            _ => Some(String::from(env!("CARGO_PKG_VERSION"))),
+        }
+    };
 }
 
 /// The trait that all plugins should implement
@@ -39,15 +119,51 @@ pub trait Plugin: Sized {
     }
     /// Called when the plugin is disabled
     ///
+    /// While the plugin is disabled, window delegates, draw callbacks, flight loop callbacks,
+    /// menu item handlers, and command handlers created with this crate are not called.
+    /// Owned datarefs can still be read and written.
+    ///
     /// The default implementation does nothing.
     fn disable(&mut self) {}
 
     /// Returns information on this plugin
-    fn info(&self) -> PluginInfo;
+    ///
+    /// Empty fields are filled in from the Cargo.toml file of the plugin crate: the package
+    /// name is used as the name and signature, and the package description as the description.
+    ///
+    /// The default implementation returns information with empty fields and no version, so
+    /// that all the information comes from Cargo.toml.
+    fn info(&self) -> PluginInfo {
+        PluginInfo::new("", "", "")
+    }
+
+    #[allow(unused_variables)]
+    /// Called before X-Plane writes its preferences and before the plugin is disabled, to
+    /// save values that should be restored the next time the plugin starts
+    ///
+    /// If the plugin changes any values, they are written to a file named after the plugin
+    /// signature. The plugin can read them with [`PluginState::load`], usually in
+    /// [`start`](Plugin::start). See the [`state`] module for an example.
+    ///
+    /// The default implementation does nothing.
+    fn save_state(&mut self, state: &mut PluginState) {}
 
     #[allow(unused_variables)]
-    /// Called when the plugin receives a message
+    /// Called when the user enters or exits VR
+    ///
+    /// in_vr is true when the user has entered VR and false when the user is about to exit VR.
+    /// Windows that follow VR have already moved when this is called. See
+    /// [`Window::set_follow_vr`](crate::window::Window::set_follow_vr).
+    ///
+    /// The default implementation does nothing.
+    fn vr_changed(&mut self, in_vr: bool) {}
+
+    #[allow(unused_variables)]
+    /// Called when the plugin receives a message from X-Plane or another plugin
+    ///
+    /// Standard X-Plane messages are decoded. For other messages, the meaning of param
+    /// depends on the message. See the [`messages`] module for an example.
     ///
     /// The default implementation does nothing.
-    fn receive_message(&mut self, from: i32, message: i32, param: *mut c_void) {}
+    fn receive_message(&mut self, from: PluginId, message: PluginMessage, param: *mut c_void) {}
 }
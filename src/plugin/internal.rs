@@ -1,12 +1,25 @@
+use std::cell::RefCell;
 use std::os::raw::{c_char, c_int, c_void};
 use std::panic;
 use std::panic::AssertUnwindSafe;
 use std::ptr;
 
+use super::super::about::{About, AboutInfo};
+use super::super::data::owned::OwnedData;
 use super::super::debugln;
+use super::super::feature::KnownFeature;
 use super::super::internal::copy_to_c_buffer;
+use super::super::menu::Menu;
 
-use super::Plugin;
+use super::messages::{PluginId, PluginMessage};
+use super::{Plugin, PluginInfo};
+
+thread_local! {
+    /// The dataref that publishes the plugin version, if the plugin provided one
+    static VERSION_DATAREF: RefCell<Option<OwnedData<[u8]>>> = const { RefCell::new(None) };
+    /// The plugins menu entry and about item, if the plugin asked for them
+    static ABOUT: RefCell<Option<(Menu, About)>> = const { RefCell::new(None) };
+}
 
 /// Information on a plugin
 pub struct PluginData<P> {
@@ -19,12 +32,55 @@ pub struct PluginData<P> {
     pub panicked: bool,
 }
 
+/// Information from the Cargo.toml file of the crate that contains the plugin
+pub struct PackageInfo {
+    /// The package name
+    pub name: &'static str,
+    /// The package version
+    pub version: &'static str,
+    /// The package description
+    pub description: &'static str,
+    /// The package license
+    pub license: &'static str,
+    /// The package homepage
+    pub homepage: &'static str,
+}
+
+impl PackageInfo {
+    /// Fills the empty name, signature, and description fields of plugin information
+    ///
+    /// The package name is used as the name and signature.
+    fn fill(&self, mut info: PluginInfo) -> PluginInfo {
+        if info.name.is_empty() {
+            info.name = self.name.to_owned();
+        }
+        if info.signature.is_empty() {
+            info.signature = self.name.to_owned();
+        }
+        if info.description.is_empty() {
+            info.description = self.description.to_owned();
+        }
+        info
+    }
+
+    /// Returns the information for an about window
+    fn about_info(&self, info: &PluginInfo) -> AboutInfo {
+        let version = info.version.as_deref().unwrap_or(self.version);
+        AboutInfo::new(info.name.as_str(), version)
+            .description(info.description.as_str())
+            .license(self.license)
+            .website(self.homepage)
+    }
+}
+
 /// Implements the XPluginStart callback
 ///
 /// This reduces the amount of code in the xplane_plugin! macro.
 ///
 /// data is a reference to a PluginData object where the created plugin will be stored.
-/// The other parameters are the same as for XPluginStart.
+/// features are enabled before the plugin starts. package provides the plugin information that
+/// the plugin leaves empty. If about is true, a menu with an about item is added to the plugins
+/// menu. The other parameters are the same as for XPluginStart.
 ///
 /// This function tries to create and allocate a plugin. On success, it stores a pointer to the
 /// plugin in data.plugin and returns 1. If the plugin fails to start, it stores a null pointer
@@ -33,6 +89,9 @@ pub struct PluginData<P> {
 /// This function never unwinds. It catches any unwind that may occur.
 pub unsafe fn xplugin_start<P>(
     data: &mut PluginData<P>,
+    features: &[KnownFeature],
+    package: &PackageInfo,
+    about: bool,
     name: *mut c_char,
     signature: *mut c_char,
     description: *mut c_char,
@@ -41,10 +100,18 @@ where
     P: Plugin,
 {
     let unwind = panic::catch_unwind(AssertUnwindSafe(|| {
+        super::super::panics::install_hook();
         super::super::internal::xplm_init();
+        request_features(features);
         match P::start() {
             Ok(plugin) => {
-                let info = plugin.info();
+                let info = package.fill(plugin.info());
+                if let Some(ref version) = info.version {
+                    publish_version(&info.name, &info.signature, version);
+                }
+                if about {
+                    add_about_menu(package.about_info(&info));
+                }
                 copy_to_c_buffer(info.name, name);
                 copy_to_c_buffer(info.signature, signature);
                 copy_to_c_buffer(info.description, description);
@@ -56,6 +123,9 @@ where
             Err(e) => {
                 debugln!("Plugin failed to start: {}", e);
                 data.plugin = ptr::null_mut();
+                // X-Plane unloads the plugin without calling XPluginStop
+                super::super::executor::executor_stop();
+                super::super::log::log_stop();
                 0
             }
         }
@@ -64,10 +134,51 @@ where
         eprintln!("Panic in XPluginStart");
         data.panicked = true;
         data.plugin = ptr::null_mut();
+        super::super::executor::executor_stop();
+        super::super::log::log_stop();
         0
     })
 }
 
+/// Enables features that the plugin requested in the xplane_plugin! macro
+fn request_features(features: &[KnownFeature]) {
+    for feature in features {
+        if let Err(e) = feature.set_enabled(true) {
+            debugln!("[xplm] {}", e);
+        }
+    }
+}
+
+/// Writes the plugin version to the log and publishes it in the dataref `<signature>/version`
+fn publish_version(name: &str, signature: &str, version: &str) {
+    debugln!("[xplm] {} version {}", name, version);
+    let mut value = version.as_bytes().to_vec();
+    value.push(0);
+    match OwnedData::create_with_value(&format!("{}/version", signature), value.as_slice()) {
+        Ok(dataref) => VERSION_DATAREF.with(|current| *current.borrow_mut() = Some(dataref)),
+        Err(e) => debugln!("[xplm] Failed to create version dataref: {}", e),
+    }
+}
+
+/// Adds a menu named after the plugin with an about item to the plugins menu
+fn add_about_menu(info: AboutInfo) {
+    let menu = match Menu::new(info.name()) {
+        Ok(menu) => menu,
+        Err(e) => {
+            debugln!("[xplm] Failed to create about menu: {}", e);
+            return;
+        }
+    };
+    match About::new(info) {
+        Ok(about) => {
+            about.add_to_menu(&menu);
+            menu.add_to_plugins_menu();
+            ABOUT.with(|current| *current.borrow_mut() = Some((menu, about)));
+        }
+        Err(e) => debugln!("[xplm] Failed to create about item: {}", e),
+    }
+}
+
 /// Implements the XPluginStop callback
 ///
 /// This function never unwinds. It catches any unwind that may occur.
@@ -80,6 +191,10 @@ where
             let plugin = Box::from_raw(data.plugin);
             data.plugin = ptr::null_mut();
             drop(plugin);
+            VERSION_DATAREF.with(|dataref| dataref.borrow_mut().take());
+            ABOUT.with(|about| about.borrow_mut().take());
+            super::super::executor::executor_stop();
+            super::super::log::log_stop();
         }));
         if unwind.is_err() {
             eprintln!("Panic in XPluginStop");
@@ -98,16 +213,23 @@ where
     P: Plugin,
 {
     if !data.panicked {
+        // Callbacks may run while the plugin enables itself
+        super::super::internal::set_suspended(false);
         let unwind = panic::catch_unwind(AssertUnwindSafe(|| match (*data.plugin).enable() {
-            Ok(_) => 1,
+            Ok(_) => {
+                super::super::health::set_plugin_enabled(true);
+                1
+            }
             Err(e) => {
                 debugln!("Plugin failed to enable: {}", e);
+                super::super::internal::set_suspended(true);
                 0
             }
         }));
         unwind.unwrap_or_else(|_| {
             eprintln!("Panic in XPluginEnable");
             data.panicked = true;
+            super::super::internal::set_suspended(true);
             0
         })
     } else {
@@ -125,13 +247,17 @@ where
 {
     if !data.panicked {
         let unwind = panic::catch_unwind(AssertUnwindSafe(|| {
+            super::state::save_plugin_state(&mut *data.plugin);
             (*data.plugin).disable();
+            super::super::health::set_plugin_enabled(false);
         }));
         if unwind.is_err() {
             eprintln!("Panic in XPluginDisable");
             data.panicked = true;
         }
     }
+    // Callbacks may run while the plugin disables itself, but not afterwards
+    super::super::internal::set_suspended(true);
 }
 
 #[allow(unused_variables)]
@@ -148,7 +274,17 @@ pub unsafe fn xplugin_receive_message<P>(
 {
     if !data.panicked {
         let unwind = panic::catch_unwind(AssertUnwindSafe(|| {
-            (*data.plugin).receive_message(from, message, param);
+            super::super::window::handle_vr_message(message);
+            let plugin = &mut *data.plugin;
+            let message = PluginMessage::from_raw(message, param);
+            match message {
+                PluginMessage::EnteredVr => plugin.vr_changed(true),
+                PluginMessage::ExitingVr => plugin.vr_changed(false),
+                PluginMessage::WillWritePreferences => super::state::save_plugin_state(plugin),
+                PluginMessage::DatarefsAdded { .. } => super::super::data::lazy::datarefs_added(),
+                _ => {}
+            }
+            plugin.receive_message(PluginId::from_raw(from), message, param);
         }));
         if unwind.is_err() {
             eprintln!("Panic in XPluginReceiveMessage");
@@ -156,3 +292,28 @@ pub unsafe fn xplugin_receive_message<P>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PackageInfo;
+    use crate::plugin::PluginInfo;
+
+    #[test]
+    fn test_fill_info() {
+        let package = PackageInfo {
+            name: "my-plugin",
+            version: "1.0.0",
+            description: "Does things",
+            license: "MIT",
+            homepage: "",
+        };
+        let info = package.fill(PluginInfo::new("", "", ""));
+        assert_eq!(info.name, "my-plugin");
+        assert_eq!(info.signature, "my-plugin");
+        assert_eq!(info.description, "Does things");
+        let info = package.fill(PluginInfo::new("My Plugin", "org.example.plugin", ""));
+        assert_eq!(info.name, "My Plugin");
+        assert_eq!(info.signature, "org.example.plugin");
+        assert_eq!(info.description, "Does things");
+    }
+}
@@ -1,41 +1,61 @@
+//! # Other plugins
+//!
+//! A [`PluginHandle`] refers to a plugin loaded in X-Plane. Plugins can use it to detect
+//! companion plugins, read their information, and enable or disable them.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::plugin::management::{all_plugins, PluginHandle};
+//!
+//! if let Some(avitab) = PluginHandle::find_by_signature("com.avitab.avitab") {
+//!     if avitab.is_enabled() {
+//!         let info = avitab.info();
+//!         xplm::debugln!("Found {} at {}", info.name, info.path.display());
+//!     }
+//! }
+//! for plugin in all_plugins() {
+//!     xplm::debugln!("Loaded plugin {}", plugin.signature());
+//! }
+//! ```
+//!
+
 use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
 use std::os::raw::*;
 use std::path::PathBuf;
 use std::ptr;
 use xplm_sys;
 
+use super::messages::PluginId;
+
 /// Looks for a plugin with the provided signature and returns it if it exists
-pub fn plugin_with_signature(signature: &str) -> Option<Plugin> {
-    match CString::new(signature) {
-        Ok(signature) => {
-            let plugin_id = unsafe { xplm_sys::XPLMFindPluginBySignature(signature.as_ptr()) };
-            if plugin_id != xplm_sys::XPLM_NO_PLUGIN_ID {
-                Some(Plugin(plugin_id))
-            } else {
-                None
-            }
-        }
-        Err(_) => None,
-    }
+///
+/// This is equivalent to [`PluginHandle::find_by_signature`].
+pub fn plugin_with_signature(signature: &str) -> Option<PluginHandle> {
+    PluginHandle::find_by_signature(signature)
 }
 
 /// Returns the plugin that is currently running
-pub fn this_plugin() -> Plugin {
+pub fn this_plugin() -> PluginHandle {
+    crate::debug_assert_main_thread!();
     let plugin_id = unsafe { xplm_sys::XPLMGetMyID() };
     assert_ne!(
         plugin_id,
         xplm_sys::XPLM_NO_PLUGIN_ID,
         "XPLMGetMyId() returned no plugin ID"
     );
-    Plugin(plugin_id)
+    PluginHandle(plugin_id, PhantomData)
 }
 
 /// Returns an iterator over all loaded plugins
 pub fn all_plugins() -> Plugins {
+    crate::debug_assert_main_thread!();
     Plugins {
         next: 0,
         // Subtract 1 because X-Plane is considered a plugin
         count: unsafe { xplm_sys::XPLMCountPlugins() - 1 },
+        _not_send: PhantomData,
     }
 }
 
@@ -47,13 +67,17 @@ pub struct Plugins {
     next: c_int,
     /// The total number of plugins available
     count: c_int,
+    _not_send: PhantomData<*const ()>,
 }
 
 impl Iterator for Plugins {
-    type Item = Plugin;
+    type Item = PluginHandle;
     fn next(&mut self) -> Option<Self::Item> {
         if self.next < self.count {
-            let plugin = Plugin(unsafe { xplm_sys::XPLMGetNthPlugin(self.next) });
+            let plugin = PluginHandle(
+                unsafe { xplm_sys::XPLMGetNthPlugin(self.next) },
+                PhantomData,
+            );
             self.next += 1;
             // Skip past X-Plane
             if plugin.0 == xplm_sys::XPLM_PLUGIN_XPLANE as xplm_sys::XPLMPluginID {
@@ -74,11 +98,56 @@ impl Iterator for Plugins {
 impl ExactSizeIterator for Plugins {}
 
 /// Another plugin running in X-Plane (or this plugin)
-pub struct Plugin(xplm_sys::XPLMPluginID);
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PluginHandle(xplm_sys::XPLMPluginID, PhantomData<*const ()>);
+
+/// The old name of [`PluginHandle`]
+#[deprecated(note = "Renamed to PluginHandle")]
+pub type Plugin = PluginHandle;
+
+impl PluginHandle {
+    /// Looks for a plugin with the provided signature and returns it if it exists
+    pub fn find_by_signature(signature: &str) -> Option<Self> {
+        crate::debug_assert_main_thread!();
+        let signature = CString::new(signature).ok()?;
+        let plugin_id = unsafe { xplm_sys::XPLMFindPluginBySignature(signature.as_ptr()) };
+        if plugin_id != xplm_sys::XPLM_NO_PLUGIN_ID {
+            Some(PluginHandle(plugin_id, PhantomData))
+        } else {
+            None
+        }
+    }
 
-impl Plugin {
+    /// Returns the ID of this plugin
+    pub fn id(&self) -> PluginId {
+        PluginId::from_raw(self.0)
+    }
+    /// Returns the name, path, signature, and description of this plugin
+    pub fn info(&self) -> PluginDetails {
+        crate::debug_assert_main_thread!();
+        let mut name = new_buffer();
+        let mut path = new_buffer();
+        let mut signature = new_buffer();
+        let mut description = new_buffer();
+        unsafe {
+            xplm_sys::XPLMGetPluginInfo(
+                self.0,
+                name.as_mut_ptr(),
+                path.as_mut_ptr(),
+                signature.as_mut_ptr(),
+                description.as_mut_ptr(),
+            );
+        }
+        PluginDetails {
+            name: buffer_to_string(&name),
+            path: PathBuf::from(buffer_to_string(&path)),
+            signature: buffer_to_string(&signature),
+            description: buffer_to_string(&description),
+        }
+    }
     /// Returns the name of this plugin
     pub fn name(&self) -> String {
+        crate::debug_assert_main_thread!();
         read_to_buffer(|buffer| unsafe {
             xplm_sys::XPLMGetPluginInfo(
                 self.0,
@@ -91,6 +160,7 @@ impl Plugin {
     }
     /// Returns the signature of this plugin
     pub fn signature(&self) -> String {
+        crate::debug_assert_main_thread!();
         read_to_buffer(|buffer| unsafe {
             xplm_sys::XPLMGetPluginInfo(
                 self.0,
@@ -103,6 +173,7 @@ impl Plugin {
     }
     /// Returns the description of this plugin
     pub fn description(&self) -> String {
+        crate::debug_assert_main_thread!();
         read_to_buffer(|buffer| unsafe {
             xplm_sys::XPLMGetPluginInfo(
                 self.0,
@@ -115,6 +186,7 @@ impl Plugin {
     }
     /// Returns the absolute path to this plugin
     pub fn path(&self) -> PathBuf {
+        crate::debug_assert_main_thread!();
         let os_path = read_to_buffer(|buffer| unsafe {
             xplm_sys::XPLMGetPluginInfo(
                 self.0,
@@ -128,12 +200,20 @@ impl Plugin {
     }
 
     /// Returns true if this plugin is enabled
-    pub fn enabled(&self) -> bool {
+    pub fn is_enabled(&self) -> bool {
+        crate::debug_assert_main_thread!();
         unsafe { xplm_sys::XPLMIsPluginEnabled(self.0) == 1 }
     }
 
+    /// Returns true if this plugin is enabled
+    #[deprecated(note = "Use is_enabled")]
+    pub fn enabled(&self) -> bool {
+        self.is_enabled()
+    }
+
     /// Enables or disables the plugin
     pub fn set_enabled(&self, enabled: bool) {
+        crate::debug_assert_main_thread!();
         if enabled {
             unsafe {
                 xplm_sys::XPLMEnablePlugin(self.0);
@@ -146,12 +226,40 @@ impl Plugin {
     }
 }
 
+impl From<PluginId> for PluginHandle {
+    fn from(id: PluginId) -> Self {
+        PluginHandle(id.raw(), PhantomData)
+    }
+}
+
+/// Information about a plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginDetails {
+    /// The name of the plugin
+    pub name: String,
+    /// The absolute path to the plugin
+    pub path: PathBuf,
+    /// The signature of the plugin
+    pub signature: String,
+    /// The description of the plugin
+    pub description: String,
+}
+
 /// Allocates a buffer of at least 256 bytes and passes it to the provided callback, then tries
 /// to convert it into a String and returns the result
 fn read_to_buffer<F: Fn(*mut c_char)>(read_callback: F) -> String {
-    // Create a buffer of 256 nulls
-    let mut buffer: [c_char; 256] = [b'\0' as c_char; 256];
+    let mut buffer = new_buffer();
     read_callback(buffer.as_mut_ptr());
+    buffer_to_string(&buffer)
+}
+
+/// Returns a buffer of 256 nulls
+fn new_buffer() -> [c_char; 256] {
+    [b'\0' as c_char; 256]
+}
+
+/// Converts a null-terminated buffer into a String
+fn buffer_to_string(buffer: &[c_char; 256]) -> String {
     let cstr = unsafe { CStr::from_ptr(buffer.as_ptr()) };
     cstr.to_string_lossy().into_owned()
 }
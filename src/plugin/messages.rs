@@ -1,3 +1,43 @@
+//! # Plugin messages
+//!
+//! X-Plane sends messages to plugins when aircraft, scenery, or other state changes. Other
+//! plugins can also send messages. The [`Plugin::receive_message`](super::Plugin::receive_message)
+//! method receives them, with standard X-Plane messages decoded into a [`PluginMessage`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::convert::Infallible;
+//! use std::os::raw::c_void;
+//! use xplm::plugin::messages::{PluginId, PluginMessage};
+//! use xplm::plugin::{Plugin, PluginInfo};
+//!
+//! struct LiveryPlugin;
+//!
+//! impl Plugin for LiveryPlugin {
+//!     type Error = Infallible;
+//!
+//!     fn start() -> Result<Self, Self::Error> {
+//!         Ok(LiveryPlugin)
+//!     }
+//!
+//!     fn receive_message(&mut self, from: PluginId, message: PluginMessage, _param: *mut c_void) {
+//!         if let PluginMessage::LiveryLoaded { plane: 0 } = message {
+//!             xplm::debugln!("The user changed the livery");
+//!         }
+//!     }
+//!
+//!     fn info(&self) -> PluginInfo {
+//!         PluginInfo::new("Livery", "com.example.livery", "Watches the livery")
+//!     }
+//! }
+//! ```
+//!
+
+use std::os::raw::c_void;
+
+use xplm_sys::XPLMPluginID;
+
 pub const XPLM_MSG_PLANE_CRASHED: i32 = xplm_sys::XPLM_MSG_PLANE_CRASHED as i32;
 pub const XPLM_MSG_PLANE_LOADED: i32 = xplm_sys::XPLM_MSG_PLANE_LOADED as i32;
 pub const XPLM_MSG_AIRPORT_LOADED: i32 = xplm_sys::XPLM_MSG_AIRPORT_LOADED as i32;
@@ -12,3 +52,152 @@ pub const XPLM_MSG_RELEASE_PLANES: i32 = xplm_sys::XPLM_MSG_RELEASE_PLANES as i3
 pub const XPLM_MSG_FMOD_BANK_LOADED: i32 = xplm_sys::XPLM_MSG_FMOD_BANK_LOADED as i32;
 pub const XPLM_MSG_FMOD_BANK_UNLOADING: i32 = xplm_sys::XPLM_MSG_FMOD_BANK_UNLOADING as i32;
 pub const XPLM_MSG_DATAREFS_ADDED: i32 = xplm_sys::XPLM_MSG_DATAREFS_ADDED as i32;
+
+/// Identifies a plugin, or X-Plane itself
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PluginId(XPLMPluginID);
+
+impl PluginId {
+    /// The ID of X-Plane, which sends the standard messages
+    pub const XPLANE: PluginId = PluginId(xplm_sys::XPLM_PLUGIN_XPLANE as XPLMPluginID);
+
+    /// Creates an ID from the value that X-Plane uses
+    pub fn from_raw(id: XPLMPluginID) -> Self {
+        PluginId(id)
+    }
+
+    /// Returns the value that X-Plane uses for this ID
+    pub fn raw(self) -> XPLMPluginID {
+        self.0
+    }
+
+    /// Returns true if this is the ID of X-Plane
+    pub fn is_xplane(self) -> bool {
+        self == PluginId::XPLANE
+    }
+}
+
+/// A message that X-Plane or another plugin sent
+///
+/// Plane indexes are 0 for the user's aircraft and 1 or more for other aircraft.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PluginMessage {
+    /// The user's aircraft crashed
+    PlaneCrashed,
+    /// An aircraft was loaded
+    PlaneLoaded {
+        /// The index of the aircraft
+        plane: i32,
+    },
+    /// The user's aircraft was positioned at a new airport
+    AirportLoaded,
+    /// New scenery was loaded
+    SceneryLoaded,
+    /// The number of aircraft changed
+    AirplaneCountChanged,
+    /// An aircraft was unloaded
+    PlaneUnloaded {
+        /// The index of the aircraft
+        plane: i32,
+    },
+    /// X-Plane is about to write its preferences
+    WillWritePreferences,
+    /// A livery was loaded for an aircraft
+    LiveryLoaded {
+        /// The index of the aircraft
+        plane: i32,
+    },
+    /// The user entered VR
+    EnteredVr,
+    /// The user is about to exit VR
+    ExitingVr,
+    /// Another plugin wants to take control of the AI aircraft
+    ReleasePlanes,
+    /// An FMOD sound bank was loaded
+    FmodBankLoaded {
+        /// The bank, 0 for the master bank or 1 for the radio bank
+        bank: i32,
+    },
+    /// An FMOD sound bank is about to be unloaded
+    FmodBankUnloading {
+        /// The bank, 0 for the master bank or 1 for the radio bank
+        bank: i32,
+    },
+    /// Plugins created new datarefs
+    DatarefsAdded {
+        /// The total number of datarefs
+        count: i32,
+    },
+    /// A message that is not one of the standard X-Plane messages, usually from another
+    /// plugin
+    ///
+    /// The meaning of the parameter depends on the message.
+    Other(i32),
+}
+
+impl PluginMessage {
+    /// Decodes a message and its parameter
+    pub fn from_raw(message: i32, param: *mut c_void) -> Self {
+        // For these messages, the parameter is an integer, not a pointer
+        let value = param as isize as i32;
+        match message {
+            XPLM_MSG_PLANE_CRASHED => PluginMessage::PlaneCrashed,
+            XPLM_MSG_PLANE_LOADED => PluginMessage::PlaneLoaded { plane: value },
+            XPLM_MSG_AIRPORT_LOADED => PluginMessage::AirportLoaded,
+            XPLM_MSG_SCENERY_LOADED => PluginMessage::SceneryLoaded,
+            XPLM_MSG_AIRPLANE_COUNT_CHANGED => PluginMessage::AirplaneCountChanged,
+            XPLM_MSG_PLANE_UNLOADED => PluginMessage::PlaneUnloaded { plane: value },
+            XPLM_MSG_WILL_WRITE_PREFS => PluginMessage::WillWritePreferences,
+            XPLM_MSG_LIVERY_LOADED => PluginMessage::LiveryLoaded { plane: value },
+            XPLM_MSG_ENTERED_VR => PluginMessage::EnteredVr,
+            XPLM_MSG_EXITING_VR => PluginMessage::ExitingVr,
+            XPLM_MSG_RELEASE_PLANES => PluginMessage::ReleasePlanes,
+            XPLM_MSG_FMOD_BANK_LOADED => PluginMessage::FmodBankLoaded { bank: value },
+            XPLM_MSG_FMOD_BANK_UNLOADING => PluginMessage::FmodBankUnloading { bank: value },
+            XPLM_MSG_DATAREFS_ADDED => PluginMessage::DatarefsAdded { count: value },
+            other => PluginMessage::Other(other),
+        }
+    }
+
+    /// Returns the number that X-Plane uses for this message
+    pub fn raw(self) -> i32 {
+        match self {
+            PluginMessage::PlaneCrashed => XPLM_MSG_PLANE_CRASHED,
+            PluginMessage::PlaneLoaded { .. } => XPLM_MSG_PLANE_LOADED,
+            PluginMessage::AirportLoaded => XPLM_MSG_AIRPORT_LOADED,
+            PluginMessage::SceneryLoaded => XPLM_MSG_SCENERY_LOADED,
+            PluginMessage::AirplaneCountChanged => XPLM_MSG_AIRPLANE_COUNT_CHANGED,
+            PluginMessage::PlaneUnloaded { .. } => XPLM_MSG_PLANE_UNLOADED,
+            PluginMessage::WillWritePreferences => XPLM_MSG_WILL_WRITE_PREFS,
+            PluginMessage::LiveryLoaded { .. } => XPLM_MSG_LIVERY_LOADED,
+            PluginMessage::EnteredVr => XPLM_MSG_ENTERED_VR,
+            PluginMessage::ExitingVr => XPLM_MSG_EXITING_VR,
+            PluginMessage::ReleasePlanes => XPLM_MSG_RELEASE_PLANES,
+            PluginMessage::FmodBankLoaded { .. } => XPLM_MSG_FMOD_BANK_LOADED,
+            PluginMessage::FmodBankUnloading { .. } => XPLM_MSG_FMOD_BANK_UNLOADING,
+            PluginMessage::DatarefsAdded { .. } => XPLM_MSG_DATAREFS_ADDED,
+            PluginMessage::Other(message) => message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PluginMessage, XPLM_MSG_LIVERY_LOADED, XPLM_MSG_SCENERY_LOADED};
+    use std::ptr;
+
+    #[test]
+    fn test_decode() {
+        let param = ptr::null_mut::<u8>().wrapping_add(2).cast();
+        let livery = PluginMessage::from_raw(XPLM_MSG_LIVERY_LOADED, param);
+        assert_eq!(livery, PluginMessage::LiveryLoaded { plane: 2 });
+        assert_eq!(livery.raw(), XPLM_MSG_LIVERY_LOADED);
+        let scenery = PluginMessage::from_raw(XPLM_MSG_SCENERY_LOADED, ptr::null_mut());
+        assert_eq!(scenery, PluginMessage::SceneryLoaded);
+        assert_eq!(
+            PluginMessage::from_raw(0x8000_0001u32 as i32, param).raw(),
+            0x8000_0001u32 as i32
+        );
+    }
+}
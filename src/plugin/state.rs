@@ -0,0 +1,271 @@
+//! # Saving plugin state
+//!
+//! A [`PluginState`] holds named text values in a file in the X-Plane preferences folder,
+//! so that settings can be restored the next time the plugin starts.
+//!
+//! X-Plane calls [`Plugin::save_state`](super::Plugin::save_state) before it writes its own
+//! preferences and when the plugin is disabled. The values that the plugin sets are written
+//! to a file named after the plugin signature. The plugin can read them when it starts.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::convert::Infallible;
+//! use xplm::plugin::state::PluginState;
+//! use xplm::plugin::{Plugin, PluginInfo};
+//!
+//! const SIGNATURE: &str = "com.example.counter";
+//!
+//! struct CounterPlugin {
+//!     starts: u32,
+//! }
+//!
+//! impl Plugin for CounterPlugin {
+//!     type Error = Infallible;
+//!
+//!     fn start() -> Result<Self, Self::Error> {
+//!         let state = PluginState::load(SIGNATURE);
+//!         let starts = state.get("starts").unwrap_or(0) + 1;
+//!         Ok(CounterPlugin { starts })
+//!     }
+//!
+//!     fn save_state(&mut self, state: &mut PluginState) {
+//!         state.set("starts", self.starts);
+//!     }
+//!
+//!     fn info(&self) -> PluginInfo {
+//!         PluginInfo::new("Counter", SIGNATURE, "Counts how many times it has started")
+//!     }
+//! }
+//! ```
+//!
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::{Chars, FromStr};
+
+use super::super::debugln;
+use super::super::paths::preferences_folder;
+use super::Plugin;
+
+/// Named values saved in a file
+///
+/// The file contains one `name = value` line for each value. Line breaks, backslashes, and
+/// whitespace at the start or end of values are escaped. Whitespace at the start and end of
+/// names is removed.
+#[derive(Debug, Clone)]
+pub struct PluginState {
+    /// The file that values are read from and saved to
+    path: PathBuf,
+    /// The values by name
+    values: BTreeMap<String, String>,
+}
+
+impl PluginState {
+    /// Reads the state of the plugin with the provided signature
+    ///
+    /// The file is `<signature>.prf` in the X-Plane preferences folder.
+    pub fn load(signature: &str) -> Self {
+        PluginState::load_from(preferences_folder().join(format!("{}.prf", signature)))
+    }
+
+    /// Reads state from a file
+    ///
+    /// A missing file is not an error. Other problems reading the file, and lines that
+    /// cannot be parsed, are written to the log.
+    pub fn load_from<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        let values = match fs::read_to_string(&path) {
+            Ok(text) => parse_state(&text),
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    debugln!("[xplm] Could not read {}: {}", path.display(), e);
+                }
+                BTreeMap::new()
+            }
+        };
+        PluginState { path, values }
+    }
+
+    /// Returns the path of the file that this state reads and saves
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns a value as text
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.values.get(name.trim()).map(String::as_str)
+    }
+
+    /// Parses and returns a value
+    ///
+    /// This returns None if the value does not exist or cannot be parsed.
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get_str(name)?.parse().ok()
+    }
+
+    /// Sets a value
+    ///
+    /// The value is not written to the file until [`save`](PluginState::save) is called.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the name contains `=` or a line break.
+    pub fn set<T: fmt::Display>(&mut self, name: &str, value: T) {
+        assert!(
+            !name.contains(['=', '\n', '\r']),
+            "State name {:?} contains = or a line break",
+            name
+        );
+        self.values
+            .insert(name.trim().to_owned(), value.to_string());
+    }
+
+    /// Removes a value and returns it, if it existed
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.values.remove(name.trim())
+    }
+
+    /// Returns an iterator over the names and values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Writes all values to the file
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(&self.path, format_state(&self.values))
+    }
+}
+
+/// Lets a plugin update its saved state and writes the file if anything changed
+pub(crate) fn save_plugin_state<P: Plugin>(plugin: &mut P) {
+    let mut state = PluginState::load(&plugin.info().signature);
+    let before = state.values.clone();
+    plugin.save_state(&mut state);
+    if state.values != before {
+        if let Err(e) = state.save() {
+            debugln!("[xplm] Could not save {}: {}", state.path.display(), e);
+        }
+    }
+}
+
+/// Parses the lines of a state file
+fn parse_state(text: &str) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        match parse_line(line) {
+            Some((name, value)) => {
+                values.insert(name, value);
+            }
+            None => debugln!("[xplm] Ignoring invalid state line {}", line),
+        }
+    }
+    values
+}
+
+/// Parses a `name = value` line
+fn parse_line(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.split_once('=')?;
+    Some((name.trim().to_owned(), unescape(value.trim())))
+}
+
+/// Formats values as the lines of a state file
+fn format_state(values: &BTreeMap<String, String>) -> String {
+    values
+        .iter()
+        .map(|(name, value)| format!("{} = {}\n", name, escape(value)))
+        .collect()
+}
+
+/// Escapes backslashes, line breaks, and whitespace at the start or end
+///
+/// Whitespace at the start and end is escaped so that it is not removed when the line is
+/// parsed.
+fn escape(value: &str) -> String {
+    let start = value.len() - value.trim_start().len();
+    let end = value.trim_end().len().max(start);
+    let mut escaped = String::with_capacity(value.len());
+    for (i, c) in value.char_indices() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if i < start || i >= end => match c {
+                ' ' => escaped.push_str("\\s"),
+                '\t' => escaped.push_str("\\t"),
+                c => escaped.push_str(&format!("\\u{{{:x}}}", u32::from(c))),
+            },
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverses [`escape`]
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('s') => unescaped.push(' '),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => match unescape_code(&mut chars) {
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('u'),
+            },
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Parses the `{hex}` part of a `\u{hex}` escape
+fn unescape_code(chars: &mut Chars) -> Option<char> {
+    let rest = chars.as_str().strip_prefix('{')?;
+    let (hex, after) = rest.split_once('}')?;
+    let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+    *chars = after.chars();
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_state, parse_line};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut values = BTreeMap::new();
+        values.insert("volume".to_owned(), "0.75".to_owned());
+        values.insert("notes".to_owned(), "line 1\nC:\\temp".to_owned());
+        let text = format_state(&values);
+        assert_eq!(text, "notes = line 1\\nC:\\\\temp\nvolume = 0.75\n");
+        let parsed: BTreeMap<String, String> = text.lines().filter_map(parse_line).collect();
+        assert_eq!(parsed, values);
+        assert_eq!(parse_line("a=b = c "), Some(("a".into(), "b = c".into())));
+        assert_eq!(parse_line("no equals sign"), None);
+    }
+
+    #[test]
+    fn test_state_whitespace() {
+        let mut values = BTreeMap::new();
+        values.insert("padded".to_owned(), " \ta b\u{a0} ".to_owned());
+        values.insert("spaces".to_owned(), "   ".to_owned());
+        let text = format_state(&values);
+        assert_eq!(text, "padded = \\s\\ta b\\u{a0}\\s\nspaces = \\s\\s\\s\n");
+        let parsed: BTreeMap<String, String> = text.lines().filter_map(parse_line).collect();
+        assert_eq!(parsed, values);
+    }
+}
@@ -8,9 +8,44 @@
 /// 2. Implement Plugin for your plugin struct
 /// 3. Place `xplane_plugin!(YourPluginStruct)` in a file, not in any function
 ///
+/// SDK features can be enabled before the plugin starts by listing them after the plugin type.
+/// Features that X-Plane does not support are skipped with a message in Log.txt.
+///
+/// ```ignore
+/// use xplm::feature::KnownFeature;
+///
+/// xplane_plugin!(
+///     YourPluginStruct,
+///     features = [KnownFeature::WantsReflections, KnownFeature::NativeWidgetWindows]
+/// );
+/// ```
+///
+/// With `about = true`, a menu named after the plugin is added to the plugins menu. It contains
+/// an "About" item that opens a window with the plugin name, version, description, and signature,
+/// and the license and homepage from Cargo.toml. See the [`about`](crate::about) module.
+///
+/// ```ignore
+/// xplane_plugin!(YourPluginStruct, about = true);
+/// ```
+///
+/// Any empty fields of the plugin's [`info`](crate::plugin::Plugin::info) are filled in from
+/// the package name and description in the Cargo.toml file of the crate that uses this macro.
+///
+/// The generated XPluginStart function installs a panic hook that writes panics to Log.txt and
+/// optionally to a crash report file. See the [`panics`](crate::panics) module for options.
+///
 #[macro_export]
 macro_rules! xplane_plugin {
     ($plugin_type: ty) => {
+        ::xplm::xplane_plugin!($plugin_type, features = [], about = false);
+    };
+    ($plugin_type: ty, about = $about: expr) => {
+        ::xplm::xplane_plugin!($plugin_type, features = [], about = $about);
+    };
+    ($plugin_type: ty, features = [$($feature: expr),* $(,)?]) => {
+        ::xplm::xplane_plugin!($plugin_type, features = [$($feature),*], about = false);
+    };
+    ($plugin_type: ty, features = [$($feature: expr),* $(,)?], about = $about: expr) => {
         // The plugin
         static mut PLUGIN: ::xplm::plugin::internal::PluginData<$plugin_type> =
             ::xplm::plugin::internal::PluginData {
@@ -25,7 +60,21 @@ macro_rules! xplane_plugin {
             signature: *mut ::std::os::raw::c_char,
             description: *mut ::std::os::raw::c_char,
         ) -> ::std::os::raw::c_int {
-            ::xplm::plugin::internal::xplugin_start(&mut PLUGIN, name, signature, description)
+            ::xplm::plugin::internal::xplugin_start(
+                &mut PLUGIN,
+                &[$($feature),*],
+                &::xplm::plugin::internal::PackageInfo {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                    description: env!("CARGO_PKG_DESCRIPTION"),
+                    license: env!("CARGO_PKG_LICENSE"),
+                    homepage: env!("CARGO_PKG_HOMEPAGE"),
+                },
+                $about,
+                name,
+                signature,
+                description,
+            )
         }
 
         #[allow(non_snake_case)]
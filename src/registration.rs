@@ -0,0 +1,203 @@
+//! # Batch registration
+//!
+//! A plugin often creates many datarefs and commands when it starts. With the `?` operator,
+//! a single name collision or invalid name stops the whole plugin from starting.
+//!
+//! A [`RegistrationReport`] creates each item independently. Items that fail are skipped and
+//! recorded in the report, so the plugin can continue with reduced functionality and report
+//! the problems afterwards.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::command::CommandHandler;
+//! use xplm::data::owned::OwnedData;
+//! use xplm::registration::RegistrationReport;
+//!
+//! struct Reset;
+//! impl CommandHandler for Reset {
+//!     fn command_begin(&mut self) {}
+//!     fn command_continue(&mut self) {}
+//!     fn command_end(&mut self) {}
+//! }
+//!
+//! let mut report = RegistrationReport::new();
+//! let speed: Option<OwnedData<f32>> = report.try_create_data("myplugin/speed");
+//! let reset = report.try_create_command("myplugin/reset", "Reset", Reset);
+//! if !report.is_ok() {
+//!     // Writes each failure to Log.txt
+//!     report.log();
+//! }
+//! ```
+//!
+
+use std::fmt;
+
+use super::command::{CommandCreateError, CommandHandler, OwnedCommand};
+use super::data::owned::{CreateError, OwnedData};
+use super::data::{Access, DataType};
+use super::debugln;
+
+/// Creates datarefs and commands and collects the errors from items that could not be created
+#[derive(Debug, Default)]
+pub struct RegistrationReport {
+    /// The items that could not be created
+    failures: Vec<RegistrationFailure>,
+    /// The number of items that were created
+    succeeded: usize,
+}
+
+impl RegistrationReport {
+    /// Creates an empty report
+    pub fn new() -> Self {
+        RegistrationReport::default()
+    }
+
+    /// Creates a dataref containing the default value of T
+    ///
+    /// If the dataref cannot be created, this function records the error and returns None.
+    pub fn try_create_data<T, A>(&mut self, name: &str) -> Option<OwnedData<T, A>>
+    where
+        T: DataType + Default,
+        A: Access,
+    {
+        self.check(name, ItemKind::Data, OwnedData::create(name))
+    }
+
+    /// Creates a dataref containing a value
+    ///
+    /// If the dataref cannot be created, this function records the error and returns None.
+    pub fn try_create_data_with_value<T, A>(
+        &mut self,
+        name: &str,
+        value: &T,
+    ) -> Option<OwnedData<T, A>>
+    where
+        T: DataType + ?Sized,
+        A: Access,
+    {
+        self.check(
+            name,
+            ItemKind::Data,
+            OwnedData::create_with_value(name, value),
+        )
+    }
+
+    /// Creates a command, or finds an existing command, and attaches a handler
+    ///
+    /// If the command cannot be created, this function records the error and returns None.
+    pub fn try_create_command<H: CommandHandler>(
+        &mut self,
+        name: &str,
+        description: &str,
+        handler: H,
+    ) -> Option<OwnedCommand> {
+        self.check(
+            name,
+            ItemKind::Command,
+            OwnedCommand::new(name, description, handler),
+        )
+    }
+
+    /// Returns true if every item was created successfully
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns the number of items that were created successfully
+    pub fn succeeded(&self) -> usize {
+        self.succeeded
+    }
+
+    /// Returns the items that could not be created
+    pub fn failures(&self) -> &[RegistrationFailure] {
+        &self.failures
+    }
+
+    /// Writes a message for each item that could not be created to the X-Plane log
+    pub fn log(&self) {
+        for failure in &self.failures {
+            debugln!("[xplm] {}", failure);
+        }
+    }
+
+    /// Records the result of creating an item
+    fn check<T, E>(&mut self, name: &str, kind: ItemKind, result: Result<T, E>) -> Option<T>
+    where
+        E: Into<RegistrationError>,
+    {
+        match result {
+            Ok(item) => {
+                self.succeeded += 1;
+                Some(item)
+            }
+            Err(e) => {
+                self.failures.push(RegistrationFailure {
+                    name: name.to_owned(),
+                    kind,
+                    error: e.into(),
+                });
+                None
+            }
+        }
+    }
+}
+
+/// The kinds of items that can be registered
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ItemKind {
+    /// A dataref
+    Data,
+    /// A command
+    Command,
+}
+
+/// An item that could not be created
+#[derive(Debug)]
+pub struct RegistrationFailure {
+    /// The name of the item
+    name: String,
+    /// The kind of item
+    kind: ItemKind,
+    /// The error
+    error: RegistrationError,
+}
+
+impl RegistrationFailure {
+    /// Returns the name of the item that could not be created
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the kind of item that could not be created
+    pub fn kind(&self) -> ItemKind {
+        self.kind
+    }
+    /// Returns the error that occurred
+    pub fn error(&self) -> &RegistrationError {
+        &self.error
+    }
+}
+
+impl fmt::Display for RegistrationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ItemKind::Data => "dataref",
+            ItemKind::Command => "command",
+        };
+        write!(f, "Failed to create {} {}: {}", kind, self.name, self.error)
+    }
+}
+
+/// Errors that can occur when registering an item
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum RegistrationError {
+    /// A dataref could not be created
+    #[error("{0}")]
+    Data(#[from] CreateError),
+
+    /// A command could not be created
+    #[error("{0}")]
+    Command(#[from] CommandCreateError),
+}
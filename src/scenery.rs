@@ -0,0 +1,172 @@
+//! # Terrain probes and library objects
+//!
+//! A [`TerrainProbe`] finds the height of the terrain at a location in local OpenGL
+//! coordinates.
+//!
+//! [`lookup_objects`] finds the object files that a virtual path in the scenery library refers
+//! to. The files can be loaded with [`Object::load`](crate::instance::Object::load).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::instance::Object;
+//! use xplm::scenery::{self, TerrainProbe};
+//!
+//! let mut probe = TerrainProbe::new();
+//! if let Some(hit) = probe.probe(0.0, 1000.0, 0.0) {
+//!     xplm::debugln!("Terrain elevation {} m, wet: {}", hit.y, hit.is_wet);
+//! }
+//!
+//! let paths = scenery::lookup_objects("lib/airport/vehicles/pushback/tug.obj", 47.45, -122.31)
+//!     .unwrap();
+//! if let Some(path) = paths.first() {
+//!     let tug = Object::load(path).unwrap();
+//! }
+//! ```
+//!
+
+use std::ffi::{CStr, CString, NulError};
+use std::mem;
+use std::os::raw::{c_char, c_void};
+
+use xplm_sys::*;
+
+/// Finds the terrain below or above points
+///
+/// Reusing a probe is faster than creating a new one for each query.
+#[derive(Debug)]
+pub struct TerrainProbe {
+    probe: XPLMProbeRef,
+}
+
+impl TerrainProbe {
+    /// Creates a probe
+    pub fn new() -> Self {
+        crate::debug_assert_main_thread!();
+        TerrainProbe {
+            probe: unsafe { XPLMCreateProbe(xplm_ProbeY as XPLMProbeType) },
+        }
+    }
+
+    /// Finds the terrain directly below or above a point in local coordinates
+    ///
+    /// This returns None if there is no terrain at the point's horizontal location.
+    pub fn probe(&mut self, x: f32, y: f32, z: f32) -> Option<ProbeHit> {
+        crate::debug_assert_main_thread!();
+        let mut info = XPLMProbeInfo_t {
+            structSize: mem::size_of::<XPLMProbeInfo_t>() as _,
+            locationX: 0.0,
+            locationY: 0.0,
+            locationZ: 0.0,
+            normalX: 0.0,
+            normalY: 0.0,
+            normalZ: 0.0,
+            velocityX: 0.0,
+            velocityY: 0.0,
+            velocityZ: 0.0,
+            is_wet: 0,
+        };
+        let result = unsafe { XPLMProbeTerrainXYZ(self.probe, x, y, z, &mut info) };
+        if result == xplm_ProbeHitTerrain as XPLMProbeResult {
+            Some(ProbeHit {
+                x: info.locationX,
+                y: info.locationY,
+                z: info.locationZ,
+                normal: [info.normalX, info.normalY, info.normalZ],
+                velocity: [info.velocityX, info.velocityY, info.velocityZ],
+                is_wet: info.is_wet != 0,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for TerrainProbe {
+    fn default() -> Self {
+        TerrainProbe::new()
+    }
+}
+
+impl Drop for TerrainProbe {
+    fn drop(&mut self) {
+        unsafe { XPLMDestroyProbe(self.probe) }
+    }
+}
+
+/// Returns the real paths of the objects that a virtual path in the scenery library refers to
+///
+/// The library can provide different objects in different regions, so the latitude and
+/// longitude of the place where the object will be drawn are needed. The paths are relative
+/// to the X-Plane folder. If the virtual path is not in the library, the result is empty.
+pub fn lookup_objects(
+    virtual_path: &str,
+    latitude: f32,
+    longitude: f32,
+) -> Result<Vec<String>, NulError> {
+    crate::debug_assert_main_thread!();
+    let virtual_path = CString::new(virtual_path)?;
+    let mut paths: Vec<String> = Vec::new();
+    let paths_ptr: *mut Vec<String> = &mut paths;
+    unsafe {
+        XPLMLookupObjects(
+            virtual_path.as_ptr(),
+            latitude,
+            longitude,
+            Some(library_callback),
+            paths_ptr.cast(),
+        );
+    }
+    Ok(paths)
+}
+
+/// Interprets refcon as a pointer to a `Vec<String>` and adds the path to it
+unsafe extern "C" fn library_callback(path: *const c_char, refcon: *mut c_void) {
+    let paths = refcon.cast::<Vec<String>>();
+    (*paths).push(CStr::from_ptr(path).to_string_lossy().into_owned());
+}
+
+/// A point on the terrain found by a probe
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
+pub struct ProbeHit {
+    /// The X coordinate of the terrain, in meters
+    pub x: f32,
+    /// The Y coordinate (elevation) of the terrain, in meters
+    pub y: f32,
+    /// The Z coordinate of the terrain, in meters
+    pub z: f32,
+    /// The normal vector of the terrain surface
+    pub normal: [f32; 3],
+    /// The velocity of the terrain surface, in meters per second, which is nonzero for
+    /// moving surfaces such as carrier decks
+    pub velocity: [f32; 3],
+    /// If the terrain is water
+    pub is_wet: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::library_callback;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_library_callback() {
+        let mut paths: Vec<String> = Vec::new();
+        let paths_ptr: *mut Vec<String> = &mut paths;
+        for path in [
+            "Resources/default scenery/tug.obj",
+            "Custom Scenery/tug.obj",
+        ] {
+            let path = CString::new(path).unwrap();
+            unsafe { library_callback(path.as_ptr(), paths_ptr.cast()) };
+        }
+        assert_eq!(
+            paths,
+            [
+                "Resources/default scenery/tug.obj",
+                "Custom Scenery/tug.obj"
+            ]
+        );
+    }
+}
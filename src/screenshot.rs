@@ -0,0 +1,270 @@
+//! # Screenshots
+//!
+//! Captures the X-Plane frame or the contents of a window and saves it to an image file.
+//!
+//! OpenGL can only be read back while X-Plane is drawing, so captures are queued and completed
+//! in a draw callback after X-Plane has drawn its windows.
+//!
+//! This module is only available with the `screenshot` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::screenshot::Screenshots;
+//!
+//! let screenshots = Screenshots::new().unwrap();
+//! // The file is written the next time X-Plane draws a frame
+//! screenshots.save_frame("frame.tga");
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::os::raw::{c_int, c_void};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use xplm_sys;
+
+use super::debugln;
+use super::draw::{self, Draw, Phase};
+use super::geometry::Rect;
+use super::gl::*;
+use super::window::Window;
+
+/// An RGBA image read from the frame buffer
+///
+/// Rows are stored from the bottom of the image to the top, as OpenGL provides them.
+#[derive(Debug, Clone)]
+pub struct Image {
+    /// The width in pixels
+    width: u32,
+    /// The height in pixels
+    height: u32,
+    /// The pixels, 4 bytes per pixel
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Returns the width of this image in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    /// Returns the height of this image in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    /// Returns the RGBA pixels of this image, starting with the bottom row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Writes this image to a file in the uncompressed TGA format
+    pub fn save_tga<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_tga(&mut out)?;
+        out.flush()
+    }
+
+    /// Writes this image in the uncompressed TGA format
+    pub fn write_tga<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if self.width > u32::from(u16::MAX) || self.height > u32::from(u16::MAX) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Image too large for TGA",
+            ));
+        }
+        let mut header = [0u8; 18];
+        // Uncompressed true-color image
+        header[2] = 2;
+        header[12..14].copy_from_slice(&(self.width as u16).to_le_bytes());
+        header[14..16].copy_from_slice(&(self.height as u16).to_le_bytes());
+        header[16] = 32;
+        // 8 alpha bits, origin at the bottom left
+        header[17] = 8;
+        out.write_all(&header)?;
+        // TGA stores pixels as BGRA
+        let mut bgra = Vec::with_capacity(self.pixels.len());
+        for pixel in self.pixels.chunks_exact(4) {
+            bgra.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+        out.write_all(&bgra)
+    }
+}
+
+/// Captures the frame or windows and delivers the images after X-Plane draws
+///
+/// Captures are queued and completed the next time X-Plane draws. While this object exists,
+/// a draw callback is registered.
+pub struct Screenshots {
+    /// Captures that have been requested but not completed
+    pending: Rc<RefCell<Vec<Capture>>>,
+    /// The draw callback that completes captures
+    _draw: Draw,
+}
+
+impl Screenshots {
+    /// Registers the draw callback used to capture images
+    pub fn new() -> Result<Self, draw::Error> {
+        let pending: Rc<RefCell<Vec<Capture>>> = Rc::new(RefCell::new(Vec::new()));
+        let callback_pending = Rc::clone(&pending);
+        let draw = Draw::new(Phase::AfterWindows, move || {
+            let captures = callback_pending.replace(Vec::new());
+            for capture in captures {
+                let image = read_pixels(capture.region);
+                (capture.handler)(image);
+            }
+        })?;
+        Ok(Screenshots {
+            pending,
+            _draw: draw,
+        })
+    }
+
+    /// Captures the whole X-Plane frame and passes the image to the provided handler
+    pub fn capture_frame<F: FnOnce(Image) + 'static>(&self, handler: F) {
+        self.request(Region::Frame, handler);
+    }
+
+    /// Captures the area of the screen covered by a window and passes the image to the
+    /// provided handler
+    ///
+    /// The window geometry is read when the capture is requested.
+    pub fn capture_window<F: FnOnce(Image) + 'static>(&self, window: &Window, handler: F) {
+        self.request(Region::Window(window.geometry()), handler);
+    }
+
+    /// Captures the whole X-Plane frame and saves it as a TGA file
+    ///
+    /// Errors writing the file are written to the log.
+    pub fn save_frame<P: Into<PathBuf>>(&self, path: P) {
+        let path = path.into();
+        self.capture_frame(move |image| save_or_log(&image, &path));
+    }
+
+    /// Captures the area of the screen covered by a window and saves it as a TGA file
+    ///
+    /// Errors writing the file are written to the log.
+    pub fn save_window<P: Into<PathBuf>>(&self, window: &Window, path: P) {
+        let path = path.into();
+        self.capture_window(window, move |image| save_or_log(&image, &path));
+    }
+
+    /// Returns the number of captures that have not been completed
+    pub fn pending(&self) -> usize {
+        self.pending.borrow().len()
+    }
+
+    fn request<F: FnOnce(Image) + 'static>(&self, region: Region, handler: F) {
+        self.pending.borrow_mut().push(Capture {
+            region,
+            handler: Box::new(handler),
+        });
+    }
+}
+
+/// A capture that has been requested
+struct Capture {
+    /// The area to read
+    region: Region,
+    /// The handler that receives the image
+    handler: Box<dyn FnOnce(Image)>,
+}
+
+/// Areas of the frame that can be captured
+#[derive(Debug, Copy, Clone)]
+enum Region {
+    /// The whole frame
+    Frame,
+    /// A window, in global desktop coordinates
+    Window(Rect<i32>),
+}
+
+fn save_or_log(image: &Image, path: &Path) {
+    if let Err(e) = image.save_tga(path) {
+        debugln!("[xplm] Failed to save screenshot {}: {}", path.display(), e);
+    }
+}
+
+/// Reads pixels from the current frame buffer
+///
+/// This must be called from a draw callback.
+fn read_pixels(region: Region) -> Image {
+    let mut viewport: [c_int; 4] = [0; 4];
+    unsafe { glGetIntegerv(GL_VIEWPORT, viewport.as_mut_ptr()) };
+    let [viewport_x, viewport_y, viewport_width, viewport_height] = viewport;
+
+    let (x, y, width, height) = match region {
+        Region::Frame => (viewport_x, viewport_y, viewport_width, viewport_height),
+        Region::Window(geometry) => {
+            // Window geometry is in boxels, which may be scaled relative to pixels
+            let mut screen_width = 0;
+            let mut screen_height = 0;
+            unsafe { xplm_sys::XPLMGetScreenSize(&mut screen_width, &mut screen_height) };
+            let scale_x = viewport_width as f32 / screen_width.max(1) as f32;
+            let scale_y = viewport_height as f32 / screen_height.max(1) as f32;
+            let left = (geometry.left() as f32 * scale_x) as c_int;
+            let bottom = (geometry.bottom() as f32 * scale_y) as c_int;
+            let right = (geometry.right() as f32 * scale_x) as c_int;
+            let top = (geometry.top() as f32 * scale_y) as c_int;
+            // Clip to the viewport
+            let left = left.clamp(0, viewport_width);
+            let right = right.clamp(left, viewport_width);
+            let bottom = bottom.clamp(0, viewport_height);
+            let top = top.clamp(bottom, viewport_height);
+            (
+                viewport_x + left,
+                viewport_y + bottom,
+                right - left,
+                top - bottom,
+            )
+        }
+    };
+
+    let width = width.max(0);
+    let height = height.max(0);
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    if !pixels.is_empty() {
+        unsafe {
+            // Restore the pack alignment that X-Plane set
+            let _guard = draw::gl_guard();
+            glPixelStorei(GL_PACK_ALIGNMENT, 1);
+            glReadPixels(
+                x,
+                y,
+                width,
+                height,
+                GL_RGBA,
+                GL_UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut c_void,
+            );
+        }
+    }
+    Image {
+        width: width as u32,
+        height: height as u32,
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Image;
+
+    /// Checks the TGA header and BGRA pixel order
+    #[test]
+    fn test_write_tga() {
+        let image = Image {
+            width: 1,
+            height: 1,
+            pixels: vec![1, 2, 3, 4],
+        };
+        let mut out = Vec::new();
+        image.write_tga(&mut out).unwrap();
+        assert_eq!(out.len(), 18 + 4);
+        assert_eq!(out[2], 2);
+        assert_eq!(&out[12..16], &[1, 0, 1, 0]);
+        assert_eq!(&out[18..], &[3, 2, 1, 4]);
+    }
+}
@@ -0,0 +1,356 @@
+//! # Serial port hardware bridge
+//!
+//! Connects cockpit hardware on a serial port to datarefs and commands.
+//!
+//! The serial port is read and written on background threads. Received lines are passed to the
+//! X-Plane thread through a channel and applied in a flight loop callback, so datarefs and
+//! commands are only accessed from the X-Plane thread.
+//!
+//! The hardware exchanges newline-terminated text lines. A line `KEY=VALUE` sets the input bound
+//! to `KEY` to the value, and a line `KEY` triggers the command bound to `KEY`. When a published
+//! dataref changes, the bridge sends `KEY=VALUE` to the hardware.
+//!
+//! This module is only available with the `serial` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::command::Command;
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::serial::{Mapping, SerialBridge};
+//!
+//! let mut mapping = Mapping::new();
+//! mapping.bind_command("GEAR", Command::find("sim/flight_controls/landing_gear_toggle").unwrap());
+//! mapping.bind_float(
+//!     "THR",
+//!     DataRef::find("sim/cockpit2/engine/actuators/throttle_ratio_all")
+//!         .unwrap()
+//!         .writeable()
+//!         .unwrap(),
+//! );
+//! mapping.publish_float(
+//!     "ALT",
+//!     DataRef::find("sim/cockpit2/gauges/indicators/altitude_ft_pilot").unwrap(),
+//! );
+//!
+//! let bridge = SerialBridge::open("/dev/ttyUSB0", 115_200, mapping).unwrap();
+//! ```
+//!
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serialport::SerialPort;
+
+use super::command::Command;
+use super::data::{DataRead, DataReadWrite};
+use super::debugln;
+use super::flight_loop::{FlightLoop, FlightLoopCallback, LoopState};
+
+/// How long a read can block before the reader thread checks if it should stop
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The maximum length of a received line in bytes
+///
+/// Longer lines are discarded, so that hardware that never sends a newline cannot use
+/// unlimited memory.
+const MAX_LINE_LENGTH: usize = 1024;
+
+/// Connections between serial messages and datarefs or commands
+#[derive(Default)]
+pub struct Mapping {
+    /// Inputs from the hardware, by key
+    inputs: HashMap<String, Input>,
+    /// Datarefs that are sent to the hardware
+    outputs: Vec<Output>,
+}
+
+impl Mapping {
+    /// Creates an empty mapping
+    pub fn new() -> Self {
+        Mapping::default()
+    }
+
+    /// Triggers a command when the hardware sends a line containing only the key
+    pub fn bind_command<K: Into<String>>(&mut self, key: K, command: Command) {
+        self.inputs.insert(key.into(), Input::Command(command));
+    }
+    /// Sets a floating-point dataref when the hardware sends `KEY=VALUE`
+    pub fn bind_float<K, D>(&mut self, key: K, dataref: D)
+    where
+        K: Into<String>,
+        D: DataReadWrite<f32> + 'static,
+    {
+        self.inputs
+            .insert(key.into(), Input::Float(Box::new(dataref)));
+    }
+    /// Sets an integer dataref when the hardware sends `KEY=VALUE`
+    pub fn bind_int<K, D>(&mut self, key: K, dataref: D)
+    where
+        K: Into<String>,
+        D: DataReadWrite<i32> + 'static,
+    {
+        self.inputs
+            .insert(key.into(), Input::Int(Box::new(dataref)));
+    }
+    /// Sends `KEY=VALUE` to the hardware whenever a floating-point dataref changes
+    pub fn publish_float<K, D>(&mut self, key: K, dataref: D)
+    where
+        K: Into<String>,
+        D: DataRead<f32> + 'static,
+    {
+        self.outputs.push(Output {
+            key: key.into(),
+            source: OutputSource::Float(Box::new(dataref)),
+            last_sent: None,
+        });
+    }
+    /// Sends `KEY=VALUE` to the hardware whenever an integer dataref changes
+    pub fn publish_int<K, D>(&mut self, key: K, dataref: D)
+    where
+        K: Into<String>,
+        D: DataRead<i32> + 'static,
+    {
+        self.outputs.push(Output {
+            key: key.into(),
+            source: OutputSource::Int(Box::new(dataref)),
+            last_sent: None,
+        });
+    }
+
+    /// Applies a line received from the hardware
+    fn apply(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim())),
+            None => (line, None),
+        };
+        match (self.inputs.get_mut(key), value) {
+            (Some(Input::Command(command)), None) => command.trigger(),
+            (Some(Input::Float(dataref)), Some(value)) => match value.parse() {
+                Ok(value) => dataref.set(value),
+                Err(_) => debugln!("[xplm] Serial: invalid value for {}: {}", key, value),
+            },
+            (Some(Input::Int(dataref)), Some(value)) => match value.parse() {
+                Ok(value) => dataref.set(value),
+                Err(_) => debugln!("[xplm] Serial: invalid value for {}: {}", key, value),
+            },
+            (Some(_), _) => debugln!("[xplm] Serial: unexpected message format: {}", line),
+            (None, _) => debugln!("[xplm] Serial: no binding for {}", key),
+        }
+    }
+
+    /// Sends the values of outputs that have changed
+    fn send_changes(&mut self, writer: &Sender<String>) {
+        for output in self.outputs.iter_mut() {
+            let value = output.source.read();
+            if output.last_sent.as_ref() != Some(&value) {
+                if writer.send(format!("{}={}\n", output.key, value)).is_err() {
+                    // Writer thread has stopped
+                    return;
+                }
+                output.last_sent = Some(value);
+            }
+        }
+    }
+}
+
+/// Something that the hardware can change
+enum Input {
+    Command(Command),
+    Float(Box<dyn DataReadWrite<f32>>),
+    Int(Box<dyn DataReadWrite<i32>>),
+}
+
+/// A dataref that is sent to the hardware
+struct Output {
+    /// The key used in messages
+    key: String,
+    /// The dataref to read
+    source: OutputSource,
+    /// The formatted value that was last sent
+    last_sent: Option<String>,
+}
+
+enum OutputSource {
+    Float(Box<dyn DataRead<f32>>),
+    Int(Box<dyn DataRead<i32>>),
+}
+
+impl OutputSource {
+    fn read(&self) -> String {
+        match self {
+            OutputSource::Float(dataref) => dataref.get().to_string(),
+            OutputSource::Int(dataref) => dataref.get().to_string(),
+        }
+    }
+}
+
+/// A connection between a serial port and X-Plane
+///
+/// The port is closed and the background threads stop when this object is dropped.
+pub struct SerialBridge {
+    /// Tells the reader thread to stop
+    stop: Arc<AtomicBool>,
+    /// Sends lines to the writer thread
+    ///
+    /// The writer thread stops after this and the flight loop are dropped.
+    writer: Sender<String>,
+    /// The reader thread
+    reader_thread: Option<JoinHandle<()>>,
+    /// The flight loop that applies received messages
+    _flight_loop: FlightLoop,
+}
+
+impl SerialBridge {
+    /// Opens a serial port and starts exchanging messages with the hardware
+    ///
+    /// Received messages are applied and changed outputs are sent once every flight loop.
+    pub fn open(path: &str, baud_rate: u32, mapping: Mapping) -> Result<Self, SerialError> {
+        let port = serialport::new(path, baud_rate)
+            .timeout(READ_TIMEOUT)
+            .open()?;
+        let write_port = port.try_clone()?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (line_sender, line_receiver) = mpsc::channel();
+        let (write_sender, write_receiver) = mpsc::channel();
+
+        // The writer thread is started first. If the reader thread cannot be started,
+        // write_sender is dropped on return and the writer thread exits.
+        thread::Builder::new()
+            .name("xplm serial writer".into())
+            .spawn(move || write_lines(write_port, write_receiver))?;
+        let reader_stop = Arc::clone(&stop);
+        let reader_thread = thread::Builder::new()
+            .name("xplm serial reader".into())
+            .spawn(move || read_lines(port, line_sender, reader_stop))?;
+
+        let mut flight_loop = FlightLoop::new(BridgeLoop {
+            mapping,
+            lines: line_receiver,
+            writer: write_sender.clone(),
+        });
+        flight_loop.schedule_immediate();
+
+        Ok(SerialBridge {
+            stop,
+            writer: write_sender,
+            reader_thread: Some(reader_thread),
+            _flight_loop: flight_loop,
+        })
+    }
+
+    /// Sends a line of text to the hardware
+    ///
+    /// A newline is added to the end of the line.
+    pub fn send<S: AsRef<str>>(&self, line: S) {
+        let _ = self.writer.send(format!("{}\n", line.as_ref()));
+    }
+}
+
+impl Drop for SerialBridge {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // The reader thread checks the stop flag at least once per read timeout
+        if let Some(thread) = self.reader_thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The flight loop callback that connects the channels to the mapping
+struct BridgeLoop {
+    mapping: Mapping,
+    lines: Receiver<String>,
+    writer: Sender<String>,
+}
+
+impl FlightLoopCallback for BridgeLoop {
+    fn flight_loop(&mut self, state: &mut LoopState) {
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => self.mapping.apply(&line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // The reader thread has stopped, so nothing more will arrive
+                    debugln!("[xplm] Serial: port closed");
+                    state.deactivate();
+                    return;
+                }
+            }
+        }
+        self.mapping.send_changes(&self.writer);
+    }
+}
+
+/// Reads lines from a port and sends them to the X-Plane thread until stop is set
+fn read_lines(mut port: Box<dyn SerialPort>, lines: Sender<String>, stop: Arc<AtomicBool>) {
+    let mut buffer = [0u8; 256];
+    let mut line = Vec::new();
+    // True if the current line is too long and is being discarded
+    let mut overflow = false;
+    while !stop.load(Ordering::SeqCst) {
+        match port.read(&mut buffer) {
+            // End of stream
+            Ok(0) => return,
+            Ok(count) => {
+                for &byte in &buffer[..count] {
+                    if byte == b'\n' {
+                        if overflow {
+                            crate::debugln_any_thread!(
+                                "[xplm] Serial: discarded a line longer than {} bytes",
+                                MAX_LINE_LENGTH
+                            );
+                            overflow = false;
+                        } else {
+                            let text = String::from_utf8_lossy(&line).into_owned();
+                            if lines.send(text).is_err() {
+                                return;
+                            }
+                        }
+                        line.clear();
+                    } else if line.len() < MAX_LINE_LENGTH {
+                        line.push(byte);
+                    } else {
+                        overflow = true;
+                    }
+                }
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            // Other errors (for example, the device was unplugged) end the connection
+            Err(_) => return,
+        }
+    }
+}
+
+/// Writes lines to a port until the sender is dropped
+fn write_lines(mut port: Box<dyn SerialPort>, lines: Receiver<String>) {
+    for line in lines {
+        if port.write_all(line.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Errors that can occur when opening a serial bridge
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum SerialError {
+    /// The serial port could not be opened
+    #[error("Serial port error: {0}")]
+    Port(#[from] serialport::Error),
+
+    /// A background thread could not be started
+    #[error("Thread error: {0}")]
+    Thread(#[from] io::Error),
+}
@@ -0,0 +1,252 @@
+//! # In-simulator tests
+//!
+//! Some behavior can only be checked while X-Plane is running. A [`TestSuite`] holds test
+//! cases that run inside the simulator. A [`TestCommand`] runs a suite when a command is
+//! triggered and writes the results to Log.txt.
+//!
+//! A test case returns `Ok(())` if it passes. It fails if it returns an error or panics.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::data::borrowed::DataRef;
+//! use xplm::data::DataRead;
+//! use xplm::testing::{TestCommand, TestSuite};
+//!
+//! let mut suite = TestSuite::new();
+//! suite.add("find latitude", || {
+//!     let latitude: DataRef<f64> = DataRef::find("sim/flightmodel/position/latitude")?;
+//!     if latitude.get().abs() > 90.0 {
+//!         return Err("Latitude out of range".into());
+//!     }
+//!     Ok(())
+//! });
+//! // Trigger myplugin/run_tests to run the tests
+//! let command = TestCommand::new("myplugin/run_tests", "Run tests", suite).unwrap();
+//! ```
+//!
+
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use super::command::{CommandCreateError, CommandHandler, OwnedCommand};
+use super::debugln;
+use super::panics::panic_message;
+
+/// The result of a test case
+pub type TestResult = Result<(), Box<dyn Error>>;
+
+/// A named test case
+struct TestCase {
+    name: String,
+    test: Box<dyn FnMut() -> TestResult>,
+}
+
+/// A list of test cases
+#[derive(Default)]
+pub struct TestSuite {
+    cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    /// Creates a suite with no test cases
+    pub fn new() -> Self {
+        TestSuite::default()
+    }
+
+    /// Adds a test case
+    ///
+    /// Test cases run in the order they were added.
+    pub fn add<S, F>(&mut self, name: S, test: F)
+    where
+        S: Into<String>,
+        F: FnMut() -> TestResult + 'static,
+    {
+        self.cases.push(TestCase {
+            name: name.into(),
+            test: Box::new(test),
+        });
+    }
+
+    /// Returns the number of test cases
+    pub fn len(&self) -> usize {
+        self.cases.len()
+    }
+
+    /// Returns true if this suite has no test cases
+    pub fn is_empty(&self) -> bool {
+        self.cases.is_empty()
+    }
+
+    /// Runs all test cases, writes the results to Log.txt, and returns the results
+    pub fn run(&mut self) -> TestReport {
+        debugln!("[xplm] Running {} tests", self.cases.len());
+        let report = self.run_cases();
+        report.log();
+        report
+    }
+
+    /// Runs all test cases and returns the results
+    fn run_cases(&mut self) -> TestReport {
+        let outcomes = self
+            .cases
+            .iter_mut()
+            .map(|case| {
+                let error = match panic::catch_unwind(AssertUnwindSafe(&mut case.test)) {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(e.to_string()),
+                    Err(payload) => Some(format!("Panicked: {}", panic_message(payload.as_ref()))),
+                };
+                TestOutcome {
+                    name: case.name.clone(),
+                    error,
+                }
+            })
+            .collect();
+        TestReport { outcomes }
+    }
+}
+
+impl fmt::Debug for TestSuite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.cases.iter().map(|case| &case.name))
+            .finish()
+    }
+}
+
+/// The result of one test case
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    name: String,
+    error: Option<String>,
+}
+
+impl TestOutcome {
+    /// Returns the name of the test case
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the failure message, or None if the test passed
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+    /// Returns true if the test passed
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.error {
+            None => write!(f, "{} ... ok", self.name),
+            Some(ref error) => write!(f, "{} ... FAILED: {}", self.name, error),
+        }
+    }
+}
+
+/// The results of running a test suite
+#[derive(Debug, Clone)]
+pub struct TestReport {
+    outcomes: Vec<TestOutcome>,
+}
+
+impl TestReport {
+    /// Returns the result of each test case, in the order they ran
+    pub fn outcomes(&self) -> &[TestOutcome] {
+        &self.outcomes
+    }
+    /// Returns the number of tests that passed
+    pub fn passed(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.passed())
+            .count()
+    }
+    /// Returns the number of tests that failed
+    pub fn failed(&self) -> usize {
+        self.outcomes.len() - self.passed()
+    }
+    /// Returns true if all tests passed
+    pub fn is_ok(&self) -> bool {
+        self.failed() == 0
+    }
+
+    /// Writes each result and a summary to Log.txt
+    pub fn log(&self) {
+        for outcome in &self.outcomes {
+            debugln!("[xplm] test {}", outcome);
+        }
+        debugln!("[xplm] {}", self);
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "test result: {}. {} passed; {} failed",
+            if self.is_ok() { "ok" } else { "FAILED" },
+            self.passed(),
+            self.failed()
+        )
+    }
+}
+
+/// A command that runs a test suite
+///
+/// The command stops running the tests when this object is dropped.
+pub struct TestCommand {
+    _command: OwnedCommand,
+}
+
+impl TestCommand {
+    /// Creates or finds a command that runs the provided suite when triggered
+    pub fn new(
+        name: &str,
+        description: &str,
+        suite: TestSuite,
+    ) -> Result<Self, CommandCreateError> {
+        Ok(TestCommand {
+            _command: OwnedCommand::new(name, description, RunHandler(suite))?,
+        })
+    }
+}
+
+/// Runs a suite when a command begins
+struct RunHandler(TestSuite);
+
+impl CommandHandler for RunHandler {
+    fn command_begin(&mut self) {
+        self.0.run();
+    }
+    fn command_continue(&mut self) {}
+    fn command_end(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TestSuite;
+
+    #[test]
+    fn test_run_cases() {
+        let mut suite = TestSuite::new();
+        suite.add("passes", || Ok(()));
+        suite.add("returns error", || Err("bad value".into()));
+        suite.add("panics", || panic!("oops"));
+        let report = suite.run_cases();
+        assert_eq!(report.passed(), 1);
+        assert_eq!(report.failed(), 2);
+        assert!(!report.is_ok());
+        let outcomes = report.outcomes();
+        assert_eq!(outcomes[0].to_string(), "passes ... ok");
+        assert_eq!(outcomes[1].error(), Some("bad value"));
+        assert_eq!(outcomes[2].error(), Some("Panicked: oops"));
+        assert_eq!(
+            report.to_string(),
+            "test result: FAILED. 1 passed; 2 failed"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! # The main thread
+//!
+//! X-Plane calls plugins from its main thread, and almost all SDK functions must only be
+//! called from that thread. Calling them from another thread can crash X-Plane.
+//!
+//! Types in this crate that refer to X-Plane objects, such as datarefs, commands, windows, and
+//! menus, do not implement `Send` or `Sync`, so they cannot be moved to or shared with other
+//! threads. In debug builds, the functions that create and use these objects also check that
+//! they are called from the main thread, using
+//! [`debug_assert_main_thread!`](crate::debug_assert_main_thread).
+//!
+//! Code on other threads can use [`CommandProxy`](crate::command::proxy::CommandProxy), the
+//! [`executor`](crate::executor) module, and the
+//! [`debugln_any_thread!`](crate::debugln_any_thread) macro to communicate with the main
+//! thread.
+//!
+//! The main thread is the thread that started the plugin.
+//!
+//! Moving a dataref to another thread does not compile:
+//!
+//! ```compile_fail
+//! use std::thread;
+//! use xplm::data::borrowed::DataRef;
+//!
+//! let latitude: DataRef<f64> = DataRef::find("sim/flightmodel/position/latitude").unwrap();
+//! thread::spawn(move || drop(latitude));
+//! ```
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::thread;
+//!
+//! assert!(xplm::thread::is_main_thread());
+//! thread::spawn(|| {
+//!     assert!(!xplm::thread::is_main_thread());
+//! });
+//! ```
+//!
+
+use std::sync::OnceLock;
+use std::thread::{self, ThreadId};
+
+/// The thread that started the plugin
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+/// Returns true if this function is called from the thread that started the plugin
+///
+/// This returns false if the plugin has not started.
+pub fn is_main_thread() -> bool {
+    MAIN_THREAD.get() == Some(&thread::current().id())
+}
+
+/// Returns true if this function is called from the main thread or the plugin has not started
+///
+/// This is used by [`debug_assert_main_thread!`](crate::debug_assert_main_thread), so that
+/// code that runs outside X-Plane, such as unit tests, does not fail the check.
+#[doc(hidden)]
+pub fn is_main_thread_or_unknown() -> bool {
+    MAIN_THREAD
+        .get()
+        .is_none_or(|main| *main == thread::current().id())
+}
+
+/// Records the main thread
+///
+/// This is called from XPluginStart.
+pub(crate) fn thread_init() {
+    let _ = MAIN_THREAD.set(thread::current().id());
+}
+
+/// Panics in debug builds if this is not the main thread
+///
+/// The check is skipped if the plugin has not started. In release builds, this does nothing.
+///
+/// # Examples
+///
+/// ```no_run
+/// fn update_display() {
+///     xplm::debug_assert_main_thread!();
+///     // Call SDK functions
+/// }
+/// ```
+#[macro_export]
+macro_rules! debug_assert_main_thread {
+    () => {
+        debug_assert!(
+            $crate::thread::is_main_thread_or_unknown(),
+            "X-Plane SDK functions must only be called from the main thread"
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    #[test]
+    fn test_unknown_main_thread() {
+        // The main thread is not set in tests
+        assert!(!super::is_main_thread());
+        assert!(super::is_main_thread_or_unknown());
+        crate::debug_assert_main_thread!();
+        thread::spawn(|| crate::debug_assert_main_thread!())
+            .join()
+            .unwrap();
+    }
+}
@@ -1,14 +1,44 @@
+//! # Version information
+//!
+//! [`versions`] returns the version of X-Plane, the version of the plugin SDK, and the host
+//! application. Plugins that support several versions of X-Plane can use it to avoid features
+//! that are not available.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! let versions = xplm::versions();
+//! xplm::debugln!("Running in {}", versions);
+//! if versions.xplane_major() >= 12 {
+//!     // Use X-Plane 12 datarefs
+//! }
+//! if !versions.supports_sdk_301() {
+//!     xplm::debugln!("Modern windows are not available");
+//! }
+//! ```
+//!
+
+use std::fmt;
+
 use xplm_sys;
 
-#[derive(Debug)]
+/// Versions of X-Plane and the plugin SDK
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct VersionInfo {
+    /// The X-Plane version, for example 12060 for X-Plane 12.06
     pub xplane_version: i32,
+    /// The plugin SDK version, for example 400 for SDK 4.0.0
     pub xplm_version: i32,
+    /// The host application ID
+    ///
+    /// This is 1 for X-Plane.
     pub host_id: i32,
 }
 
 impl VersionInfo {
+    /// Returns the versions of the running X-Plane and plugin SDK
     pub fn get() -> Self {
+        crate::debug_assert_main_thread!();
         let mut xplane_version: i32 = -1;
         let mut xplm_version: i32 = -1;
         let mut host_id: i32 = -1;
@@ -23,4 +53,87 @@ impl VersionInfo {
             host_id,
         }
     }
+
+    /// Returns the major X-Plane version, for example 12
+    pub fn xplane_major(&self) -> i32 {
+        self.xplane_version / 1000
+    }
+
+    /// Returns the plugin SDK version as major, minor, and patch numbers, for example
+    /// (3, 0, 1)
+    pub fn sdk_version(&self) -> (i32, i32, i32) {
+        (
+            self.xplm_version / 100,
+            self.xplm_version / 10 % 10,
+            self.xplm_version % 10,
+        )
+    }
+
+    /// Returns true if the plugin SDK version is at least the provided version
+    ///
+    /// The version is written in the same way as `xplm_version`, for example 301 for SDK 3.0.1.
+    pub fn supports_sdk(&self, version: i32) -> bool {
+        self.xplm_version >= version
+    }
+
+    /// Returns true if the plugin SDK version is at least 3.0.0 (X-Plane 11.10)
+    pub fn supports_sdk_300(&self) -> bool {
+        self.supports_sdk(300)
+    }
+
+    /// Returns true if the plugin SDK version is at least 3.0.1 (X-Plane 11.20)
+    pub fn supports_sdk_301(&self) -> bool {
+        self.supports_sdk(301)
+    }
+
+    /// Returns true if the plugin SDK version is at least 4.0.0 (X-Plane 12.04)
+    pub fn supports_sdk_400(&self) -> bool {
+        self.supports_sdk(400)
+    }
+
+    /// Returns true if the host application is X-Plane
+    pub fn is_xplane(&self) -> bool {
+        self.host_id == xplm_sys::xplm_Host_XPlane as i32
+    }
+}
+
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (major, minor, patch) = self.sdk_version();
+        write!(
+            f,
+            "X-Plane {}.{:02} (SDK {}.{}.{})",
+            self.xplane_major(),
+            self.xplane_version % 1000 / 10,
+            major,
+            minor,
+            patch
+        )
+    }
+}
+
+/// Returns the versions of the running X-Plane and plugin SDK
+///
+/// This is equivalent to [`VersionInfo::get`].
+pub fn versions() -> VersionInfo {
+    VersionInfo::get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VersionInfo;
+
+    #[test]
+    fn test_versions() {
+        let versions = VersionInfo {
+            xplane_version: 12060,
+            xplm_version: 301,
+            host_id: 1,
+        };
+        assert_eq!(versions.xplane_major(), 12);
+        assert_eq!(versions.sdk_version(), (3, 0, 1));
+        assert!(versions.supports_sdk_301());
+        assert!(!versions.supports_sdk_400());
+        assert_eq!(versions.to_string(), "X-Plane 12.06 (SDK 3.0.1)");
+    }
 }
@@ -0,0 +1,897 @@
+//! # Widgets
+//!
+//! Widgets are the native user interface elements of X-Plane: windows, panes, buttons, check
+//! boxes, text fields, captions, and scroll bars. They look like the rest of X-Plane's user
+//! interface and handle drawing, mouse input, and keyboard input themselves.
+//!
+//! A [`WidgetWindow`] is a root widget. Other widgets are created inside a parent widget.
+//! Widget geometry is in global window coordinates, like [`Window`](crate::window::Window)
+//! geometry.
+//!
+//! Each widget is destroyed when its object is dropped. Dropping a window also destroys all
+//! widgets inside it, and the objects of those widgets then have no effect.
+//!
+//! Widgets notify a [`WidgetDelegate`] when the user presses a button, changes a check box,
+//! edits a text field, moves a scroll bar, or closes a window. Notifications go to the widget
+//! that sent them and then to its parents, so one delegate on a window can handle the
+//! notifications of all widgets in it. A [`TextListener`] on a single text field receives its
+//! text whenever it is edited and when the user presses enter.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geometry::Rect;
+//! use xplm::widgets::{Button, Caption, CheckBox, WidgetDelegate, WidgetId, WidgetWindow};
+//!
+//! struct Settings {
+//!     apply: WidgetId,
+//! }
+//!
+//! impl WidgetDelegate for Settings {
+//!     fn button_pressed(&mut self, button: WidgetId) {
+//!         if button == self.apply {
+//!             xplm::debugln!("Apply pressed");
+//!         }
+//!     }
+//!     fn button_state_changed(&mut self, _button: WidgetId, checked: bool) {
+//!         xplm::debugln!("Checked: {}", checked);
+//!     }
+//! }
+//!
+//! let window = WidgetWindow::new(Rect::from_left_top_right_bottom(100, 400, 400, 200), "Settings")
+//!     .unwrap();
+//! window.set_close_box(true);
+//! let _caption = Caption::new(
+//!     &window,
+//!     Rect::from_left_top_right_bottom(110, 370, 390, 350),
+//!     "Display options",
+//! )
+//! .unwrap();
+//! let _check = CheckBox::new(&window, Rect::from_left_top_right_bottom(110, 340, 130, 320)).unwrap();
+//! let apply = Button::new(
+//!     &window,
+//!     Rect::from_left_top_right_bottom(300, 230, 390, 210),
+//!     "Apply",
+//! )
+//! .unwrap();
+//! window.set_delegate(Settings { apply: apply.id() });
+//! ```
+//!
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ffi::{CString, NulError};
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, Range};
+use std::os::raw::c_int;
+use std::ptr;
+use std::rc::Rc;
+
+use xplm_sys::*;
+
+use super::geometry::Rect;
+
+/// Identifies a widget
+///
+/// Delegates receive the ID of the widget that sent a notification. It can be compared with
+/// [`Widget::id`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WidgetId(XPWidgetID);
+
+/// Receives notifications from widgets
+///
+/// All functions have default implementations that do nothing, except
+/// [`close_button_pushed`](WidgetDelegate::close_button_pushed), which hides the window.
+pub trait WidgetDelegate: 'static {
+    /// Called when the user presses a push button
+    fn button_pressed(&mut self, _button: WidgetId) {}
+    /// Called when the user checks or unchecks a check box
+    fn button_state_changed(&mut self, _button: WidgetId, _checked: bool) {}
+    /// Called when the user changes the text in a text field
+    fn text_changed(&mut self, _field: WidgetId) {}
+    /// Called when the user moves a scroll bar
+    fn scroll_bar_moved(&mut self, _scroll_bar: WidgetId, _position: i32) {}
+    /// Called when the user clicks the close box of a window
+    ///
+    /// The default implementation hides the window.
+    fn close_button_pushed(&mut self, window: &Widget) {
+        window.set_visible(false);
+    }
+}
+
+/// Receives the text of a text field when it changes
+///
+/// Both functions have default implementations that do nothing.
+pub trait TextListener: 'static {
+    /// Called after the user types, deletes, or pastes text
+    fn text_edited(&mut self, _text: &str) {}
+    /// Called when the user presses return or enter
+    fn enter_pressed(&mut self, _text: &str) {}
+}
+
+/// A notification that a widget sends to its delegate
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Notification {
+    ButtonPressed(WidgetId),
+    ButtonStateChanged(WidgetId, bool),
+    TextChanged(WidgetId),
+    ScrollBarMoved(WidgetId, i32),
+    CloseButtonPushed,
+    /// The return or enter key was pressed in the widget that has keyboard focus
+    EnterPressed,
+}
+
+impl Notification {
+    /// Converts a widget message into a notification, if it is one
+    fn from_message(message: XPWidgetMessage, param1: isize, param2: isize) -> Option<Self> {
+        let source = WidgetId(param1 as XPWidgetID);
+        let notification = match message as u32 {
+            xplm_sys::xpMsg_PushButtonPressed => Notification::ButtonPressed(source),
+            xplm_sys::xpMsg_ButtonStateChanged => {
+                Notification::ButtonStateChanged(source, param2 != 0)
+            }
+            xplm_sys::xpMsg_TextFieldChanged => Notification::TextChanged(source),
+            xplm_sys::xpMsg_ScrollBarSliderPositionChanged => {
+                Notification::ScrollBarMoved(source, param2 as i32)
+            }
+            xplm_sys::xpMessage_CloseButtonPushed => Notification::CloseButtonPushed,
+            _ => return None,
+        };
+        Some(notification)
+    }
+
+    /// Converts a key press into a notification, if it is one
+    fn from_key(key: &XPKeyState_t) -> Option<Self> {
+        let down = key.flags & xplm_DownFlag as XPLMKeyFlags != 0;
+        let enter = matches!(
+            key.vkey as u8 as u32,
+            xplm_sys::XPLM_VK_RETURN | xplm_sys::XPLM_VK_ENTER
+        );
+        if down && enter {
+            Some(Notification::EnterPressed)
+        } else {
+            None
+        }
+    }
+}
+
+/// The handlers of a widget, shared between the widget objects and the widget callback
+#[derive(Default)]
+struct Handlers {
+    delegate: Option<Box<dyn WidgetDelegate>>,
+    text_listener: Option<Box<dyn TextListener>>,
+}
+
+impl Handlers {
+    /// Passes a notification to the handlers and returns true if it was handled
+    fn deliver(&mut self, widget: XPWidgetID, notification: Notification) -> bool {
+        if let Some(listener) = self.text_listener.as_mut() {
+            match notification {
+                Notification::TextChanged(field) if field.0 == widget => {
+                    listener.text_edited(&descriptor_of(widget))
+                }
+                Notification::EnterPressed => {
+                    listener.enter_pressed(&descriptor_of(widget));
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        match self.delegate.as_mut() {
+            Some(delegate) => deliver(delegate.as_mut(), widget, notification),
+            None => false,
+        }
+    }
+}
+
+thread_local! {
+    /// The handlers of each widget that has any
+    ///
+    /// The widget callback removes the handlers of a widget when it is destroyed.
+    static HANDLERS: RefCell<HashMap<WidgetId, Rc<RefCell<Handlers>>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Returns the handlers of a widget, if it has any
+fn handlers_of(widget: XPWidgetID) -> Option<Rc<RefCell<Handlers>>> {
+    HANDLERS.with(|handlers| handlers.borrow().get(&WidgetId(widget)).cloned())
+}
+
+/// State shared by all widgets in a window
+#[derive(Debug)]
+struct Tree {
+    /// False after the root widget has been destroyed
+    alive: Cell<bool>,
+}
+
+/// A widget
+///
+/// The typed widgets, such as [`Button`], dereference to this type.
+pub struct Widget {
+    id: XPWidgetID,
+    tree: Rc<Tree>,
+    /// True if this is the root widget of its tree
+    root: bool,
+}
+
+impl Widget {
+    /// Creates a widget of a standard class
+    fn create(
+        geometry: Rect<i32>,
+        descriptor: &str,
+        parent: Option<&Widget>,
+        class: u32,
+    ) -> Result<Self, NulError> {
+        crate::debug_assert_main_thread!();
+        let descriptor = CString::new(descriptor)?;
+        let id = unsafe {
+            XPCreateWidget(
+                geometry.left(),
+                geometry.top(),
+                geometry.right(),
+                geometry.bottom(),
+                1,
+                descriptor.as_ptr(),
+                parent.is_none() as c_int,
+                parent.map_or(ptr::null_mut(), |parent| parent.id),
+                class as XPWidgetClass,
+            )
+        };
+        Ok(Widget {
+            id,
+            tree: parent.map_or_else(
+                || {
+                    Rc::new(Tree {
+                        alive: Cell::new(true),
+                    })
+                },
+                |parent| Rc::clone(&parent.tree),
+            ),
+            root: parent.is_none(),
+        })
+    }
+
+    /// Returns true if this widget still exists
+    fn exists(&self) -> bool {
+        self.tree.alive.get()
+    }
+
+    /// Returns the ID of this widget
+    pub fn id(&self) -> WidgetId {
+        WidgetId(self.id)
+    }
+
+    /// Returns the geometry of this widget in global window coordinates
+    pub fn geometry(&self) -> Rect<i32> {
+        let (mut left, mut top, mut right, mut bottom) = (0, 0, 0, 0);
+        if self.exists() {
+            unsafe { XPGetWidgetGeometry(self.id, &mut left, &mut top, &mut right, &mut bottom) };
+        }
+        Rect::from_left_top_right_bottom(left, top, right, bottom)
+    }
+
+    /// Moves or resizes this widget
+    ///
+    /// Moving a widget does not move the widgets inside it.
+    pub fn set_geometry(&self, geometry: Rect<i32>) {
+        if self.exists() {
+            unsafe {
+                XPSetWidgetGeometry(
+                    self.id,
+                    geometry.left(),
+                    geometry.top(),
+                    geometry.right(),
+                    geometry.bottom(),
+                )
+            };
+        }
+    }
+
+    /// Returns the descriptor of this widget
+    ///
+    /// The descriptor is the title of a window, the label of a button, or the text of a text
+    /// field or caption.
+    pub fn descriptor(&self) -> String {
+        if self.exists() {
+            descriptor_of(self.id)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Sets the descriptor of this widget
+    pub fn set_descriptor(&self, descriptor: &str) -> Result<(), NulError> {
+        let descriptor = CString::new(descriptor)?;
+        if self.exists() {
+            unsafe { XPSetWidgetDescriptor(self.id, descriptor.as_ptr()) };
+        }
+        Ok(())
+    }
+
+    /// Returns true if this widget is visible
+    ///
+    /// A widget inside a hidden widget can be visible but not shown.
+    pub fn visible(&self) -> bool {
+        self.exists() && unsafe { XPIsWidgetVisible(self.id) != 0 }
+    }
+
+    /// Shows or hides this widget
+    pub fn set_visible(&self, visible: bool) {
+        if self.exists() {
+            if visible {
+                unsafe { XPShowWidget(self.id) };
+            } else {
+                unsafe { XPHideWidget(self.id) };
+            }
+        }
+    }
+
+    /// Returns true if this widget is enabled
+    pub fn enabled(&self) -> bool {
+        self.property(xpProperty_Enabled as i32).unwrap_or(0) != 0
+    }
+
+    /// Enables or disables this widget
+    ///
+    /// Disabled widgets are drawn differently and do not respond to the user.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.set_property(xpProperty_Enabled as i32, enabled as isize);
+    }
+
+    /// Returns the value of a widget property, or None if this widget does not have it
+    pub fn property(&self, property: i32) -> Option<isize> {
+        if !self.exists() {
+            return None;
+        }
+        let mut exists: c_int = 0;
+        let value = unsafe { XPGetWidgetProperty(self.id, property, &mut exists) };
+        if exists != 0 {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Sets the value of a widget property
+    pub fn set_property(&self, property: i32, value: isize) {
+        if self.exists() {
+            unsafe { XPSetWidgetProperty(self.id, property, value) };
+        }
+    }
+
+    /// Gives this widget keyboard focus
+    pub fn take_keyboard_focus(&self) {
+        if self.exists() {
+            unsafe { XPSetKeyboardFocus(self.id) };
+        }
+    }
+
+    /// Removes keyboard focus from this widget and the widgets inside it
+    pub fn release_keyboard_focus(&self) {
+        if self.exists() {
+            unsafe { XPLoseKeyboardFocus(self.id) };
+        }
+    }
+
+    /// Sets the delegate that receives notifications from this widget and the widgets inside
+    /// it
+    ///
+    /// This replaces any delegate that was set before.
+    pub fn set_delegate<D: WidgetDelegate>(&self, delegate: D) {
+        self.with_handlers(|handlers| handlers.delegate = Some(Box::new(delegate)));
+    }
+
+    /// Calls a function with the handlers of this widget, creating them if necessary
+    ///
+    /// The function is not called if the handlers are running.
+    fn with_handlers<F: FnOnce(&mut Handlers)>(&self, f: F) {
+        if !self.exists() {
+            return;
+        }
+        let cell = handlers_of(self.id).unwrap_or_else(|| {
+            let cell = Rc::new(RefCell::new(Handlers::default()));
+            HANDLERS.with(|handlers| {
+                handlers
+                    .borrow_mut()
+                    .insert(WidgetId(self.id), Rc::clone(&cell))
+            });
+            unsafe { XPAddWidgetCallback(self.id, Some(widget_callback)) };
+            cell
+        });
+        if let Ok(mut handlers) = cell.try_borrow_mut() {
+            f(&mut handlers);
+        };
+    }
+}
+
+/// Returns the descriptor of a widget that exists
+fn descriptor_of(widget: XPWidgetID) -> String {
+    let length = unsafe { XPGetWidgetDescriptor(widget, ptr::null_mut(), 0) };
+    let mut buffer = vec![0u8; length.max(0) as usize + 1];
+    unsafe { XPGetWidgetDescriptor(widget, buffer.as_mut_ptr().cast(), length + 1) };
+    buffer.truncate(length.max(0) as usize);
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+impl Drop for Widget {
+    fn drop(&mut self) {
+        if self.exists() {
+            unsafe { XPDestroyWidget(self.id, self.root as c_int) };
+            if self.root {
+                self.tree.alive.set(false);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Widget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Widget")
+            .field("id", &self.id)
+            .field("exists", &self.exists())
+            .finish()
+    }
+}
+
+/// Passes notifications to the handlers of a widget
+unsafe extern "C" fn widget_callback(
+    message: XPWidgetMessage,
+    widget: XPWidgetID,
+    param1: isize,
+    param2: isize,
+) -> c_int {
+    // Keep the handlers alive even if a handler destroys the widget
+    let Some(cell) = handlers_of(widget) else {
+        return 0;
+    };
+    let cell_ptr = Rc::as_ptr(&cell);
+    let notification = match message as u32 {
+        xplm_sys::xpMsg_Destroy => {
+            HANDLERS.with(|handlers| handlers.borrow_mut().remove(&WidgetId(widget)));
+            crate::panics::forget(cell_ptr);
+            return 0;
+        }
+        xplm_sys::xpMsg_KeyPress => Notification::from_key(&*(param1 as *const XPKeyState_t)),
+        _ => Notification::from_message(message, param1, param2),
+    };
+    let Some(notification) = notification else {
+        return 0;
+    };
+    let handled = match cell.try_borrow_mut() {
+        Ok(mut handlers) => crate::panics::contain("widget", cell_ptr, false, || {
+            handlers.deliver(widget, notification)
+        }),
+        // The handlers are already running
+        Err(_) => false,
+    };
+    handled as c_int
+}
+
+/// Calls the delegate function for a notification and returns true if it was handled
+fn deliver(
+    delegate: &mut dyn WidgetDelegate,
+    widget: XPWidgetID,
+    notification: Notification,
+) -> bool {
+    match notification {
+        Notification::ButtonPressed(button) => delegate.button_pressed(button),
+        Notification::ButtonStateChanged(button, checked) => {
+            delegate.button_state_changed(button, checked)
+        }
+        Notification::TextChanged(field) => delegate.text_changed(field),
+        Notification::ScrollBarMoved(scroll_bar, position) => {
+            delegate.scroll_bar_moved(scroll_bar, position)
+        }
+        Notification::CloseButtonPushed => {
+            // A temporary object that refers to the window without owning it. It is never
+            // dropped, so it cannot destroy the window, even if the delegate panics.
+            let window = ManuallyDrop::new(Widget {
+                id: widget,
+                tree: Rc::new(Tree {
+                    alive: Cell::new(true),
+                }),
+                root: false,
+            });
+            delegate.close_button_pushed(&window);
+            // Release the tree without dropping the widget
+            let window = ManuallyDrop::into_inner(window);
+            window.tree.alive.set(false);
+        }
+        Notification::EnterPressed => return false,
+    }
+    true
+}
+
+/// Implements Deref to Widget for a typed widget
+macro_rules! deref_widget {
+    ($type: ty) => {
+        impl Deref for $type {
+            type Target = Widget;
+            fn deref(&self) -> &Widget {
+                &self.widget
+            }
+        }
+    };
+}
+
+/// A window that contains other widgets
+#[derive(Debug)]
+pub struct WidgetWindow {
+    widget: Widget,
+}
+
+deref_widget!(WidgetWindow);
+
+impl WidgetWindow {
+    /// Creates a visible window with a title
+    pub fn new(geometry: Rect<i32>, title: &str) -> Result<Self, NulError> {
+        Ok(WidgetWindow {
+            widget: Widget::create(geometry, title, None, xpWidgetClass_MainWindow)?,
+        })
+    }
+
+    /// Shows or hides the close box in the title bar
+    ///
+    /// Clicking the close box calls
+    /// [`WidgetDelegate::close_button_pushed`].
+    pub fn set_close_box(&self, close_box: bool) {
+        self.set_property(
+            xpProperty_MainWindowHasCloseBoxes as i32,
+            close_box as isize,
+        );
+    }
+
+    /// Draws this window as a dark translucent window instead of a normal window
+    pub fn set_translucent(&self, translucent: bool) {
+        let style = if translucent {
+            xpMainWindowStyle_Translucent
+        } else {
+            xpMainWindowStyle_MainWindow
+        };
+        self.set_property(xpProperty_MainWindowType as i32, style as isize);
+    }
+
+    /// Moves this window in front of other windows
+    pub fn bring_to_front(&self) {
+        if self.exists() {
+            unsafe { XPBringRootWidgetToFront(self.id) };
+        }
+    }
+}
+
+/// The styles of a pane
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PaneStyle {
+    /// A recessed area of a window
+    #[default]
+    SubWindow,
+    /// A dark screen, like a display
+    Screen,
+    /// A white area for lists
+    ListView,
+}
+
+/// A rectangular area inside a window that groups other widgets
+#[derive(Debug)]
+pub struct Pane {
+    widget: Widget,
+}
+
+deref_widget!(Pane);
+
+impl Pane {
+    /// Creates a pane
+    pub fn new(parent: &Widget, geometry: Rect<i32>) -> Result<Self, NulError> {
+        Ok(Pane {
+            widget: Widget::create(geometry, "", Some(parent), xpWidgetClass_SubWindow)?,
+        })
+    }
+
+    /// Sets the style of this pane
+    pub fn set_style(&self, style: PaneStyle) {
+        let style = match style {
+            PaneStyle::SubWindow => xpSubWindowStyle_SubWindow,
+            PaneStyle::Screen => xpSubWindowStyle_Screen,
+            PaneStyle::ListView => xpSubWindowStyle_ListView,
+        };
+        self.set_property(xpProperty_SubWindowType as i32, style as isize);
+    }
+}
+
+/// A push button
+#[derive(Debug)]
+pub struct Button {
+    widget: Widget,
+}
+
+deref_widget!(Button);
+
+impl Button {
+    /// Creates a button with a label
+    ///
+    /// Pressing the button calls [`WidgetDelegate::button_pressed`].
+    pub fn new(parent: &Widget, geometry: Rect<i32>, label: &str) -> Result<Self, NulError> {
+        let widget = Widget::create(geometry, label, Some(parent), xpWidgetClass_Button)?;
+        widget.set_property(xpProperty_ButtonType as i32, xpPushButton as isize);
+        widget.set_property(
+            xpProperty_ButtonBehavior as i32,
+            xpButtonBehaviorPushButton as isize,
+        );
+        Ok(Button { widget })
+    }
+}
+
+/// A check box
+#[derive(Debug)]
+pub struct CheckBox {
+    widget: Widget,
+}
+
+deref_widget!(CheckBox);
+
+impl CheckBox {
+    /// Creates an unchecked check box
+    ///
+    /// Check boxes do not have labels. A [`Caption`] can be placed next to the check box.
+    /// Checking or unchecking it calls [`WidgetDelegate::button_state_changed`].
+    pub fn new(parent: &Widget, geometry: Rect<i32>) -> Result<Self, NulError> {
+        let widget = Widget::create(geometry, "", Some(parent), xpWidgetClass_Button)?;
+        widget.set_property(xpProperty_ButtonType as i32, xpRadioButton as isize);
+        widget.set_property(
+            xpProperty_ButtonBehavior as i32,
+            xpButtonBehaviorCheckBox as isize,
+        );
+        Ok(CheckBox { widget })
+    }
+
+    /// Returns true if this check box is checked
+    pub fn checked(&self) -> bool {
+        self.property(xpProperty_ButtonState as i32).unwrap_or(0) != 0
+    }
+
+    /// Checks or unchecks this check box
+    pub fn set_checked(&self, checked: bool) {
+        self.set_property(xpProperty_ButtonState as i32, checked as isize);
+    }
+}
+
+/// A single-line text field
+#[derive(Debug)]
+pub struct TextField {
+    widget: Widget,
+}
+
+deref_widget!(TextField);
+
+impl TextField {
+    /// Creates an empty text field
+    ///
+    /// Editing the text calls [`WidgetDelegate::text_changed`] and
+    /// [`TextListener::text_edited`].
+    pub fn new(parent: &Widget, geometry: Rect<i32>) -> Result<Self, NulError> {
+        let widget = Widget::create(geometry, "", Some(parent), xpWidgetClass_TextField)?;
+        widget.set_property(xpProperty_TextFieldType as i32, xpTextEntryField as isize);
+        Ok(TextField { widget })
+    }
+
+    /// Returns the text in this field
+    pub fn text(&self) -> String {
+        self.descriptor()
+    }
+
+    /// Replaces the text in this field
+    ///
+    /// This does not notify the delegate or listener.
+    pub fn set_text(&self, text: &str) -> Result<(), NulError> {
+        self.set_descriptor(text)
+    }
+
+    /// Sets the listener that receives the text of this field when it changes
+    ///
+    /// This replaces any listener that was set before. When a listener is set, the return
+    /// and enter keys are handled by the listener and are not passed to other widgets.
+    pub fn set_listener<L: TextListener>(&self, listener: L) {
+        self.with_handlers(|handlers| handlers.text_listener = Some(Box::new(listener)));
+    }
+
+    /// Returns the selected part of the text as a range of byte offsets
+    ///
+    /// If nothing is selected, the range is empty and starts at the cursor.
+    pub fn selection(&self) -> Range<usize> {
+        selection_range(
+            self.property(xpProperty_EditFieldSelStart as i32)
+                .unwrap_or(0),
+            self.property(xpProperty_EditFieldSelEnd as i32)
+                .unwrap_or(0),
+        )
+    }
+
+    /// Selects part of the text
+    ///
+    /// An empty range moves the cursor to its start. Offsets past the end of the text select
+    /// up to the end.
+    pub fn set_selection(&self, selection: Range<usize>) {
+        let length = self.text().len();
+        let start = selection.start.min(length);
+        let end = selection.end.clamp(start, length);
+        self.set_property(xpProperty_EditFieldSelStart as i32, start as isize);
+        self.set_property(xpProperty_EditFieldSelEnd as i32, end as isize);
+        self.set_property(xpProperty_EditFieldSelDragStart as i32, start as isize);
+    }
+
+    /// Selects all of the text
+    pub fn select_all(&self) {
+        self.set_selection(0..usize::MAX);
+    }
+
+    /// Moves the cursor to a byte offset and selects nothing
+    pub fn set_cursor(&self, position: usize) {
+        self.set_selection(position..position);
+    }
+
+    /// Shows asterisks instead of the text, or shows the text normally
+    pub fn set_password_mode(&self, password: bool) {
+        self.set_property(xpProperty_PasswordMode as i32, password as isize);
+    }
+
+    /// Sets the maximum number of characters that the user can enter, or 0 for no limit
+    pub fn set_max_characters(&self, max: usize) {
+        self.set_property(xpProperty_MaxCharacters as i32, max as isize);
+    }
+}
+
+/// Converts selection properties into a range, putting the start first
+fn selection_range(start: isize, end: isize) -> Range<usize> {
+    let start = start.max(0) as usize;
+    let end = end.max(0) as usize;
+    start.min(end)..start.max(end)
+}
+
+/// A text label
+#[derive(Debug)]
+pub struct Caption {
+    widget: Widget,
+}
+
+deref_widget!(Caption);
+
+impl Caption {
+    /// Creates a caption
+    pub fn new(parent: &Widget, geometry: Rect<i32>, text: &str) -> Result<Self, NulError> {
+        Ok(Caption {
+            widget: Widget::create(geometry, text, Some(parent), xpWidgetClass_Caption)?,
+        })
+    }
+
+    /// Replaces the text of this caption
+    pub fn set_text(&self, text: &str) -> Result<(), NulError> {
+        self.set_descriptor(text)
+    }
+
+    /// Draws the text in a lit color for dark backgrounds, or in the normal color
+    pub fn set_lit(&self, lit: bool) {
+        self.set_property(xpProperty_CaptionLit as i32, lit as isize);
+    }
+}
+
+/// The styles of a scroll bar
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ScrollBarStyle {
+    /// A scroll bar with arrows at each end
+    #[default]
+    ScrollBar,
+    /// A slider with a knob
+    Slider,
+}
+
+/// A scroll bar or slider
+///
+/// Scroll bars that are taller than they are wide are vertical.
+#[derive(Debug)]
+pub struct ScrollBar {
+    widget: Widget,
+}
+
+deref_widget!(ScrollBar);
+
+impl ScrollBar {
+    /// Creates a scroll bar with a range of positions, at the minimum position
+    ///
+    /// Moving it calls [`WidgetDelegate::scroll_bar_moved`].
+    pub fn new(
+        parent: &Widget,
+        geometry: Rect<i32>,
+        style: ScrollBarStyle,
+        min: i32,
+        max: i32,
+    ) -> Result<Self, NulError> {
+        let widget = Widget::create(geometry, "", Some(parent), xpWidgetClass_ScrollBar)?;
+        let style = match style {
+            ScrollBarStyle::ScrollBar => xpScrollBarTypeScrollBar,
+            ScrollBarStyle::Slider => xpScrollBarTypeSlider,
+        };
+        widget.set_property(xpProperty_ScrollBarType as i32, style as isize);
+        let scroll_bar = ScrollBar { widget };
+        scroll_bar.set_range(min, max);
+        scroll_bar.set_position(min);
+        Ok(scroll_bar)
+    }
+
+    /// Returns the current position
+    pub fn position(&self) -> i32 {
+        self.property(xpProperty_ScrollBarSliderPosition as i32)
+            .unwrap_or(0) as i32
+    }
+
+    /// Sets the current position
+    pub fn set_position(&self, position: i32) {
+        self.set_property(xpProperty_ScrollBarSliderPosition as i32, position as isize);
+    }
+
+    /// Sets the minimum and maximum positions
+    pub fn set_range(&self, min: i32, max: i32) {
+        self.set_property(xpProperty_ScrollBarMin as i32, min as isize);
+        self.set_property(xpProperty_ScrollBarMax as i32, max as isize);
+    }
+
+    /// Sets how far the position changes when the user clicks beside the knob
+    pub fn set_page_amount(&self, amount: i32) {
+        self.set_property(xpProperty_ScrollBarPageAmount as i32, amount as isize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{selection_range, Notification, WidgetId};
+    use std::ptr;
+    use xplm_sys::*;
+
+    #[test]
+    fn test_notifications() {
+        let source = WidgetId(ptr::null_mut::<u8>().wrapping_add(16).cast());
+        assert_eq!(
+            Notification::from_message(xpMsg_ButtonStateChanged as i32, 16, 1),
+            Some(Notification::ButtonStateChanged(source, true))
+        );
+        assert_eq!(
+            Notification::from_message(xpMsg_ScrollBarSliderPositionChanged as i32, 16, -5),
+            Some(Notification::ScrollBarMoved(source, -5))
+        );
+        assert_eq!(
+            Notification::from_message(xpMessage_CloseButtonPushed as i32, 16, 0),
+            Some(Notification::CloseButtonPushed)
+        );
+        assert_eq!(Notification::from_message(xpMsg_Paint as i32, 0, 0), None);
+    }
+
+    #[test]
+    fn test_key_notifications() {
+        let key = |vkey: u32, flags: u32| XPKeyState_t {
+            key: 0,
+            flags: flags as XPLMKeyFlags,
+            vkey: vkey as u8 as _,
+        };
+        assert_eq!(
+            Notification::from_key(&key(XPLM_VK_RETURN, xplm_DownFlag)),
+            Some(Notification::EnterPressed)
+        );
+        assert_eq!(
+            Notification::from_key(&key(XPLM_VK_ENTER, xplm_DownFlag)),
+            Some(Notification::EnterPressed)
+        );
+        assert_eq!(
+            Notification::from_key(&key(XPLM_VK_RETURN, xplm_UpFlag)),
+            None
+        );
+        assert_eq!(Notification::from_key(&key(XPLM_VK_A, xplm_DownFlag)), None);
+    }
+
+    #[test]
+    fn test_selection_range() {
+        assert_eq!(selection_range(2, 5), 2..5);
+        assert_eq!(selection_range(5, 2), 2..5);
+        assert_eq!(selection_range(-1, 3), 0..3);
+        assert!(selection_range(4, 4).is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::{CString, NulError};
 use std::mem;
 use std::ops::Deref;
 use std::os::raw::*;
@@ -5,12 +7,32 @@ use std::ptr;
 
 use xplm_sys;
 
+use super::data::borrowed::DataRef;
+use super::data::DataRead;
 use super::geometry::{Point, Rect};
+use super::plugin::messages::{XPLM_MSG_ENTERED_VR, XPLM_MSG_EXITING_VR};
+
+/// Groups of windows that are managed together
+pub mod group;
+/// Clickable regions of the 2D panel
+pub mod hotspot;
+/// Monitor bounds and coordinate systems
+pub mod monitor;
+/// Saving and restoring window positions, including popped-out windows
+pub mod placement;
+/// Drag-to-resize behavior for undecorated windows
+pub mod resize;
+/// Backgrounds and borders that match X-Plane windows
+pub mod style;
+/// Immediate-mode user interface controls drawn in windows
+pub mod ui;
 
 /// Cursor states that windows can apply
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub enum Cursor {
     /// X-Plane draws the default cursor
+    #[default]
     Default,
     /// X-Plane draws an arrow cursor (not any other cursor type)
     Arrow,
@@ -29,12 +51,6 @@ impl Cursor {
     }
 }
 
-impl Default for Cursor {
-    fn default() -> Self {
-        Cursor::Default
-    }
-}
-
 /// Trait for things that can define the behavior of a window
 pub trait WindowDelegate: 'static {
     /// Draws this window
@@ -43,6 +59,13 @@ pub trait WindowDelegate: 'static {
     ///
     /// The default implementation does nothing
     fn keyboard_event(&mut self, _window: &Window, _event: KeyEvent) {}
+    /// Called when this window loses keyboard focus
+    ///
+    /// This happens when another window takes focus, when the user clicks outside the window,
+    /// and when the window gives up focus.
+    ///
+    /// The default implementation does nothing
+    fn focus_lost(&mut self, _window: &Window) {}
     /// Handles a mouse event
     ///
     /// Return false to consume the event or true to propagate it.
@@ -59,6 +82,15 @@ pub trait WindowDelegate: 'static {
     fn scroll_event(&mut self, _window: &Window, _event: ScrollEvent) -> bool {
         true
     }
+    /// Returns the distance that one click of a scroll wheel moves along an axis
+    ///
+    /// This sets the [`delta`](ScrollEvent::delta) and [`steps`](ScrollEvent::steps) of scroll
+    /// events. For example, a speed of 0.25 produces one step for every four clicks.
+    ///
+    /// The default implementation returns 1.
+    fn scroll_speed(&mut self, _window: &Window, _axis: ScrollAxis) -> f32 {
+        1.0
+    }
     /// Tells X-Plane what cursor to draw over a section of the window
     ///
     /// The default implementation allows X-Plane to draw the default cursor.
@@ -80,6 +112,10 @@ impl Deref for WindowRef {
     }
 }
 
+/// An identifier for a window created by this plugin
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WindowId(xplm_sys::XPLMWindowID);
+
 /// A basic window that may appear on the screen
 ///
 /// A window has a position and size, but no appearance. Plugins must draw in their draw callbacks
@@ -89,18 +125,57 @@ pub struct Window {
     id: xplm_sys::XPLMWindowID,
     /// The delegate
     delegate: Box<dyn WindowDelegate>,
+    /// If this window should move into VR when the user enters VR
+    follow_vr: Cell<bool>,
+    /// The positioning mode most recently set
+    positioning_mode: Cell<PositioningMode>,
+    /// The 2D geometry and positioning mode of this window from before it was moved into VR
+    placement_before_vr: Cell<Option<(Rect<i32>, PositioningMode)>>,
+    /// The minimum and maximum width and height
+    resizing_limits: Cell<SizeLimits>,
+    /// The fractions of a step that have been scrolled but not delivered
+    scroll: Cell<ScrollAccumulator>,
+    /// True while a delegate function is running
+    delegate_active: Cell<bool>,
+    /// True if the window lost focus while a delegate function was running
+    focus_lost_pending: Cell<bool>,
 }
 
 impl Window {
     /// Creates a new window with the provided geometry and returns a reference to it
     ///
     /// The window is originally not visible.
+    ///
+    /// The window has no decoration and is in the floating window layer. Use a
+    /// [`WindowBuilder`] to create a window with other options.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new<R: Into<Rect<i32>>, D: WindowDelegate>(geometry: R, delegate: D) -> WindowRef {
-        let geometry = geometry.into();
+        Window::create(
+            geometry.into(),
+            delegate,
+            Decoration::None,
+            Layer::FloatingWindows,
+        )
+    }
 
+    /// Creates a window with a decoration and layer
+    fn create<D: WindowDelegate>(
+        geometry: Rect<i32>,
+        delegate: D,
+        decoration: Decoration,
+        layer: Layer,
+    ) -> WindowRef {
+        crate::debug_assert_main_thread!();
         let mut window_box = Box::new(Window {
             id: ptr::null_mut(),
             delegate: Box::new(delegate),
+            follow_vr: Cell::new(false),
+            positioning_mode: Cell::new(PositioningMode::Free),
+            placement_before_vr: Cell::new(None),
+            resizing_limits: Cell::new(SizeLimits::default()),
+            scroll: Cell::new(ScrollAccumulator::default()),
+            delegate_active: Cell::new(false),
+            focus_lost_pending: Cell::new(false),
         });
         let window_ptr: *mut Window = &mut *window_box;
 
@@ -117,17 +192,57 @@ impl Window {
             handleCursorFunc: Some(window_cursor),
             handleMouseWheelFunc: Some(window_scroll),
             refcon: window_ptr as *mut _,
-            decorateAsFloatingWindow: 0,
-            layer: xplm_sys::xplm_WindowLayerFloatingWindows as _,
+            decorateAsFloatingWindow: decoration.to_xplm(),
+            layer: layer.to_xplm(),
             handleRightClickFunc: None,
         };
 
         let window_id = unsafe { xplm_sys::XPLMCreateWindowEx(&mut window_info) };
         window_box.id = window_id;
+        if !window_id.is_null() {
+            WINDOWS.with(|windows| windows.borrow_mut().push(WindowId(window_id)));
+        }
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::created("Window", window_ptr);
 
         WindowRef { window: window_box }
     }
 
+    /// Creates a new window with the provided geometry and returns a reference to it, or
+    /// returns an error if X-Plane could not create the window
+    ///
+    /// The window is originally not visible.
+    pub fn try_new<R: Into<Rect<i32>>, D: WindowDelegate>(
+        geometry: R,
+        delegate: D,
+    ) -> Result<WindowRef, WindowCreateError> {
+        WindowBuilder::new(geometry).build(delegate)
+    }
+
+    /// Creates a window and checks that X-Plane created it
+    fn try_create<D: WindowDelegate>(
+        geometry: Rect<i32>,
+        delegate: D,
+        decoration: Decoration,
+        layer: Layer,
+    ) -> Result<WindowRef, WindowCreateError> {
+        #[cfg(feature = "fault-injection")]
+        if super::fault::triggered(super::fault::Fault::WindowCreate, None) {
+            return Err(WindowCreateError);
+        }
+        let window = Window::create(geometry, delegate, decoration, layer);
+        if window.id.is_null() {
+            Err(WindowCreateError)
+        } else {
+            Ok(window)
+        }
+    }
+
+    /// Returns the identifier of this window
+    pub fn id(&self) -> WindowId {
+        WindowId(self.id)
+    }
+
     /// Returns the geometry of this window
     pub fn geometry(&self) -> Rect<i32> {
         unsafe {
@@ -153,6 +268,138 @@ impl Window {
         }
     }
 
+    /// Returns true if this window is in its own operating system window
+    pub fn is_popped_out(&self) -> bool {
+        1 == unsafe { xplm_sys::XPLMWindowIsPoppedOut(self.id) }
+    }
+    /// Moves this window into or out of its own operating system window
+    ///
+    /// A popped-out window can be moved to any monitor, including monitors that X-Plane is
+    /// not using. Use [`set_os_geometry`](Window::set_os_geometry) to position it, for example
+    /// with a monitor from [`monitor::monitors_os`].
+    ///
+    /// When the window is moved back into X-Plane, it can be moved anywhere in the X-Plane
+    /// window, as with [`PositioningMode::Free`].
+    pub fn set_popped_out(&self, popped_out: bool) {
+        if popped_out {
+            self.set_positioning_mode(PositioningMode::PopOut);
+        } else if self.is_popped_out() {
+            self.set_positioning_mode(PositioningMode::Free);
+        }
+    }
+    /// Returns the geometry of this window in operating system coordinates
+    ///
+    /// This is only meaningful if the window is popped out.
+    pub fn os_geometry(&self) -> Rect<i32> {
+        unsafe {
+            let mut left = 0;
+            let mut top = 0;
+            let mut right = 0;
+            let mut bottom = 0;
+            xplm_sys::XPLMGetWindowGeometryOS(
+                self.id,
+                &mut left,
+                &mut top,
+                &mut right,
+                &mut bottom,
+            );
+            Rect::from_left_top_right_bottom(left, top, right, bottom)
+        }
+    }
+    /// Sets the geometry of this window in operating system coordinates
+    ///
+    /// This has no effect unless the window is popped out.
+    pub fn set_os_geometry<R: Into<Rect<i32>>>(&self, geometry: R) {
+        let geometry = geometry.into();
+        unsafe {
+            xplm_sys::XPLMSetWindowGeometryOS(
+                self.id,
+                geometry.left(),
+                geometry.top(),
+                geometry.right(),
+                geometry.bottom(),
+            );
+        }
+    }
+
+    /// Returns the minimum and maximum size of this window
+    pub fn resizing_limits(&self) -> SizeLimits {
+        self.resizing_limits.get()
+    }
+    /// Sets the minimum and maximum size of this window
+    ///
+    /// X-Plane applies these limits when the user resizes a decorated window.
+    /// A [`ResizeGrip`](resize::ResizeGrip) applies them to undecorated windows.
+    pub fn set_resizing_limits(&self, limits: SizeLimits) {
+        self.resizing_limits.set(limits);
+        unsafe {
+            xplm_sys::XPLMSetWindowResizingLimits(
+                self.id,
+                limits.min_width,
+                limits.min_height,
+                limits.max_width,
+                limits.max_height,
+            );
+        }
+    }
+
+    /// Sets the title of this window
+    ///
+    /// X-Plane shows the title in the title bar of a window with
+    /// [`Decoration::Floating`] and in the operating system window of a popped-out window.
+    pub fn set_title(&self, title: &str) -> Result<(), NulError> {
+        let title_c = CString::new(title)?;
+        unsafe {
+            xplm_sys::XPLMSetWindowTitle(self.id, title_c.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Gives this window keyboard focus
+    ///
+    /// While this window has focus, keyboard events go to its
+    /// [`keyboard_event`](WindowDelegate::keyboard_event) function instead of X-Plane.
+    pub fn take_keyboard_focus(&self) {
+        unsafe {
+            xplm_sys::XPLMTakeKeyboardFocus(self.id);
+        }
+    }
+    /// Returns keyboard focus to X-Plane if this window has it
+    ///
+    /// Returns true if this window had focus. The delegate's
+    /// [`focus_lost`](WindowDelegate::focus_lost) function is called before this returns, or,
+    /// if this is called from a delegate function of this window, after that function returns.
+    pub fn give_up_focus(&self) -> bool {
+        if self.has_keyboard_focus() {
+            unsafe {
+                xplm_sys::XPLMTakeKeyboardFocus(ptr::null_mut());
+            }
+            true
+        } else {
+            false
+        }
+    }
+    /// Returns keyboard focus to X-Plane if this window has it
+    #[deprecated(note = "Use give_up_focus")]
+    pub fn release_keyboard_focus(&self) {
+        self.give_up_focus();
+    }
+    /// Returns true if this window has keyboard focus
+    pub fn has_keyboard_focus(&self) -> bool {
+        1 == unsafe { xplm_sys::XPLMHasKeyboardFocus(self.id) }
+    }
+
+    /// Moves this window in front of the other windows in its layer
+    pub fn bring_to_front(&self) {
+        unsafe {
+            xplm_sys::XPLMBringWindowToFront(self.id);
+        }
+    }
+    /// Returns true if this window is in front of the other windows in its layer
+    pub fn is_in_front(&self) -> bool {
+        1 == unsafe { xplm_sys::XPLMIsWindowInFront(self.id) }
+    }
+
     /// Returns true if this window is visible
     pub fn visible(&self) -> bool {
         1 == unsafe { xplm_sys::XPLMGetWindowIsVisible(self.id) }
@@ -163,20 +410,527 @@ impl Window {
             xplm_sys::XPLMSetWindowIsVisible(self.id, visible as _);
         }
     }
+
+    /// Sets how X-Plane positions this window
+    ///
+    /// Windows that follow VR (see [`set_follow_vr`](Window::set_follow_vr)) change their
+    /// positioning mode when the user enters or exits VR.
+    pub fn set_positioning_mode(&self, mode: PositioningMode) {
+        self.positioning_mode.set(mode);
+        let (mode, monitor) = mode.to_xplm();
+        unsafe {
+            xplm_sys::XPLMSetWindowPositioningMode(self.id, mode, monitor);
+        }
+    }
+
+    /// Sets how this window moves when the X-Plane window is resized
+    pub fn set_gravity(&self, gravity: Gravity) {
+        unsafe {
+            xplm_sys::XPLMSetWindowGravity(
+                self.id,
+                gravity.left,
+                gravity.top,
+                gravity.right,
+                gravity.bottom,
+            );
+        }
+    }
+
+    /// Returns true if this window is currently displayed in VR
+    pub fn in_vr(&self) -> bool {
+        1 == unsafe { xplm_sys::XPLMWindowIsInVR(self.id) }
+    }
+    /// Returns the width and height of this window in VR, in boxels
+    ///
+    /// This is only meaningful if the window is in VR.
+    pub fn vr_size(&self) -> (i32, i32) {
+        let mut width = 0;
+        let mut height = 0;
+        unsafe {
+            xplm_sys::XPLMGetWindowGeometryVR(self.id, &mut width, &mut height);
+        }
+        (width, height)
+    }
+    /// Sets the width and height of this window in VR, in boxels
+    ///
+    /// This has no effect unless the window is in VR.
+    pub fn set_vr_size(&self, width: i32, height: i32) {
+        unsafe {
+            xplm_sys::XPLMSetWindowGeometryVR(self.id, width, height);
+        }
+    }
+    /// Returns true if this window automatically moves into VR
+    pub fn follows_vr(&self) -> bool {
+        self.follow_vr.get()
+    }
+    /// Enables or disables automatic migration of this window into VR
+    ///
+    /// When enabled, this window moves into VR when the user enters VR. When the user exits VR,
+    /// the window returns to the positioning mode and 2D position it had before. This is normally called just after
+    /// the window is created. If VR is already active, the window moves into VR immediately.
+    pub fn set_follow_vr(&self, follow: bool) {
+        if follow == self.follow_vr.get() {
+            return;
+        }
+        self.follow_vr.set(follow);
+        let window_ptr: *const Window = self;
+        VR_WINDOWS.with(|windows| {
+            let mut windows = windows.borrow_mut();
+            if follow {
+                windows.push(window_ptr);
+            } else {
+                windows.retain(|&other| other != window_ptr);
+            }
+        });
+        if follow && vr_enabled() {
+            self.enter_vr();
+        } else if !follow && self.in_vr() {
+            self.exit_vr();
+        }
+    }
+
+    /// Saves the 2D geometry and positioning mode of this window and moves it into VR
+    fn enter_vr(&self) {
+        if self.in_vr() {
+            return;
+        }
+        // The user can pop a window out or back in, so the popped-out state comes from X-Plane
+        let placement = if self.is_popped_out() {
+            (self.os_geometry(), PositioningMode::PopOut)
+        } else {
+            let mode = match self.positioning_mode.get() {
+                PositioningMode::PopOut | PositioningMode::Vr => PositioningMode::Free,
+                mode => mode,
+            };
+            (self.geometry(), mode)
+        };
+        self.placement_before_vr.set(Some(placement));
+        self.set_positioning_mode(PositioningMode::Vr);
+    }
+    /// Moves this window out of VR and restores its saved 2D geometry and positioning mode
+    fn exit_vr(&self) {
+        match self.placement_before_vr.take() {
+            Some((geometry, PositioningMode::PopOut)) => {
+                self.set_positioning_mode(PositioningMode::PopOut);
+                self.set_os_geometry(geometry);
+            }
+            Some((geometry, mode)) => {
+                self.set_positioning_mode(mode);
+                self.set_geometry(geometry);
+            }
+            None => self.set_positioning_mode(PositioningMode::Free),
+        }
+    }
 }
 
 impl Drop for Window {
     fn drop(&mut self) {
-        unsafe {
-            xplm_sys::XPLMDestroyWindow(self.id);
+        #[cfg(feature = "drop-diagnostics")]
+        super::diagnostics::dropped("Window", self);
+        super::panics::forget(self);
+        let id = self.id();
+        WINDOWS.with(|windows| windows.borrow_mut().retain(|&other| other != id));
+        if self.follow_vr.get() {
+            let window_ptr: *const Window = self;
+            VR_WINDOWS.with(|windows| windows.borrow_mut().retain(|&other| other != window_ptr));
+        }
+        if !self.id.is_null() {
+            unsafe {
+                xplm_sys::XPLMDestroyWindow(self.id);
+            }
+        }
+    }
+}
+
+/// The minimum and maximum width and height of a window, in pixels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// The minimum width
+    pub min_width: i32,
+    /// The minimum height
+    pub min_height: i32,
+    /// The maximum width
+    pub max_width: i32,
+    /// The maximum height
+    pub max_height: i32,
+}
+
+impl SizeLimits {
+    /// Creates limits with a minimum and maximum (width, height)
+    pub fn new(min: (i32, i32), max: (i32, i32)) -> Self {
+        SizeLimits {
+            min_width: min.0,
+            min_height: min.1,
+            max_width: max.0,
+            max_height: max.1,
+        }
+    }
+
+    /// Limits a width and height
+    pub fn apply(&self, width: i32, height: i32) -> (i32, i32) {
+        (
+            width.max(self.min_width).min(self.max_width),
+            height.max(self.min_height).min(self.max_height),
+        )
+    }
+}
+
+/// The default limits allow any size
+impl Default for SizeLimits {
+    fn default() -> Self {
+        SizeLimits::new((0, 0), (i32::MAX, i32::MAX))
+    }
+}
+
+/// Creates windows with options that [`Window::new`] does not provide
+///
+/// # Examples
+///
+/// ```no_run
+/// use xplm::geometry::Rect;
+/// use xplm::window::{Decoration, Gravity, Layer, Window, WindowBuilder, WindowDelegate};
+///
+/// struct Notification;
+/// impl WindowDelegate for Notification {
+///     fn draw(&mut self, _window: &Window) {}
+/// }
+///
+/// let window = WindowBuilder::new(Rect::from_left_top_right_bottom(20, 120, 320, 20))
+///     .decoration(Decoration::Floating)
+///     .layer(Layer::GrowlNotifications)
+///     .gravity(Gravity::BOTTOM_LEFT)
+///     .visible(true)
+///     .build(Notification)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct WindowBuilder {
+    geometry: Rect<i32>,
+    decoration: Decoration,
+    layer: Layer,
+    positioning_mode: Option<PositioningMode>,
+    gravity: Option<Gravity>,
+    resizing_limits: Option<SizeLimits>,
+    visible: bool,
+}
+
+impl WindowBuilder {
+    /// Creates a builder for an invisible window with the provided geometry
+    ///
+    /// By default, the window has no decoration and is in the floating window layer.
+    pub fn new<R: Into<Rect<i32>>>(geometry: R) -> Self {
+        WindowBuilder {
+            geometry: geometry.into(),
+            decoration: Decoration::None,
+            layer: Layer::FloatingWindows,
+            positioning_mode: None,
+            gravity: None,
+            resizing_limits: None,
+            visible: false,
+        }
+    }
+
+    /// Sets how X-Plane decorates the window
+    pub fn decoration(self, decoration: Decoration) -> Self {
+        WindowBuilder { decoration, ..self }
+    }
+
+    /// Sets the layer that the window appears in
+    pub fn layer(self, layer: Layer) -> Self {
+        WindowBuilder { layer, ..self }
+    }
+
+    /// Sets how X-Plane positions the window
+    pub fn positioning_mode(self, mode: PositioningMode) -> Self {
+        WindowBuilder {
+            positioning_mode: Some(mode),
+            ..self
+        }
+    }
+
+    /// Sets how the window moves when the X-Plane window is resized
+    pub fn gravity(self, gravity: Gravity) -> Self {
+        WindowBuilder {
+            gravity: Some(gravity),
+            ..self
+        }
+    }
+
+    /// Sets the minimum and maximum size of the window
+    pub fn resizing_limits(self, limits: SizeLimits) -> Self {
+        WindowBuilder {
+            resizing_limits: Some(limits),
+            ..self
+        }
+    }
+
+    /// Sets if the window is visible when it is created
+    pub fn visible(self, visible: bool) -> Self {
+        WindowBuilder { visible, ..self }
+    }
+
+    /// Creates the window
+    pub fn build<D: WindowDelegate>(self, delegate: D) -> Result<WindowRef, WindowCreateError> {
+        let window = Window::try_create(self.geometry, delegate, self.decoration, self.layer)?;
+        if let Some(gravity) = self.gravity {
+            window.set_gravity(gravity);
+        }
+        if let Some(limits) = self.resizing_limits {
+            window.set_resizing_limits(limits);
+        }
+        if let Some(mode) = self.positioning_mode {
+            window.set_positioning_mode(mode);
+        }
+        if self.visible {
+            window.set_visible(true);
+        }
+        Ok(window)
+    }
+}
+
+/// How X-Plane draws the frame of a window
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Decoration {
+    /// X-Plane draws nothing around the window
+    None,
+    /// X-Plane draws a standard floating window frame with a title bar and close button
+    Floating,
+    /// The plugin draws the window frame
+    SelfDecorated,
+    /// The plugin draws the window frame, and X-Plane lets the user resize the window
+    SelfDecoratedResizable,
+}
+
+impl Decoration {
+    fn to_xplm(self) -> xplm_sys::XPLMWindowDecoration {
+        let decoration = match self {
+            Decoration::None => xplm_sys::xplm_WindowDecorationNone,
+            Decoration::Floating => xplm_sys::xplm_WindowDecorationRoundRectangle,
+            Decoration::SelfDecorated => xplm_sys::xplm_WindowDecorationSelfDecorated,
+            Decoration::SelfDecoratedResizable => {
+                xplm_sys::xplm_WindowDecorationSelfDecoratedResizable
+            }
+        };
+        decoration as _
+    }
+}
+
+/// The layers that windows can appear in, from bottom to top
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Layer {
+    /// Below all floating windows, for instruments drawn over the simulator view
+    FlightOverlay,
+    /// The layer of normal windows
+    FloatingWindows,
+    /// Above floating windows, for windows that need an immediate response from the user
+    Modal,
+    /// Above all other windows, for short notifications
+    GrowlNotifications,
+}
+
+impl Layer {
+    fn to_xplm(self) -> xplm_sys::XPLMWindowLayer {
+        let layer = match self {
+            Layer::FlightOverlay => xplm_sys::xplm_WindowLayerFlightOverlay,
+            Layer::FloatingWindows => xplm_sys::xplm_WindowLayerFloatingWindows,
+            Layer::Modal => xplm_sys::xplm_WindowLayerModal,
+            Layer::GrowlNotifications => xplm_sys::xplm_WindowLayerGrowlNotifications,
+        };
+        layer as _
+    }
+}
+
+/// Ways that X-Plane can position a window
+///
+/// Monitor indices are the same as those used by X-Plane's monitor bounds functions. A monitor
+/// of None means the main monitor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PositioningMode {
+    /// The window can be moved anywhere in the X-Plane window
+    Free,
+    /// The window is centered on a monitor
+    CenterOnMonitor(Option<i32>),
+    /// The window fills a monitor
+    FullScreenOnMonitor(Option<i32>),
+    /// The window fills all monitors
+    FullScreenOnAllMonitors,
+    /// The window is in its own operating system window
+    PopOut,
+    /// The window is displayed in VR
+    Vr,
+}
+
+impl PositioningMode {
+    /// Returns the mode and monitor index
+    fn to_xplm(self) -> (xplm_sys::XPLMWindowPositioningMode, c_int) {
+        let (mode, monitor) = match self {
+            PositioningMode::Free => (xplm_sys::xplm_WindowPositionFree, None),
+            PositioningMode::CenterOnMonitor(monitor) => {
+                (xplm_sys::xplm_WindowCenterOnMonitor, monitor)
+            }
+            PositioningMode::FullScreenOnMonitor(monitor) => {
+                (xplm_sys::xplm_WindowFullScreenOnMonitor, monitor)
+            }
+            PositioningMode::FullScreenOnAllMonitors => {
+                (xplm_sys::xplm_WindowFullScreenOnAllMonitors, None)
+            }
+            PositioningMode::PopOut => (xplm_sys::xplm_WindowPopOut, None),
+            PositioningMode::Vr => (xplm_sys::xplm_WindowVR, None),
+        };
+        (mode as _, monitor.unwrap_or(-1))
+    }
+}
+
+/// How the edges of a window move when the X-Plane window is resized
+///
+/// Each value is the fraction of the change in the X-Plane window size that is applied to an
+/// edge. For the left and right edges, 0 keeps the edge a fixed distance from the left side of
+/// the X-Plane window and 1 keeps it a fixed distance from the right side. For the top and
+/// bottom edges, 0 keeps the edge a fixed distance from the bottom and 1 keeps it a fixed
+/// distance from the top.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Gravity {
+    /// The gravity of the left edge
+    pub left: f32,
+    /// The gravity of the top edge
+    pub top: f32,
+    /// The gravity of the right edge
+    pub right: f32,
+    /// The gravity of the bottom edge
+    pub bottom: f32,
+}
+
+impl Gravity {
+    /// Keeps the window a fixed distance from the top left corner (the default)
+    pub const TOP_LEFT: Gravity = Gravity::new(0.0, 1.0, 0.0, 1.0);
+    /// Keeps the window a fixed distance from the top right corner
+    pub const TOP_RIGHT: Gravity = Gravity::new(1.0, 1.0, 1.0, 1.0);
+    /// Keeps the window a fixed distance from the bottom left corner
+    pub const BOTTOM_LEFT: Gravity = Gravity::new(0.0, 0.0, 0.0, 0.0);
+    /// Keeps the window a fixed distance from the bottom right corner
+    pub const BOTTOM_RIGHT: Gravity = Gravity::new(1.0, 0.0, 1.0, 0.0);
+    /// Keeps each edge a fixed distance from the nearest side, so that the window stretches
+    pub const STRETCH: Gravity = Gravity::new(0.0, 1.0, 1.0, 0.0);
+
+    /// Creates a gravity from the values for each edge
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Gravity {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity::TOP_LEFT
+    }
+}
+
+/// An error returned when X-Plane cannot create a window
+#[derive(thiserror::Error, Debug)]
+#[error("Could not create window")]
+pub struct WindowCreateError;
+
+thread_local! {
+    /// The windows that this plugin has created and not yet destroyed
+    static WINDOWS: RefCell<Vec<WindowId>> = const { RefCell::new(Vec::new()) };
+    /// Windows that move into VR when the user enters VR
+    ///
+    /// Each window is allocated in a Box and removes itself from this list when it is dropped.
+    static VR_WINDOWS: RefCell<Vec<*const Window>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the window created by this plugin that has keyboard focus, if any
+pub(crate) fn focused_window() -> Option<WindowId> {
+    WINDOWS.with(|windows| {
+        windows
+            .borrow()
+            .iter()
+            .copied()
+            .find(|window| 1 == unsafe { xplm_sys::XPLMHasKeyboardFocus(window.0) })
+    })
+}
+
+/// Returns true if X-Plane is currently displaying in VR
+///
+/// Plugins can implement [`Plugin::vr_changed`](crate::plugin::Plugin::vr_changed) to find out
+/// when this changes.
+pub fn vr_enabled() -> bool {
+    DataRef::<bool>::find("sim/graphics/VR/enabled")
+        .map(|enabled| enabled.get())
+        .unwrap_or(false)
+}
+
+/// Moves windows that follow VR in or out of VR in response to a plugin message
+///
+/// This is called from the XPluginReceiveMessage callback before the message is passed on to
+/// the plugin.
+pub(crate) fn handle_vr_message(message: c_int) {
+    if message != XPLM_MSG_ENTERED_VR && message != XPLM_MSG_EXITING_VR {
+        return;
+    }
+    // Iterate over a copy so that the list can change during the calls below. A window that is
+    // dropped removes itself from the list, so each window is checked before it is used.
+    let windows = VR_WINDOWS.with(|windows| windows.borrow().clone());
+    for window in windows {
+        if !VR_WINDOWS.with(|windows| windows.borrow().contains(&window)) {
+            continue;
+        }
+        let window = unsafe { &*window };
+        if message == XPLM_MSG_ENTERED_VR {
+            window.enter_vr();
+        } else {
+            window.exit_vr();
         }
     }
 }
 
+/// Calls a delegate function of a window and catches any panic
+///
+/// The delegate is not called, and this returns default, if another delegate function of the
+/// window is already running. If the window lost focus while the delegate function was
+/// running, focus_lost is called afterwards.
+unsafe fn call_delegate<R>(
+    window: *mut Window,
+    kind: &'static str,
+    default: R,
+    f: impl FnOnce(&mut dyn WindowDelegate, &Window) -> R,
+) -> R {
+    if (*window).delegate_active.get() {
+        return default;
+    }
+    (*window).delegate_active.set(true);
+    let result = super::panics::contain(kind, window, default, || {
+        f((*window).delegate.as_mut(), &*window)
+    });
+    if (*window).focus_lost_pending.take() {
+        super::panics::contain("window keyboard", window, (), || {
+            (*window).delegate.focus_lost(&*window)
+        });
+    }
+    (*window).delegate_active.set(false);
+    result
+}
+
 /// Callback in which windows are drawn
 unsafe extern "C" fn window_draw(_window: xplm_sys::XPLMWindowID, refcon: *mut c_void) {
+    if super::internal::suspended() {
+        return;
+    }
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("Window", refcon) {
+        return;
+    }
     let window = refcon as *mut Window;
-    (*window).delegate.draw(&*window);
+    call_delegate(window, "window draw", (), |delegate, window| {
+        delegate.draw(window)
+    });
 }
 
 /// Keyboard callback
@@ -188,12 +942,30 @@ unsafe extern "C" fn window_key(
     refcon: *mut c_void,
     losing_focus: c_int,
 ) {
+    if super::internal::suspended() {
+        return;
+    }
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("Window", refcon) {
+        return;
+    }
     let window = refcon as *mut Window;
-    if losing_focus == 0 {
-        match KeyEvent::from_xplm(key, flags, virtual_key) {
-            Ok(event) => (*window).delegate.keyboard_event(&*window, event),
-            Err(e) => super::debugln!("Invalid key event received: {:?}", e),
+    if losing_focus != 0 {
+        if (*window).delegate_active.get() {
+            // The window gave up focus in a delegate function
+            (*window).focus_lost_pending.set(true);
+        } else {
+            call_delegate(window, "window keyboard", (), |delegate, window| {
+                delegate.focus_lost(window)
+            });
         }
+        return;
+    }
+    match KeyEvent::from_xplm(key, flags, virtual_key) {
+        Ok(event) => call_delegate(window, "window keyboard", (), |delegate, window| {
+            delegate.keyboard_event(window, event)
+        }),
+        Err(e) => super::debugln!("Invalid key event received: {:?}", e),
     }
 }
 
@@ -205,11 +977,21 @@ unsafe extern "C" fn window_mouse(
     status: xplm_sys::XPLMMouseStatus,
     refcon: *mut c_void,
 ) -> c_int {
+    if super::internal::suspended() {
+        // Propagate
+        return 0;
+    }
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("Window", refcon) {
+        return 0;
+    }
     let window = refcon as *mut Window;
     if let Some(action) = MouseAction::from_xplm(status) {
         let position = Point::from((x, y));
         let event = MouseEvent::new(position, action);
-        let propagate = (*window).delegate.mouse_event(&*window, event);
+        let propagate = call_delegate(window, "window mouse", true, |delegate, window| {
+            delegate.mouse_event(window, event)
+        });
         if propagate {
             0
         } else {
@@ -228,8 +1010,20 @@ unsafe extern "C" fn window_cursor(
     y: c_int,
     refcon: *mut c_void,
 ) -> xplm_sys::XPLMCursorStatus {
+    if super::internal::suspended() {
+        return Cursor::Default.as_xplm();
+    }
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("Window", refcon) {
+        return Cursor::Default.as_xplm();
+    }
     let window = refcon as *mut Window;
-    let cursor = (*window).delegate.cursor(&*window, Point::from((x, y)));
+    let cursor = call_delegate(
+        window,
+        "window cursor",
+        Cursor::Default,
+        |delegate, window| delegate.cursor(window, Point::from((x, y))),
+    );
     cursor.as_xplm()
 }
 
@@ -242,19 +1036,28 @@ unsafe extern "C" fn window_scroll(
     clicks: c_int,
     refcon: *mut c_void,
 ) -> c_int {
+    if super::internal::suspended() {
+        // Propagate
+        return 0;
+    }
+    #[cfg(feature = "drop-diagnostics")]
+    if !super::diagnostics::callback("Window", refcon) {
+        return 0;
+    }
     let window = refcon as *mut Window;
 
     let position = Point::from((x, y));
-    let (dx, dy) = if wheel == 1 {
-        // Horizontal
-        (clicks, 0)
-    } else {
-        // Vertical
-        (0, clicks)
-    };
-    let event = ScrollEvent::new(position, dx, dy);
+    let axis = ScrollAxis::from_xplm(wheel);
 
-    let propagate = (*window).delegate.scroll_event(&*window, event);
+    let propagate = call_delegate(window, "window scroll", true, |delegate, window| {
+        let speed = delegate.scroll_speed(window, axis);
+        let delta = clicks as f32 * speed;
+        let mut accumulator = window.scroll.get();
+        let steps = accumulator.add(axis, delta);
+        window.scroll.set(accumulator);
+        let event = ScrollEvent::new(position, axis, clicks, delta, steps);
+        delegate.scroll_event(window, event)
+    });
     if propagate {
         0
     } else {
@@ -264,6 +1067,7 @@ unsafe extern "C" fn window_scroll(
 
 /// Key actions
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum KeyAction {
     /// The key was pressed down
     Press,
@@ -273,6 +1077,7 @@ pub enum KeyAction {
 
 /// Keys that may be pressed
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Key {
     Back,
     Tab,
@@ -409,8 +1214,16 @@ pub enum Key {
 }
 
 impl Key {
+    /// Converts this key into an XPLM virtual key code
+    pub(crate) fn to_xplm(&self) -> c_char {
+        (0..=u8::MAX)
+            .map(|code| code as c_char)
+            .find(|&code| Key::from_xplm(code).as_ref() == Some(self))
+            .expect("Every key has a virtual key code")
+    }
+
     /// Converts an XPLM virtual key code into a Key
-    fn from_xplm(xplm_key: c_char) -> Option<Self> {
+    pub(crate) fn from_xplm(xplm_key: c_char) -> Option<Self> {
         match xplm_key as u32 {
             xplm_sys::XPLM_VK_BACK => Some(Key::Back),
             xplm_sys::XPLM_VK_TAB => Some(Key::Tab),
@@ -547,7 +1360,7 @@ pub struct KeyEvent {
 
 impl KeyEvent {
     /// Creates a key event from XPLM key information
-    fn from_xplm(
+    pub(crate) fn from_xplm(
         key: c_char,
         flags: xplm_sys::XPLMKeyFlags,
         virtual_key: c_char,
@@ -612,7 +1425,7 @@ impl KeyEvent {
 
 /// Key event creation error
 #[derive(thiserror::Error, Debug)]
-enum KeyEventError {
+pub(crate) enum KeyEventError {
     #[error("Unexpected key flags {0:b}")]
     InvalidFlags(xplm_sys::XPLMKeyFlags),
 
@@ -622,6 +1435,7 @@ enum KeyEventError {
 
 /// Actions that the mouse/cursor can perform
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum MouseAction {
     /// The user pressed the mouse button down
     Down,
@@ -670,24 +1484,53 @@ impl MouseEvent {
     }
 }
 
+/// The directions that a scroll wheel can move
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScrollAxis {
+    /// Up and down
+    Vertical,
+    /// Left and right
+    Horizontal,
+}
+
+impl ScrollAxis {
+    fn from_xplm(wheel: c_int) -> Self {
+        if wheel == 1 {
+            ScrollAxis::Horizontal
+        } else {
+            ScrollAxis::Vertical
+        }
+    }
+}
+
 /// A scroll event
+///
+/// X-Plane sends a separate event for each axis. A device that scrolls in both directions at
+/// once produces a vertical event and a horizontal event.
 #[derive(Debug, Clone)]
 pub struct ScrollEvent {
     /// The position of the mouse, in global window coordinates
     position: Point<i32>,
-    /// The amount of scroll in the X direction
-    scroll_x: i32,
-    /// The amount of scroll in the Y direction
-    scroll_y: i32,
+    /// The direction of scroll
+    axis: ScrollAxis,
+    /// The number of clicks reported by X-Plane
+    clicks: i32,
+    /// The clicks multiplied by the scroll speed
+    delta: f32,
+    /// The whole steps after adding delta to the accumulated fractions
+    steps: i32,
 }
 
 impl ScrollEvent {
     /// Creates a new event
-    fn new(position: Point<i32>, scroll_x: i32, scroll_y: i32) -> Self {
+    fn new(position: Point<i32>, axis: ScrollAxis, clicks: i32, delta: f32, steps: i32) -> Self {
         ScrollEvent {
             position,
-            scroll_x,
-            scroll_y,
+            axis,
+            clicks,
+            delta,
+            steps,
         }
     }
     /// Returns the position of the mouse, in global coordinates relative to the X-Plane
@@ -695,12 +1538,85 @@ impl ScrollEvent {
     pub fn position(&self) -> Point<i32> {
         self.position
     }
-    /// Returns the amount of scroll in the X direction
+    /// Returns the direction of scroll
+    pub fn axis(&self) -> ScrollAxis {
+        self.axis
+    }
+    /// Returns the number of clicks that the wheel moved
+    ///
+    /// Positive values are up or right.
+    pub fn clicks(&self) -> i32 {
+        self.clicks
+    }
+    /// Returns the distance scrolled, which is the number of clicks multiplied by the
+    /// [scroll speed](WindowDelegate::scroll_speed)
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+    /// Returns the number of whole steps scrolled
+    ///
+    /// Fractions of a step are saved and added to later events on the same axis, so that a
+    /// slow scroll speed or a high-resolution wheel still produces steps. The saved fraction
+    /// is discarded when the direction changes.
+    pub fn steps(&self) -> i32 {
+        self.steps
+    }
+    /// Returns the number of clicks in the X direction, or 0 for a vertical event
     pub fn scroll_x(&self) -> i32 {
-        self.scroll_x
+        match self.axis {
+            ScrollAxis::Horizontal => self.clicks,
+            ScrollAxis::Vertical => 0,
+        }
     }
-    /// Returns the amount of scroll in the Y direction
+    /// Returns the number of clicks in the Y direction, or 0 for a horizontal event
     pub fn scroll_y(&self) -> i32 {
-        self.scroll_y
+        match self.axis {
+            ScrollAxis::Vertical => self.clicks,
+            ScrollAxis::Horizontal => 0,
+        }
+    }
+}
+
+/// Fractions of a scroll step on each axis that have not been delivered
+#[derive(Debug, Default, Copy, Clone)]
+struct ScrollAccumulator {
+    vertical: f32,
+    horizontal: f32,
+}
+
+impl ScrollAccumulator {
+    /// Adds a distance on an axis and returns the number of whole steps
+    fn add(&mut self, axis: ScrollAxis, delta: f32) -> i32 {
+        let total = match axis {
+            ScrollAxis::Vertical => &mut self.vertical,
+            ScrollAxis::Horizontal => &mut self.horizontal,
+        };
+        if *total * delta < 0.0 {
+            // Changed direction
+            *total = 0.0;
+        }
+        *total += delta;
+        let steps = total.trunc();
+        *total -= steps;
+        steps as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScrollAccumulator, ScrollAxis};
+
+    #[test]
+    fn test_scroll_accumulator() {
+        let mut accumulator = ScrollAccumulator::default();
+        assert_eq!(accumulator.add(ScrollAxis::Vertical, 0.25), 0);
+        assert_eq!(accumulator.add(ScrollAxis::Horizontal, 0.5), 0);
+        assert_eq!(accumulator.add(ScrollAxis::Vertical, 0.25), 0);
+        assert_eq!(accumulator.add(ScrollAxis::Vertical, 0.75), 1);
+        assert_eq!(accumulator.add(ScrollAxis::Horizontal, 0.5), 1);
+        assert_eq!(accumulator.add(ScrollAxis::Vertical, 2.5), 2);
+        // Reversing discards the remaining 0.75
+        assert_eq!(accumulator.add(ScrollAxis::Vertical, -0.5), 0);
+        assert_eq!(accumulator.add(ScrollAxis::Vertical, -0.5), -1);
     }
 }
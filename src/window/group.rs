@@ -0,0 +1,216 @@
+//! # Window groups
+//!
+//! Plugins with several windows, such as separate displays and a settings panel, often need to
+//! show, hide, or move them together. A [`WindowGroup`] owns a set of windows and applies
+//! visibility, VR migration, and layout changes to all of them.
+//!
+//! A [`GroupLayout`] records the position and visibility of each window. It can be converted
+//! to and from text so that a plugin can store it in a preferences file.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geometry::Rect;
+//! use xplm::window::group::WindowGroup;
+//! use xplm::window::{Window, WindowDelegate};
+//!
+//! struct Display;
+//! impl WindowDelegate for Display {
+//!     fn draw(&mut self, _window: &Window) {}
+//! }
+//!
+//! let mut displays = WindowGroup::new();
+//! displays.add(Window::new(Rect::from_left_top_right_bottom(0, 400, 400, 0), Display));
+//! displays.add(Window::new(Rect::from_left_top_right_bottom(400, 400, 800, 0), Display));
+//! displays.set_follow_vr(true);
+//! displays.set_visible(true);
+//!
+//! // Save the layout when the plugin stops
+//! let saved = displays.layout().to_string();
+//! // Restore it the next time
+//! displays.restore_layout(&saved.parse().unwrap());
+//! ```
+//!
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::super::geometry::Rect;
+use super::{Window, WindowRef};
+
+/// A set of windows that are shown, hidden, and positioned together
+#[derive(Default)]
+pub struct WindowGroup {
+    /// The windows, in the order they were added
+    windows: Vec<WindowRef>,
+}
+
+impl WindowGroup {
+    /// Creates an empty group
+    pub fn new() -> Self {
+        WindowGroup::default()
+    }
+
+    /// Adds a window to this group and returns its index
+    pub fn add(&mut self, window: WindowRef) -> usize {
+        self.windows.push(window);
+        self.windows.len() - 1
+    }
+
+    /// Returns the window with the provided index, if it exists
+    pub fn get(&self, index: usize) -> Option<&Window> {
+        self.windows.get(index).map(|window| &**window)
+    }
+
+    /// Returns an iterator over the windows in this group
+    pub fn iter(&self) -> impl Iterator<Item = &Window> {
+        self.windows.iter().map(|window| &**window)
+    }
+
+    /// Returns the number of windows in this group
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    /// Returns true if this group has no windows
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Returns true if any window in this group is visible
+    pub fn any_visible(&self) -> bool {
+        self.iter().any(Window::visible)
+    }
+
+    /// Shows or hides all windows in this group
+    pub fn set_visible(&self, visible: bool) {
+        for window in self.iter() {
+            window.set_visible(visible);
+        }
+    }
+
+    /// Hides all windows if any are visible, or otherwise shows all windows
+    pub fn toggle_visible(&self) {
+        self.set_visible(!self.any_visible());
+    }
+
+    /// Enables or disables automatic migration into VR for all windows in this group
+    ///
+    /// See [`Window::set_follow_vr`] for details.
+    pub fn set_follow_vr(&self, follow: bool) {
+        for window in self.iter() {
+            window.set_follow_vr(follow);
+        }
+    }
+
+    /// Returns the current geometry and visibility of all windows in this group
+    pub fn layout(&self) -> GroupLayout {
+        GroupLayout {
+            windows: self
+                .iter()
+                .map(|window| WindowLayout {
+                    geometry: window.geometry(),
+                    visible: window.visible(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Applies a saved layout to the windows in this group
+    ///
+    /// The layouts are applied to windows in order. If the layout and the group have
+    /// different numbers of windows, the extra windows or layouts are ignored.
+    pub fn restore_layout(&self, layout: &GroupLayout) {
+        for (window, saved) in self.iter().zip(layout.windows.iter()) {
+            window.set_geometry(saved.geometry);
+            window.set_visible(saved.visible);
+        }
+    }
+}
+
+/// The geometry and visibility of each window in a group
+///
+/// A layout can be converted into text with [`ToString`] and parsed with [`str::parse`]. The
+/// text contains one line per window in the form `left,top,right,bottom,visible`, where
+/// visible is 1 or 0.
+#[derive(Debug, Clone, Default)]
+pub struct GroupLayout {
+    windows: Vec<WindowLayout>,
+}
+
+impl GroupLayout {
+    /// Returns the layout of each window
+    pub fn windows(&self) -> &[WindowLayout] {
+        &self.windows
+    }
+}
+
+/// The geometry and visibility of a window
+#[derive(Debug, Copy, Clone)]
+pub struct WindowLayout {
+    /// The window geometry in global desktop coordinates
+    pub geometry: Rect<i32>,
+    /// If the window is visible
+    pub visible: bool,
+}
+
+impl fmt::Display for GroupLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for window in &self.windows {
+            let geometry = window.geometry;
+            writeln!(
+                f,
+                "{},{},{},{},{}",
+                geometry.left(),
+                geometry.top(),
+                geometry.right(),
+                geometry.bottom(),
+                window.visible as u8
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GroupLayout {
+    type Err = ParseLayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut windows = Vec::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let values = line
+                .split(',')
+                .map(|value| value.trim().parse::<i32>())
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|_| ParseLayoutError(line.to_owned()))?;
+            match values[..] {
+                [left, top, right, bottom, visible] => windows.push(WindowLayout {
+                    geometry: Rect::from_left_top_right_bottom(left, top, right, bottom),
+                    visible: visible != 0,
+                }),
+                _ => return Err(ParseLayoutError(line.to_owned())),
+            }
+        }
+        Ok(GroupLayout { windows })
+    }
+}
+
+/// An error returned when a layout cannot be parsed
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid window layout line: {0}")]
+pub struct ParseLayoutError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::GroupLayout;
+
+    #[test]
+    fn test_layout_round_trip() {
+        let layout: GroupLayout = "10,400,300,100,1\n-5,20,30,0,0\n".parse().unwrap();
+        assert_eq!(layout.windows().len(), 2);
+        assert!(layout.windows()[0].visible);
+        assert_eq!(layout.windows()[1].geometry.left(), -5);
+        assert_eq!(layout.to_string(), "10,400,300,100,1\n-5,20,30,0,0\n");
+        assert!("1,2,3\n".parse::<GroupLayout>().is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! # Clickable panel regions
+//!
+//! Plugins that draw on the 2D panel often need parts of it to respond to clicks. A
+//! [`Hotspots`] object holds rectangular regions in panel coordinates, each with a handler
+//! that is called when the user clicks, drags, or releases the mouse in the region.
+//!
+//! Internally, a transparent window covers the screen in the flight overlay layer. It
+//! converts mouse positions into panel coordinates and lets clicks outside the regions pass
+//! through to X-Plane. Hotspots only respond while the 2D panel is shown.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geometry::Rect;
+//! use xplm::window::hotspot::Hotspots;
+//! use xplm::window::MouseAction;
+//!
+//! let hotspots = Hotspots::new().unwrap();
+//! let id = hotspots.add(
+//!     Rect::from_left_top_right_bottom(100, 240, 160, 200),
+//!     |event| {
+//!         if let MouseAction::Down = event.action() {
+//!             xplm::debugln!("Switch clicked at {:?}", event.position());
+//!         }
+//!     },
+//! );
+//! // Later
+//! hotspots.remove(id);
+//! ```
+//!
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::super::data::borrowed::{DataRef, FindError};
+use super::super::data::DataRead;
+use super::super::geometry::{Point, Rect};
+use super::monitor::screen_bounds;
+use super::{
+    Decoration, Layer, MouseAction, MouseEvent, Window, WindowBuilder, WindowCreateError,
+    WindowDelegate, WindowRef,
+};
+
+/// The value of `sim/graphics/view/view_type` when the 2D panel is shown
+const VIEW_2D_PANEL: i32 = 1000;
+
+/// Identifies a hotspot so that it can be removed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HotspotId(u64);
+
+/// A mouse event in a hotspot
+#[derive(Debug, Clone)]
+pub struct HotspotEvent {
+    /// The position of the mouse in panel coordinates
+    position: Point<i32>,
+    action: MouseAction,
+}
+
+impl HotspotEvent {
+    /// Returns the position of the mouse in panel coordinates
+    ///
+    /// During a drag, the position may be outside the hotspot.
+    pub fn position(&self) -> Point<i32> {
+        self.position
+    }
+    /// Returns the action that the user performed with the mouse
+    pub fn action(&self) -> MouseAction {
+        self.action.clone()
+    }
+}
+
+/// A hotspot handler, shared so that it can be called without borrowing the list
+type Handler = Rc<RefCell<dyn FnMut(HotspotEvent)>>;
+
+/// Rectangular regions of the 2D panel that respond to mouse events
+///
+/// The hotspots are removed when this object is dropped.
+pub struct Hotspots {
+    inner: Rc<RefCell<Inner>>,
+    _window: WindowRef,
+}
+
+impl Hotspots {
+    /// Creates an object with no hotspots
+    pub fn new() -> Result<Self, HotspotError> {
+        let inner = Rc::new(RefCell::new(Inner::default()));
+        let delegate = HotspotDelegate {
+            inner: Rc::clone(&inner),
+            panel: PanelDataRefs::find()?,
+            active: None,
+        };
+        let window = WindowBuilder::new(screen_bounds())
+            .decoration(Decoration::None)
+            .layer(Layer::FlightOverlay)
+            .visible(true)
+            .build(delegate)?;
+        Ok(Hotspots {
+            inner,
+            _window: window,
+        })
+    }
+
+    /// Adds a hotspot and returns its ID
+    ///
+    /// The region is in panel coordinates. If hotspots overlap, the one added last receives
+    /// the events.
+    pub fn add<F>(&self, region: Rect<i32>, handler: F) -> HotspotId
+    where
+        F: FnMut(HotspotEvent) + 'static,
+    {
+        let mut inner = self.inner.borrow_mut();
+        let id = HotspotId(inner.next_id);
+        inner.next_id += 1;
+        inner.hotspots.push(Hotspot {
+            id,
+            region,
+            handler: Rc::new(RefCell::new(handler)),
+        });
+        id
+    }
+
+    /// Moves or resizes a hotspot
+    ///
+    /// This has no effect if the hotspot has been removed.
+    pub fn set_region(&self, id: HotspotId, region: Rect<i32>) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(hotspot) = inner.hotspots.iter_mut().find(|hotspot| hotspot.id == id) {
+            hotspot.region = region;
+        }
+    }
+
+    /// Removes a hotspot
+    ///
+    /// This has no effect if the hotspot has already been removed.
+    pub fn remove(&self, id: HotspotId) {
+        self.inner
+            .borrow_mut()
+            .hotspots
+            .retain(|hotspot| hotspot.id != id);
+    }
+
+    /// Returns the number of hotspots
+    pub fn len(&self) -> usize {
+        self.inner.borrow().hotspots.len()
+    }
+
+    /// Returns true if there are no hotspots
+    pub fn is_empty(&self) -> bool {
+        self.inner.borrow().hotspots.is_empty()
+    }
+}
+
+impl fmt::Debug for Hotspots {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hotspots")
+            .field("hotspots", &self.len())
+            .finish()
+    }
+}
+
+/// The hotspots
+#[derive(Default)]
+struct Inner {
+    hotspots: Vec<Hotspot>,
+    next_id: u64,
+}
+
+impl Inner {
+    /// Returns the handler of the last hotspot that contains a point in panel coordinates
+    fn hit(&self, position: Point<i32>) -> Option<(HotspotId, Handler)> {
+        self.hotspots
+            .iter()
+            .rev()
+            .find(|hotspot| hotspot.region.contains(position))
+            .map(|hotspot| (hotspot.id, Rc::clone(&hotspot.handler)))
+    }
+}
+
+struct Hotspot {
+    id: HotspotId,
+    region: Rect<i32>,
+    handler: Handler,
+}
+
+/// Datarefs that describe where the panel is drawn
+struct PanelDataRefs {
+    view_type: DataRef<i32>,
+    panel: [DataRef<i32>; 4],
+    window: [DataRef<i32>; 4],
+}
+
+impl PanelDataRefs {
+    fn find() -> Result<Self, FindError> {
+        let sides = |prefix: &str| -> Result<[DataRef<i32>; 4], FindError> {
+            Ok([
+                DataRef::find(&format!("{}_l", prefix))?,
+                DataRef::find(&format!("{}_t", prefix))?,
+                DataRef::find(&format!("{}_r", prefix))?,
+                DataRef::find(&format!("{}_b", prefix))?,
+            ])
+        };
+        Ok(PanelDataRefs {
+            view_type: DataRef::find("sim/graphics/view/view_type")?,
+            panel: sides("sim/graphics/view/panel_visible_pnl")?,
+            window: sides("sim/graphics/view/panel_visible_win")?,
+        })
+    }
+
+    /// Returns the current mapping, or None if the 2D panel is not shown
+    fn mapping(&self) -> Option<PanelMapping> {
+        if self.view_type.get() != VIEW_2D_PANEL {
+            return None;
+        }
+        let rect = |sides: &[DataRef<i32>; 4]| {
+            let [left, top, right, bottom] = sides;
+            Rect::from_left_top_right_bottom(left.get(), top.get(), right.get(), bottom.get())
+        };
+        Some(PanelMapping {
+            panel: rect(&self.panel),
+            window: rect(&self.window),
+        })
+    }
+}
+
+/// The visible part of the panel, in panel and window coordinates
+#[derive(Debug, Copy, Clone)]
+struct PanelMapping {
+    panel: Rect<i32>,
+    window: Rect<i32>,
+}
+
+impl PanelMapping {
+    /// Converts a point in window coordinates into panel coordinates
+    ///
+    /// Returns None if the visible panel area is empty.
+    fn to_panel(self, position: Point<i32>) -> Option<Point<i32>> {
+        let scale = |value: i32, from: (i32, i32), to: (i32, i32)| -> Option<i32> {
+            let span = from.1 - from.0;
+            if span == 0 {
+                return None;
+            }
+            let fraction = f64::from(value - from.0) / f64::from(span);
+            Some(to.0 + (fraction * f64::from(to.1 - to.0)).round() as i32)
+        };
+        Some(Point::from_xy(
+            scale(
+                position.x(),
+                (self.window.left(), self.window.right()),
+                (self.panel.left(), self.panel.right()),
+            )?,
+            scale(
+                position.y(),
+                (self.window.bottom(), self.window.top()),
+                (self.panel.bottom(), self.panel.top()),
+            )?,
+        ))
+    }
+}
+
+/// Receives events from the transparent window
+struct HotspotDelegate {
+    inner: Rc<RefCell<Inner>>,
+    panel: PanelDataRefs,
+    /// The hotspot that received the mouse down event, which receives drag and up events
+    active: Option<(HotspotId, Handler)>,
+}
+
+impl WindowDelegate for HotspotDelegate {
+    fn draw(&mut self, window: &Window) {
+        // Keep covering the screen if its size changes
+        let bounds = screen_bounds();
+        if window.geometry().into_left_top_bottom_right() != bounds.into_left_top_bottom_right() {
+            window.set_geometry(bounds);
+        }
+    }
+
+    fn mouse_event(&mut self, _window: &Window, event: MouseEvent) -> bool {
+        let Some(position) = self
+            .panel
+            .mapping()
+            .and_then(|mapping| mapping.to_panel(event.position()))
+        else {
+            return true;
+        };
+        let handler = match event.action() {
+            MouseAction::Down => {
+                self.active = self.inner.borrow().hit(position);
+                self.active.clone()
+            }
+            MouseAction::Drag => self.active.clone(),
+            MouseAction::Up => self.active.take(),
+        };
+        match handler {
+            // Only call handlers of hotspots that have not been removed
+            Some((id, handler)) if self.inner.borrow().hotspots.iter().any(|h| h.id == id) => {
+                (handler.borrow_mut())(HotspotEvent {
+                    position,
+                    action: event.action(),
+                });
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Errors that can occur when creating hotspots
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum HotspotError {
+    /// A panel dataref could not be found
+    #[error("Panel dataref not found")]
+    DataRef(#[from] FindError),
+    /// The window that receives mouse events could not be created
+    #[error("Failed to create window")]
+    Window(#[from] WindowCreateError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PanelMapping;
+    use crate::geometry::{Point, Rect};
+
+    #[test]
+    fn test_to_panel() {
+        // A 2048 by 1024 panel drawn at half size, offset by 100 pixels
+        let mapping = PanelMapping {
+            panel: Rect::from_left_top_right_bottom(0, 1024, 2048, 0),
+            window: Rect::from_left_top_right_bottom(100, 612, 1124, 100),
+        };
+        let to_panel = |x, y| mapping.to_panel(Point::from_xy(x, y)).map(Point::into_xy);
+        assert_eq!(to_panel(100, 100), Some((0, 0)));
+        assert_eq!(to_panel(612, 356), Some((1024, 512)));
+        let empty = PanelMapping {
+            panel: mapping.panel,
+            window: Rect::from_left_top_right_bottom(0, 0, 0, 0),
+        };
+        assert!(empty.to_panel(Point::from_xy(5, 5)).is_none());
+    }
+}
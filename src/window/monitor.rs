@@ -0,0 +1,134 @@
+//! # Monitors
+//!
+//! Window positions use two coordinate systems:
+//!
+//! * Global desktop coordinates, in boxels, are used for windows inside X-Plane. A boxel is a
+//!   pixel scaled by X-Plane's user interface scale. The origin is the lower left corner of
+//!   the main monitor, and Y increases upwards. Monitors to the left of or below the main
+//!   monitor have negative coordinates.
+//! * Operating system coordinates, in pixels, are used for popped-out windows (see
+//!   [`Window::os_geometry`](crate::window::Window::os_geometry)). The origin is the lower
+//!   left corner of the main monitor.
+//!
+//! [`monitors`] and [`monitors_os`] return the bounds of each monitor that X-Plane is using,
+//! so that windows can be placed on a particular monitor.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::window::monitor;
+//!
+//! // Put a window in the middle of the last monitor
+//! if let Some(monitor) = monitor::monitors().last() {
+//!     let geometry = monitor.centered(400, 300);
+//!     xplm::debugln!("Monitor {}: {:?}", monitor.index, geometry);
+//! }
+//! ```
+//!
+
+use std::os::raw::{c_int, c_void};
+
+use super::super::geometry::Rect;
+
+/// A monitor and its bounds
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
+pub struct Monitor {
+    /// The index of the monitor, as used by
+    /// [`PositioningMode`](crate::window::PositioningMode)
+    pub index: i32,
+    /// The bounds of the monitor
+    pub bounds: Rect<i32>,
+}
+
+impl Monitor {
+    /// Returns a rectangle with the provided width and height in the center of this monitor
+    ///
+    /// If the rectangle is larger than the monitor, it is reduced to the size of the monitor.
+    pub fn centered(&self, width: i32, height: i32) -> Rect<i32> {
+        let monitor_width = self.bounds.right() - self.bounds.left();
+        let monitor_height = self.bounds.top() - self.bounds.bottom();
+        let width = width.clamp(0, monitor_width.max(0));
+        let height = height.clamp(0, monitor_height.max(0));
+        let left = self.bounds.left() + (monitor_width - width) / 2;
+        let bottom = self.bounds.bottom() + (monitor_height - height) / 2;
+        Rect::from_left_top_right_bottom(left, bottom + height, left + width, bottom)
+    }
+}
+
+/// Returns the monitors that X-Plane is using, with bounds in global desktop coordinates
+///
+/// In windowed mode, this includes only the monitors that the X-Plane window covers.
+pub fn monitors() -> Vec<Monitor> {
+    crate::debug_assert_main_thread!();
+    let mut monitors = Vec::new();
+    let monitors_ptr: *mut Vec<Monitor> = &mut monitors;
+    unsafe {
+        xplm_sys::XPLMGetAllMonitorBoundsGlobal(Some(monitor_callback), monitors_ptr.cast());
+    }
+    monitors
+}
+
+/// Returns all monitors, with bounds in operating system coordinates
+///
+/// Unlike [`monitors`], this includes monitors that X-Plane is not using, which can be used
+/// for popped-out windows.
+pub fn monitors_os() -> Vec<Monitor> {
+    crate::debug_assert_main_thread!();
+    let mut monitors = Vec::new();
+    let monitors_ptr: *mut Vec<Monitor> = &mut monitors;
+    unsafe {
+        xplm_sys::XPLMGetAllMonitorBoundsOS(Some(monitor_callback), monitors_ptr.cast());
+    }
+    monitors
+}
+
+/// Returns the bounds of all monitors that X-Plane is using, in global desktop coordinates
+///
+/// In full-screen mode, this covers all the monitors that X-Plane is using. In windowed
+/// mode, this is the area of the X-Plane window.
+pub fn screen_bounds() -> Rect<i32> {
+    crate::debug_assert_main_thread!();
+    let (mut left, mut top, mut right, mut bottom) = (0, 0, 0, 0);
+    unsafe {
+        xplm_sys::XPLMGetScreenBoundsGlobal(&mut left, &mut top, &mut right, &mut bottom);
+    }
+    Rect::from_left_top_right_bottom(left, top, right, bottom)
+}
+
+/// Interprets refcon as a pointer to a `Vec<Monitor>` and adds the monitor to it
+unsafe extern "C" fn monitor_callback(
+    index: c_int,
+    left: c_int,
+    top: c_int,
+    right: c_int,
+    bottom: c_int,
+    refcon: *mut c_void,
+) {
+    let monitors = refcon.cast::<Vec<Monitor>>();
+    (*monitors).push(Monitor {
+        index,
+        bounds: Rect::from_left_top_right_bottom(left, top, right, bottom),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Monitor;
+    use crate::geometry::Rect;
+
+    #[test]
+    fn test_centered() {
+        let monitor = Monitor {
+            index: 1,
+            bounds: Rect::from_left_top_right_bottom(-1920, 1080, 0, 0),
+        };
+        let centered = monitor.centered(400, 300);
+        assert_eq!(
+            centered.into_left_top_bottom_right(),
+            (-1160, 690, 390, -760)
+        );
+        let full = monitor.centered(4000, 3000);
+        assert_eq!(full.into_left_top_bottom_right(), (-1920, 1080, 0, 0));
+    }
+}
@@ -0,0 +1,257 @@
+//! # Saved window placement
+//!
+//! Users who pop a plugin window out to a second monitor expect to find it there the next
+//! time they start X-Plane. A [`WindowPlacement`] records whether a window is popped out,
+//! its geometry, and its visibility. A [`PlacementStore`] keeps the placements of several
+//! windows in a text file, normally in the X-Plane preferences folder.
+//!
+//! The geometry of a popped-out window is recorded in operating system coordinates, and the
+//! geometry of other windows is recorded in X-Plane global desktop coordinates.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geometry::Rect;
+//! use xplm::window::placement::PlacementStore;
+//! use xplm::window::{Window, WindowDelegate};
+//!
+//! struct Map;
+//! impl WindowDelegate for Map {
+//!     fn draw(&mut self, _window: &Window) {}
+//! }
+//!
+//! let mut store = PlacementStore::in_preferences("MyPlugin windows.txt");
+//! let window = Window::new(Rect::from_left_top_right_bottom(100, 500, 500, 100), Map);
+//! if !store.restore("map", &window) {
+//!     window.set_visible(true);
+//! }
+//!
+//! // When the plugin stops
+//! store.record("map", &window);
+//! store.save().unwrap();
+//! ```
+//!
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::super::debugln;
+use super::super::geometry::Rect;
+use super::super::paths::preferences_folder;
+use super::{PositioningMode, Window};
+
+/// The position and visibility of a window
+///
+/// A placement can be converted into text with [`ToString`] and parsed with [`str::parse`].
+/// The text has the form `mode,left,top,right,bottom,visible`, where mode is `popout` or
+/// `desktop` and visible is 1 or 0.
+#[derive(Debug, Copy, Clone)]
+pub struct WindowPlacement {
+    /// If the window is in its own operating system window
+    pub popped_out: bool,
+    /// The window geometry, in operating system coordinates if the window is popped out or
+    /// global desktop coordinates otherwise
+    pub geometry: Rect<i32>,
+    /// If the window is visible
+    pub visible: bool,
+}
+
+impl WindowPlacement {
+    /// Returns the current placement of a window
+    pub fn of(window: &Window) -> Self {
+        let popped_out = window.is_popped_out();
+        WindowPlacement {
+            popped_out,
+            geometry: if popped_out {
+                window.os_geometry()
+            } else {
+                window.geometry()
+            },
+            visible: window.visible(),
+        }
+    }
+
+    /// Moves a window to this placement
+    pub fn apply(&self, window: &Window) {
+        if self.popped_out {
+            window.set_positioning_mode(PositioningMode::PopOut);
+            window.set_os_geometry(self.geometry);
+        } else {
+            if window.is_popped_out() {
+                window.set_positioning_mode(PositioningMode::Free);
+            }
+            window.set_geometry(self.geometry);
+        }
+        window.set_visible(self.visible);
+    }
+}
+
+impl fmt::Display for WindowPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{}",
+            if self.popped_out { "popout" } else { "desktop" },
+            self.geometry.left(),
+            self.geometry.top(),
+            self.geometry.right(),
+            self.geometry.bottom(),
+            self.visible as u8
+        )
+    }
+}
+
+impl FromStr for WindowPlacement {
+    type Err = ParsePlacementError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParsePlacementError(s.to_owned());
+        let (mode, values) = s.trim().split_once(',').ok_or_else(error)?;
+        let popped_out = match mode.trim() {
+            "popout" => true,
+            "desktop" => false,
+            _ => return Err(error()),
+        };
+        let values = values
+            .split(',')
+            .map(|value| value.trim().parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>()
+            .map_err(|_| error())?;
+        match values[..] {
+            [left, top, right, bottom, visible] => Ok(WindowPlacement {
+                popped_out,
+                geometry: Rect::from_left_top_right_bottom(left, top, right, bottom),
+                visible: visible != 0,
+            }),
+            _ => Err(error()),
+        }
+    }
+}
+
+/// An error returned when a placement cannot be parsed
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid window placement: {0}")]
+pub struct ParsePlacementError(String);
+
+/// Named window placements stored in a file
+///
+/// The file contains one `name = placement` line for each window.
+#[derive(Debug, Clone)]
+pub struct PlacementStore {
+    /// The file that placements are read from and saved to
+    path: PathBuf,
+    /// The placements by window name
+    placements: BTreeMap<String, WindowPlacement>,
+}
+
+impl PlacementStore {
+    /// Reads placements from a file
+    ///
+    /// A missing file is not an error. Other problems reading the file, and lines that
+    /// cannot be parsed, are written to the log.
+    pub fn load<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        let placements = match fs::read_to_string(&path) {
+            Ok(text) => parse_store(&text),
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    debugln!("[xplm] Could not read {}: {}", path.display(), e);
+                }
+                BTreeMap::new()
+            }
+        };
+        PlacementStore { path, placements }
+    }
+
+    /// Reads placements from a file with the provided name in the X-Plane preferences folder
+    pub fn in_preferences(file_name: &str) -> Self {
+        PlacementStore::load(preferences_folder().join(file_name))
+    }
+
+    /// Returns the path of the file that this store reads and saves
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the saved placement of a window
+    pub fn get(&self, name: &str) -> Option<&WindowPlacement> {
+        self.placements.get(name)
+    }
+
+    /// Records the current placement of a window
+    ///
+    /// The placement is not written to the file until [`save`](PlacementStore::save) is
+    /// called.
+    pub fn record(&mut self, name: &str, window: &Window) {
+        self.placements
+            .insert(name.to_owned(), WindowPlacement::of(window));
+    }
+
+    /// Moves a window to its saved placement
+    ///
+    /// This returns false and leaves the window unchanged if no placement has been saved.
+    pub fn restore(&self, name: &str, window: &Window) -> bool {
+        match self.placements.get(name) {
+            Some(placement) => {
+                placement.apply(window);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes all placements to the file
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(&self.path, format_store(&self.placements))
+    }
+}
+
+/// Parses the lines of a placement file
+fn parse_store(text: &str) -> BTreeMap<String, WindowPlacement> {
+    let mut placements = BTreeMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let parsed = line
+            .split_once('=')
+            .and_then(|(name, placement)| Some((name.trim(), placement.parse().ok()?)));
+        match parsed {
+            Some((name, placement)) => {
+                placements.insert(name.to_owned(), placement);
+            }
+            None => debugln!("[xplm] Ignoring invalid window placement line {}", line),
+        }
+    }
+    placements
+}
+
+/// Formats placements as the lines of a placement file
+fn format_store(placements: &BTreeMap<String, WindowPlacement>) -> String {
+    placements
+        .iter()
+        .map(|(name, placement)| format!("{} = {}\n", name, placement))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_store, WindowPlacement};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_placement_round_trip() {
+        let placement: WindowPlacement = "popout, 1920,1080,2420,580, 1".parse().unwrap();
+        assert!(placement.popped_out);
+        assert_eq!(placement.geometry.left(), 1920);
+        assert_eq!(placement.to_string(), "popout,1920,1080,2420,580,1");
+        assert!("window,1,2,3,4,1".parse::<WindowPlacement>().is_err());
+        assert!("desktop,1,2,3,1".parse::<WindowPlacement>().is_err());
+
+        let mut placements = BTreeMap::new();
+        placements.insert("map".to_owned(), placement);
+        let text = format_store(&placements);
+        assert_eq!(text, "map = popout,1920,1080,2420,580,1\n");
+    }
+}
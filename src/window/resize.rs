@@ -0,0 +1,250 @@
+//! # Resizing undecorated windows
+//!
+//! X-Plane only lets users resize windows that it decorates. A [`ResizeGrip`] adds a grip to
+//! the bottom right corner of an undecorated window. Dragging the grip resizes the window
+//! within its [resizing limits](super::Window::set_resizing_limits), keeping the top left
+//! corner in place.
+//!
+//! The delegate of the window passes its events to the grip.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geometry::{Point, Rect};
+//! use xplm::window::resize::ResizeGrip;
+//! use xplm::window::style::WindowStyle;
+//! use xplm::window::{Cursor, MouseEvent, SizeLimits, Window, WindowDelegate};
+//!
+//! struct Resizable {
+//!     grip: ResizeGrip,
+//! }
+//!
+//! impl WindowDelegate for Resizable {
+//!     fn draw(&mut self, window: &Window) {
+//!         WindowStyle::XPLANE.draw(window.geometry());
+//!         // Draw the contents, then the grip on top
+//!         self.grip.draw(window);
+//!     }
+//!     fn mouse_event(&mut self, window: &Window, event: MouseEvent) -> bool {
+//!         if self.grip.mouse_event(window, &event) {
+//!             return false;
+//!         }
+//!         // Handle other clicks
+//!         false
+//!     }
+//!     fn cursor(&mut self, window: &Window, position: Point<i32>) -> Cursor {
+//!         self.grip.cursor(window, position).unwrap_or_default()
+//!     }
+//! }
+//!
+//! let window = Window::new(
+//!     Rect::from_left_top_right_bottom(100, 400, 400, 100),
+//!     Resizable {
+//!         grip: ResizeGrip::new(),
+//!     },
+//! );
+//! window.set_resizing_limits(SizeLimits::new((200, 150), (800, 600)));
+//! ```
+//!
+
+use std::cell::Cell;
+
+use super::super::draw::{self, GraphicsState};
+use super::super::geometry::{Point, Rect};
+use super::super::gl;
+use super::style::TEXT;
+use super::{Cursor, MouseAction, MouseEvent, SizeLimits, Window};
+
+/// The default width and height of the grip in pixels
+pub const DEFAULT_GRIP_SIZE: i32 = 14;
+
+/// The distance from the center of the resize cursor to the tip of each arrow
+const CURSOR_SIZE: f32 = 7.0;
+
+/// A drag in progress
+#[derive(Debug, Copy, Clone)]
+struct Drag {
+    /// The mouse position when the drag started
+    start: Point<i32>,
+    /// The window geometry when the drag started
+    geometry: Rect<i32>,
+}
+
+/// A corner of a window that the user can drag to resize it
+#[derive(Debug)]
+pub struct ResizeGrip {
+    /// The width and height of the grip
+    size: i32,
+    drag: Option<Drag>,
+    /// The mouse position, if the mouse is over the grip or dragging it
+    hover: Cell<Option<Point<i32>>>,
+}
+
+impl ResizeGrip {
+    /// Creates a grip with the default size
+    pub fn new() -> Self {
+        ResizeGrip {
+            size: DEFAULT_GRIP_SIZE,
+            drag: None,
+            hover: Cell::new(None),
+        }
+    }
+
+    /// Sets the width and height of the grip in pixels
+    pub fn size(self, size: i32) -> Self {
+        ResizeGrip { size, ..self }
+    }
+
+    /// Returns true if the user is dragging the grip
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Returns true if a point is in the grip of a window
+    pub fn contains(&self, window: &Window, position: Point<i32>) -> bool {
+        let geometry = window.geometry();
+        Rect::from_left_top_right_bottom(
+            geometry.right() - self.size,
+            geometry.bottom() + self.size,
+            geometry.right(),
+            geometry.bottom(),
+        )
+        .contains(position)
+    }
+
+    /// Handles a mouse event and returns true if the grip used it
+    ///
+    /// This should be called from
+    /// [`WindowDelegate::mouse_event`](super::WindowDelegate::mouse_event). If it returns
+    /// true, the delegate should not handle the event.
+    pub fn mouse_event(&mut self, window: &Window, event: &MouseEvent) -> bool {
+        let position = event.position();
+        match (event.action(), self.drag) {
+            (MouseAction::Down, _) if self.contains(window, position) => {
+                self.drag = Some(Drag {
+                    start: position,
+                    geometry: window.geometry(),
+                });
+                self.hover.set(Some(position));
+                true
+            }
+            (MouseAction::Drag, Some(drag)) => {
+                window.set_geometry(resized(drag, position, window.resizing_limits()));
+                self.hover.set(Some(position));
+                true
+            }
+            (MouseAction::Up, Some(drag)) => {
+                window.set_geometry(resized(drag, position, window.resizing_limits()));
+                self.drag = None;
+                self.hover.set(None);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the cursor to use at a position, or None if the position is not over the grip
+    ///
+    /// This should be called from [`WindowDelegate::cursor`](super::WindowDelegate::cursor).
+    /// Over the grip, X-Plane hides its cursor and [`draw`](ResizeGrip::draw) draws a resize
+    /// cursor.
+    pub fn cursor(&self, window: &Window, position: Point<i32>) -> Option<Cursor> {
+        if self.is_dragging() || self.contains(window, position) {
+            self.hover.set(Some(position));
+            Some(Cursor::None)
+        } else {
+            self.hover.set(None);
+            None
+        }
+    }
+
+    /// Draws the grip, and the resize cursor if the mouse is over the grip
+    ///
+    /// This should be called at the end of
+    /// [`WindowDelegate::draw`](super::WindowDelegate::draw) so that the grip is drawn over
+    /// the window contents.
+    pub fn draw(&self, window: &Window) {
+        let geometry = window.geometry();
+        let (right, bottom) = (geometry.right() as f32, geometry.bottom() as f32);
+        draw::set_state(&GraphicsState::new().alpha_blending(true));
+        let [red, green, blue, alpha] = TEXT;
+        unsafe {
+            gl::glColor4f(red, green, blue, alpha * 0.6);
+            gl::glBegin(gl::GL_LINES);
+            // Diagonal lines in the corner
+            for i in 1..=3 {
+                let offset = self.size as f32 * i as f32 / 4.0;
+                gl::glVertex2f(right - offset, bottom + 1.0);
+                gl::glVertex2f(right - 1.0, bottom + offset);
+            }
+            gl::glEnd();
+            if let Some(position) = self.hover.get() {
+                gl::glColor4f(red, green, blue, alpha);
+                draw_cursor(position.x() as f32, position.y() as f32);
+            }
+        }
+    }
+}
+
+impl Default for ResizeGrip {
+    fn default() -> Self {
+        ResizeGrip::new()
+    }
+}
+
+/// Draws a diagonal double-headed arrow centered on a point
+unsafe fn draw_cursor(x: f32, y: f32) {
+    let s = CURSOR_SIZE;
+    let head = CURSOR_SIZE / 2.0;
+    gl::glBegin(gl::GL_LINES);
+    gl::glVertex2f(x - s, y + s);
+    gl::glVertex2f(x + s, y - s);
+    gl::glEnd();
+    gl::glBegin(gl::GL_TRIANGLES);
+    // Top left arrow head
+    gl::glVertex2f(x - s, y + s);
+    gl::glVertex2f(x - s + head, y + s);
+    gl::glVertex2f(x - s, y + s - head);
+    // Bottom right arrow head
+    gl::glVertex2f(x + s, y - s);
+    gl::glVertex2f(x + s - head, y - s);
+    gl::glVertex2f(x + s, y - s + head);
+    gl::glEnd();
+}
+
+/// Returns the window geometry after the mouse has moved from the start of a drag to a
+/// position
+fn resized(drag: Drag, position: Point<i32>, limits: SizeLimits) -> Rect<i32> {
+    let geometry = drag.geometry;
+    let width = geometry.right() - geometry.left() + position.x() - drag.start.x();
+    let height = geometry.top() - geometry.bottom() - (position.y() - drag.start.y());
+    let (width, height) = limits.apply(width, height);
+    Rect::from_left_top_right_bottom(
+        geometry.left(),
+        geometry.top(),
+        geometry.left() + width,
+        geometry.top() - height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resized, Drag};
+    use crate::geometry::{Point, Rect};
+    use crate::window::SizeLimits;
+
+    #[test]
+    fn test_resized() {
+        let drag = Drag {
+            start: Point::from_xy(300, 100),
+            geometry: Rect::from_left_top_right_bottom(100, 400, 300, 100),
+        };
+        let limits = SizeLimits::new((150, 100), (500, 400));
+        // Drag right and down
+        let geometry = resized(drag, Point::from_xy(350, 50), limits);
+        assert_eq!(geometry.into_left_top_bottom_right(), (100, 400, 50, 350));
+        // Drag past the minimum size
+        let geometry = resized(drag, Point::from_xy(0, 500), limits);
+        assert_eq!(geometry.into_left_top_bottom_right(), (100, 400, 300, 250));
+    }
+}
@@ -0,0 +1,233 @@
+//! # Window styles
+//!
+//! Windows created with [`Window::new`](super::Window::new) have no decoration, so their
+//! draw callbacks must draw a background. A [`WindowStyle`] draws a background and border
+//! that look like the windows X-Plane draws, without copying colors and corner sizes into
+//! each plugin.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geometry::Rect;
+//! use xplm::window::style::WindowStyle;
+//! use xplm::window::{Window, WindowDelegate};
+//!
+//! struct Panel;
+//! impl WindowDelegate for Panel {
+//!     fn draw(&mut self, window: &Window) {
+//!         WindowStyle::XPLANE.draw(window.geometry());
+//!         // Draw the window contents
+//!     }
+//! }
+//!
+//! let window = Window::new(Rect::from_left_top_right_bottom(100, 400, 400, 100), Panel);
+//! ```
+//!
+
+use std::f32::consts::FRAC_PI_2;
+
+use super::super::draw::{self, GraphicsState};
+use super::super::geometry::Rect;
+use super::super::gl;
+
+/// A color with red, green, blue, and alpha components from 0 to 1
+pub type Rgba = [f32; 4];
+
+/// The background color of X-Plane windows
+pub const BACKGROUND: Rgba = [0.11, 0.13, 0.16, 0.92];
+/// The border color of X-Plane windows
+pub const BORDER: Rgba = [0.30, 0.34, 0.40, 1.0];
+/// The color of text in X-Plane windows
+pub const TEXT: Rgba = [0.90, 0.92, 0.95, 1.0];
+/// The color of highlighted items in X-Plane windows
+pub const HIGHLIGHT: Rgba = [0.20, 0.45, 0.75, 1.0];
+/// The corner radius of X-Plane windows, in pixels
+pub const CORNER_RADIUS: f32 = 6.0;
+
+/// The number of line segments used to draw each rounded corner
+const CORNER_SEGMENTS: usize = 6;
+
+/// How the area of a window is filled
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Background {
+    /// Nothing is drawn
+    None,
+    /// The translucent dark box that X-Plane draws behind text
+    ///
+    /// This background always has square corners.
+    TranslucentDark,
+    /// A color, which may be translucent
+    Fill(Rgba),
+}
+
+/// The background and border of a window
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct WindowStyle {
+    /// The background
+    pub background: Background,
+    /// The border color, or None to draw no border
+    pub border: Option<Rgba>,
+    /// The border width in pixels
+    pub border_width: f32,
+    /// The corner radius in pixels
+    ///
+    /// The radius is reduced if it is more than half the width or height of the window.
+    pub corner_radius: f32,
+}
+
+impl WindowStyle {
+    /// The style of X-Plane 11 and 12 windows, with rounded corners and a thin border
+    pub const XPLANE: WindowStyle = WindowStyle {
+        background: Background::Fill(BACKGROUND),
+        border: Some(BORDER),
+        border_width: 1.0,
+        corner_radius: CORNER_RADIUS,
+    };
+
+    /// The translucent dark box of older X-Plane windows, with no border
+    pub const TRANSLUCENT: WindowStyle = WindowStyle {
+        background: Background::TranslucentDark,
+        border: None,
+        border_width: 0.0,
+        corner_radius: 0.0,
+    };
+
+    /// Sets the background
+    pub fn background(self, background: Background) -> Self {
+        WindowStyle { background, ..self }
+    }
+
+    /// Sets the border color and width in pixels
+    pub fn border(self, color: Rgba, width: f32) -> Self {
+        WindowStyle {
+            border: Some(color),
+            border_width: width,
+            ..self
+        }
+    }
+
+    /// Removes the border
+    pub fn no_border(self) -> Self {
+        WindowStyle {
+            border: None,
+            ..self
+        }
+    }
+
+    /// Sets the corner radius in pixels
+    pub fn corner_radius(self, corner_radius: f32) -> Self {
+        WindowStyle {
+            corner_radius,
+            ..self
+        }
+    }
+
+    /// Draws the background and border in the provided rectangle
+    ///
+    /// This must only be called from a draw callback. It changes the graphics state to
+    /// enable alpha blending and disable textures.
+    pub fn draw(&self, geometry: Rect<i32>) {
+        crate::debug_assert_main_thread!();
+        if let Background::TranslucentDark = self.background {
+            unsafe {
+                xplm_sys::XPLMDrawTranslucentDarkBox(
+                    geometry.left(),
+                    geometry.top(),
+                    geometry.right(),
+                    geometry.bottom(),
+                );
+            }
+        }
+        draw::set_state(&GraphicsState::new().alpha_blending(true));
+        let outline = outline(geometry, self.corner_radius);
+        unsafe {
+            if let Background::Fill(color) = self.background {
+                let (left, top, right, bottom) = rect_f32(geometry);
+                gl::glColor4f(color[0], color[1], color[2], color[3]);
+                gl::glBegin(gl::GL_TRIANGLE_FAN);
+                gl::glVertex2f((left + right) / 2.0, (top + bottom) / 2.0);
+                for &(x, y) in outline.iter().chain(outline.first()) {
+                    gl::glVertex2f(x, y);
+                }
+                gl::glEnd();
+            }
+            if let Some(color) = self.border {
+                gl::glColor4f(color[0], color[1], color[2], color[3]);
+                gl::glLineWidth(self.border_width);
+                gl::glBegin(gl::GL_LINE_LOOP);
+                for &(x, y) in &outline {
+                    gl::glVertex2f(x, y);
+                }
+                gl::glEnd();
+            }
+        }
+    }
+}
+
+impl Default for WindowStyle {
+    fn default() -> Self {
+        WindowStyle::XPLANE
+    }
+}
+
+/// Returns the left, top, right, and bottom of a rectangle as floats
+fn rect_f32(geometry: Rect<i32>) -> (f32, f32, f32, f32) {
+    (
+        geometry.left() as f32,
+        geometry.top() as f32,
+        geometry.right() as f32,
+        geometry.bottom() as f32,
+    )
+}
+
+/// Returns the points around the edge of a rectangle with rounded corners, counterclockwise
+/// from the top right corner
+fn outline(geometry: Rect<i32>, radius: f32) -> Vec<(f32, f32)> {
+    let (left, top, right, bottom) = rect_f32(geometry);
+    let radius = radius
+        .min((right - left).abs() / 2.0)
+        .min((top - bottom).abs() / 2.0)
+        .max(0.0);
+    if radius == 0.0 {
+        return vec![(right, top), (left, top), (left, bottom), (right, bottom)];
+    }
+    // The center of each corner and the angle where it starts
+    let corners = [
+        (right - radius, top - radius, 0.0),
+        (left + radius, top - radius, FRAC_PI_2),
+        (left + radius, bottom + radius, 2.0 * FRAC_PI_2),
+        (right - radius, bottom + radius, 3.0 * FRAC_PI_2),
+    ];
+    let mut points = Vec::with_capacity(corners.len() * (CORNER_SEGMENTS + 1));
+    for (x, y, start) in corners {
+        for i in 0..=CORNER_SEGMENTS {
+            let angle = start + FRAC_PI_2 * i as f32 / CORNER_SEGMENTS as f32;
+            points.push((x + radius * angle.cos(), y + radius * angle.sin()));
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::outline;
+    use crate::geometry::Rect;
+
+    #[test]
+    fn test_outline() {
+        let square = outline(Rect::from_left_top_right_bottom(0, 10, 20, 0), 0.0);
+        assert_eq!(square, [(20.0, 10.0), (0.0, 10.0), (0.0, 0.0), (20.0, 0.0)]);
+
+        // The radius is limited to half the height
+        let rounded = outline(Rect::from_left_top_right_bottom(0, 10, 20, 0), 8.0);
+        assert_eq!(rounded.len(), 28);
+        for &(x, y) in &rounded {
+            assert!((-0.001..=20.001).contains(&x));
+            assert!((-0.001..=10.001).contains(&y));
+        }
+        let (x, y) = rounded[0];
+        assert!((x - 20.0).abs() < 0.001 && (y - 5.0).abs() < 0.001);
+    }
+}
@@ -0,0 +1,501 @@
+//! # Immediate-mode user interface
+//!
+//! A [`UiWindow`] is a window delegate that draws simple controls: labels, buttons, check
+//! boxes, and sliders. Each time the window is drawn, it calls a function that describes the
+//! controls. The controls are laid out from top to bottom, or from left to right in a
+//! [`horizontal`](Ui::horizontal) row. Functions that create controls return true when the
+//! user has changed something, so the state of a settings window can live in ordinary
+//! variables instead of callbacks.
+//!
+//! The controls are drawn with X-Plane's fonts and the colors in the [`style`](super::style)
+//! module. They do not use the widget library.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use xplm::geometry::Rect;
+//! use xplm::window::ui::UiWindow;
+//! use xplm::window::Window;
+//!
+//! let mut enabled = true;
+//! let mut volume = 0.5;
+//! let window = Window::new(
+//!     Rect::from_left_top_right_bottom(100, 400, 350, 250),
+//!     UiWindow::new(move |ui| {
+//!         ui.label("Sound settings");
+//!         if ui.checkbox(&mut enabled, "Enabled") {
+//!             xplm::debugln!("Sound enabled: {}", enabled);
+//!         }
+//!         ui.horizontal(|ui| {
+//!             ui.label("Volume");
+//!             ui.slider(&mut volume, 0.0..=1.0);
+//!         });
+//!         if ui.button("Reset") {
+//!             volume = 0.5;
+//!         }
+//!     }),
+//! );
+//! ```
+//!
+
+use std::ops::RangeInclusive;
+
+use super::super::draw::text::{self, Font, Rgb};
+use super::super::geometry::{Point, Rect};
+use super::style::{self, Background, Rgba, WindowStyle};
+use super::{Cursor, MouseAction, MouseEvent, Window, WindowDelegate};
+
+/// The space between the edges of the window and the controls, in pixels
+const PADDING: i32 = 8;
+/// The space between controls, in pixels
+const SPACING: i32 = 4;
+/// The width of a slider in a horizontal row, in pixels
+const SLIDER_WIDTH: i32 = 150;
+/// The width of a slider knob, in pixels
+const KNOB_WIDTH: i32 = 8;
+/// The color of buttons and check boxes
+const CONTROL: Rgba = [0.22, 0.26, 0.32, 1.0];
+/// The color of buttons and check boxes under the mouse
+const CONTROL_HOVER: Rgba = [0.29, 0.34, 0.42, 1.0];
+
+/// A window delegate that draws controls described by a function
+///
+/// The function is called every time the window is drawn.
+pub struct UiWindow<F> {
+    build: F,
+    style: WindowStyle,
+    input: Input,
+}
+
+impl<F: FnMut(&mut Ui) + 'static> UiWindow<F> {
+    /// Creates a delegate that draws the X-Plane window style behind the controls
+    pub fn new(build: F) -> Self {
+        UiWindow {
+            build,
+            style: WindowStyle::XPLANE,
+            input: Input::default(),
+        }
+    }
+
+    /// Sets the background and border drawn behind the controls
+    ///
+    /// Windows with decorations already have a background, so they can use a style with
+    /// [`Background::None`] and no border.
+    pub fn style(self, style: WindowStyle) -> Self {
+        UiWindow { style, ..self }
+    }
+}
+
+impl<F: FnMut(&mut Ui) + 'static> WindowDelegate for UiWindow<F> {
+    fn draw(&mut self, window: &Window) {
+        let geometry = window.geometry();
+        self.style.draw(geometry);
+        let mut ui = Ui::new(&mut self.input, geometry);
+        (self.build)(&mut ui);
+        self.input.end_frame();
+    }
+
+    fn mouse_event(&mut self, _window: &Window, event: MouseEvent) -> bool {
+        self.input.mouse_event(&event);
+        false
+    }
+
+    fn cursor(&mut self, _window: &Window, position: Point<i32>) -> Cursor {
+        self.input.hover = Some(position);
+        Cursor::Default
+    }
+}
+
+/// Mouse input collected between frames
+#[derive(Debug, Default)]
+struct Input {
+    /// The position of the mouse, if it is over the window
+    hover: Option<Point<i32>>,
+    /// The position of the mouse while its button is down
+    drag: Option<Point<i32>>,
+    /// Where the mouse button was pressed since the last frame
+    pressed: Option<Point<i32>>,
+    /// Where the mouse button was released since the last frame
+    released: Option<Point<i32>>,
+    /// The control that the mouse button was pressed on
+    active: Option<usize>,
+}
+
+impl Input {
+    fn mouse_event(&mut self, event: &MouseEvent) {
+        let position = event.position();
+        match event.action() {
+            MouseAction::Down => {
+                self.pressed = Some(position);
+                self.drag = Some(position);
+            }
+            MouseAction::Drag => self.drag = Some(position),
+            MouseAction::Up => {
+                self.released = Some(position);
+                self.drag = None;
+            }
+        }
+    }
+
+    /// Updates the state of a control and returns how the user is interacting with it
+    fn interact(&mut self, id: usize, rect: Rect<i32>) -> Interaction {
+        if self.pressed.is_some_and(|pressed| rect.contains(pressed)) {
+            self.active = Some(id);
+        }
+        let active = self.active == Some(id);
+        Interaction {
+            hovered: active || self.hover.is_some_and(|hover| rect.contains(hover)),
+            active,
+            clicked: active
+                && self
+                    .released
+                    .is_some_and(|released| rect.contains(released)),
+            // While the button is down, or where it was released
+            position: if active {
+                self.drag.or(self.released)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Clears the input that only applies to one frame
+    fn end_frame(&mut self) {
+        if self.released.is_some() {
+            self.active = None;
+        }
+        self.hover = None;
+        self.pressed = None;
+        self.released = None;
+    }
+}
+
+/// How the user is interacting with a control
+#[derive(Debug, Copy, Clone)]
+struct Interaction {
+    /// The mouse is over the control, or the button was pressed on it
+    hovered: bool,
+    /// The mouse button was pressed on the control and has not been released
+    active: bool,
+    /// The mouse button was pressed and released on the control
+    clicked: bool,
+    /// The position of the mouse, if the control is active
+    position: Option<Point<i32>>,
+}
+
+/// Creates and draws controls
+///
+/// This is passed to the function of a [`UiWindow`] each time the window is drawn.
+pub struct Ui<'a> {
+    input: &'a mut Input,
+    layout: Layout,
+    font: Font,
+    char_height: i32,
+    /// The identifier of the next control, based on the order controls are created in
+    next_id: usize,
+}
+
+impl<'a> Ui<'a> {
+    fn new(input: &'a mut Input, bounds: Rect<i32>) -> Self {
+        let font = Font::Proportional;
+        let char_height = text::font_dimensions(font).char_height;
+        Ui {
+            input,
+            layout: Layout::new(bounds, (char_height + 8).max(18)),
+            font,
+            char_height,
+            next_id: 0,
+        }
+    }
+
+    /// Returns the number of pixels available for controls to the right of the current
+    /// position
+    pub fn available_width(&self) -> i32 {
+        self.layout.available_width()
+    }
+
+    /// Adds empty space below the previous control, or to its right in a horizontal row
+    pub fn space(&mut self, pixels: i32) {
+        self.layout.space(pixels);
+    }
+
+    /// Places the controls created in a function from left to right
+    ///
+    /// The next control after the row is placed below it.
+    pub fn horizontal<R, G: FnOnce(&mut Ui) -> R>(&mut self, contents: G) -> R {
+        if self.layout.horizontal {
+            return contents(self);
+        }
+        self.layout.begin_row();
+        let result = contents(self);
+        self.layout.end_row();
+        result
+    }
+
+    /// Draws text
+    pub fn label(&mut self, text: &str) {
+        let rect = self.layout.allocate(self.text_width(text));
+        self.draw_text(rect, text);
+    }
+
+    /// Draws a button and returns true if the user clicked it
+    pub fn button(&mut self, label: &str) -> bool {
+        let rect = self.layout.allocate(self.text_width(label) + 2 * PADDING);
+        let interaction = self.interact(rect);
+        let color = if interaction.active && interaction.hovered {
+            style::HIGHLIGHT
+        } else if interaction.hovered {
+            CONTROL_HOVER
+        } else {
+            CONTROL
+        };
+        fill(rect, color, true);
+        let (left, top, bottom, right) = rect.into_left_top_bottom_right();
+        self.draw_text(
+            Rect::from_left_top_right_bottom(left + PADDING, top, right, bottom),
+            label,
+        );
+        interaction.clicked
+    }
+
+    /// Draws a check box with a label and returns true if the user checked or unchecked it
+    ///
+    /// Clicking the check box or its label changes checked.
+    pub fn checkbox(&mut self, checked: &mut bool, label: &str) -> bool {
+        let height = self.layout.row_height;
+        let rect = self
+            .layout
+            .allocate(height + SPACING + self.text_width(label));
+        let interaction = self.interact(rect);
+        if interaction.clicked {
+            *checked = !*checked;
+        }
+        let (left, top, bottom, _) = rect.into_left_top_bottom_right();
+        let size = height - 6;
+        let box_rect = Rect::from_left_top_right_bottom(left, top - 3, left + size, bottom + 3);
+        fill(
+            box_rect,
+            if interaction.hovered {
+                CONTROL_HOVER
+            } else {
+                CONTROL
+            },
+            true,
+        );
+        if *checked {
+            let mark =
+                Rect::from_left_top_right_bottom(left + 3, top - 6, left + size - 3, bottom + 6);
+            fill(mark, style::HIGHLIGHT, false);
+        }
+        self.draw_text(
+            Rect::from_left_top_right_bottom(left + height + SPACING, top, rect.right(), bottom),
+            label,
+        );
+        interaction.clicked
+    }
+
+    /// Draws a slider and returns true if the user changed value
+    ///
+    /// The slider fills the width of the window, or is a fixed width in a horizontal row.
+    pub fn slider(&mut self, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        let width = if self.layout.horizontal {
+            SLIDER_WIDTH.min(self.available_width())
+        } else {
+            self.available_width()
+        };
+        let rect = self.layout.allocate(width.max(KNOB_WIDTH));
+        let interaction = self.interact(rect);
+        let (left, top, bottom, right) = rect.into_left_top_bottom_right();
+        let track_left = left + KNOB_WIDTH / 2;
+        let track_right = right - KNOB_WIDTH / 2;
+        let mut changed = false;
+        if let Some(position) = interaction.position {
+            let new_value = slider_value(position.x(), track_left, track_right, &range);
+            changed = new_value != *value;
+            *value = new_value;
+        }
+        let middle = (top + bottom) / 2;
+        fill(
+            Rect::from_left_top_right_bottom(track_left, middle + 2, track_right, middle - 2),
+            style::BORDER,
+            false,
+        );
+        let knob_x = track_left
+            + (slider_fraction(*value, &range) * (track_right - track_left) as f32).round() as i32;
+        fill(
+            Rect::from_left_top_right_bottom(
+                knob_x - KNOB_WIDTH / 2,
+                top - 2,
+                knob_x + KNOB_WIDTH / 2,
+                bottom + 2,
+            ),
+            if interaction.hovered {
+                style::HIGHLIGHT
+            } else {
+                style::TEXT
+            },
+            false,
+        );
+        changed
+    }
+
+    /// Returns the width of text in pixels
+    fn text_width(&self, text: &str) -> i32 {
+        text::measure_string(self.font, text).ceil() as i32
+    }
+
+    /// Draws text at the left of a rectangle, centered vertically
+    fn draw_text(&self, rect: Rect<i32>, text: &str) {
+        let [r, g, b, _] = style::TEXT;
+        let height = rect.top() - rect.bottom();
+        let baseline = rect.bottom() + (height - self.char_height) / 2 + 2;
+        text::draw_string(Rgb::new(r, g, b), rect.left(), baseline, text, self.font);
+    }
+
+    /// Updates the state of the next control
+    fn interact(&mut self, rect: Rect<i32>) -> Interaction {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.input.interact(id, rect)
+    }
+}
+
+/// Fills a rectangle with slightly rounded corners, and optionally draws a border
+fn fill(rect: Rect<i32>, color: Rgba, border: bool) {
+    let style = WindowStyle::XPLANE
+        .background(Background::Fill(color))
+        .corner_radius(3.0);
+    let style = if border {
+        style.border(style::BORDER, 1.0)
+    } else {
+        style.no_border()
+    };
+    style.draw(rect);
+}
+
+/// Returns the position of a value in a range, from 0 to 1
+fn slider_fraction(value: f32, range: &RangeInclusive<f32>) -> f32 {
+    let span = range.end() - range.start();
+    if span == 0.0 {
+        0.0
+    } else {
+        ((value - range.start()) / span).clamp(0.0, 1.0)
+    }
+}
+
+/// Returns the value of a slider when the mouse is at x
+fn slider_value(x: i32, left: i32, right: i32, range: &RangeInclusive<f32>) -> f32 {
+    let fraction = if right > left {
+        ((x - left) as f32 / (right - left) as f32).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    range.start() + fraction * (range.end() - range.start())
+}
+
+/// Places controls in a window
+#[derive(Debug, Clone)]
+struct Layout {
+    left: i32,
+    right: i32,
+    /// The left edge of the next control in a horizontal row
+    x: i32,
+    /// The top edge of the next control
+    y: i32,
+    /// The height of each control
+    row_height: i32,
+    /// True if controls are placed from left to right
+    horizontal: bool,
+}
+
+impl Layout {
+    fn new(bounds: Rect<i32>, row_height: i32) -> Self {
+        let left = bounds.left() + PADDING;
+        Layout {
+            left,
+            right: bounds.right() - PADDING,
+            x: left,
+            y: bounds.top() - PADDING,
+            row_height,
+            horizontal: false,
+        }
+    }
+
+    fn available_width(&self) -> i32 {
+        (self.right - self.x).max(0)
+    }
+
+    /// Returns the rectangle for the next control
+    fn allocate(&mut self, width: i32) -> Rect<i32> {
+        let rect = Rect::from_left_top_right_bottom(
+            self.x,
+            self.y,
+            self.x + width,
+            self.y - self.row_height,
+        );
+        if self.horizontal {
+            self.x += width + SPACING;
+        } else {
+            self.y -= self.row_height + SPACING;
+        }
+        rect
+    }
+
+    fn space(&mut self, pixels: i32) {
+        if self.horizontal {
+            self.x += pixels;
+        } else {
+            self.y -= pixels;
+        }
+    }
+
+    fn begin_row(&mut self) {
+        self.horizontal = true;
+    }
+
+    fn end_row(&mut self) {
+        self.horizontal = false;
+        self.x = self.left;
+        self.y -= self.row_height + SPACING;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{slider_fraction, slider_value, Layout, PADDING, SPACING};
+    use crate::geometry::Rect;
+
+    #[test]
+    fn test_layout() {
+        let mut layout = Layout::new(Rect::from_left_top_right_bottom(0, 200, 100, 0), 20);
+        let first = layout.allocate(50);
+        assert_eq!(
+            first.into_left_top_bottom_right(),
+            (PADDING, 200 - PADDING, 180 - PADDING, 50 + PADDING)
+        );
+        layout.begin_row();
+        let a = layout.allocate(30);
+        let b = layout.allocate(10);
+        assert_eq!(a.top(), 200 - PADDING - (20 + SPACING));
+        assert_eq!(b.left(), PADDING + 30 + SPACING);
+        assert_eq!(
+            layout.available_width(),
+            100 - 2 * PADDING - 40 - 2 * SPACING
+        );
+        layout.end_row();
+        let below = layout.allocate(10);
+        assert_eq!(below.left(), PADDING);
+        assert_eq!(below.top(), 200 - PADDING - 2 * (20 + SPACING));
+    }
+
+    #[test]
+    fn test_slider_mapping() {
+        let range = -10.0..=10.0;
+        assert_eq!(slider_fraction(0.0, &range), 0.5);
+        assert_eq!(slider_fraction(20.0, &range), 1.0);
+        assert_eq!(slider_fraction(3.0, &(3.0..=3.0)), 0.0);
+        assert_eq!(slider_value(50, 0, 100, &range), 0.0);
+        assert_eq!(slider_value(-5, 0, 100, &range), -10.0);
+        assert_eq!(slider_value(150, 0, 100, &range), 10.0);
+    }
+}
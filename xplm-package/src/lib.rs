@@ -0,0 +1,212 @@
+//! # Plugin packaging
+//!
+//! X-Plane loads a plugin from a folder in one of its plugins folders. A plugin that supports
+//! several operating systems is usually distributed as a "fat plugin", which contains a
+//! library for each operating system:
+//!
+//! ```text
+//! MyPlugin/
+//!     64/
+//!         win.xpl
+//!         mac.xpl
+//!         lin.xpl
+//! ```
+//!
+//! Cargo names the library that it builds after the crate and the operating system, for
+//! example `libmy_plugin.so`. The functions in this module copy a library that Cargo has
+//! built to the right place in this layout.
+//!
+//! Cargo runs build scripts before it compiles a crate, so a build script cannot package the
+//! library of its own crate. This crate is intended for a separate packaging program, such as
+//! an `xtask` binary that is run after `cargo build`. It does not depend on `xplm` or the
+//! X-Plane SDK, so the packaging program can run without X-Plane.
+//!
+//! The `xplane_plugin!` macro creates the same entry points for all operating systems and
+//! SDK versions, so no other changes are needed to build a fat plugin.
+//!
+//! X-Plane on macOS runs on both Intel and Apple processors, and a fat plugin has only one
+//! `mac.xpl`. [`install_mac_universal`] combines the libraries for both processors into one
+//! universal library. [`install`] with [`Platform::Mac`] copies the library for only one
+//! processor, and replaces any `mac.xpl` that is already there.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use xplm_package::{self as packaging, Platform};
+//!
+//! // After cargo build --release --target x86_64-pc-windows-gnu
+//! let platform = Platform::from_target("x86_64-pc-windows-gnu").unwrap();
+//! let library = Path::new("target/x86_64-pc-windows-gnu/release")
+//!     .join(platform.library_file_name("my-plugin"));
+//! let installed = packaging::install(&library, Path::new("dist"), "MyPlugin", platform).unwrap();
+//! assert_eq!(installed, Path::new("dist/MyPlugin/64/win.xpl"));
+//! ```
+//!
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An operating system that X-Plane runs on
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// Windows
+    Windows,
+    /// macOS
+    Mac,
+    /// Linux
+    Linux,
+}
+
+impl Platform {
+    /// Returns the platform that this code was compiled for, if X-Plane runs on it
+    pub fn current() -> Option<Platform> {
+        if cfg!(target_os = "windows") {
+            Some(Platform::Windows)
+        } else if cfg!(target_os = "macos") {
+            Some(Platform::Mac)
+        } else if cfg!(target_os = "linux") {
+            Some(Platform::Linux)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the platform of a Rust target triple, such as `x86_64-unknown-linux-gnu`
+    ///
+    /// Both `x86_64-apple-darwin` and `aarch64-apple-darwin` are [`Platform::Mac`].
+    pub fn from_target(target: &str) -> Option<Platform> {
+        if target.contains("-windows") {
+            Some(Platform::Windows)
+        } else if target.contains("-apple-darwin") {
+            Some(Platform::Mac)
+        } else if target.contains("-linux") {
+            Some(Platform::Linux)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the name of the plugin file for this platform in a fat plugin
+    pub fn xpl_file_name(self) -> &'static str {
+        match self {
+            Platform::Windows => "win.xpl",
+            Platform::Mac => "mac.xpl",
+            Platform::Linux => "lin.xpl",
+        }
+    }
+
+    /// Returns the name of the library file that Cargo builds for a crate on this platform
+    ///
+    /// Hyphens in the crate name are replaced with underscores, as Cargo does.
+    pub fn library_file_name(self, crate_name: &str) -> String {
+        let crate_name = crate_name.replace('-', "_");
+        match self {
+            Platform::Windows => format!("{}.dll", crate_name),
+            Platform::Mac => format!("lib{}.dylib", crate_name),
+            Platform::Linux => format!("lib{}.so", crate_name),
+        }
+    }
+}
+
+/// Returns the path of the plugin file for a platform in a fat plugin
+///
+/// folder is the folder that contains the plugin folder, and name is the name of the plugin
+/// folder.
+pub fn plugin_path(folder: &Path, name: &str, platform: Platform) -> PathBuf {
+    folder.join(name).join("64").join(platform.xpl_file_name())
+}
+
+/// Copies a library to its place in a fat plugin and returns the path of the copy
+///
+/// The plugin folder is created if it does not exist, and an existing plugin file for the
+/// platform is replaced. Files for other platforms are not changed, so this can be called once
+/// for each platform to build a fat plugin.
+///
+/// For macOS, this copies a library for one processor. Use [`install_mac_universal`] to
+/// support both Intel and Apple processors.
+pub fn install(
+    library: &Path,
+    folder: &Path,
+    name: &str,
+    platform: Platform,
+) -> io::Result<PathBuf> {
+    let path = plugin_path(folder, name, platform);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(library, &path)?;
+    Ok(path)
+}
+
+/// Combines macOS libraries for different processors into a universal `mac.xpl` in a fat
+/// plugin and returns its path
+///
+/// libraries are usually the libraries built for `x86_64-apple-darwin` and
+/// `aarch64-apple-darwin`. This runs the `lipo` tool, which is part of the Xcode command line
+/// tools. An existing `mac.xpl` is replaced.
+pub fn install_mac_universal(
+    libraries: &[&Path],
+    folder: &Path,
+    name: &str,
+) -> io::Result<PathBuf> {
+    let path = plugin_path(folder, name, Platform::Mac);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let status = Command::new("lipo")
+        .arg("-create")
+        .args(libraries)
+        .arg("-output")
+        .arg(&path)
+        .status()?;
+    if status.success() {
+        Ok(path)
+    } else {
+        Err(io::Error::other(format!("lipo failed: {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plugin_path, Platform};
+    use std::path::Path;
+
+    #[test]
+    fn test_from_target() {
+        let targets = [
+            ("x86_64-pc-windows-msvc", Some(Platform::Windows)),
+            ("x86_64-pc-windows-gnu", Some(Platform::Windows)),
+            ("aarch64-apple-darwin", Some(Platform::Mac)),
+            ("x86_64-apple-darwin", Some(Platform::Mac)),
+            ("x86_64-unknown-linux-gnu", Some(Platform::Linux)),
+            ("aarch64-apple-ios", None),
+            ("wasm32-unknown-unknown", None),
+        ];
+        for (target, platform) in targets {
+            assert_eq!(Platform::from_target(target), platform, "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(
+            Platform::Windows.library_file_name("my-plugin"),
+            "my_plugin.dll"
+        );
+        assert_eq!(
+            Platform::Mac.library_file_name("my-plugin"),
+            "libmy_plugin.dylib"
+        );
+        assert_eq!(
+            Platform::Linux.library_file_name("my-plugin"),
+            "libmy_plugin.so"
+        );
+        assert_eq!(
+            plugin_path(Path::new("dist"), "MyPlugin", Platform::Mac),
+            Path::new("dist/MyPlugin/64/mac.xpl")
+        );
+    }
+}